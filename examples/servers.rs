@@ -0,0 +1,28 @@
+//! Reads the multiplayer server list from `servers.dat` and writes it back with a server added.
+use std::{env, fs::File};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Server {
+    ip: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Servers {
+    servers: Vec<Server>,
+}
+
+fn main() -> nbt::Result<()> {
+    let path = env::args().nth(1).unwrap_or_else(|| "servers.dat".into());
+    let mut list: Servers = nbt::from_slice(&std::fs::read(&path)?)?;
+    println!("{:#?}", list);
+
+    list.servers.push(Server {
+        ip: "localhost".into(),
+        name: "Minecraft Server".into(),
+    });
+    nbt::to_writer(File::create(path)?, &list)?;
+    Ok(())
+}
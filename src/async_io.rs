@@ -0,0 +1,68 @@
+//! Reading and writing NBT over tokio's `AsyncRead` and `AsyncWrite`. Documents are buffered in
+//! memory and decoded or encoded in one go, so no call blocks the runtime on IO.
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    de::{from_slice_with_options, DeserializerOptions},
+    error,
+    ser::to_bytes,
+};
+
+#[test]
+fn test_async_round_trip() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Status {
+        players: i32,
+        motd: String,
+    }
+
+    let status = Status {
+        players: 3,
+        motd: "hello".into(),
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let decoded: Status = runtime.block_on(async {
+        let mut bytes = Vec::new();
+        to_async_writer(&mut bytes, &status).await.unwrap();
+        from_async_reader(bytes.as_slice()).await.unwrap()
+    });
+    assert_eq!(decoded, status);
+}
+
+/// Reads an uncompressed document from `reader` until it ends.
+pub async fn from_async_reader<T, R>(reader: R) -> error::Result<T>
+where
+    T: DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    from_async_reader_with_options(reader, DeserializerOptions::default()).await
+}
+
+pub async fn from_async_reader_with_options<T, R>(
+    mut reader: R,
+    options: DeserializerOptions,
+) -> error::Result<T>
+where
+    T: DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    from_slice_with_options(&bytes, options)
+}
+
+/// Writes `value` to `writer` and flushes it.
+pub async fn to_async_writer<T, W>(mut writer: W, value: &T) -> error::Result<()>
+where
+    T: Serialize,
+    W: AsyncWrite + Unpin,
+{
+    writer.write_all(&to_bytes(value)?).await?;
+    writer.flush().await?;
+    Ok(())
+}
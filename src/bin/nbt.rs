@@ -0,0 +1,293 @@
+//! The `nbt` command line tool. `nbt explore <file|region>` browses an NBT file, or the chunks of
+//! a region file, as a tree in the terminal, on top of [`Explorer`].
+use std::{
+    collections::HashSet,
+    env,
+    io::{self, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use nbt::{
+    explore::{Explorer, NodeId, Row},
+    region::RegionFile,
+    tag::NBTPath,
+    NBTValue,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+const USAGE: &str = "usage: nbt explore <file|region>";
+const HELP: &str = "↑↓ move  ←→ collapse/expand  enter toggle  / search  n/N next/previous match  \
+                    y copy as SNBT  q quit";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [command, path] = args.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    if command != "explore" {
+        eprintln!("nbt: unknown command {:?}\n{}", command, USAGE);
+        return ExitCode::from(2);
+    }
+    match explore(Path::new(path)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("nbt: {}: {}", path, err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn explore(path: &Path) -> nbt::Result<()> {
+    let is_region = path
+        .extension()
+        .is_some_and(|extension| extension == "mca" || extension == "mcr");
+    let explorer = if is_region {
+        Explorer::from_region(RegionFile::open(path)?)
+    } else {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        Explorer::new(name, nbt::from_file::<NBTValue, _>(path)?)
+    };
+    let title = path.display().to_string();
+    let mut terminal = ratatui::init();
+    let result = App::new(explorer, title).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+struct App {
+    explorer: Explorer,
+    title: String,
+    rows: Vec<Row>,
+    list: ListState,
+    // The nodes the last search found, in order, and the one `n` goes to next.
+    matches: Vec<NodeId>,
+    highlighted: HashSet<NodeId>,
+    next_match: usize,
+    // The search pattern being typed, if any.
+    input: Option<String>,
+    status: String,
+    page: usize,
+}
+
+impl App {
+    fn new(explorer: Explorer, title: String) -> Self {
+        let rows = explorer.rows();
+        Self {
+            explorer,
+            title,
+            rows,
+            list: ListState::default().with_selected(Some(0)),
+            matches: Vec::new(),
+            highlighted: HashSet::new(),
+            next_match: 0,
+            input: None,
+            status: HELP.to_owned(),
+            page: 1,
+        }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> nbt::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(input) = &mut self.input {
+                match key.code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Esc => self.input = None,
+                    KeyCode::Enter => {
+                        let pattern = self.input.take().unwrap_or_default();
+                        self.search(&pattern);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                KeyCode::PageDown => self.move_by(self.page as isize),
+                KeyCode::PageUp => self.move_by(-(self.page as isize)),
+                KeyCode::Home | KeyCode::Char('g') => self.list.select(Some(0)),
+                KeyCode::End | KeyCode::Char('G') => {
+                    self.list.select(Some(self.rows.len().saturating_sub(1)))
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => self.toggle(),
+                KeyCode::Right | KeyCode::Char('l')
+                    if self
+                        .selected()
+                        .is_some_and(|row| row.expandable && !row.expanded) =>
+                {
+                    self.toggle()
+                }
+                KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+                KeyCode::Char('/') => self.input = Some(String::new()),
+                KeyCode::Char('n') => self.jump(1),
+                KeyCode::Char('N') => self.jump(-1),
+                KeyCode::Char('y') => self.copy()?,
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        self.page = main.height.saturating_sub(2).max(1) as usize;
+        let items = self.rows.iter().map(|row| {
+            let marker = match (row.expandable, row.expanded) {
+                (false, _) => "  ",
+                (true, false) => "▸ ",
+                (true, true) => "▾ ",
+            };
+            let line = format!("{}{}{}", "  ".repeat(row.depth), marker, row.label);
+            let style = if self.highlighted.contains(&row.node) {
+                Style::new().yellow()
+            } else {
+                Style::new()
+            };
+            ListItem::new(line).style(style)
+        });
+        let list = List::new(items)
+            .block(Block::bordered().title(self.title.as_str()))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(list, main, &mut self.list);
+        let status_line = match &self.input {
+            Some(input) => format!("/{}", input),
+            None => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn selected(&self) -> Option<&Row> {
+        self.list.selected().and_then(|i| self.rows.get(i))
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.rows.len().saturating_sub(1);
+        let current = self.list.selected().unwrap_or(0);
+        self.list
+            .select(Some(current.saturating_add_signed(delta).min(last)));
+    }
+
+    // Selects the row of `node`, if it is shown.
+    fn select(&mut self, node: &NodeId) {
+        if let Some(i) = self.rows.iter().position(|row| &row.node == node) {
+            self.list.select(Some(i));
+        }
+    }
+
+    // Rebuilds the rows after the tree changed, keeping the selected node selected.
+    fn refresh(&mut self) {
+        let selected = self.selected().map(|row| row.node.clone());
+        self.rows = self.explorer.rows();
+        if let Some(node) = selected {
+            self.select(&node);
+        }
+    }
+
+    fn toggle(&mut self) {
+        let Some(node) = self.selected().map(|row| row.node.clone()) else {
+            return;
+        };
+        if let Err(err) = self.explorer.toggle(&node) {
+            self.status = format!("error: {}", err);
+        }
+        self.refresh();
+    }
+
+    // Collapses the selected node, or moves up to its parent if it isn't expanded.
+    fn collapse(&mut self) {
+        let Some(row) = self.selected().cloned() else {
+            return;
+        };
+        if row.expanded {
+            self.toggle();
+            return;
+        }
+        let (index, path) = &row.node;
+        if let Some((_, parent)) = path.segments().split_last() {
+            let parent = (*index, NBTPath(parent.to_vec()));
+            self.select(&parent);
+        }
+    }
+
+    fn search(&mut self, pattern: &str) {
+        if pattern.is_empty() {
+            return;
+        }
+        self.matches = self.explorer.search(pattern);
+        self.highlighted = self.matches.iter().cloned().collect();
+        self.next_match = 0;
+        self.refresh();
+        self.status = format!("{} matches for {:?}", self.matches.len(), pattern);
+        self.jump(1);
+    }
+
+    // Selects the next match, or the previous one if `step` is negative.
+    fn jump(&mut self, step: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        let current = if step < 0 {
+            (self.next_match as isize - 2).rem_euclid(len)
+        } else {
+            self.next_match as isize
+        };
+        let node = self.matches[current as usize].clone();
+        self.next_match = ((current + 1) % len) as usize;
+        self.select(&node);
+    }
+
+    // Copies the selected node as SNBT through the terminal, with an OSC 52 escape sequence.
+    fn copy(&mut self) -> nbt::Result<()> {
+        let Some(row) = self.selected() else {
+            return Ok(());
+        };
+        let Some(snbt) = self.explorer.snbt(&row.node) else {
+            self.status = "expand the chunk before copying it".to_owned();
+            return Ok(());
+        };
+        let mut stdout = io::stdout();
+        write!(stdout, "\x1b]52;c;{}\x07", base64(snbt.as_bytes()))?;
+        stdout.flush()?;
+        self.status = format!("copied {} bytes of SNBT", snbt.len());
+        Ok(())
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let n = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,124 @@
+//! Block states, as stored in the palettes of chunks and structures.
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+#[test]
+fn test_block_state_text() {
+    let state: BlockState = "minecraft:oak_stairs[facing=east,half=top]"
+        .parse()
+        .unwrap();
+    assert_eq!(state.name, "minecraft:oak_stairs");
+    assert_eq!(state.property("facing"), Some("east"));
+    assert_eq!(
+        state.to_string(),
+        "minecraft:oak_stairs[facing=east,half=top]"
+    );
+
+    let stone: BlockState = "minecraft:stone".parse().unwrap();
+    assert_eq!(stone, BlockState::new("minecraft:stone"));
+    assert_eq!(stone.to_string(), "minecraft:stone");
+    // Whitespace around names and values is ignored.
+    assert_eq!(
+        " minecraft:oak_stairs[ half = top , facing=east ] "
+            .parse::<BlockState>()
+            .unwrap(),
+        state
+    );
+
+    for invalid in [
+        "",
+        "[facing=east]",
+        "a[facing]",
+        "a[facing=east",
+        "a[=b]",
+        "a[b=c,b=d]",
+    ] {
+        assert!(invalid.parse::<BlockState>().is_err(), "{:?}", invalid);
+    }
+
+    let bytes = crate::to_bytes(&state).unwrap();
+    assert_eq!(crate::from_slice::<BlockState>(&bytes).unwrap(), state);
+}
+
+/// A block and the values of its properties, such as
+/// `minecraft:oak_stairs[facing=east,half=top]`. Serializes to the compound with `Name` and
+/// `Properties` used by palettes, and converts to and from the bracketed text form through
+/// [`Display`](fmt::Display) and [`FromStr`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BlockState {
+    /// Namespaced block id.
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(
+        rename = "Properties",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl BlockState {
+    /// The block `name` with no properties set.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+}
+
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if self.properties.is_empty() {
+            return Ok(());
+        }
+        f.write_str("[")?;
+        for (i, (key, value)) in self.properties.iter().enumerate() {
+            if i != 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}={}", key, value)?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl FromStr for BlockState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidBlockState(s.to_owned());
+        let s = s.trim();
+        let (name, properties) = match s.split_once('[') {
+            Some((name, rest)) => (name, Some(rest.strip_suffix(']').ok_or_else(invalid)?)),
+            None => (s, None),
+        };
+        let mut state = BlockState::new(name.trim());
+        if state.name.is_empty() {
+            return Err(invalid());
+        }
+        for property in properties.into_iter().flat_map(|p| p.split(',')) {
+            let (key, value) = property.split_once('=').ok_or_else(invalid)?;
+            let (key, value) = (key.trim(), value.trim());
+            if key.is_empty() || value.is_empty() {
+                return Err(invalid());
+            }
+            if state
+                .properties
+                .insert(key.to_owned(), value.to_owned())
+                .is_some()
+            {
+                return Err(invalid());
+            }
+        }
+        Ok(state)
+    }
+}
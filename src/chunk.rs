@@ -0,0 +1,805 @@
+//! Chunk NBT as stored in region files, in the layouts used since the flattening in 1.13.
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    block::BlockState,
+    error::{self, Error},
+    kind::NBTKind,
+    tag::{CompoundMap, NBTValue},
+};
+
+#[test]
+fn test_chunk_schema_selection() {
+    let stone = || vec!["minecraft:stone".parse::<BlockState>().unwrap()];
+    let legacy = LegacyChunk {
+        data_version: 2730,
+        level: LegacyLevel {
+            x_pos: 3,
+            z_pos: -2,
+            status: "full".into(),
+            last_update: 100,
+            inhabited_time: 20,
+            sections: vec![
+                LegacySection {
+                    y: -1,
+                    palette: Vec::new(),
+                    block_states: Vec::new(),
+                },
+                LegacySection {
+                    y: 0,
+                    palette: stone(),
+                    block_states: vec![0; 256],
+                },
+            ],
+            tile_entities: Vec::new(),
+        },
+    };
+    let chunk = Chunk::from_slice(&crate::to_bytes(&legacy).unwrap()).unwrap();
+    assert_eq!((chunk.x, chunk.z, chunk.status.as_str()), (3, -2, "full"));
+    assert_eq!(chunk.sections.len(), 2);
+    assert_eq!(chunk.sections[1].block_states.palette, stone());
+    assert_eq!(chunk.sections[1].block_states.data.len(), 256);
+
+    // Edits are stored back in the layout the chunk was read in.
+    let mut raw = RawChunk::from_slice(&crate::to_bytes(&legacy).unwrap()).unwrap();
+    let mut edited = raw.chunk().unwrap();
+    edited.sections[1].block_states = PalettedContainer {
+        palette: vec![BlockState::new("minecraft:dirt")],
+        data: Vec::new(),
+    };
+    raw.update(&edited).unwrap();
+    let stored: LegacyChunk = crate::from_slice(&raw.to_bytes().unwrap()).unwrap();
+    assert_eq!(stored.level.sections[0], legacy.level.sections[0]);
+    assert_eq!(
+        stored.level.sections[1].palette,
+        [BlockState::new("minecraft:dirt")]
+    );
+    assert_eq!(stored.level.sections[1].block_states, [0; 256]);
+
+    let modern = ModernChunk {
+        data_version: 3465,
+        x_pos: 3,
+        y_pos: -4,
+        z_pos: -2,
+        status: "minecraft:full".into(),
+        last_update: 100,
+        inhabited_time: 20,
+        sections: vec![ModernSection {
+            y: 0,
+            block_states: PalettedContainer {
+                palette: stone(),
+                data: Vec::new(),
+            },
+            biomes: Some(PalettedContainer {
+                palette: vec!["minecraft:plains".into()],
+                data: Vec::new(),
+            }),
+        }],
+        block_entities: Vec::new(),
+    };
+    let chunk = Chunk::from_slice(&crate::to_bytes(&modern).unwrap()).unwrap();
+    assert_eq!((chunk.data_version, chunk.x, chunk.z), (3465, 3, -2));
+    assert_eq!(chunk.sections[0].block_states.palette, stone());
+
+    let old = LegacyChunk {
+        data_version: 1343,
+        ..legacy
+    };
+    assert!(matches!(
+        Chunk::from_slice(&crate::to_bytes(&old).unwrap()),
+        Err(Error::UnsupportedDataVersion(1343))
+    ));
+}
+
+#[test]
+fn test_raw_chunk() {
+    use crate::tag::CompoundBuilder;
+
+    let modern = CompoundBuilder::new()
+        .int("DataVersion", 3465)
+        .int("xPos", 0)
+        .int("yPos", -4)
+        .int("zPos", 0)
+        .string("Status", "minecraft:full")
+        .long("LastUpdate", 0)
+        .long("InhabitedTime", 500)
+        .boolean("isLightOn", true)
+        .string("Unknown", "kept")
+        .list(
+            "sections",
+            [CompoundBuilder::new()
+                .byte("Y", 0)
+                .byte_array("BlockLight", vec![15; 2048])
+                .byte_array("SkyLight", vec![15; 2048])
+                .build()],
+        )
+        .build();
+    let mut chunk = RawChunk::from_slice(&crate::to_bytes(&modern).unwrap()).unwrap();
+    assert_eq!(chunk.inhabited_time(), Some(500));
+    assert_eq!(chunk.status(), Some("minecraft:full"));
+    assert!(chunk.is_light_on());
+    chunk.set_inhabited_time(0);
+    chunk.set_status("minecraft:features");
+    chunk.set_light_on(false);
+    let bytes = chunk.to_bytes().unwrap();
+    let read = Chunk::from_slice(&bytes).unwrap();
+    assert_eq!(
+        (read.inhabited_time, read.status.as_str()),
+        (0, "minecraft:features")
+    );
+    let NBTValue::Compound(written) = crate::from_slice(&bytes).unwrap() else {
+        panic!("chunk is not a compound");
+    };
+    assert_eq!(written["isLightOn"], NBTValue::Byte(0));
+    assert_eq!(written["Unknown"], NBTValue::String("kept".into()));
+
+    chunk.set_light_on(true);
+    chunk.strip_light();
+    assert!(!chunk.is_light_on());
+    let NBTValue::List(sections) = &chunk.root()["sections"] else {
+        panic!("sections is not a list");
+    };
+    let NBTValue::Compound(section) = &sections[0] else {
+        panic!("section is not a compound");
+    };
+    assert_eq!(section.keys().collect::<Vec<_>>(), ["Y"]);
+
+    let legacy = CompoundBuilder::new()
+        .int("DataVersion", 2730)
+        .compound("Level", CompoundBuilder::new().string("Status", "full"))
+        .build();
+    let mut chunk = RawChunk::from_slice(&crate::to_bytes(&legacy).unwrap()).unwrap();
+    assert_eq!(chunk.inhabited_time(), None);
+    assert!(!chunk.is_light_on());
+    chunk.set_inhabited_time(7);
+    let NBTValue::Compound(written) = crate::from_slice(&chunk.to_bytes().unwrap()).unwrap() else {
+        panic!("chunk is not a compound");
+    };
+    let NBTValue::Compound(level) = &written["Level"] else {
+        panic!("level is not a compound");
+    };
+    assert_eq!(level["InhabitedTime"], NBTValue::Long(7));
+}
+
+#[test]
+fn test_paletted_container() {
+    let values: Vec<u32> = (0..4096).map(|i| i % 20).collect();
+    let aligned = PalettedContainer::pack(&values, 4, false);
+    assert_eq!(aligned.palette.len(), 20);
+    // 5 bits per entry, 12 entries to a long.
+    assert_eq!(aligned.data.len(), 342);
+    assert_eq!(aligned.unpack(4096).unwrap(), values);
+    let spanning = PalettedContainer::pack(&values, 4, true);
+    assert_eq!(spanning.data.len(), 320);
+    assert_eq!(spanning.unpack(4096).unwrap(), values);
+
+    let single = PalettedContainer::pack(&[7u32; 64], 1, false);
+    assert!(single.data.is_empty());
+    assert_eq!(single.unpack(64).unwrap(), [7; 64]);
+    let broken = PalettedContainer {
+        palette: vec![1u32, 2],
+        data: vec![0; 3],
+    };
+    assert!(matches!(
+        broken.unpack(4096),
+        Err(Error::MalformedPalettedData {
+            palette: 2,
+            longs: 3
+        })
+    ));
+}
+
+#[test]
+fn test_optimize_palette() {
+    let state = |name: &str| BlockState::new(name);
+    // Wasteful 8 bits per entry for three states, one of which is unused.
+    let mut data = vec![0i64; 512];
+    data[0] = 2;
+    let mut section = Section {
+        y: 0,
+        block_states: PalettedContainer {
+            palette: vec![
+                state("minecraft:air"),
+                state("minecraft:dirt"),
+                state("minecraft:stone"),
+            ],
+            data,
+        },
+    };
+    let before = section.block_states.unpack(4096).unwrap();
+    section.optimize_palette().unwrap();
+    assert_eq!(
+        section.block_states.palette,
+        [state("minecraft:stone"), state("minecraft:air")]
+    );
+    assert_eq!(section.block_states.data.len(), 256);
+    assert_eq!(section.block_states.unpack(4096).unwrap(), before);
+
+    let mut chunk = Chunk {
+        data_version: 3465,
+        x: 0,
+        z: 0,
+        status: "minecraft:full".into(),
+        last_update: 0,
+        inhabited_time: 0,
+        sections: vec![Section {
+            y: 1,
+            block_states: PalettedContainer {
+                palette: vec![state("minecraft:air"), state("minecraft:dirt")],
+                data: vec![0; 256],
+            },
+        }],
+        block_entities: Vec::new(),
+    };
+    chunk.optimize().unwrap();
+    assert_eq!(
+        chunk.sections[0].block_states.palette,
+        [state("minecraft:air")]
+    );
+    assert!(chunk.sections[0].block_states.data.is_empty());
+}
+
+/// First `DataVersion` with flattened block states (17w47a, during 1.13).
+pub const FLATTENING: i32 = 1451;
+/// First `DataVersion` without the `Level` compound around the chunk's data (21w43a, during 1.18).
+pub const NO_LEVEL_WRAPPER: i32 = 2844;
+
+/// First `DataVersion` that packs palette indices without letting them span two longs (20w17a,
+/// during 1.16).
+pub const ALIGNED_PACKING: i32 = 2527;
+
+/// A chunk in a shape common to every layout since 1.13. Build it with [`Chunk::from_slice`],
+/// which picks the layout from the chunk's `DataVersion`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub data_version: i32,
+    /// Chunk coordinates, that is block coordinates divided by 16.
+    pub x: i32,
+    pub z: i32,
+    /// Generation stage. Versions before 1.18 store it without the `minecraft:` namespace.
+    pub status: String,
+    /// Game time of the last save, in ticks.
+    pub last_update: i64,
+    /// Ticks players have spent in the chunk, summed over all players.
+    pub inhabited_time: i64,
+    pub sections: Vec<Section>,
+    pub block_entities: Vec<NBTValue>,
+}
+
+/// 16 blocks high slice of a chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Section coordinate, that is block y divided by 16.
+    pub y: i8,
+    pub block_states: PalettedContainer<BlockState>,
+}
+
+/// Values stored as indices into a palette, packed into longs. `data` is empty when the palette
+/// has a single entry, which then fills the whole container.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PalettedContainer<T> {
+    pub palette: Vec<T>,
+    #[serde(
+        default,
+        serialize_with = "crate::long_array",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub data: Vec<i64>,
+}
+
+impl<T: Clone + PartialEq> PalettedContainer<T> {
+    /// Packs `values` with a palette of the distinct values in the order they first appear, using
+    /// as few bits per entry as the palette allows but at least `min_bits`: 4 for block states
+    /// and 1 for biomes. `spanning` packs entries across long boundaries, as before
+    /// [`ALIGNED_PACKING`]. A container with a single value has no data.
+    pub fn pack(values: &[T], min_bits: u32, spanning: bool) -> Self {
+        let mut palette: Vec<T> = Vec::new();
+        let indices: Vec<usize> = values
+            .iter()
+            .map(
+                |value| match palette.iter().position(|known| known == value) {
+                    Some(index) => index,
+                    None => {
+                        palette.push(value.clone());
+                        palette.len() - 1
+                    }
+                },
+            )
+            .collect();
+        if palette.len() <= 1 {
+            return Self {
+                palette,
+                data: Vec::new(),
+            };
+        }
+        let bits = bits_for(palette.len()).max(min_bits) as usize;
+        let mut data = vec![0i64; packed_len(values.len(), bits, spanning)];
+        for (i, index) in indices.into_iter().enumerate() {
+            let (long, shift) = entry_position(i, bits, spanning);
+            data[long] |= (index as u64 as i64) << shift;
+            if spanning && shift + bits > 64 {
+                data[long + 1] |= (index >> (64 - shift)) as i64;
+            }
+        }
+        Self { palette, data }
+    }
+
+    /// The `len` values of the container in index order, which for the 4096 block states of a
+    /// section is `(y * 16 + z) * 16 + x`. The number of bits per entry and whether entries span
+    /// longs are told from the length of the data.
+    pub fn unpack(&self, len: usize) -> error::Result<Vec<T>> {
+        let malformed = || Error::MalformedPalettedData {
+            palette: self.palette.len(),
+            longs: self.data.len(),
+        };
+        if self.data.is_empty() {
+            return match self.palette.as_slice() {
+                [value] => Ok(vec![value.clone(); len]),
+                _ => Err(malformed()),
+            };
+        }
+        let (bits, spanning) = self.layout(len).ok_or_else(malformed)?;
+        let mask = (1u64 << bits) - 1;
+        (0..len)
+            .map(|i| {
+                let (long, shift) = entry_position(i, bits, spanning);
+                let mut index = self.data[long] as u64 >> shift;
+                if spanning && shift + bits > 64 {
+                    index |= (self.data[long + 1] as u64) << (64 - shift);
+                }
+                self.palette
+                    .get((index & mask) as usize)
+                    .cloned()
+                    .ok_or_else(malformed)
+            })
+            .collect()
+    }
+
+    /// Drops palette entries no value refers to and packs the data again with as few bits per
+    /// entry as the remaining palette allows, but at least `min_bits`. Entries keep spanning longs
+    /// or not as they did.
+    pub fn optimize(&mut self, len: usize, min_bits: u32) -> error::Result<()> {
+        let spanning = self.layout(len).is_some_and(|(_, spanning)| spanning);
+        *self = Self::pack(&self.unpack(len)?, min_bits, spanning);
+        Ok(())
+    }
+
+    // Bits per entry and whether entries span longs, for data holding `len` entries.
+    fn layout(&self, len: usize) -> Option<(usize, bool)> {
+        (bits_for(self.palette.len()).max(1)..=32)
+            .flat_map(|bits| [(bits as usize, false), (bits as usize, true)])
+            .find(|&(bits, spanning)| packed_len(len, bits, spanning) == self.data.len())
+    }
+}
+
+impl Section {
+    /// Removes block states the section doesn't use from its palette and packs the rest at the
+    /// fewest bits per entry, see [`PalettedContainer::optimize`].
+    pub fn optimize_palette(&mut self) -> error::Result<()> {
+        self.block_states.optimize(4096, 4)
+    }
+}
+
+// Bits needed to tell `palette` entries apart.
+fn bits_for(palette: usize) -> u32 {
+    usize::BITS - palette.saturating_sub(1).leading_zeros()
+}
+
+fn packed_len(len: usize, bits: usize, spanning: bool) -> usize {
+    if spanning {
+        (len * bits).div_ceil(64)
+    } else {
+        len.div_ceil(64 / bits)
+    }
+}
+
+// The long holding the start of entry `i`, and the bit it starts at.
+fn entry_position(i: usize, bits: usize, spanning: bool) -> (usize, usize) {
+    if spanning {
+        (i * bits / 64, i * bits % 64)
+    } else {
+        let per_long = 64 / bits;
+        (i / per_long, i % per_long * bits)
+    }
+}
+
+impl Chunk {
+    /// Reads the uncompressed NBT of a chunk, such as the bytes returned by
+    /// [`RegionFile::read_chunk`](crate::region::RegionFile::read_chunk), choosing the layout
+    /// from its `DataVersion`. Chunks from before the flattening are reported as
+    /// [`Error::UnsupportedDataVersion`].
+    pub fn from_slice(bytes: &[u8]) -> error::Result<Self> {
+        #[derive(Deserialize)]
+        struct Version {
+            #[serde(rename = "DataVersion", default)]
+            data_version: i32,
+        }
+
+        match crate::from_slice::<Version>(bytes)?.data_version {
+            version if version < FLATTENING => Err(Error::UnsupportedDataVersion(version)),
+            version if version < NO_LEVEL_WRAPPER => {
+                Ok(crate::from_slice::<LegacyChunk>(bytes)?.into())
+            }
+            _ => Ok(crate::from_slice::<ModernChunk>(bytes)?.into()),
+        }
+    }
+
+    /// Optimizes the palette of every section, see [`Section::optimize_palette`].
+    pub fn optimize(&mut self) -> error::Result<()> {
+        self.sections
+            .iter_mut()
+            .try_for_each(Section::optimize_palette)
+    }
+}
+
+/// A chunk as stored from 1.13 up to 1.17, with its data in a `Level` compound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacyChunk {
+    #[serde(rename = "DataVersion")]
+    pub data_version: i32,
+    #[serde(rename = "Level")]
+    pub level: LegacyLevel,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacyLevel {
+    #[serde(rename = "xPos")]
+    pub x_pos: i32,
+    #[serde(rename = "zPos")]
+    pub z_pos: i32,
+    pub status: String,
+    pub last_update: i64,
+    pub inhabited_time: i64,
+    #[serde(default)]
+    pub sections: Vec<LegacySection>,
+    #[serde(default)]
+    pub tile_entities: Vec<NBTValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacySection {
+    #[serde(rename = "Y")]
+    pub y: i8,
+    /// Missing from sections that only hold light.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palette: Vec<BlockState>,
+    #[serde(
+        default,
+        serialize_with = "crate::long_array",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub block_states: Vec<i64>,
+}
+
+/// A chunk as stored since 1.18.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModernChunk {
+    #[serde(rename = "DataVersion")]
+    pub data_version: i32,
+    #[serde(rename = "xPos")]
+    pub x_pos: i32,
+    /// Lowest section of the chunk.
+    #[serde(rename = "yPos")]
+    pub y_pos: i32,
+    #[serde(rename = "zPos")]
+    pub z_pos: i32,
+    #[serde(rename = "Status")]
+    pub status: String,
+    #[serde(rename = "LastUpdate")]
+    pub last_update: i64,
+    #[serde(rename = "InhabitedTime")]
+    pub inhabited_time: i64,
+    #[serde(default)]
+    pub sections: Vec<ModernSection>,
+    #[serde(default)]
+    pub block_entities: Vec<NBTValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModernSection {
+    #[serde(rename = "Y")]
+    pub y: i8,
+    /// Missing from sections that only hold light, which are treated as air.
+    #[serde(default = "air")]
+    pub block_states: PalettedContainer<BlockState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub biomes: Option<PalettedContainer<String>>,
+}
+
+fn air() -> PalettedContainer<BlockState> {
+    PalettedContainer {
+        palette: vec![BlockState::new("minecraft:air")],
+        data: Vec::new(),
+    }
+}
+
+impl From<LegacyChunk> for Chunk {
+    fn from(chunk: LegacyChunk) -> Self {
+        let level = chunk.level;
+        Chunk {
+            data_version: chunk.data_version,
+            x: level.x_pos,
+            z: level.z_pos,
+            status: level.status,
+            last_update: level.last_update,
+            inhabited_time: level.inhabited_time,
+            sections: level
+                .sections
+                .into_iter()
+                .map(|section| Section {
+                    y: section.y,
+                    block_states: if section.palette.is_empty() {
+                        air()
+                    } else {
+                        PalettedContainer {
+                            palette: section.palette,
+                            data: section.block_states,
+                        }
+                    },
+                })
+                .collect(),
+            block_entities: level.tile_entities,
+        }
+    }
+}
+
+impl From<ModernChunk> for Chunk {
+    fn from(chunk: ModernChunk) -> Self {
+        Chunk {
+            data_version: chunk.data_version,
+            x: chunk.x_pos,
+            z: chunk.z_pos,
+            status: chunk.status,
+            last_update: chunk.last_update,
+            inhabited_time: chunk.inhabited_time,
+            sections: chunk
+                .sections
+                .into_iter()
+                .map(|section| Section {
+                    y: section.y,
+                    block_states: section.block_states,
+                })
+                .collect(),
+            block_entities: chunk.block_entities,
+        }
+    }
+}
+
+/// The whole NBT of a chunk, for changing a few fields while keeping everything else as it was.
+/// The accessors find the fields at the root of chunks since 1.18 and in the `Level` compound
+/// before that.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawChunk {
+    root: CompoundMap,
+}
+
+impl RawChunk {
+    /// Reads the uncompressed NBT of a chunk, such as the bytes returned by
+    /// [`RegionFile::read_chunk`](crate::region::RegionFile::read_chunk).
+    pub fn from_slice(bytes: &[u8]) -> error::Result<Self> {
+        match crate::from_slice(bytes)? {
+            NBTValue::Compound(root) => Ok(Self { root }),
+            _ => Err(Error::ExpectedRootCompound),
+        }
+    }
+
+    /// Writes the chunk back, ready for
+    /// [`RegionFile::write_chunk`](crate::region::RegionFile::write_chunk).
+    pub fn to_bytes(&self) -> error::Result<Vec<u8>> {
+        crate::to_bytes(&self.root)
+    }
+
+    pub fn root(&self) -> &CompoundMap {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut CompoundMap {
+        &mut self.root
+    }
+
+    pub fn data_version(&self) -> Option<i32> {
+        match self.root.get("DataVersion") {
+            Some(NBTValue::Int(version)) => Some(*version),
+            _ => None,
+        }
+    }
+
+    /// Ticks players have spent in the chunk, which the game uses to scale local difficulty.
+    pub fn inhabited_time(&self) -> Option<i64> {
+        match self.level().get("InhabitedTime") {
+            Some(NBTValue::Long(ticks)) => Some(*ticks),
+            _ => None,
+        }
+    }
+
+    pub fn set_inhabited_time(&mut self, ticks: i64) {
+        self.level_mut()
+            .insert("InhabitedTime".into(), NBTValue::Long(ticks));
+    }
+
+    /// Generation stage, such as `minecraft:full`.
+    pub fn status(&self) -> Option<&str> {
+        match self.level().get("Status") {
+            Some(NBTValue::String(status)) => Some(status),
+            _ => None,
+        }
+    }
+
+    /// Sets the generation stage. Setting an earlier stage than `full` makes the game generate
+    /// the chunk again from that stage on.
+    pub fn set_status<S: Into<String>>(&mut self, status: S) {
+        self.level_mut()
+            .insert("Status".into(), NBTValue::String(status.into()));
+    }
+
+    /// Whether the light stored with the chunk is up to date. A missing flag reads as `false`.
+    pub fn is_light_on(&self) -> bool {
+        matches!(self.level().get("isLightOn"), Some(NBTValue::Byte(1..)))
+    }
+
+    /// Clearing the flag makes the game compute the chunk's light again when it loads.
+    pub fn set_light_on(&mut self, on: bool) {
+        self.level_mut()
+            .insert("isLightOn".into(), NBTValue::Byte(on as i8));
+    }
+
+    /// Removes the `BlockLight` and `SkyLight` of every section and clears `isLightOn`, so that
+    /// the game lights the chunk from scratch when it loads. This is all that is needed after
+    /// editing blocks, and makes the chunk smaller in the meantime.
+    pub fn strip_light(&mut self) {
+        let level = self.level_mut();
+        for key in ["sections", "Sections"] {
+            if let Some(NBTValue::List(sections)) = level.get_mut(key) {
+                for section in sections {
+                    if let NBTValue::Compound(section) = section {
+                        section.remove("BlockLight");
+                        section.remove("SkyLight");
+                    }
+                }
+            }
+        }
+        self.set_light_on(false);
+    }
+
+    /// Reads the fields that [`Chunk`] models, to edit them and store them back with
+    /// [`update`](Self::update).
+    pub fn chunk(&self) -> error::Result<Chunk> {
+        Chunk::from_slice(&self.to_bytes()?)
+    }
+
+    /// Stores the generation stage, times, block states and block entities of `chunk` in the
+    /// layout this chunk is saved in, keeping everything else as it was: biomes, light,
+    /// heightmaps and fields `Chunk` doesn't know about. Sections are matched by their `Y`, and
+    /// those `chunk` adds are inserted in order. The position and `DataVersion` of `chunk` are
+    /// not written.
+    pub fn update(&mut self, chunk: &Chunk) -> error::Result<()> {
+        let legacy = self.has_level_wrapper();
+        let level = self.level_mut();
+        level.insert("Status".into(), NBTValue::String(chunk.status.clone()));
+        level.insert("LastUpdate".into(), NBTValue::Long(chunk.last_update));
+        level.insert("InhabitedTime".into(), NBTValue::Long(chunk.inhabited_time));
+        let (sections_key, block_entities_key) = if legacy {
+            ("Sections", "TileEntities")
+        } else {
+            ("sections", "block_entities")
+        };
+        level.insert(
+            block_entities_key.into(),
+            NBTValue::List(chunk.block_entities.clone()),
+        );
+        let sections = match level
+            .entry(sections_key.into())
+            .or_insert_with(|| NBTValue::List(Vec::new()))
+        {
+            NBTValue::List(sections) => sections,
+            other => return Err(Error::MismatchedTag(other.kind(), NBTKind::List)),
+        };
+
+        for section in &chunk.sections {
+            let existing = sections
+                .iter()
+                .position(|raw| section_y(raw) == Some(section.y));
+            let raw = match existing {
+                Some(at) => &mut sections[at],
+                None => {
+                    let at = sections
+                        .iter()
+                        .take_while(|raw| section_y(raw).is_some_and(|y| y < section.y))
+                        .count();
+                    let mut raw = CompoundMap::default();
+                    raw.insert("Y".into(), NBTValue::Byte(section.y));
+                    sections.insert(at, NBTValue::Compound(raw));
+                    &mut sections[at]
+                }
+            };
+            let NBTValue::Compound(raw) = raw else {
+                continue;
+            };
+            // Sections that only hold light read as air, and stay without block states.
+            let stored = if legacy { "Palette" } else { "block_states" };
+            if !raw.contains_key(stored) && section.block_states == air() {
+                continue;
+            }
+
+            let states = &section.block_states;
+            let palette = NBTValue::List(states.palette.iter().map(block_state_value).collect());
+            if legacy {
+                // The layout before 1.18 keeps the data even for a single block state.
+                let data = if states.data.is_empty() {
+                    vec![0; packed_len(4096, 4, false)]
+                } else {
+                    states.data.clone()
+                };
+                raw.insert("Palette".into(), palette);
+                raw.insert("BlockStates".into(), NBTValue::LongArray(data));
+            } else {
+                let mut block_states = CompoundMap::default();
+                block_states.insert("palette".into(), palette);
+                if !states.data.is_empty() {
+                    block_states.insert("data".into(), NBTValue::LongArray(states.data.clone()));
+                }
+                raw.insert("block_states".into(), NBTValue::Compound(block_states));
+            }
+        }
+        Ok(())
+    }
+
+    /// Optimizes the palette of every section, see [`Chunk::optimize`], and stores the sections
+    /// back with [`update`](Self::update).
+    pub fn optimize(&mut self) -> error::Result<()> {
+        let mut chunk = self.chunk()?;
+        chunk.optimize()?;
+        self.update(&chunk)
+    }
+
+    fn has_level_wrapper(&self) -> bool {
+        self.data_version()
+            .is_some_and(|version| version < NO_LEVEL_WRAPPER)
+            && matches!(self.root.get("Level"), Some(NBTValue::Compound(_)))
+    }
+
+    // The compound holding the chunk's fields.
+    fn level(&self) -> &CompoundMap {
+        match self.root.get("Level") {
+            Some(NBTValue::Compound(level)) if self.has_level_wrapper() => level,
+            _ => &self.root,
+        }
+    }
+
+    fn level_mut(&mut self) -> &mut CompoundMap {
+        if !self.has_level_wrapper() {
+            return &mut self.root;
+        }
+        match self.root.get_mut("Level") {
+            Some(NBTValue::Compound(level)) => level,
+            _ => unreachable!("the chunk has a level compound"),
+        }
+    }
+}
+
+// The `Y` of a raw section.
+fn section_y(section: &NBTValue) -> Option<i8> {
+    match section {
+        NBTValue::Compound(section) => match section.get("Y") {
+            Some(NBTValue::Byte(y)) => Some(*y),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn block_state_value(state: &BlockState) -> NBTValue {
+    let mut compound = CompoundMap::default();
+    compound.insert("Name".into(), NBTValue::String(state.name.clone()));
+    if !state.properties.is_empty() {
+        let properties = state
+            .properties
+            .iter()
+            .map(|(key, value)| (key.clone(), NBTValue::String(value.clone())))
+            .collect();
+        compound.insert("Properties".into(), NBTValue::Compound(properties));
+    }
+    NBTValue::Compound(compound)
+}
@@ -1,74 +1,1429 @@
-use std::io::{self, Cursor};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt;
 
+#[cfg(feature = "std")]
+use crate::parser::BufIoRead;
 use crate::{
     error::{self, Error},
+    io,
     kind::NBTKind,
-    parser::Parser,
+    parser::{Entry, Input, IoRead, Limited, Parser, SliceRead, Str},
+    ser::{BYTE_ARRAY_NAME, INT_ARRAY_NAME, LONG_ARRAY_NAME},
+    tag::{NBTPath, NBTValue, PathSegment, VALUE_NAME},
+    writer::Writer,
 };
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+#[cfg(feature = "compression")]
 use flate2::read::{GzDecoder, ZlibDecoder};
 use serde::{
-    de::{self, MapAccess, SeqAccess},
+    de::{self, IntoDeserializer, MapAccess, SeqAccess},
     forward_to_deserialize_any, Deserialize,
 };
 
+#[test]
+fn test_from_slice_borrows_input() {
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        ip: String,
+        name: String,
+    }
+
+    let server = Server {
+        ip: "loucaskreger.com".into(),
+        name: "Minecraft Server".into(),
+    };
+    let bytes = crate::to_bytes(&server).unwrap();
+    let decoded: Server = from_slice(&bytes).unwrap();
+    assert_eq!(decoded, server);
+    // The buffer is still usable after parsing.
+    assert_eq!(bytes[0], NBTKind::Compound.header_byte());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_buf_reader_strings_across_buffer_boundary() {
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        ip: String,
+        name: String,
+    }
+
+    let server = Server {
+        ip: "loucaskreger.com".into(),
+        name: "Minecraft Server".into(),
+    };
+    let bytes = crate::to_bytes(&server).unwrap();
+    // A tiny buffer forces strings to straddle refills and take the copying path.
+    let reader = std::io::BufReader::with_capacity(3, bytes.as_slice());
+    let decoded: Server = from_buf_reader(reader).unwrap();
+    assert_eq!(decoded, server);
+
+    let decoded: BTreeMap<String, String> = from_buf_reader(bytes.as_slice()).unwrap();
+    assert_eq!(decoded["name"], "Minecraft Server");
+}
+
+#[test]
+fn test_from_reader_limited() {
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        name: String,
+    }
+
+    let server = Server {
+        name: "Minecraft Server".into(),
+    };
+    let bytes = crate::to_bytes(&server).unwrap();
+
+    let decoded: Server = from_reader_limited(bytes.as_slice(), bytes.len() as u64).unwrap();
+    assert_eq!(decoded, server);
+
+    let result: error::Result<Server> =
+        from_reader_limited(bytes.as_slice(), bytes.len() as u64 - 1);
+    assert!(matches!(result, Err(Error::SizeLimitExceeded(_))));
+}
+
+#[test]
+fn test_skip_unknown_fields_of_every_kind() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Nested {
+        name: String,
+        values: Vec<i64>,
+    }
+
+    #[derive(Serialize)]
+    struct Everything {
+        byte: i8,
+        short: i16,
+        int: i32,
+        long: i64,
+        float: f32,
+        double: f64,
+        #[serde(serialize_with = "crate::byte_array")]
+        byte_array: Vec<i8>,
+        string: String,
+        list: Vec<Nested>,
+        nested_list: Vec<Vec<i16>>,
+        empty_list: Vec<i32>,
+        compound: Nested,
+        #[serde(serialize_with = "crate::int_array")]
+        int_array: Vec<i32>,
+        #[serde(serialize_with = "crate::long_array")]
+        long_array: Vec<i64>,
+        known: i32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Known {
+        known: i32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Strict {
+        known: i32,
+    }
+
+    let nested = || Nested {
+        name: "minecraft:chest".into(),
+        values: vec![1, 2, 3],
+    };
+    let bytes = crate::to_bytes(&Everything {
+        byte: 1,
+        short: 2,
+        int: 3,
+        long: 4,
+        float: 5.0,
+        double: 6.0,
+        byte_array: vec![7, 8],
+        string: "nine".into(),
+        list: vec![nested(), nested()],
+        nested_list: vec![vec![10, 11], vec![12]],
+        empty_list: vec![],
+        compound: nested(),
+        int_array: vec![13, 14],
+        long_array: vec![15, 16],
+        known: 42,
+    })
+    .unwrap();
+
+    let known: Known = from_slice(&bytes).unwrap();
+    assert_eq!(known.known, 42);
+
+    let err = from_slice::<Strict>(&bytes).unwrap_err();
+    assert!(err.to_string().contains("unknown field `byte`"), "{}", err);
+}
+
+#[test]
+fn test_duplicate_key_policy() {
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Duplicated {
+        #[serde(rename = "a")]
+        first: i32,
+        #[serde(rename = "a")]
+        second: i32,
+    }
+
+    let bytes = crate::to_bytes(&Duplicated {
+        first: 1,
+        second: 2,
+    })
+    .unwrap();
+    let with_policy = |duplicate_keys| DeserializerOptions {
+        duplicate_keys,
+        ..Default::default()
+    };
+
+    let map: BTreeMap<String, i32> = from_slice(&bytes).unwrap();
+    assert_eq!(map["a"], 2);
+
+    let map: BTreeMap<String, i32> =
+        from_slice_with_options(&bytes, with_policy(DuplicateKeyPolicy::FirstWins)).unwrap();
+    assert_eq!(map["a"], 1);
+
+    let result: error::Result<BTreeMap<String, i32>> =
+        from_slice_with_options(&bytes, with_policy(DuplicateKeyPolicy::Error));
+    assert!(matches!(result, Err(Error::DuplicateKey(key)) if key == "a"));
+}
+
+#[test]
+fn test_malformed_list() {
+    use serde::Serialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct Holder<T> {
+        list: Vec<T>,
+    }
+
+    let mut bytes = crate::to_bytes(&Holder { list: vec![-1, -1] }).unwrap();
+    // Root header (3 bytes), then the list's tag id and name, then its element kind at offset 10.
+    bytes[10] = NBTKind::Compound.header_byte();
+    let result: error::Result<Holder<de::IgnoredAny>> = from_slice(&bytes);
+    assert!(matches!(
+        result,
+        Err(Error::MalformedList {
+            declared: NBTKind::Compound,
+            offset: 10
+        })
+    ));
+
+    let bytes = crate::to_bytes(&Holder { list: vec![1, 2] }).unwrap();
+    let truncated = &bytes[..bytes.len() - 3];
+    let result: error::Result<Holder<i32>> = from_reader(truncated);
+    assert!(matches!(result, Err(Error::MalformedList { .. })));
+    // In memory the length is checked against what is left before any element is read.
+    let result: error::Result<Holder<i32>> = from_slice(truncated);
+    assert!(matches!(result, Err(Error::InvalidLength(2))));
+}
+
+#[test]
+fn test_end_typed_list_lengths() {
+    #[derive(Debug, Deserialize)]
+    struct Holder {
+        list: Vec<i32>,
+    }
+
+    let list_of_end = |length: i32| {
+        let mut bytes = vec![10, 0, 0, 9, 0, 4];
+        bytes.extend_from_slice(b"list");
+        bytes.push(NBTKind::End.header_byte());
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes.push(NBTKind::End.header_byte());
+        bytes
+    };
+
+    for length in [0, -1, i32::MIN] {
+        let holder: Holder = from_slice(&list_of_end(length)).unwrap();
+        assert!(holder.list.is_empty());
+    }
+    let result: error::Result<Holder> = from_slice(&list_of_end(3));
+    assert!(matches!(result, Err(Error::NonEmptyEndList(3))));
+}
+
+#[test]
+fn test_numeric_tags() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Narrow {
+        count: i8,
+        total: i64,
+        ratio: f32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Wide {
+        count: i32,
+        total: i32,
+        ratio: f64,
+    }
+
+    let bytes = crate::to_bytes(&Narrow {
+        count: -3,
+        total: 70_000,
+        ratio: 0.5,
+    })
+    .unwrap();
+
+    // Numbers widen, and narrow when the value fits.
+    let wide: Wide = from_slice(&bytes).unwrap();
+    assert_eq!(
+        wide,
+        Wide {
+            count: -3,
+            total: 70_000,
+            ratio: 0.5
+        }
+    );
+
+    let options = DeserializerOptions {
+        strict_numbers: true,
+        ..Default::default()
+    };
+    let result: error::Result<Wide> = from_slice_with_options(&bytes, options);
+    assert!(matches!(
+        result,
+        Err(Error::MismatchedTag(NBTKind::Byte, NBTKind::Int))
+    ));
+}
+
+#[test]
+fn test_boolean_policy() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Stored<T> {
+        flag: T,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Flag {
+        flag: bool,
+    }
+
+    let read = |bytes: &[u8], booleans| {
+        let options = DeserializerOptions {
+            booleans,
+            ..Default::default()
+        };
+        from_slice_with_options::<Flag>(bytes, options).map(|flag| flag.flag)
+    };
+    let two = crate::to_bytes(&Stored { flag: 2i8 }).unwrap();
+    let string = crate::to_bytes(&Stored { flag: "false" }).unwrap();
+    assert!(matches!(
+        read(&two, BooleanPolicy::Strict),
+        Err(Error::ExpectedBooleanByte(2))
+    ));
+    assert!(read(&two, BooleanPolicy::NonZero).unwrap());
+    assert!(read(&string, BooleanPolicy::NonZero).is_err());
+    assert!(!read(&string, BooleanPolicy::Lenient).unwrap());
+    let other = crate::to_bytes(&Stored { flag: "yes" }).unwrap();
+    assert!(read(&other, BooleanPolicy::Lenient).is_err());
+
+    let options = crate::ser::SerializerOptions {
+        booleans_as_strings: true,
+        ..Default::default()
+    };
+    let bytes = crate::ser::to_bytes_with_options(&Stored { flag: true }, options).unwrap();
+    assert_eq!(bytes, crate::to_bytes(&Stored { flag: "true" }).unwrap());
+    assert!(read(&bytes, BooleanPolicy::Lenient).unwrap());
+}
+
+#[test]
+fn test_report() {
+    use crate::CompoundBuilder;
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        id: String,
+        #[serde(default)]
+        count: i8,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Player {
+        #[serde(default)]
+        health: f32,
+        inventory: Vec<Item>,
+    }
+
+    let item = |id: &str| CompoundBuilder::new().string("id", id).build();
+    let player = CompoundBuilder::new()
+        .list("inventory", vec![item("stone"), item("dirt")])
+        .int("xp", 3)
+        .build();
+    let bytes = crate::to_bytes(&player).unwrap();
+
+    let (decoded, report) = from_slice_with_report::<Player>(&bytes).unwrap();
+    assert_eq!(decoded.inventory[1].id, "dirt");
+    assert_eq!((decoded.health, decoded.inventory[1].count), (0.0, 0));
+    assert!(!report.is_exact());
+    let paths = |paths: &[NBTPath]| paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        paths(&report.defaulted),
+        ["inventory[0].count", "inventory[1].count", "health"]
+    );
+    assert_eq!(paths(&report.ignored), ["xp"]);
+}
+
+#[test]
+fn test_length_policy() {
+    use crate::NBTValue;
+
+    // A root compound holding the LongArray "a" with the given length and no payload.
+    let document = |length: i32| {
+        let mut bytes = vec![10, 0, 0, 12, 0, 1, b'a'];
+        bytes.extend(length.to_be_bytes());
+        bytes.push(0);
+        bytes
+    };
+    let strict = DeserializerOptions {
+        lengths: LengthPolicy::Strict,
+        ..Default::default()
+    };
+
+    let value: NBTValue = from_slice(&document(-1)).unwrap();
+    assert_eq!(
+        value,
+        crate::CompoundBuilder::new().long_array("a", []).build()
+    );
+    let result: error::Result<NBTValue> = from_slice_with_options(&document(-1), strict);
+    assert!(matches!(result, Err(Error::InvalidLength(-1))));
+    // Rejected before anything is allocated, since the input can't hold that many longs.
+    let result: error::Result<NBTValue> = from_slice(&document(i32::MAX));
+    assert!(matches!(result, Err(Error::InvalidLength(length)) if length == i32::MAX as i64));
+}
+
+#[test]
+fn test_any_root() {
+    use crate::{ser::SerializerOptions, NBTValue};
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Level {
+        seed: i64,
+    }
+
+    fn write<T: Serialize>(value: &T) -> Vec<u8> {
+        let options = SerializerOptions {
+            any_root: true,
+            ..Default::default()
+        };
+        crate::to_bytes_with_options(value, options).unwrap()
+    }
+    let read = DeserializerOptions {
+        any_root: true,
+        ..Default::default()
+    };
+
+    let bytes = write(&vec![1, 2, 3]);
+    assert_eq!(
+        &bytes[..4],
+        [
+            NBTKind::List.header_byte(),
+            0,
+            0,
+            NBTKind::Int.header_byte()
+        ]
+    );
+    let list: Vec<i32> = from_slice_with_options(&bytes, read.clone()).unwrap();
+    assert_eq!(list, [1, 2, 3]);
+    let value: NBTValue = from_slice_with_options(&bytes, read.clone()).unwrap();
+    assert_eq!(value, NBTValue::List((1..=3).map(NBTValue::Int).collect()));
+    let result: error::Result<Vec<i32>> = from_slice(&bytes);
+    assert!(matches!(result, Err(Error::ExpectedRootCompound)));
+    assert!(matches!(
+        crate::to_bytes(&[1, 2, 3]),
+        Err(Error::ExpectedRootCompound)
+    ));
+
+    let number: i16 = from_slice_with_options(&write(&7i16), read.clone()).unwrap();
+    assert_eq!(number, 7);
+    // Compound roots read the same either way.
+    let level = Level { seed: 42 };
+    let decoded: Level = from_slice_with_options(&write(&level), read).unwrap();
+    assert_eq!(decoded, level);
+}
+
+#[test]
+fn test_key_map() {
+    use crate::tag::CompoundBuilder;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Chunk {
+        status: String,
+        block_entities: Vec<i32>,
+        #[serde(rename = "xPos")]
+        x_pos: i32,
+    }
+
+    let expected = Chunk {
+        status: "full".into(),
+        block_entities: vec![1, 2],
+        x_pos: 3,
+    };
+    let write = |builder: CompoundBuilder| {
+        let mut bytes = Vec::new();
+        builder.build().write_to(&mut bytes, "").unwrap();
+        bytes
+    };
+    let old = write(
+        CompoundBuilder::new()
+            .string("Status", "full")
+            .int_array("BlockEntities", vec![1, 2])
+            .int("xPos", 3),
+    );
+    let new = write(
+        CompoundBuilder::new()
+            .string("status", "full")
+            .int_array("block_entities", vec![1, 2])
+            .int("xPos", 3),
+    );
+    let options = DeserializerOptions {
+        key_map: Some(KeyMap::snake_case()),
+        ..Default::default()
+    };
+    for bytes in [&old, &new] {
+        let chunk: Chunk = from_slice_with_options(bytes, options.clone()).unwrap();
+        assert_eq!(chunk, expected);
+    }
+    assert!(from_slice::<Chunk>(&old).is_err());
+
+    let options = DeserializerOptions {
+        key_map: Some(KeyMap::from_pairs([
+            ("Status", "status"),
+            ("BlockEntities", "block_entities"),
+        ])),
+        ..Default::default()
+    };
+    let chunk: Chunk = from_slice_with_options(&old, options).unwrap();
+    assert_eq!(chunk, expected);
+}
+
+#[test]
+fn test_case_insensitive_fields() {
+    use crate::tag::{CompoundBuilder, NBTValue};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Entity {
+        id: String,
+        #[serde(rename = "Health")]
+        health: f32,
+        #[serde(default, rename = "health")]
+        legacy_health: Option<f32>,
+    }
+
+    let write = |builder: CompoundBuilder| {
+        let mut bytes = Vec::new();
+        builder.build().write_to(&mut bytes, "").unwrap();
+        bytes
+    };
+    let options = |duplicate_keys| DeserializerOptions {
+        case_insensitive_fields: true,
+        duplicate_keys,
+        ..Default::default()
+    };
+
+    let bytes = write(
+        CompoundBuilder::new()
+            .string("ID", "minecraft:pig")
+            .value("HEALTH", NBTValue::Float(10.0)),
+    );
+    let entity: Entity = from_slice_with_options(&bytes, options(Default::default())).unwrap();
+    assert_eq!(entity.id, "minecraft:pig");
+    assert_eq!(entity.health, 10.0);
+    assert!(from_slice::<Entity>(&bytes).is_err());
+
+    // Exact matches win, so fields that only differ in case still get their own keys.
+    let bytes = write(
+        CompoundBuilder::new()
+            .string("id", "minecraft:pig")
+            .value("health", NBTValue::Float(5.0))
+            .value("Health", NBTValue::Float(10.0)),
+    );
+    let entity: Entity = from_slice_with_options(&bytes, options(Default::default())).unwrap();
+    assert_eq!((entity.health, entity.legacy_health), (10.0, Some(5.0)));
+
+    // Keys that fold onto the same field collide like any other duplicate.
+    #[derive(serde::Serialize)]
+    struct Colliding {
+        id: &'static str,
+        #[serde(rename = "Id")]
+        other_id: &'static str,
+        #[serde(rename = "Health")]
+        health: f32,
+    }
+    let bytes = crate::to_bytes(&Colliding {
+        id: "minecraft:pig",
+        other_id: "minecraft:cow",
+        health: 10.0,
+    })
+    .unwrap();
+    let first = from_slice_with_options::<Entity>(&bytes, options(DuplicateKeyPolicy::FirstWins));
+    assert_eq!(first.unwrap().id, "minecraft:pig");
+    assert!(matches!(
+        from_slice_with_options::<Entity>(&bytes, options(DuplicateKeyPolicy::Error)),
+        Err(Error::DuplicateKey(key)) if key == "id"
+    ));
+    assert!(
+        from_slice_with_options::<Entity>(&bytes, options(DuplicateKeyPolicy::LastWins)).is_err()
+    );
+}
+
+#[test]
+fn test_unknown_tag_hook() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Note {
+        a: i8,
+        note: String,
+    }
+
+    fn entry(id: u8, name: &str, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![id];
+        bytes.extend((name.len() as u16).to_be_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.extend(payload);
+        bytes
+    }
+    let mut inner = entry(10, "inner", &[]);
+    inner.extend(entry(21, "skip", &[0, 0, 0, 1]));
+    inner.push(0);
+    let mut bytes = entry(10, "", &[]);
+    bytes.extend(entry(1, "a", &[5]));
+    bytes.extend(entry(20, "note", &[0, 2, b'h', b'i']));
+    bytes.extend(entry(21, "skip", &[0, 0, 0, 1]));
+    bytes.extend(inner);
+    bytes.push(0);
+
+    // Tag 20 is a string, tag 21 an int that is skipped.
+    let hook = UnknownTagHook::new(|id, input| {
+        let mut payload = vec![0; if id == 20 { 2 } else { 4 }];
+        input.read_exact(&mut payload)?;
+        if id == 21 {
+            return Ok(None);
+        }
+        let mut text = vec![0; u16::from_be_bytes([payload[0], payload[1]]) as usize];
+        input.read_exact(&mut text)?;
+        Ok(Some(NBTValue::String(String::from_utf8(text).unwrap())))
+    });
+    let options = DeserializerOptions {
+        unknown_tags: Some(hook),
+        ..Default::default()
+    };
+
+    let note: Note = from_slice_with_options(&bytes, options.clone()).unwrap();
+    assert_eq!(
+        note,
+        Note {
+            a: 5,
+            note: "hi".into()
+        }
+    );
+    let NBTValue::Compound(value) = from_reader_with_options(bytes.as_slice(), options).unwrap()
+    else {
+        panic!("document is not a compound");
+    };
+    assert_eq!(value.len(), 3);
+    assert_eq!(value["note"], NBTValue::String("hi".into()));
+    assert_eq!(value["inner"], NBTValue::Compound(Default::default()));
+
+    assert!(matches!(
+        from_slice::<Note>(&bytes),
+        Err(Error::InvalidTagId)
+    ));
+}
+
+#[test]
+fn test_from_reader_at() {
+    use crate::tag::{CompoundBuilder, NBTValue};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Player {
+        name: String,
+        level: i32,
+    }
+
+    let player = CompoundBuilder::new()
+        .string("name", "Steve")
+        .int("level", 7);
+    let item = CompoundBuilder::new().string("id", "minecraft:stone");
+    let document = CompoundBuilder::new()
+        .long("Seed", 42)
+        .list("Items", [item.build()])
+        .compound(
+            "Data",
+            CompoundBuilder::new()
+                .byte("Flag", 1)
+                .compound("Player", player),
+        )
+        .build();
+    let mut bytes = Vec::new();
+    document.write_to(&mut bytes, "").unwrap();
+
+    let decoded: Player = from_reader_at(bytes.as_slice(), "Data.Player").unwrap();
+    assert_eq!(
+        decoded,
+        Player {
+            name: "Steve".into(),
+            level: 7
+        }
+    );
+    let id: String = from_slice_at(&bytes, "Items[0].id").unwrap();
+    assert_eq!(id, "minecraft:stone");
+    let seed: NBTValue = from_slice_at(&bytes, "Seed").unwrap();
+    assert_eq!(seed, NBTValue::Long(42));
+
+    for missing in ["Data.Enemy", "Items[1]", "Seed.Value"] {
+        assert!(matches!(
+            from_slice_at::<NBTValue>(&bytes, missing),
+            Err(Error::PathNotFound(path)) if path == missing
+        ));
+    }
+    assert!(matches!(
+        from_slice_at::<NBTValue>(&bytes, "Items[x]"),
+        Err(Error::InvalidPath(_))
+    ));
+}
+
+#[test]
+fn test_budget() {
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Names {
+        names: Vec<String>,
+    }
+
+    let names = Names {
+        names: vec![String::new(); 100],
+    };
+    let bytes = crate::to_bytes(&names).unwrap();
+    let options = |max_elements, max_decoded_bytes| DeserializerOptions {
+        max_elements,
+        max_decoded_bytes,
+        ..Default::default()
+    };
+
+    // The root's name, the field name, and the list's 100 elements that are strings themselves.
+    let decoded: Names = from_slice_with_options(&bytes, options(Some(202), None)).unwrap();
+    assert_eq!(decoded, names);
+    let result: error::Result<Names> = from_slice_with_options(&bytes, options(Some(201), None));
+    assert!(matches!(result, Err(Error::BudgetExceeded)));
+
+    let result: error::Result<Names> =
+        from_reader_with_options(bytes.as_slice(), options(None, Some(4)));
+    assert!(matches!(result, Err(Error::BudgetExceeded)));
+    let decoded: Names =
+        from_reader_with_options(bytes.as_slice(), options(None, Some(5))).unwrap();
+    assert_eq!(decoded, names);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_reused_deserializer() {
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Packet {
+        channel: String,
+        id: i32,
+    }
+
+    let packet = |id| Packet {
+        channel: "minecraft:brand".into(),
+        id,
+    };
+    // The previous document's buffer is handed back by `reset` and reused for the next one.
+    let mut deserializer = NBTDeserializer::new(
+        std::io::Cursor::new(Vec::new()),
+        DeserializerOptions::default(),
+    );
+    let mut buffer = Vec::new();
+    for id in 0..3 {
+        crate::to_bytes_into(&mut buffer, &packet(id)).unwrap();
+        buffer = deserializer
+            .reset(std::io::Cursor::new(buffer))
+            .into_inner();
+        assert_eq!(Packet::deserialize(&mut deserializer).unwrap(), packet(id));
+    }
+}
+
+#[test]
+fn test_deserialize_seed() {
+    use serde::{de::IgnoredAny, Serialize};
+    use std::fmt;
+
+    #[derive(Serialize)]
+    struct Server {
+        ip: String,
+        name: String,
+    }
+
+    // Collects the names of the root compound's entries into a list owned by the caller.
+    struct Names<'a>(&'a mut Vec<String>);
+
+    impl<'de> de::DeserializeSeed<'de> for Names<'_> {
+        type Value = ();
+
+        fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+            deserializer.deserialize_map(self)
+        }
+    }
+
+    impl<'de> de::Visitor<'de> for Names<'_> {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a compound")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+            while let Some(name) = map.next_key::<String>()? {
+                map.next_value::<IgnoredAny>()?;
+                self.0.push(name);
+            }
+            Ok(())
+        }
+    }
+
+    let bytes = crate::to_bytes(&Server {
+        ip: "loucaskreger.com".into(),
+        name: "Minecraft Server".into(),
+    })
+    .unwrap();
+    let mut names = Vec::new();
+    from_slice_seed(Names(&mut names), &bytes).unwrap();
+    from_reader_seed(Names(&mut names), bytes.as_slice()).unwrap();
+    assert_eq!(names, ["ip", "name", "ip", "name"]);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_verify_gzip() {
+    use flate2::{write::GzEncoder, Compression};
+    use serde::Serialize;
+    use std::io::Write;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Level {
+        seed: i64,
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&crate::to_bytes(&Level { seed: 42 }).unwrap())
+        .unwrap();
+    let gzip = encoder.finish().unwrap();
+    // Cut into the trailer, the document itself is still complete.
+    let truncated = &gzip[..gzip.len() - 3];
+    let options = DeserializerOptions {
+        verify_gzip: true,
+        ..Default::default()
+    };
+
+    let level: Level = from_gzip_reader(truncated).unwrap();
+    assert_eq!(level, Level { seed: 42 });
+    let level: Level = from_gzip_reader_with_options(gzip.as_slice(), options.clone()).unwrap();
+    assert_eq!(level, Level { seed: 42 });
+    let result: error::Result<Level> = from_gzip_reader_with_options(truncated, options);
+    assert!(matches!(result, Err(Error::TruncatedGzip)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_owned_deserializer_on_thread() {
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Server {
+        name: String,
+    }
+
+    let bytes = crate::to_bytes(&Server {
+        name: "Minecraft Server".into(),
+    })
+    .unwrap();
+    let mut deserializer = OwnedDeserializer::from_vec(bytes, DeserializerOptions::default());
+    let server = std::thread::spawn(move || Server::deserialize(&mut deserializer))
+        .join()
+        .unwrap()
+        .unwrap();
+    assert_eq!(server.name, "Minecraft Server");
+}
+
 // Wrapper deserializeer that consumes the nameless root compound NBT tag
-pub struct NBTDeserializer<R: io::Read> {
-    parser: Parser<R>,
+pub struct NBTDeserializer<R, O: ByteOrder = BigEndian> {
+    parser: Parser<R, O>,
 }
 
-impl NBTDeserializer<Cursor<Vec<u8>>> {
-    fn from_slice(bytes: Vec<u8>) -> Self {
-        let reader = Cursor::new(bytes);
+/// A deserializer that owns the document it reads. It borrows nothing, so it can be moved into a
+/// thread pool or a `'static` task.
+#[cfg(feature = "std")]
+pub type OwnedDeserializer = NBTDeserializer<BufIoRead<std::io::Cursor<Vec<u8>>>>;
+
+// Deserializers over owned or `Send` input can be moved to other threads, and shared when the
+// input can be.
+assert_send_sync!(NBTDeserializer<SliceRead<'static>>, Error);
+#[cfg(feature = "std")]
+assert_send_sync!(NBTDeserializer<IoRead<std::fs::File>>, OwnedDeserializer);
+
+impl<'a, O: ByteOrder> NBTDeserializer<SliceRead<'a>, O> {
+    fn from_slice(bytes: &'a [u8], options: DeserializerOptions) -> Self {
         NBTDeserializer {
-            parser: Parser::new(reader),
+            parser: Parser::new(SliceRead::new(bytes), options),
         }
     }
 }
 
-impl<R: io::Read> NBTDeserializer<R> {
-    fn from_reader(reader: R) -> Self {
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead, O: ByteOrder> NBTDeserializer<BufIoRead<R>, O> {
+    fn from_buf_reader(reader: R, options: DeserializerOptions) -> Self {
         NBTDeserializer {
-            parser: Parser::new(reader),
+            parser: Parser::new(BufIoRead::new(reader), options),
         }
     }
 }
 
+impl<R: io::Read, O: ByteOrder> NBTDeserializer<IoRead<R>, O> {
+    fn from_reader(reader: R, options: DeserializerOptions) -> Self {
+        NBTDeserializer {
+            parser: Parser::new(IoRead::new(reader), options),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl NBTDeserializer<BufIoRead<std::io::Cursor<Vec<u8>>>> {
+    /// Creates a deserializer for the document in `bytes`, see [`OwnedDeserializer`].
+    pub fn from_vec(bytes: Vec<u8>, options: DeserializerOptions) -> Self {
+        Self::from_buf_reader(std::io::Cursor::new(bytes), options)
+    }
+}
+
+impl<R: io::Read> NBTDeserializer<IoRead<R>> {
+    /// Creates a deserializer that can be used for many documents in turn with
+    /// [`NBTDeserializer::reset`], reusing its buffers instead of allocating new ones each time.
+    pub fn new(reader: R, options: DeserializerOptions) -> Self {
+        Self::from_reader(reader, options)
+    }
+
+    /// Points the deserializer at the next document and returns the previous reader.
+    pub fn reset(&mut self, reader: R) -> R {
+        self.parser.reset_budget();
+        self.parser.input_mut().reset(reader)
+    }
+}
+
+/// What to do when a compound contains the same name more than once.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Hand every occurrence to the visitor. Maps keep the last value, derived structs reject the
+    /// duplicate field.
+    #[default]
+    LastWins,
+    /// Keep the first occurrence and skip any later ones.
+    FirstWins,
+    /// Fail with [`Error::DuplicateKey`].
+    Error,
+}
+
+/// Which tags `bool` fields accept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanPolicy {
+    /// Only a Byte of 0 or 1, anything else fails with [`Error::ExpectedBooleanByte`].
+    #[default]
+    Strict,
+    /// Any Byte, where every value other than 0 is `true`, as the game reads them.
+    NonZero,
+    /// Any Byte as with [`NonZero`](Self::NonZero), and also the Strings `"true"` and `"false"`
+    /// that some older and modded data stores.
+    Lenient,
+}
+
+/// How the `i32` lengths that prefix lists and arrays are checked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPolicy {
+    /// Read negative lengths as empty, which is what the game does.
+    #[default]
+    Lenient,
+    /// Fail on negative lengths with [`Error::InvalidLength`].
+    Strict,
+}
+
+/// Renames compound keys before they are matched against struct fields, so one struct can read
+/// documents from versions that spell its keys differently.
+///
+/// The mapping gets each key along with the fields of the struct being read, empty when reading
+/// into a map, and returns the name to use instead or `None` to keep the key.
+#[derive(Clone)]
+pub struct KeyMap(Arc<KeyMapFn>);
+
+type KeyMapFn = dyn Fn(&str, &[&str]) -> Option<String> + Send + Sync;
+
+impl KeyMap {
+    pub fn new<F>(map: F) -> Self
+    where
+        F: Fn(&str, &[&str]) -> Option<String> + Send + Sync + 'static,
+    {
+        KeyMap(Arc::new(map))
+    }
+
+    /// Renames the keys found in `pairs` of `(key, name)` and keeps every other key.
+    pub fn from_pairs<I, K, V>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let table: BTreeMap<String, String> = pairs
+            .into_iter()
+            .map(|(key, name)| (key.into(), name.into()))
+            .collect();
+        KeyMap::new(move |key, _| table.get(key).cloned())
+    }
+
+    /// Reads `PascalCase` and `camelCase` keys into `snake_case` fields, e.g. `BlockEntities` into
+    /// `block_entities`. Keys are only renamed when the struct has a field of the new name.
+    pub fn snake_case() -> Self {
+        KeyMap::new(|key, fields| {
+            let mut name = String::with_capacity(key.len() + 4);
+            for (i, c) in key.char_indices() {
+                if c.is_ascii_uppercase() {
+                    if i > 0 {
+                        name.push('_');
+                    }
+                    name.push(c.to_ascii_lowercase());
+                } else {
+                    name.push(c);
+                }
+            }
+            fields.contains(&name.as_str()).then_some(name)
+        })
+    }
+
+    fn apply(&self, key: &str, fields: &[&str]) -> Option<String> {
+        (self.0)(key, fields)
+    }
+}
+
+impl fmt::Debug for KeyMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyMap(..)")
+    }
+}
+
+/// Reads the payload of tags with ids the spec doesn't define, which some third-party formats and
+/// forks use, instead of failing with [`Error::InvalidTagId`].
+///
+/// The hook gets the tag id and the input positioned at the payload, after the entry's name. It
+/// has to consume exactly the payload, and returns the value to read the entry as or `None` to
+/// skip the entry. Only compound entries are handed to the hook, lists of unknown tags still fail.
+#[derive(Clone)]
+pub struct UnknownTagHook(Arc<UnknownTagFn>);
+
+type UnknownTagFn = dyn Fn(u8, &mut dyn io::Read) -> error::Result<Option<NBTValue>> + Send + Sync;
+
+impl UnknownTagHook {
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: Fn(u8, &mut dyn io::Read) -> error::Result<Option<NBTValue>> + Send + Sync + 'static,
+    {
+        UnknownTagHook(Arc::new(hook))
+    }
+
+    pub(crate) fn read(&self, id: u8, input: &mut dyn io::Read) -> error::Result<Option<NBTValue>> {
+        (self.0)(id, input)
+    }
+}
+
+impl fmt::Debug for UnknownTagHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UnknownTagHook(..)")
+    }
+}
+
+/// Options that change how values are read by the deserializer.
+#[derive(Debug, Default, Clone)]
+pub struct DeserializerOptions {
+    /// How compounds that contain the same name twice are handled.
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Require the tag of `i8`, `i16`, `i32`, `i64`, `f32` and `f64` fields to match their type
+    /// exactly. By default numeric fields accept any numeric tag their type can hold the value
+    /// of, e.g. an `i64` field read from a Byte, an `f64` field from a Float, or an `i32` field
+    /// from a Long in its range. Unsigned fields always accept any integer tag in range, since
+    /// NBT has no unsigned tags.
+    pub strict_numbers: bool,
+    /// When reading gzip, read to the end of the stream so the CRC and length in its trailer are
+    /// checked, and report a stream that ends early as [`Error::TruncatedGzip`].
+    pub verify_gzip: bool,
+    /// Caps the number of values one document may decode: every string and name, and every
+    /// element of a list or array. Exceeding it fails with [`Error::BudgetExceeded`] before the
+    /// values are allocated.
+    pub max_elements: Option<u64>,
+    /// Caps the bytes one document may decode into strings, names and arrays, summed over the
+    /// whole document.
+    pub max_decoded_bytes: Option<u64>,
+    /// Which tags `bool` fields accept.
+    pub booleans: BooleanPolicy,
+    /// Accept documents whose root tag is a List, a number or any other kind instead of a
+    /// Compound, as some older and modded files have. The root is read like a compound entry of
+    /// its kind, so it can be deserialized into any type that fits it, including
+    /// [`NBTValue`](crate::NBTValue).
+    pub any_root: bool,
+    /// Whether negative list and array lengths are read as empty or rejected. Lengths that need
+    /// more bytes than are left in an in-memory document are rejected either way, before anything
+    /// is allocated for them.
+    pub lengths: LengthPolicy,
+    /// Renames compound keys before they are matched against struct fields. Duplicate keys are
+    /// detected after renaming, so two keys that map to the same field count as duplicates.
+    pub key_map: Option<KeyMap>,
+    /// Match compound keys to struct fields ignoring ASCII case when no field matches exactly,
+    /// after any [`key_map`](Self::key_map). Keys that only differ in case then name the same
+    /// field and are handled as duplicates.
+    pub case_insensitive_fields: bool,
+    /// Hands compound entries with unknown tag ids to user code, see [`UnknownTagHook`]. When
+    /// unset, they fail with [`Error::InvalidTagId`].
+    pub unknown_tags: Option<UnknownTagHook>,
+    /// Read string fields from a List of Strings as well, by joining its elements. That is how
+    /// [`SerializerOptions::long_strings`](crate::ser::SerializerOptions::long_strings) writes
+    /// strings too long for a String tag.
+    pub long_strings: bool,
+}
+
 pub fn from_reader<'a, T, R>(s: R) -> error::Result<T>
 where
     T: Deserialize<'a>,
     R: io::Read,
 {
-    let mut deserializer = NBTDeserializer::from_reader(s);
+    from_reader_with_options(s, DeserializerOptions::default())
+}
+
+pub fn from_reader_with_options<'a, T, R>(s: R, options: DeserializerOptions) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    R: io::Read,
+{
+    let mut deserializer = NBTDeserializer::<_, BigEndian>::from_reader(s, options);
+    T::deserialize(&mut deserializer)
+}
+
+/// Like [`from_reader`], but for the little-endian NBT used by Bedrock Edition.
+pub fn from_reader_le<'a, T, R>(s: R) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    R: io::Read,
+{
+    let mut deserializer =
+        NBTDeserializer::<_, LittleEndian>::from_reader(s, DeserializerOptions::default());
+    T::deserialize(&mut deserializer)
+}
+
+/// Like [`from_reader`], but deserializes through `seed`, which can carry state such as an
+/// interner or an arena into the deserialization.
+pub fn from_reader_seed<'a, S, R>(seed: S, s: R) -> error::Result<S::Value>
+where
+    S: de::DeserializeSeed<'a>,
+    R: io::Read,
+{
+    let mut deserializer =
+        NBTDeserializer::<_, BigEndian>::from_reader(s, DeserializerOptions::default());
+    seed.deserialize(&mut deserializer)
+}
+
+/// Like [`from_reader`], but errors carry the path to the field that failed to deserialize.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct Written {
+///     port: String,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Server {
+///     port: i32,
+/// }
+///
+/// let bytes = nbt::to_bytes(&Written { port: "25565".into() }).unwrap();
+/// let err = nbt::de::from_reader_traced::<Server, _>(bytes.as_slice()).unwrap_err();
+/// assert_eq!(err.path().to_string(), "port");
+/// ```
+pub fn from_reader_traced<'a, T, R>(s: R) -> Result<T, serde_path_to_error::Error<Error>>
+where
+    T: Deserialize<'a>,
+    R: io::Read,
+{
+    let mut deserializer =
+        NBTDeserializer::<_, BigEndian>::from_reader(s, DeserializerOptions::default());
+    serde_path_to_error::deserialize(&mut deserializer)
+}
+
+/// What was noticed while deserializing a document, see [`from_reader_with_report`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeserializeReport {
+    /// Struct fields the document has no entry for, which were filled by `#[serde(default)]` or
+    /// left `None`.
+    pub defaulted: Vec<NBTPath>,
+    /// Entries of the document that no struct field took, and that were skipped.
+    pub ignored: Vec<NBTPath>,
+}
+
+impl DeserializeReport {
+    /// Whether the document had exactly the fields of the types it was read into.
+    pub fn is_exact(&self) -> bool {
+        self.defaulted.is_empty() && self.ignored.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ReportState {
+    path: Vec<PathSegment>,
+    report: DeserializeReport,
+}
+
+impl ReportState {
+    fn record(&mut self, key: &str, ignored: bool) {
+        let mut path = self.path.clone();
+        path.push(PathSegment::Key(key.to_owned()));
+        match ignored {
+            true => self.report.ignored.push(NBTPath(path)),
+            false => self.report.defaulted.push(NBTPath(path)),
+        }
+    }
+}
+
+/// Like [`from_reader`], but also reports which struct fields were missing from the document and
+/// which of its entries no field took. Migration tools can use this to tell whether a file is in
+/// an older or newer layout than the types it is read into. Only structs are checked: maps,
+/// flattened fields and [`NBTValue`](crate::NBTValue)s take every entry.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct Old {
+///     name: String,
+///     legacy: i32,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct New {
+///     name: String,
+///     #[serde(default)]
+///     version: i32,
+/// }
+///
+/// let bytes = nbt::to_bytes(&Old { name: "a".into(), legacy: 1 }).unwrap();
+/// let (_, report) = nbt::from_reader_with_report::<New, _>(bytes.as_slice()).unwrap();
+/// assert_eq!(report.defaulted[0].to_string(), "version");
+/// assert_eq!(report.ignored[0].to_string(), "legacy");
+/// ```
+pub fn from_reader_with_report<'a, T, R>(s: R) -> error::Result<(T, DeserializeReport)>
+where
+    T: Deserialize<'a>,
+    R: io::Read,
+{
+    let mut deserializer =
+        NBTDeserializer::<_, BigEndian>::from_reader(s, DeserializerOptions::default());
+    deserializer.parser.report = Some(ReportState::default());
+    let value = T::deserialize(&mut deserializer)?;
+    let report = deserializer.parser.report.take().unwrap_or_default();
+    Ok((value, report.report))
+}
+
+/// Like [`from_reader_with_report`], for a document in memory.
+pub fn from_slice_with_report<'a, T>(s: &'a [u8]) -> error::Result<(T, DeserializeReport)>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer =
+        NBTDeserializer::<_, BigEndian>::from_slice(s, DeserializerOptions::default());
+    deserializer.parser.report = Some(ReportState::default());
+    let value = T::deserialize(&mut deserializer)?;
+    let report = deserializer.parser.report.take().unwrap_or_default();
+    Ok((value, report.report))
+}
+
+/// Like [`from_reader`], but deserializes only the value at `path`, e.g. `"Data.Player"` or
+/// `"Inventory[0].tag"`. Everything before it is skipped without being decoded and nothing after
+/// it is read. Fails with [`Error::PathNotFound`] if the document has no value there.
+pub fn from_reader_at<'a, T, R>(s: R, path: &str) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    R: io::Read,
+{
+    let path = path.parse()?;
+    let mut deserializer =
+        NBTDeserializer::<_, BigEndian>::from_reader(s, DeserializerOptions::default());
+    let mut de_impl = deserializer.seek(&path)?;
+    T::deserialize(&mut de_impl)
+}
+
+/// Like [`from_reader_at`], for a document in memory.
+pub fn from_slice_at<'a, T>(s: &'a [u8], path: &str) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let path = path.parse()?;
+    let mut deserializer =
+        NBTDeserializer::<_, BigEndian>::from_slice(s, DeserializerOptions::default());
+    let mut de_impl = deserializer.seek(&path)?;
+    T::deserialize(&mut de_impl)
+}
+
+/// Like [`from_reader`], but fails with [`Error::SizeLimitExceeded`] instead of reading more than
+/// `max_bytes` bytes from `s`. Use this for data from untrusted peers; the vanilla client caps
+/// network NBT at 2MiB.
+pub fn from_reader_limited<'a, T, R>(s: R, max_bytes: u64) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    R: io::Read,
+{
+    from_reader(Limited::new(s, max_bytes))
+}
+
+/// Like [`from_reader`], but strings are decoded directly out of the reader's buffer when possible.
+#[cfg(feature = "std")]
+pub fn from_buf_reader<'a, T, R>(s: R) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    R: std::io::BufRead,
+{
+    from_buf_reader_with_options(s, DeserializerOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub fn from_buf_reader_with_options<'a, T, R>(
+    s: R,
+    options: DeserializerOptions,
+) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    R: std::io::BufRead,
+{
+    let mut deserializer = NBTDeserializer::<_, BigEndian>::from_buf_reader(s, options);
     T::deserialize(&mut deserializer)
 }
 
+#[cfg(feature = "compression")]
 pub fn from_gzip_reader<'a, T, R>(s: R) -> error::Result<T>
 where
     T: Deserialize<'a>,
     R: io::Read,
 {
-    let gzip = GzDecoder::new(s);
-    from_reader(gzip)
+    from_gzip_reader_with_options(s, DeserializerOptions::default())
+}
+
+#[cfg(feature = "compression")]
+pub fn from_gzip_reader_with_options<'a, T, R>(
+    s: R,
+    options: DeserializerOptions,
+) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    R: io::Read,
+{
+    let verify = options.verify_gzip;
+    let gzip = std::io::BufReader::new(GzDecoder::new(s));
+    let mut deserializer = NBTDeserializer::<_, BigEndian>::from_buf_reader(gzip, options);
+    let result = T::deserialize(&mut deserializer);
+    if !verify {
+        return result;
+    }
+    let value = result.map_err(gzip_truncation)?;
+    // The decoder only checks the trailer once it reaches the end of the stream, which the
+    // deserializer stops short of.
+    std::io::copy(deserializer.parser.input_mut(), &mut std::io::sink())
+        .map_err(|err| gzip_truncation(err.into()))?;
+    Ok(value)
 }
 
+#[cfg(feature = "compression")]
+fn gzip_truncation(err: Error) -> Error {
+    match err {
+        Error::Eof => Error::TruncatedGzip,
+        Error::IoError(ref io) if io.kind() == io::ErrorKind::UnexpectedEof => Error::TruncatedGzip,
+        err => err,
+    }
+}
+
+#[cfg(feature = "compression")]
 pub fn from_zlib_reader<'a, T, R>(s: R) -> error::Result<T>
 where
     T: Deserialize<'a>,
     R: io::Read,
 {
     let zlib = ZlibDecoder::new(s);
-    from_reader(zlib)
+    from_buf_reader(std::io::BufReader::new(zlib))
 }
 
-pub fn from_slice<'a, T>(s: Vec<u8>) -> error::Result<T>
+/// Reads a zstd-compressed document, as stored by some servers and backup tools.
+#[cfg(feature = "zstd")]
+pub fn from_zstd_reader<'a, T, R>(s: R) -> error::Result<T>
 where
     T: Deserialize<'a>,
+    R: io::Read,
 {
-    let mut deserializer: NBTDeserializer<Cursor<Vec<u8>>> = NBTDeserializer::from_slice(s);
+    let zstd = zstd::stream::read::Decoder::new(s)?;
+    from_buf_reader(std::io::BufReader::new(zstd))
+}
+
+pub fn from_slice<'a, T>(s: &'a [u8]) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_slice_with_options(s, DeserializerOptions::default())
+}
+
+pub fn from_slice_with_options<'a, T>(s: &'a [u8], options: DeserializerOptions) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = NBTDeserializer::<_, BigEndian>::from_slice(s, options);
+    T::deserialize(&mut deserializer)
+}
+
+/// Like [`from_slice`], but for the little-endian NBT used by Bedrock Edition.
+pub fn from_slice_le<'a, T>(s: &'a [u8]) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer =
+        NBTDeserializer::<_, LittleEndian>::from_slice(s, DeserializerOptions::default());
     T::deserialize(&mut deserializer)
 }
 
-impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeserializer<R> {
+/// Like [`from_slice`], but deserializes through `seed`, see [`from_reader_seed`].
+pub fn from_slice_seed<'a, S>(seed: S, s: &'a [u8]) -> error::Result<S::Value>
+where
+    S: de::DeserializeSeed<'a>,
+{
+    let mut deserializer =
+        NBTDeserializer::<_, BigEndian>::from_slice(s, DeserializerOptions::default());
+    seed.deserialize(&mut deserializer)
+}
+
+impl<'de, R: Input<'de>, O: ByteOrder> serde::de::Deserializer<'de> for &mut NBTDeserializer<R, O> {
     type Error = Error;
 
     forward_to_deserialize_any! {
@@ -76,10 +1431,14 @@ impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeseriali
         unit seq tuple_struct tuple option enum identifier ignored_any
     }
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
+        if self.parser.options().any_root {
+            let mut de_impl = self.any_root()?;
+            return (&mut de_impl).deserialize_any(visitor);
+        }
         // File is not valid if there is no root compound NBT tag.
         Err(Error::ExpectedRootCompound)
     }
@@ -97,12 +1456,20 @@ impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeseriali
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
+        if self.parser.options().any_root {
+            let mut de_impl = self.any_root()?;
+            return (&mut de_impl).deserialize_newtype_struct(name, visitor);
+        }
+        // A whole document read as an `NBTValue` is its root compound.
+        if name == VALUE_NAME {
+            return self.deserialize_map(visitor);
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -110,114 +1477,305 @@ impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeseriali
     where
         V: de::Visitor<'de>,
     {
-        // Error if there is no root compound NBT tag
-        let kind = self.parser.parse_kind()?;
-        if let NBTKind::Compound = kind {
-            let _ = self.parser.parse_string()?;
-            // Effectively a list of named tags. Order is not guaranteed.
-            visitor.visit_map(NBTMapDeserializer::new(&mut self.parser))
-        } else {
-            Err(Error::ExpectedRootCompound)
+        if self.parser.options().any_root {
+            let mut de_impl = self.any_root()?;
+            return (&mut de_impl).deserialize_map(visitor);
         }
+        self.deserialize_root(None, visitor)
     }
 
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
+        name: &'static str,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        // Effectively a list of named tags. Order is not guaranteed.
-        self.deserialize_map(visitor)
+        if self.parser.options().any_root {
+            let mut de_impl = self.any_root()?;
+            return (&mut de_impl).deserialize_struct(name, fields, visitor);
+        }
+        self.deserialize_root(Some(fields), visitor)
+    }
+}
+
+impl<'de, R: Input<'de>, O: ByteOrder> NBTDeserializer<R, O> {
+    // Reads the header of a root tag of any kind, and hands back a deserializer for its payload.
+    fn any_root(&mut self) -> error::Result<NBTDeserializerImpl<'_, R, O>> {
+        let kind = self.parser.parse_kind()?;
+        if kind == NBTKind::End {
+            return Err(Error::InvalidTagId);
+        }
+        self.parser.parse_str(|_| Ok(()))?;
+        Ok(NBTDeserializerImpl::new(&mut self.parser, kind))
+    }
+
+    // Walks from the root compound to the value at `path`, skipping everything before it, and
+    // hands back a deserializer for that value.
+    fn seek(&mut self, path: &NBTPath) -> error::Result<NBTDeserializerImpl<'_, R, O>> {
+        if self.parser.parse_kind()? != NBTKind::Compound {
+            return Err(Error::ExpectedRootCompound);
+        }
+        self.parser.parse_str(|_| Ok(()))?;
+        let not_found = || Error::PathNotFound(path.to_string());
+        let mut kind = NBTKind::Compound;
+        for segment in path.segments() {
+            match (segment, kind) {
+                (PathSegment::Key(key), NBTKind::Compound) => loop {
+                    let entry = match self.parser.parse_entry()? {
+                        Entry::Known(NBTKind::End) => return Err(not_found()),
+                        Entry::Known(entry) => entry,
+                        Entry::Unknown(..) => continue,
+                    };
+                    if self.parser.parse_str(|name| Ok(name == key))? {
+                        kind = entry;
+                        break;
+                    }
+                    self.parser.skip_payload(entry)?;
+                },
+                (PathSegment::Index(index), NBTKind::List) => {
+                    let (element, length) = self.parser.parse_list_header()?;
+                    if *index >= length {
+                        return Err(not_found());
+                    }
+                    for _ in 0..*index {
+                        self.parser.skip_payload(element)?;
+                    }
+                    kind = element;
+                }
+                _ => return Err(not_found()),
+            }
+        }
+        Ok(NBTDeserializerImpl::new(&mut self.parser, kind))
+    }
+
+    // Reads the root compound into a map, or a struct with `fields`.
+    fn deserialize_root<V>(
+        &mut self,
+        fields: Option<&'static [&'static str]>,
+        visitor: V,
+    ) -> error::Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        // Error if there is no root compound NBT tag
+        let kind = self.parser.parse_kind()?;
+        if let NBTKind::Compound = kind {
+            self.parser.parse_str(|_| Ok(()))?;
+            // Effectively a list of named tags. Order is not guaranteed.
+            visitor.visit_map(NBTMapDeserializer::new(&mut self.parser, fields))
+        } else {
+            Err(Error::ExpectedRootCompound)
+        }
     }
 }
 
 /// Deserializer for compound NBT tags.
 /// Holds the outer NBT deserializer since thats where all the parsing functions are.
-struct NBTMapDeserializer<'a, R: io::Read> {
-    parser: &'a mut Parser<R>,
+struct NBTMapDeserializer<'a, R, O: ByteOrder> {
+    parser: &'a mut Parser<R, O>,
     kind: Option<NBTKind>,
+    // Names seen so far, only tracked when the duplicate key policy or a report needs them.
+    seen_keys: BTreeSet<String>,
+    // The fields of the struct being read, if it is one. Only used for reports.
+    fields: Option<&'static [&'static str]>,
+    // Name of the entry whose value is next, kept for the path of a report.
+    key: String,
+    // Value the unknown tag hook read for the entry whose value is next.
+    unknown: Option<NBTValue>,
 }
 
-impl<'de, 'a, R: io::Read> NBTMapDeserializer<'a, R> {
-    fn new(parser: &'a mut Parser<R>) -> Self {
-        Self { parser, kind: None }
+impl<'de, 'a, R: Input<'de>, O: ByteOrder> NBTMapDeserializer<'a, R, O> {
+    fn new(parser: &'a mut Parser<R, O>, fields: Option<&'static [&'static str]>) -> Self {
+        Self {
+            parser,
+            kind: None,
+            seen_keys: BTreeSet::new(),
+            fields,
+            key: String::new(),
+            unknown: None,
+        }
     }
 }
 
-impl<'de, 'a, R: io::Read> MapAccess<'de> for NBTMapDeserializer<'a, R> {
+impl<'de, 'a, R: Input<'de>, O: ByteOrder> MapAccess<'de> for NBTMapDeserializer<'a, R, O> {
     type Error = Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
     where
         K: de::DeserializeSeed<'de>,
     {
-        let kind = self.parser.parse_kind()?;
+        let policy = self.parser.options().duplicate_keys;
+        loop {
+            let (kind, name) = match self.parser.parse_entry()? {
+                Entry::Known(kind) => (kind, None),
+                Entry::Unknown(_, None) => continue,
+                Entry::Unknown(name, Some(value)) => {
+                    let kind = value.kind();
+                    self.unknown = Some(value);
+                    (kind, Some(name))
+                }
+            };
 
-        if let NBTKind::End = kind {
-            return Ok(None);
-        }
+            if let NBTKind::End = kind {
+                self.report_defaulted();
+                return Ok(None);
+            }
 
-        // Save the kind so 'next_value_seed' can get it.
-        self.kind = Some(kind);
+            // Save the kind so 'next_value_seed' can get it.
+            self.kind = Some(kind);
 
-        // Treat the key of the compound NBT tag as a string
-        let mut de_impl = NBTDeserializerImpl::new(self.parser, NBTKind::String);
+            let reporting = self.parser.report.is_some();
+            let key_map = self.parser.options().key_map.clone();
+            let case_insensitive = self.parser.options().case_insensitive_fields;
+            if policy == DuplicateKeyPolicy::LastWins
+                && !reporting
+                && key_map.is_none()
+                && !case_insensitive
+                && name.is_none()
+            {
+                // Treat the key of the compound NBT tag as a string
+                let mut de_impl = NBTDeserializerImpl::new(self.parser, NBTKind::String);
+                return Ok(Some(seed.deserialize(&mut de_impl)?));
+            }
 
-        Ok(Some(seed.deserialize(&mut de_impl)?))
+            let name = match name {
+                Some(name) => name,
+                None => self.parser.parse_str(|string| Ok(string.to_owned()))?,
+            };
+            let fields = self.fields.unwrap_or_default();
+            let mut key = match &key_map {
+                Some(key_map) => key_map.apply(&name, fields).unwrap_or_else(|| name.clone()),
+                None => name.clone(),
+            };
+            if case_insensitive && !fields.contains(&key.as_str()) {
+                if let Some(field) = fields.iter().find(|field| field.eq_ignore_ascii_case(&key)) {
+                    key = field.to_string();
+                }
+            }
+            if policy != DuplicateKeyPolicy::LastWins && self.seen_keys.contains(&key) {
+                match policy {
+                    DuplicateKeyPolicy::Error => return Err(Error::DuplicateKey(key)),
+                    _ => {
+                        if self.unknown.take().is_none() {
+                            self.parser.skip_payload(kind)?;
+                        }
+                        continue;
+                    }
+                }
+            }
+            let key_deserializer: de::value::StrDeserializer<Error> =
+                key.as_str().into_deserializer();
+            let value = seed.deserialize(key_deserializer)?;
+            if let (Some(report), Some(fields)) = (&mut self.parser.report, self.fields) {
+                if !fields.contains(&key.as_str()) {
+                    report.record(&name, true);
+                }
+            }
+            if reporting {
+                self.key = name;
+            }
+            self.seen_keys.insert(key);
+            return Ok(Some(value));
+        }
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
     where
         V: de::DeserializeSeed<'de>,
     {
-        match self.kind {
-            Some(kind) => {
-                let mut de_impl = NBTDeserializerImpl::new(self.parser, kind);
-                Ok(seed.deserialize(&mut de_impl)?)
+        let Some(kind) = self.kind else {
+            unreachable!("Cannot get the next value seed if the kind is 'None'")
+        };
+        if let Some(report) = &mut self.parser.report {
+            report.path.push(PathSegment::Key(self.key.clone()));
+        }
+        let value = match self.unknown.take() {
+            Some(unknown) => deserialize_unknown(&unknown, self.parser.options(), seed)?,
+            None => seed.deserialize(&mut NBTDeserializerImpl::new(self.parser, kind))?,
+        };
+        if let Some(report) = &mut self.parser.report {
+            report.path.pop();
+        }
+        Ok(value)
+    }
+}
+
+// Deserializes the value the unknown tag hook read for an entry, by writing it out and reading
+// it back like any other payload.
+fn deserialize_unknown<'de, V>(
+    value: &NBTValue,
+    options: &DeserializerOptions,
+    seed: V,
+) -> error::Result<V::Value>
+where
+    V: de::DeserializeSeed<'de>,
+{
+    let mut bytes = Vec::new();
+    Writer::<_, BigEndian>::new(&mut bytes, Default::default()).write_value(value)?;
+    let mut parser = Parser::<_, BigEndian>::new(IoRead::new(bytes.as_slice()), options.clone());
+    seed.deserialize(&mut NBTDeserializerImpl::new(&mut parser, value.kind()))
+}
+
+impl<R, O: ByteOrder> NBTMapDeserializer<'_, R, O> {
+    // Reports the fields of the struct that the compound had no entry for.
+    fn report_defaulted(&mut self) {
+        let (Some(report), Some(fields)) = (&mut self.parser.report, self.fields) else {
+            return;
+        };
+        for field in fields {
+            if !self.seen_keys.contains(*field) {
+                report.record(field, false);
             }
-            None => unreachable!("Cannot get the next value seed if the kind is 'None'"),
         }
     }
 }
 
 /// Deserializes a compound NBT tag
-struct NBTSeqDeserializer<'a, R: io::Read> {
-    parser: &'a mut Parser<R>,
+struct NBTSeqDeserializer<'a, R, O: ByteOrder> {
+    parser: &'a mut Parser<R, O>,
     kind: NBTKind,
-    length: i32,
-    current_pos: i32,
+    length: usize,
+    current_pos: usize,
+    // Offset of the list payload, `None` for arrays.
+    list_offset: Option<u64>,
 }
 
-impl<'a, R: io::Read> NBTSeqDeserializer<'a, R> {
+impl<'de, 'a, R: Input<'de>, O: ByteOrder> NBTSeqDeserializer<'a, R, O> {
     /// Creates a sequence deserializer for a NBT list where the type is defined as part of the list
-    fn from_list(parser: &'a mut Parser<R>) -> io::Result<Self> {
-        let kind = parser.parse_kind()?;
-        let length = parser.parse_i32()?;
+    fn from_list(parser: &'a mut Parser<R, O>) -> error::Result<Self> {
+        let offset = parser.position();
+        let (kind, length) = parser.parse_list_header()?;
         Ok(Self {
             parser,
             kind,
             length,
             current_pos: 0,
+            list_offset: Some(offset),
         })
     }
 
     /// Creates a sequence deserializer for a NBT array of type `kind`
-    fn from_array(parser: &'a mut Parser<R>, kind: NBTKind) -> io::Result<Self> {
-        let length = parser.parse_i32()?;
+    fn from_array(parser: &'a mut Parser<R, O>, kind: NBTKind) -> error::Result<Self> {
+        let element_size = match kind {
+            NBTKind::Byte => 1,
+            NBTKind::Int => 4,
+            _ => 8,
+        };
+        let length = parser.parse_array_length(element_size)?;
         Ok(Self {
             parser,
             kind,
             length,
             current_pos: 0,
+            list_offset: None,
         })
     }
 }
 
-impl<'de, 'a, R: io::Read> SeqAccess<'de> for NBTSeqDeserializer<'a, R> {
+impl<'de, 'a, R: Input<'de>, O: ByteOrder> SeqAccess<'de> for NBTSeqDeserializer<'a, R, O> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
@@ -228,32 +1786,146 @@ impl<'de, 'a, R: io::Read> SeqAccess<'de> for NBTSeqDeserializer<'a, R> {
             return Ok(None);
         }
 
+        if let Some(report) = &mut self.parser.report {
+            report.path.push(PathSegment::Index(self.current_pos));
+        }
         // Deserialize the next element in the list/array
         let mut de_impl = NBTDeserializerImpl::new(self.parser, self.kind);
-        let value = seed.deserialize(&mut de_impl)?;
+        let value = seed
+            .deserialize(&mut de_impl)
+            .map_err(|err| match self.list_offset {
+                Some(offset) => err.in_list(self.kind, offset),
+                None => err,
+            })?;
+        if let Some(report) = &mut self.parser.report {
+            report.path.pop();
+        }
         self.current_pos += 1;
         Ok(Some(value))
     }
 }
 
 /// Actual implementation of deserializing NBT tags
-struct NBTDeserializerImpl<'a, R: io::Read> {
-    parser: &'a mut Parser<R>,
+struct NBTDeserializerImpl<'a, R, O: ByteOrder> {
+    parser: &'a mut Parser<R, O>,
     kind: NBTKind,
 }
 
-impl<'a, R: io::Read> NBTDeserializerImpl<'a, R> {
-    pub fn new(parser: &'a mut Parser<R>, kind: NBTKind) -> Self {
+impl<'de, 'a, R: Input<'de>, O: ByteOrder> NBTDeserializerImpl<'a, R, O> {
+    pub fn new(parser: &'a mut Parser<R, O>, kind: NBTKind) -> Self {
         Self { parser, kind }
     }
+
+    // With `strict_numbers`, fails unless the current tag is `target`.
+    fn expect_number(&self, target: NBTKind) -> error::Result<()> {
+        if self.parser.options().strict_numbers && self.kind != target {
+            return Err(Error::MismatchedTag(self.kind, target));
+        }
+        Ok(())
+    }
 }
 
-impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeserializerImpl<'a, R> {
+impl<'de, 'a, R: Input<'de>, O: ByteOrder> serde::de::Deserializer<'de>
+    for &'a mut NBTDeserializerImpl<'a, R, O>
+{
     type Error = Error;
 
     forward_to_deserialize_any! {
-        u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string bytes byte_buf seq
-        map tuple_struct struct tuple enum identifier ignored_any
+        u8 u16 u32 u64 char seq
+        map tuple_struct tuple enum
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.kind != NBTKind::List || !self.parser.options().long_strings {
+            return self.deserialize_any(visitor);
+        }
+        let (kind, length) = self.parser.parse_list_header()?;
+        if length > 0 && kind != NBTKind::String {
+            return Err(Error::MismatchedTag(kind, NBTKind::String));
+        }
+        let mut string = String::new();
+        for _ in 0..length {
+            self.parser.parse_str(|piece| {
+                string.push_str(piece);
+                Ok(())
+            })?;
+        }
+        visitor.visit_string(string)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.kind {
+            NBTKind::Compound => {
+                visitor.visit_map(NBTMapDeserializer::new(self.parser, Some(fields)))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.expect_number(NBTKind::Byte)?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.expect_number(NBTKind::Short)?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.expect_number(NBTKind::Int)?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.expect_number(NBTKind::Long)?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.expect_number(NBTKind::Float)?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.expect_number(NBTKind::Double)?;
+        self.deserialize_any(visitor)
     }
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -278,7 +1950,10 @@ impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeseriali
                 visitor.visit_seq(NBTSeqDeserializer::from_array(self.parser, NBTKind::Byte)?)
             }
             // A length-prefixed modified UTF-8 string. The prefix is an unsigned short (thus 2 bytes) signifying the length of the string in bytes
-            NBTKind::String => visitor.visit_string(self.parser.parse_string()?),
+            NBTKind::String => self.parser.parse_borrowed_str(|string| match string {
+                Str::Borrowed(string) => visitor.visit_borrowed_str(string),
+                Str::Transient(string) => visitor.visit_str(string),
+            }),
             // A list of nameless tags, all of the same type.
             // The list is prefixed with the Type ID of the items it contains (thus 1 byte),
             // and the length of the list as a signed integer (a further 4 bytes).
@@ -288,7 +1963,7 @@ impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeseriali
             // if the length is <= 0).
             NBTKind::List => visitor.visit_seq(NBTSeqDeserializer::from_list(self.parser)?),
             // Effectively a list of named tags. Order is not guaranteed.
-            NBTKind::Compound => visitor.visit_map(NBTMapDeserializer::new(self.parser)),
+            NBTKind::Compound => visitor.visit_map(NBTMapDeserializer::new(self.parser, None)),
             // A length-prefixed array of signed integers. The prefix is a signed integer (thus 4 bytes) and indicates the number of 4 byte integers.
             NBTKind::IntArray => {
                 visitor.visit_seq(NBTSeqDeserializer::from_array(self.parser, NBTKind::Int)?)
@@ -301,16 +1976,74 @@ impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeseriali
         }
     }
 
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // Unknown compound keys are skipped byte-wise instead of being decoded into values.
+        self.parser.skip_payload(self.kind)?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // Compound keys and enum variants are always names, hand them over borrowed so field
+        // matching doesn't need an allocation.
+        match self.kind {
+            NBTKind::String => self.parser.parse_str(|string| visitor.visit_str(string)),
+            _ => Err(Error::MismatchedTag(self.kind, NBTKind::String)),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // Arrays are handed over as raw bytes so `serde_bytes` fields keep their bit pattern, and
+        // borrowed straight from the input when it is a slice.
+        let element_size = match self.kind {
+            NBTKind::ByteArray => 1,
+            NBTKind::IntArray => 4,
+            NBTKind::LongArray => 8,
+            _ => return self.deserialize_any(visitor),
+        };
+        match self.parser.parse_array_bytes(element_size)? {
+            Cow::Borrowed(bytes) => visitor.visit_borrowed_bytes(bytes),
+            Cow::Owned(bytes) => visitor.visit_byte_buf(bytes),
+        }
+    }
+
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
+        let policy = self.parser.options().booleans;
         match self.kind {
             NBTKind::Byte => match self.parser.parse_i8()? {
                 0 => visitor.visit_bool(false),
                 1 => visitor.visit_bool(true),
+                _ if policy != BooleanPolicy::Strict => visitor.visit_bool(true),
                 value => Err(Error::ExpectedBooleanByte(value)),
             },
+            NBTKind::String if policy == BooleanPolicy::Lenient => {
+                self.parser.parse_str(|string| match string {
+                    "true" => visitor.visit_bool(true),
+                    "false" => visitor.visit_bool(false),
+                    _ => Err(Error::Message(format!(
+                        "expected \"true\" or \"false\" but got {:?}",
+                        string
+                    ))),
+                })
+            }
             _ => Err(Error::MismatchedTag(self.kind, NBTKind::Byte)),
         }
     }
@@ -342,12 +2075,68 @@ impl<'de, 'a, R: io::Read> serde::de::Deserializer<'de> for &'a mut NBTDeseriali
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        if name == VALUE_NAME {
+            let array_name = match self.kind {
+                NBTKind::ByteArray => BYTE_ARRAY_NAME,
+                NBTKind::IntArray => INT_ARRAY_NAME,
+                NBTKind::LongArray => LONG_ARRAY_NAME,
+                _ => return self.deserialize_any(visitor),
+            };
+            return visitor.visit_map(NBTArrayDeserializer {
+                parser: self.parser,
+                kind: self.kind,
+                name: Some(array_name),
+            });
+        }
+        // Array views ask for the payload of one kind of array by its name.
+        let array_kind = match name {
+            BYTE_ARRAY_NAME => NBTKind::ByteArray,
+            INT_ARRAY_NAME => NBTKind::IntArray,
+            LONG_ARRAY_NAME => NBTKind::LongArray,
+            _ => return visitor.visit_newtype_struct(self),
+        };
+        if self.kind != array_kind {
+            return Err(Error::MismatchedTag(self.kind, array_kind));
+        }
+        self.deserialize_byte_buf(visitor)
+    }
+}
+
+/// Hands an array to [`NBTValue`](crate::NBTValue) as a map with a single entry, keyed by the
+/// same name the serializer uses for that kind of array, so the value can tell it from a list.
+struct NBTArrayDeserializer<'a, R, O: ByteOrder> {
+    parser: &'a mut Parser<R, O>,
+    kind: NBTKind,
+    // Taken once the key has been handed out.
+    name: Option<&'static str>,
+}
+
+impl<'de, 'a, R: Input<'de>, O: ByteOrder> MapAccess<'de> for NBTArrayDeserializer<'a, R, O> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.name.take() {
+            Some(name) => {
+                let key_deserializer: de::value::StrDeserializer<Error> = name.into_deserializer();
+                seed.deserialize(key_deserializer).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut NBTDeserializerImpl::new(self.parser, self.kind))
     }
 }
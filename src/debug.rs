@@ -1,71 +1,291 @@
 use std::{
-    collections::{HashMap, BTreeMap},
     fs,
     io::{self, Cursor, Read},
 };
 
 use byteorder::ReadBytesExt;
+#[cfg(feature = "compression")]
 use flate2::bufread::GzDecoder;
 
-use crate::{error::{self, Error}, kind::NBTKind};
-
-#[derive(Debug)]
-enum NBTPayload {
-    Empty,
-    Byte(i8),
-    Short(i16),
-    Int(i32),
-    Long(i64),
-    Float(f32),
-    Double(f64),
-    ByteArray(Vec<i8>),
-    String(String),
-    List(Vec<NBTPayload>),
-    Compound(BTreeMap<String, NBTPayload>),
-    IntArray(Vec<i32>),
-    LongArray(Vec<i64>),
-}
-
-#[derive(Debug)]
-struct NBTTag {
-    kind: NBTKind,
-    name: Option<String>,
-    payload: NBTPayload,
+use crate::{
+    de::LengthPolicy,
+    error::{self, Error},
+    kind::NBTKind,
+    parser::{checked_length, min_payload_size},
+    tag::{CompoundMap, NBTValue},
+};
+
+#[test]
+fn test_dump_ndjson() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    #[allow(non_snake_case)]
+    struct GameRules {
+        doFireTick: String,
+    }
+
+    #[derive(Serialize)]
+    #[allow(non_snake_case)]
+    struct Data {
+        GameRules: GameRules,
+        Version: Vec<i16>,
+    }
+
+    #[derive(Serialize)]
+    struct Level {
+        #[serde(rename = "Data")]
+        data: Data,
+    }
+
+    let bytes = crate::to_bytes(&Level {
+        data: Data {
+            GameRules: GameRules {
+                doFireTick: "true".into(),
+            },
+            Version: vec![3],
+        },
+    })
+    .unwrap();
+    let mut out = Vec::new();
+    dump_ndjson_from_bytes(bytes, &mut out).unwrap();
+    let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+    assert_eq!(
+        lines,
+        [
+            r#"{"path": "", "kind": "Compound"}"#,
+            r#"{"path": "Data", "kind": "Compound"}"#,
+            r#"{"path": "Data.GameRules", "kind": "Compound"}"#,
+            r#"{"path": "Data.GameRules.doFireTick", "kind": "String", "value": "true"}"#,
+            r#"{"path": "Data.Version", "kind": "List", "length": 1}"#,
+            r#"{"path": "Data.Version[0]", "kind": "Short", "value": 3}"#,
+        ]
+    );
 }
 
-impl NBTTag {
-    fn new(kind: NBTKind, name: Option<String>, payload: NBTPayload) -> Self {
-        Self {
-            kind,
-            name,
-            payload,
-        }
+#[cfg(feature = "compression")]
+#[test]
+fn test_dump_ndjson_gzip_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("level.dat");
+    let value = crate::CompoundBuilder::new().int("version", 1).build();
+    crate::to_file(&path, &value, crate::Compression::Gzip).unwrap();
+
+    // Nothing but the events ends up in the output.
+    let mut out = Vec::new();
+    dump_ndjson(path.to_str().unwrap(), &mut out).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&out)
+            .unwrap()
+            .lines()
+            .collect::<Vec<_>>(),
+        [
+            r#"{"path": "", "kind": "Compound"}"#,
+            r#"{"path": "version", "kind": "Int", "value": 1}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_hexdump() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Level {
+        version: i32,
+        tags: Vec<String>,
+    }
+
+    let bytes = crate::to_bytes(&Level {
+        version: 1,
+        tags: vec!["a".into()],
+    })
+    .unwrap();
+    let mut out = Vec::new();
+    hexdump_from_bytes(bytes, &mut out).unwrap();
+    let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+    assert_eq!(
+        lines,
+        [
+            r#"00000000  0a 00 00                                         Compound "" payload 3..33"#,
+            r#"00000003  03 00 07 76 65 72 73 69 6f 6e                      Int "version" payload 13..17"#,
+            r#"0000000d  00 00 00 01                                          Int(1)"#,
+            r#"00000011  09 00 04 74 61 67 73                               List "tags" payload 24..32"#,
+            r#"00000018  08 00 00 00 01                                       1 entries of String"#,
+            r#"0000001d  00 01 61                                             [0] String("a")"#,
+            r#"00000020  00                                                 End"#,
+        ]
+    );
+}
+
+#[test]
+fn test_hexdump_file() {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, bytes) in [("empty.nbt", &[][..]), ("short.nbt", &[0x1f][..])] {
+        let path = dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        let mut out = Vec::new();
+        assert!(hexdump(path.to_str().unwrap(), &mut out).is_err());
+    }
+
+    // Gzip files are dumped decompressed.
+    #[cfg(feature = "compression")]
+    {
+        let path = dir.path().join("level.dat");
+        let value = crate::CompoundBuilder::new().build();
+        crate::to_file(&path, &value, crate::Compression::Gzip).unwrap();
+        let mut out = Vec::new();
+        hexdump(path.to_str().unwrap(), &mut out).unwrap();
+        assert!(out.starts_with(b"00000000  0a 00 00 "));
     }
 }
 
+#[test]
+fn test_read_and_write_again() {
+    use crate::CompoundBuilder;
+
+    let document = CompoundBuilder::new()
+        .string("name", "world")
+        .long_array("states", [1, -1])
+        .list("sections", [CompoundBuilder::new().byte("Y", -4).build()])
+        .list("empty", [])
+        .build();
+    let mut bytes = Vec::new();
+    document.write_to(&mut bytes, "root").unwrap();
+    let (name, value) = read_nbt_from_bytes(bytes.clone()).unwrap();
+    assert_eq!((name.as_str(), &value), ("root", &document));
+
+    let mut written = Vec::new();
+    value.write_to(&mut written, &name).unwrap();
+    let (name, value) = crate::stream::NBTStreamReader::new(written.as_slice())
+        .read_document()
+        .unwrap();
+    assert_eq!((name.as_str(), value), ("root", document));
+}
+
+// A named tag, End tags have no payload.
+struct NBTTag {
+    name: String,
+    payload: Option<NBTValue>,
+}
+
 // #[cfg(feature = "debug")]
 pub fn dump_nbt(filename: &str) -> error::Result<()> {
-    let mut stream = NBTReader::new(filename).unwrap();
-    let tag = stream.parse_nbt();
-    match tag {
-        Some(tag) => {
-            println!("{:#?}", tag.payload);
-            Ok(())
-        }
-        None => Err(Error::ExpectedRootCompound),
-    }
+    let (_, value) = read_nbt(filename)?;
+    println!("{:#?}", value);
+    Ok(())
 }
 
 pub fn dump_nbt_from_bytes(bytes: Vec<u8>) -> error::Result<()> {
+    let (_, value) = read_nbt_from_bytes(bytes)?;
+    println!("{:#?}", value);
+    Ok(())
+}
+
+/// Reads the file the way [`dump_nbt`] does, returning the name and value of its first tag. The
+/// value can be written out again with [`NBTValue::write_to`].
+pub fn read_nbt(filename: &str) -> error::Result<(String, NBTValue)> {
+    NBTReader::new(filename)?.parse_nbt()
+}
+
+pub fn read_nbt_from_bytes(bytes: Vec<u8>) -> error::Result<(String, NBTValue)> {
+    NBTReader::from(bytes).parse_nbt()
+}
+
+/// Writes one JSON object per tag in the file to `out`, e.g.
+/// `{"path": "Data.GameRules.doFireTick", "kind": "String", "value": "true"}`.
+/// Compounds and lists get an event of their own (lists include their `length`) before their
+/// children. Events are written as they are parsed so large files can be piped into other tools.
+pub fn dump_ndjson<W: io::Write>(filename: &str, out: W) -> error::Result<()> {
+    let mut stream = NBTReader::new(filename)?;
+    stream.dump_ndjson(out)
+}
+
+pub fn dump_ndjson_from_bytes<W: io::Write>(bytes: Vec<u8>, out: W) -> error::Result<()> {
+    let mut stream = NBTReader::from(bytes);
+    stream.dump_ndjson(out)
+}
+
+/// Writes a hexdump of the file to `out` in which the raw bytes are split at tag boundaries and
+/// every piece is labelled with what it encodes: the offset, kind and name of each tag along with
+/// the extent of its payload, list headers and decoded values. Useful for finding where a
+/// corrupt document goes wrong or where two writers differ.
+pub fn hexdump<W: io::Write>(filename: &str, out: W) -> error::Result<()> {
+    let mut stream = NBTReader::new(filename)?;
+    stream.hexdump(out)
+}
+
+pub fn hexdump_from_bytes<W: io::Write>(bytes: Vec<u8>, out: W) -> error::Result<()> {
     let mut stream = NBTReader::from(bytes);
-    let tag = stream.parse_nbt();
-    match tag {
-        Some(tag) => {
-            println!("{:#?}", tag.payload);
-            Ok(())
+    stream.hexdump(out)
+}
+
+// A run of bytes in a hexdump and the label written next to it.
+struct Segment {
+    start: u64,
+    end: u64,
+    depth: usize,
+    label: String,
+}
+
+// Sixteen bytes to a line, the label goes on the first line of the segment.
+fn write_hex_segment<W: io::Write>(out: &mut W, bytes: &[u8], segment: &Segment) -> io::Result<()> {
+    let data = &bytes[segment.start as usize..segment.end as usize];
+    let mut lines = data.chunks(16);
+    let first = lines.next().unwrap_or(&[]);
+    write!(out, "{:08x} ", segment.start)?;
+    for byte in first {
+        write!(out, " {:02x}", byte)?;
+    }
+    let padding = (16 - first.len()) * 3 + 2 + segment.depth * 2;
+    writeln!(out, "{:1$}{2}", "", padding, segment.label)?;
+    for (i, line) in lines.enumerate() {
+        write!(out, "{:08x} ", segment.start + (i as u64 + 1) * 16)?;
+        for byte in line {
+            write!(out, " {:02x}", byte)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_json_string<W: io::Write>(out: &mut W, string: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    for c in string.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+// JSON has no representation for NaN or infinities so those are written as strings.
+fn write_json_float<W: io::Write>(out: &mut W, value: f64) -> io::Result<()> {
+    if value.is_finite() {
+        write!(out, "{}", value)
+    } else {
+        write_json_string(out, &value.to_string())
+    }
+}
+
+fn write_json_array<W: io::Write, T: std::fmt::Display>(
+    out: &mut W,
+    values: &[T],
+) -> io::Result<()> {
+    out.write_all(b"[")?;
+    for (i, value) in values.iter().enumerate() {
+        if i != 0 {
+            out.write_all(b",")?;
         }
-        None => Err(Error::ExpectedRootCompound),
+        write!(out, "{}", value)?;
     }
+    out.write_all(b"]")
 }
 
 struct NBTReader {
@@ -75,27 +295,30 @@ struct NBTReader {
 impl From<Vec<u8>> for NBTReader {
     fn from(value: Vec<u8>) -> Self {
         Self {
-            cursor: Cursor::new(value)
+            cursor: Cursor::new(value),
         }
     }
 }
 
+#[cfg(feature = "compression")]
 const GZIP_SIGNATURE: [u8; 2] = [0x1f, 0x8b];
 impl NBTReader {
     fn new(filename: &str) -> io::Result<Self> {
         let bytes = fs::read(filename)?;
         // Decompress the file if its gzipped
-        let cursor = if bytes[..2] == GZIP_SIGNATURE {
+        #[cfg(feature = "compression")]
+        if bytes.starts_with(&GZIP_SIGNATURE) {
             let mut decoder = GzDecoder::new(bytes.as_slice());
             let mut decompressed_bytes = Vec::new();
             decoder.read_to_end(&mut decompressed_bytes)?;
-            println!("{:#x?}", decompressed_bytes);
-            Cursor::new(decompressed_bytes)
-        } else {
-            Cursor::new(bytes)
-        };
+            return Ok(Self {
+                cursor: Cursor::new(decompressed_bytes),
+            });
+        }
 
-        Ok(Self { cursor })
+        Ok(Self {
+            cursor: Cursor::new(bytes),
+        })
     }
 
     fn has_bytes_left(&self) -> bool {
@@ -103,56 +326,66 @@ impl NBTReader {
         (self.cursor.position() as usize) < len.saturating_sub(1)
     }
 
-    fn parse_nbt(&mut self) -> Option<NBTTag> {
+    fn parse_nbt(&mut self) -> error::Result<(String, NBTValue)> {
         if !self.has_bytes_left() {
-            None
-        } else {
-            match self.parse_nbt_tag() {
-                Ok(tag) => Some(tag),
-                Err(e) => panic!("Error reading tag {}", e),
-            }
+            return Err(Error::ExpectedRootCompound);
+        }
+        let tag = self.parse_nbt_tag()?;
+        Ok((tag.name, tag.payload.ok_or(Error::ExpectedRootCompound)?))
+    }
+
+    // Reads an array length, checked against the bytes left in the document.
+    fn parse_length(&mut self, element_size: u64) -> error::Result<usize> {
+        let length = self.cursor.read_i32::<byteorder::BigEndian>()?;
+        let remaining = self.cursor.get_ref().len() as u64 - self.cursor.position();
+        checked_length(length, element_size, Some(remaining), LengthPolicy::Lenient)
+    }
+
+    // Reads the length of a list of `element_kind`, which can only be End if it is empty.
+    fn parse_list_length(&mut self, element_kind: NBTKind) -> error::Result<usize> {
+        let length = self.parse_length(min_payload_size(element_kind))?;
+        if element_kind == NBTKind::End && length > 0 {
+            // Came from an `i32`, so it fits back in one.
+            return Err(Error::NonEmptyEndList(length as i32));
         }
+        Ok(length)
     }
 
     // Reads the elements of an array of type T
     fn parse_array<T>(
         &mut self,
+        element_size: u64,
         element_type: fn(&mut NBTReader) -> io::Result<T>,
-    ) -> io::Result<Vec<T>> {
-        let length = self.cursor.read_i32::<byteorder::BigEndian>()?;
-        let mut array = Vec::with_capacity(length as usize);
+    ) -> error::Result<Vec<T>> {
+        let length = self.parse_length(element_size)?;
+        let mut array = Vec::with_capacity(length);
         for _ in 0..length {
             array.push(element_type(self)?);
         }
         Ok(array)
     }
 
-    fn parse_nbt_payload(&mut self, kind: &NBTKind) -> io::Result<NBTPayload> {
+    fn parse_nbt_payload(&mut self, kind: &NBTKind) -> error::Result<NBTValue> {
         Ok(match kind {
             // A single signed byte
-            NBTKind::Byte => NBTPayload::Byte(self.cursor.read_i8()?),
+            NBTKind::Byte => NBTValue::Byte(self.cursor.read_i8()?),
             // A single signed, big endian 16 bit integer
-            NBTKind::Short => NBTPayload::Short(self.cursor.read_i16::<byteorder::BigEndian>()?),
+            NBTKind::Short => NBTValue::Short(self.cursor.read_i16::<byteorder::BigEndian>()?),
             // A single signed, big endian 32 bit integer
-            NBTKind::Int => NBTPayload::Int(self.cursor.read_i32::<byteorder::BigEndian>()?),
+            NBTKind::Int => NBTValue::Int(self.cursor.read_i32::<byteorder::BigEndian>()?),
             // A single signed, big endian 64 bit integer
-            NBTKind::Long => NBTPayload::Long(self.cursor.read_i64::<byteorder::BigEndian>()?),
+            NBTKind::Long => NBTValue::Long(self.cursor.read_i64::<byteorder::BigEndian>()?),
             // A single, big endian IEEE-754 single-precision floating point number (NaN possible)
-            NBTKind::Float => NBTPayload::Float(self.cursor.read_f32::<byteorder::BigEndian>()?),
+            NBTKind::Float => NBTValue::Float(self.cursor.read_f32::<byteorder::BigEndian>()?),
             // A single, big endian IEEE-754 double-precision floating point number (NaN possible)
-            NBTKind::Double => NBTPayload::Double(self.cursor.read_f64::<byteorder::BigEndian>()?),
+            NBTKind::Double => NBTValue::Double(self.cursor.read_f64::<byteorder::BigEndian>()?),
             // A length-prefixed array of signed bytes. The prefix is a signed integer (thus 4 bytes)
             NBTKind::ByteArray => {
-                let bytes = self.parse_array(|reader| reader.cursor.read_i8())?;
-                NBTPayload::ByteArray(bytes)
+                let bytes = self.parse_array(1, |reader| reader.cursor.read_i8())?;
+                NBTValue::ByteArray(bytes)
             }
             // A length-prefixed modified UTF-8 string. The prefix is an unsigned short (thus 2 bytes) signifying the length of the string in bytes
-            NBTKind::String => {
-                let str_len = self.cursor.read_u16::<byteorder::BigEndian>()?;
-                let mut str_bytes = vec![0u8; str_len as usize];
-                self.cursor.read_exact(&mut str_bytes)?;
-                NBTPayload::String(String::from_utf8(str_bytes).unwrap())
-            }
+            NBTKind::String => NBTValue::String(self.parse_name()?),
             // A list of nameless tags, all of the same type.
             // The list is prefixed with the Type ID of the items it contains (thus 1 byte),
             // and the length of the list as a signed integer (a further 4 bytes).
@@ -161,60 +394,242 @@ impl NBTReader {
             // but another reference implementation by Mojang uses 1 instead; parsers should accept any type
             // if the length is <= 0).
             NBTKind::List => {
-                let list_nbt_type = NBTKind::from(self.cursor.read_u8()?);
-                let length = self.cursor.read_i32::<byteorder::BigEndian>()?;
-                let mut payload = Vec::with_capacity(length as usize);
+                let list_nbt_type = NBTKind::try_from(self.cursor.read_u8()?)?;
+                let length = self.parse_list_length(list_nbt_type)?;
+                let mut payload = Vec::with_capacity(length);
                 for _ in 0..length {
                     let tag_value = self.parse_nbt_payload(&list_nbt_type)?;
                     payload.push(tag_value)
                 }
 
-                NBTPayload::List(payload)
+                NBTValue::List(payload)
             }
             // Effectively a list of named tags. Order is not guaranteed.
             NBTKind::Compound => {
-                let mut map: BTreeMap<String, NBTPayload> = BTreeMap::new();
-                loop {
-                    let tag = self.parse_nbt_tag()?;
-                    if let NBTKind::End = tag.kind {
-                        break;
-                    }
-                    map.insert(tag.name.unwrap(), tag.payload);
+                let mut map = CompoundMap::default();
+                while let NBTTag {
+                    name,
+                    payload: Some(payload),
+                } = self.parse_nbt_tag()?
+                {
+                    map.insert(name, payload);
                 }
-                NBTPayload::Compound(map)
+                NBTValue::Compound(map)
             }
             // A length-prefixed array of signed integers. The prefix is a signed integer (thus 4 bytes) and indicates the number of 4 byte integers.
             NBTKind::IntArray => {
                 let ints =
-                    self.parse_array(|reader| reader.cursor.read_i32::<byteorder::BigEndian>())?;
-                NBTPayload::IntArray(ints)
+                    self.parse_array(4, |reader| reader.cursor.read_i32::<byteorder::BigEndian>())?;
+                NBTValue::IntArray(ints)
             }
             // A length-prefixed array of signed longs. The prefix is a signed integer (thus 4 bytes) and indicates the number of 8 byte longs.
             NBTKind::LongArray => {
                 let longs =
-                    self.parse_array(|reader| reader.cursor.read_i64::<byteorder::BigEndian>())?;
-                NBTPayload::LongArray(longs)
+                    self.parse_array(8, |reader| reader.cursor.read_i64::<byteorder::BigEndian>())?;
+                NBTValue::LongArray(longs)
             }
-            _ => NBTPayload::Empty,
+            NBTKind::End => return Err(Error::InvalidTagId),
         })
     }
 
-    fn parse_nbt_tag(&mut self) -> io::Result<NBTTag> {
+    fn dump_ndjson<W: io::Write>(&mut self, mut out: W) -> error::Result<()> {
+        let kind = NBTKind::try_from(self.cursor.read_u8()?)?;
+        if kind != NBTKind::Compound {
+            return Err(Error::ExpectedRootCompound);
+        }
+        // The root name is not part of any path.
+        self.parse_name()?;
+        let mut path = String::new();
+        self.write_ndjson_events(kind, &mut path, &mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn write_ndjson_events<W: io::Write>(
+        &mut self,
+        kind: NBTKind,
+        path: &mut String,
+        out: &mut W,
+    ) -> error::Result<()> {
+        out.write_all(b"{\"path\": ")?;
+        write_json_string(out, path)?;
+        write!(out, ", \"kind\": \"{}\"", kind)?;
+        match kind {
+            NBTKind::Compound => {
+                out.write_all(b"}\n")?;
+                loop {
+                    let child_kind = NBTKind::try_from(self.cursor.read_u8()?)?;
+                    if let NBTKind::End = child_kind {
+                        break;
+                    }
+                    let name = self.parse_name()?;
+                    let parent_length = path.len();
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(&name);
+                    self.write_ndjson_events(child_kind, path, out)?;
+                    path.truncate(parent_length);
+                }
+            }
+            NBTKind::List => {
+                let element_kind = NBTKind::try_from(self.cursor.read_u8()?)?;
+                let length = self.parse_list_length(element_kind)?;
+                writeln!(out, ", \"length\": {}}}", length)?;
+                for i in 0..length {
+                    let parent_length = path.len();
+                    path.push_str(&format!("[{}]", i));
+                    self.write_ndjson_events(element_kind, path, out)?;
+                    path.truncate(parent_length);
+                }
+            }
+            _ => {
+                out.write_all(b", \"value\": ")?;
+                match self.parse_nbt_payload(&kind)? {
+                    NBTValue::Byte(value) => write!(out, "{}", value)?,
+                    NBTValue::Short(value) => write!(out, "{}", value)?,
+                    NBTValue::Int(value) => write!(out, "{}", value)?,
+                    NBTValue::Long(value) => write!(out, "{}", value)?,
+                    NBTValue::Float(value) => write_json_float(out, value as f64)?,
+                    NBTValue::Double(value) => write_json_float(out, value)?,
+                    NBTValue::String(value) => write_json_string(out, &value)?,
+                    NBTValue::ByteArray(values) => write_json_array(out, &values)?,
+                    NBTValue::IntArray(values) => write_json_array(out, &values)?,
+                    NBTValue::LongArray(values) => write_json_array(out, &values)?,
+                    _ => return Err(Error::InvalidTagId),
+                }
+                out.write_all(b"}\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn hexdump<W: io::Write>(&mut self, mut out: W) -> error::Result<()> {
+        let mut segments = Vec::new();
+        if self.annotate_tag(0, &mut segments)? != NBTKind::Compound {
+            return Err(Error::ExpectedRootCompound);
+        }
+        for segment in &segments {
+            write_hex_segment(&mut out, self.cursor.get_ref(), segment)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    // Splits the next named tag into segments and returns its kind.
+    fn annotate_tag(
+        &mut self,
+        depth: usize,
+        segments: &mut Vec<Segment>,
+    ) -> error::Result<NBTKind> {
+        let start = self.cursor.position();
+        let kind = NBTKind::try_from(self.cursor.read_u8()?)?;
+        if kind == NBTKind::End {
+            segments.push(Segment {
+                start,
+                end: start + 1,
+                depth,
+                label: "End".to_owned(),
+            });
+            return Ok(kind);
+        }
+        let name = self.parse_name()?;
+        let payload_start = self.cursor.position();
+        // The label needs the end of the payload, so it is filled in once that has been read.
+        let header = segments.len();
+        segments.push(Segment {
+            start,
+            end: payload_start,
+            depth,
+            label: String::new(),
+        });
+        self.annotate_payload(kind, depth + 1, String::new(), segments)?;
+        segments[header].label = format!(
+            "{} {:?} payload {}..{}",
+            kind,
+            name,
+            payload_start,
+            self.cursor.position()
+        );
+        Ok(kind)
+    }
+
+    // Splits the next payload of type `kind` into segments. `prefix` is prepended to the label of
+    // its first segment, list elements use it for their index.
+    fn annotate_payload(
+        &mut self,
+        kind: NBTKind,
+        depth: usize,
+        prefix: String,
+        segments: &mut Vec<Segment>,
+    ) -> error::Result<()> {
+        let start = self.cursor.position();
+        match kind {
+            NBTKind::Compound => {
+                if !prefix.is_empty() {
+                    segments.push(Segment {
+                        start,
+                        end: start,
+                        depth,
+                        label: format!("{}Compound", prefix),
+                    });
+                }
+                while self.annotate_tag(depth, segments)? != NBTKind::End {}
+            }
+            NBTKind::List => {
+                let element_kind = NBTKind::try_from(self.cursor.read_u8()?)?;
+                let length = self.parse_list_length(element_kind)?;
+                segments.push(Segment {
+                    start,
+                    end: self.cursor.position(),
+                    depth,
+                    label: format!("{}{} entries of {}", prefix, length, element_kind),
+                });
+                for i in 0..length {
+                    self.annotate_payload(element_kind, depth, format!("[{}] ", i), segments)?;
+                }
+            }
+            _ => {
+                let label = match self.parse_nbt_payload(&kind)? {
+                    NBTValue::ByteArray(array) => format!("{} bytes", array.len()),
+                    NBTValue::IntArray(array) => format!("{} ints", array.len()),
+                    NBTValue::LongArray(array) => format!("{} longs", array.len()),
+                    payload => format!("{:?}", payload),
+                };
+                segments.push(Segment {
+                    start,
+                    end: self.cursor.position(),
+                    depth,
+                    label: prefix + &label,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_name(&mut self) -> io::Result<String> {
+        // A two byte big-endian unsigned integer for the length of the name
+        let name_length = self.cursor.read_u16::<byteorder::BigEndian>()?;
+        let mut buffer = vec![0u8; name_length as usize];
+        self.cursor.read_exact(&mut buffer)?;
+        String::from_utf8(buffer).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn parse_nbt_tag(&mut self) -> error::Result<NBTTag> {
         // The first byte in a tag is the tag type (ID)
-        let kind = NBTKind::from(self.cursor.read_u8()?);
-        let name = if let NBTKind::End = kind {
+        let kind = NBTKind::try_from(self.cursor.read_u8()?)?;
+        if let NBTKind::End = kind {
             // (Note TAG_End is not named and does not contain the extra 2 bytes; the name is assumed to be empty).
-            String::new()
-        } else {
-            // // followed by a two byte big-endian unsigned integer for the length of the name
-            let name_length = self.cursor.read_u16::<byteorder::BigEndian>()?;
-            let mut buffer = vec![0u8; name_length as usize];
-            self.cursor.read_exact(&mut buffer)?;
-            String::from_utf8(buffer).unwrap()
-        };
-
+            return Ok(NBTTag {
+                name: String::new(),
+                payload: None,
+            });
+        }
+        let name = self.parse_name()?;
         let payload = self.parse_nbt_payload(&kind)?;
-
-        Ok(NBTTag::new(kind, Some(name), payload))
+        Ok(NBTTag {
+            name,
+            payload: Some(payload),
+        })
     }
 }
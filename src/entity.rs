@@ -0,0 +1,447 @@
+//! Entities as stored in the entity region files that Java Edition has used since 1.17.
+use std::{
+    collections::HashMap,
+    fs,
+    ops::ControlFlow,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{self, Error},
+    item::AttributeModifier,
+    region::{chunk_at_block, region_at_chunk, Progress, RegionFile},
+    tag::{CompoundMap, NBTValue},
+    world::SessionLock,
+};
+
+#[test]
+fn test_find_entities() {
+    #[derive(Serialize)]
+    struct EntityChunk {
+        #[serde(rename = "Entities")]
+        entities: Vec<Entity>,
+    }
+
+    let entity = |id: &str, x: f64| Entity {
+        id: id.to_owned(),
+        pos: vec![x, 64.0, 0.5],
+        uuid: u128::MAX - 1,
+    };
+    let chunk = |entities| crate::to_bytes(&EntityChunk { entities }).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("entities")).unwrap();
+    fs::write(
+        dir.path().join("entities/r.0.0.mca"),
+        crate::region::test_region(&[
+            (0, 0, chunk(vec![entity("minecraft:villager", 1.0)])),
+            (1, 0, chunk(vec![entity("minecraft:cow", 17.0)])),
+        ]),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("entities/r.-1.0.mca"),
+        crate::region::test_region(&[(31, 0, chunk(vec![entity("minecraft:villager", -3.0)]))]),
+    )
+    .unwrap();
+
+    let mut villagers = find_entities(dir.path(), |e| e.id == "minecraft:villager").unwrap();
+    villagers.sort_by(|a, b| a.pos[0].total_cmp(&b.pos[0]));
+    assert_eq!(
+        villagers,
+        [
+            entity("minecraft:villager", -3.0),
+            entity("minecraft:villager", 1.0)
+        ]
+    );
+    let nearby = find_entities(dir.path(), |e| e.within([0.0; 3], [32.0, 128.0, 32.0])).unwrap();
+    assert_eq!(nearby.len(), 2);
+
+    let mut reports = Vec::new();
+    find_entities_with_progress(
+        dir.path(),
+        |_| true,
+        |progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+    assert_eq!(reports.len(), 3);
+    assert_eq!(reports[2].chunks_done, 3);
+    assert_eq!(reports[2].chunks_total, 3);
+    assert!(reports[0].bytes_processed > 0);
+    let cancelled = find_entities_with_progress(dir.path(), |_| true, |_| ControlFlow::Break(()));
+    assert!(matches!(cancelled, Err(Error::Cancelled)));
+}
+
+#[test]
+fn test_remove_and_relocate_entities() {
+    use crate::tag::CompoundBuilder;
+
+    let entity = |id: &str, x: f64, uuid: i32| {
+        CompoundBuilder::new()
+            .string("id", id)
+            .list("Pos", [x, 64.0, 0.5].map(NBTValue::Double))
+            .int_array("UUID", [0, 0, 0, uuid])
+            .int("Age", 7)
+            .build()
+    };
+    let chunk = |x: i32, entities: Vec<NBTValue>| {
+        let chunk = CompoundBuilder::new()
+            .int("DataVersion", 3465)
+            .int_array("Position", [x, 0])
+            .list("Entities", entities)
+            .build();
+        crate::to_bytes(&chunk).unwrap()
+    };
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("entities")).unwrap();
+    fs::write(
+        dir.path().join("entities/r.0.0.mca"),
+        crate::region::test_region(&[(
+            0,
+            0,
+            chunk(
+                0,
+                vec![
+                    entity("minecraft:cow", 1.0, 1),
+                    entity("minecraft:cow", 2.0, 2),
+                    entity("minecraft:item", 3.0, 3),
+                    entity("minecraft:pig", 4.0, 4),
+                ],
+            ),
+        )]),
+    )
+    .unwrap();
+
+    let lock = crate::world::World::open(dir.path())
+        .unwrap()
+        .lock_session()
+        .unwrap();
+    let removed = remove_entities(dir.path(), &lock, |e| {
+        e.id == "minecraft:item" || e.uuid == 2
+    })
+    .unwrap();
+    assert_eq!(removed, 2);
+    // One pig moves within its chunk and the cow to a chunk in another region.
+    let moved = relocate_entities(dir.path(), &lock, |e| match e.id.as_str() {
+        "minecraft:pig" => Some([5.0, 70.0, 0.5]),
+        "minecraft:cow" => Some([-20.0, 64.0, 0.5]),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(moved, 2);
+
+    let mut found = find_entities(dir.path(), |_| true).unwrap();
+    found.sort_by_key(|e| e.uuid);
+    let found: Vec<_> = found.iter().map(|e| (e.uuid, e.pos.clone())).collect();
+    assert_eq!(
+        found,
+        [(1, vec![-20.0, 64.0, 0.5]), (4, vec![5.0, 70.0, 0.5])]
+    );
+    let mut region = RegionFile::open(dir.path().join("entities/r.-1.0.mca")).unwrap();
+    let cow: NBTValue = crate::from_slice(&region.read_chunk(30, 0).unwrap().unwrap()).unwrap();
+    let NBTValue::Compound(cow) = cow else {
+        panic!("chunk is not a compound");
+    };
+    assert_eq!(cow["Position"], NBTValue::IntArray(vec![-2, 0]));
+    assert_eq!(cow["DataVersion"], NBTValue::Int(3465));
+    let NBTValue::List(entities) = &cow["Entities"] else {
+        panic!("entities are not a list");
+    };
+    assert_eq!(entities[0], {
+        let mut cow = entity("minecraft:cow", -20.0, 1);
+        if let NBTValue::Compound(cow) = &mut cow {
+            cow.insert(
+                "Pos".into(),
+                NBTValue::List([-20.0, 64.0, 0.5].map(NBTValue::Double).into()),
+            );
+        }
+        cow
+    });
+
+    fs::write(dir.path().join("session.lock"), 1i64.to_be_bytes()).unwrap();
+    let removed = remove_entities(dir.path(), &lock, |_| true);
+    assert!(matches!(removed, Err(Error::SessionLocked)));
+}
+
+/// The fields every entity has that identify it and place it in the world. Other fields are
+/// skipped when reading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    /// Namespaced type, such as `minecraft:villager`.
+    pub id: String,
+    /// Position as x, y and z.
+    #[serde(rename = "Pos")]
+    pub pos: Vec<f64>,
+    #[serde(rename = "UUID", with = "crate::with::u128_as_int_array")]
+    pub uuid: u128,
+}
+
+impl Entity {
+    /// Whether the entity stands inside the box spanned by the corners `min` and `max`.
+    pub fn within(&self, min: [f64; 3], max: [f64; 3]) -> bool {
+        self.pos.len() == 3 && (0..3).all(|i| (min[i]..=max[i]).contains(&self.pos[i]))
+    }
+}
+
+/// An entry of a living entity's `Attributes` list, up to 1.20.4.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attribute {
+    /// Namespaced attribute, such as `minecraft:generic.max_health`.
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Base")]
+    pub base: f64,
+    #[serde(rename = "Modifiers", default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+#[derive(Deserialize)]
+struct EntityChunk {
+    #[serde(rename = "Entities", default)]
+    entities: Vec<Entity>,
+}
+
+/// Reads every entity in the `entities` folder of the dimension stored in `dir` and returns those
+/// that `filter` accepts. With the `rayon` feature the region files are read in parallel, in which
+/// case the order of the results is unspecified.
+pub fn find_entities<P, F>(dir: P, filter: F) -> error::Result<Vec<Entity>>
+where
+    P: AsRef<Path>,
+    F: Fn(&Entity) -> bool + Sync,
+{
+    find_entities_with_progress(dir, filter, |_| ControlFlow::Continue(()))
+}
+
+/// Like [`find_entities`], but calls `progress` after each chunk that was read.
+pub fn find_entities_with_progress<P, F, G>(
+    dir: P,
+    filter: F,
+    progress: G,
+) -> error::Result<Vec<Entity>>
+where
+    P: AsRef<Path>,
+    F: Fn(&Entity) -> bool + Sync,
+    G: FnMut(Progress) -> ControlFlow<()> + Send,
+{
+    let mut paths = Vec::new();
+    let mut chunks_total = 0;
+    for entry in fs::read_dir(dir.as_ref().join("entities"))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "mca") {
+            // Only the header is read here, the files are opened again one at a time below.
+            chunks_total += RegionFile::open(&path)?.chunks().count();
+            paths.push(path);
+        }
+    }
+
+    let state = Mutex::new((
+        Progress {
+            chunks_total,
+            ..Progress::default()
+        },
+        progress,
+    ));
+    let cancelled = AtomicBool::new(false);
+    let find_in = |path: &Path| -> error::Result<Vec<Entity>> {
+        let mut region = RegionFile::open(path)?;
+        let mut found = Vec::new();
+        for (x, z) in region.chunks().collect::<Vec<_>>() {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err(Error::Cancelled);
+            }
+            let Some(stored) = region.read_stored_chunk(x, z)? else {
+                continue;
+            };
+            let chunk: EntityChunk = crate::from_slice(&stored.decompress()?)?;
+            found.extend(chunk.entities.into_iter().filter(&filter));
+
+            let mut state = state.lock().unwrap();
+            let (progress, callback) = &mut *state;
+            if let Err(err) = progress.advance(stored.data.len() as u64 + 5, callback) {
+                cancelled.store(true, Ordering::Relaxed);
+                return Err(err);
+            }
+        }
+        Ok(found)
+    };
+
+    #[cfg(feature = "rayon")]
+    let found: error::Result<Vec<_>> = paths.par_iter().map(|path| find_in(path)).collect();
+    #[cfg(not(feature = "rayon"))]
+    let found: error::Result<Vec<_>> = paths.iter().map(|path| find_in(path)).collect();
+    Ok(found?.into_iter().flatten().collect())
+}
+
+/// Removes every entity in the `entities` folder of the dimension stored in `dir` that `filter`
+/// accepts, such as those of one id or with one UUID, and returns how many were removed. `lock`
+/// must have been taken on the world that `dir` belongs to with
+/// [`World::lock_session`](crate::world::World::lock_session), and is checked before each chunk is
+/// written, failing with [`Error::SessionLocked`] if someone took the world over.
+pub fn remove_entities<P, F>(dir: P, lock: &SessionLock, filter: F) -> error::Result<usize>
+where
+    P: AsRef<Path>,
+    F: Fn(&Entity) -> bool,
+{
+    edit_entities(dir.as_ref(), lock, |entity| filter(entity).then_some(None))
+}
+
+/// Moves entities in the `entities` folder of the dimension stored in `dir` to the position
+/// `relocate` returns for them, leaving those it returns `None` for where they are. Entities that
+/// end up in another chunk are stored with that chunk, which is created if it has no entities yet.
+/// Returns how many entities were moved. Passengers keep their old position, which the game
+/// corrects when it loads them. `lock` is checked as in [`remove_entities`].
+pub fn relocate_entities<P, F>(dir: P, lock: &SessionLock, relocate: F) -> error::Result<usize>
+where
+    P: AsRef<Path>,
+    F: Fn(&Entity) -> Option<[f64; 3]>,
+{
+    edit_entities(dir.as_ref(), lock, |entity| relocate(entity).map(Some))
+}
+
+// Rewrites the entity chunks in `dir`. `edit` returns `None` to keep an entity as it is,
+// `Some(None)` to remove it and `Some(Some(pos))` to move it to `pos`.
+fn edit_entities<F>(dir: &Path, lock: &SessionLock, edit: F) -> error::Result<usize>
+where
+    F: Fn(&Entity) -> Option<Option<[f64; 3]>>,
+{
+    let dir = dir.join("entities");
+    let mut edited = 0;
+    // Entities leaving their chunk, by the chunk they go to, along with the data version of the
+    // chunk they came from.
+    let mut moved: HashMap<(i32, i32), Vec<(NBTValue, NBTValue)>> = HashMap::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "mca") {
+            continue;
+        }
+        let mut region = RegionFile::open_writable(&path)?;
+        for (x, z) in region.chunks().collect::<Vec<_>>() {
+            let Some(bytes) = region.read_chunk(x, z)? else {
+                continue;
+            };
+            let NBTValue::Compound(mut chunk) = crate::from_slice(&bytes)? else {
+                continue;
+            };
+            // Only put back if something changed, otherwise the chunk isn't written.
+            let Some(NBTValue::List(entities)) = chunk.remove("Entities") else {
+                continue;
+            };
+            let position = chunk_position(chunk.get("Position"));
+            let data_version = chunk.get("DataVersion").cloned();
+            let mut changed = false;
+            let mut kept = Vec::new();
+            for mut value in entities {
+                let Some(action) = entity_of(&value).and_then(|entity| edit(&entity)) else {
+                    kept.push(value);
+                    continue;
+                };
+                changed = true;
+                edited += 1;
+                let Some(pos) = action else {
+                    continue;
+                };
+                if let NBTValue::Compound(entity) = &mut value {
+                    entity.insert(
+                        "Pos".into(),
+                        NBTValue::List(pos.map(NBTValue::Double).into()),
+                    );
+                }
+                let target = chunk_at_block(pos[0].floor() as i32, pos[2].floor() as i32);
+                if Some(target) == position {
+                    kept.push(value);
+                } else {
+                    let data_version = data_version.clone().unwrap_or(NBTValue::Int(0));
+                    moved.entry(target).or_default().push((value, data_version));
+                }
+            }
+            if changed {
+                chunk.insert("Entities".into(), NBTValue::List(kept));
+                let bytes = crate::to_bytes(&NBTValue::Compound(chunk))?;
+                lock.check_for(&dir)?;
+                region.write_chunk(x, z, &bytes)?;
+            }
+        }
+    }
+
+    for ((chunk_x, chunk_z), arriving) in moved {
+        let (region_x, region_z) = region_at_chunk(chunk_x, chunk_z);
+        let path = dir.join(format!("r.{}.{}.mca", region_x, region_z));
+        let mut region = RegionFile::open_writable(path)?;
+        let (x, z) = ((chunk_x & 31) as usize, (chunk_z & 31) as usize);
+        let mut chunk = match region.read_chunk(x, z)? {
+            Some(bytes) => match crate::from_slice(&bytes)? {
+                NBTValue::Compound(chunk) => chunk,
+                _ => CompoundMap::default(),
+            },
+            None => CompoundMap::default(),
+        };
+        if !chunk.contains_key("DataVersion") {
+            chunk.insert("DataVersion".into(), arriving[0].1.clone());
+        }
+        chunk.insert(
+            "Position".into(),
+            NBTValue::IntArray(vec![chunk_x, chunk_z]),
+        );
+        let entities = chunk
+            .entry("Entities".into())
+            .or_insert_with(|| NBTValue::List(Vec::new()));
+        if let NBTValue::List(entities) = entities {
+            entities.extend(arriving.into_iter().map(|(entity, _)| entity));
+        }
+        let bytes = crate::to_bytes(&NBTValue::Compound(chunk))?;
+        lock.check_for(&dir)?;
+        region.write_chunk(x, z, &bytes)?;
+    }
+    Ok(edited)
+}
+
+// Chunk coordinates from the `Position` of an entity chunk.
+fn chunk_position(position: Option<&NBTValue>) -> Option<(i32, i32)> {
+    match position {
+        Some(NBTValue::IntArray(position)) if position.len() == 2 => {
+            Some((position[0], position[1]))
+        }
+        _ => None,
+    }
+}
+
+// The identifying fields of an entity compound, or `None` if it lacks any of them.
+fn entity_of(value: &NBTValue) -> Option<Entity> {
+    let NBTValue::Compound(entity) = value else {
+        return None;
+    };
+    let Some(NBTValue::String(id)) = entity.get("id") else {
+        return None;
+    };
+    let Some(NBTValue::List(pos)) = entity.get("Pos") else {
+        return None;
+    };
+    let pos = pos
+        .iter()
+        .map(|coordinate| match coordinate {
+            NBTValue::Double(coordinate) => Some(*coordinate),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let uuid = match entity.get("UUID") {
+        Some(NBTValue::IntArray(ints)) if ints.len() == 4 => ints
+            .iter()
+            .fold(0, |uuid, &int| uuid << 32 | int as u32 as u128),
+        _ => return None,
+    };
+    Some(Entity {
+        id: id.clone(),
+        pos,
+        uuid,
+    })
+}
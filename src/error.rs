@@ -1,10 +1,8 @@
-use crate::kind::NBTKind;
+use crate::{io, kind::NBTKind};
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display};
 use serde::{de, ser};
-use std::{
-    fmt::{self, Display},
-    io,
-};
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
@@ -13,9 +11,47 @@ pub enum Error {
     Eof,
     ExpectedRootCompound,
     InvalidTagId,
+    UnknownKind(String),
     MismatchedTag(NBTKind, NBTKind),
     ExpectedBooleanByte(i8),
     Unrepresentable,
+    SizeLimitExceeded(u64),
+    DuplicateKey(String),
+    MalformedList { declared: NBTKind, offset: u64 },
+    NonEmptyEndList(i32),
+    InvalidLength(i64),
+    StringTooLong(usize),
+    InvalidPath(String),
+    PathNotFound(String),
+    TruncatedGzip,
+    UnknownCompression(u8),
+    InvalidBlockState(String),
+    InvalidResourceLocation(String),
+    UnsupportedDataVersion(i32),
+    ChunkOutsideRegion(i32, i32),
+    MalformedPalettedData { palette: usize, longs: usize },
+    Cancelled,
+    SessionLocked,
+    BudgetExceeded,
+}
+
+impl Error {
+    /// Reports errors that mean a list's elements don't line up with its declared type as a
+    /// malformed list starting at `offset`, rather than whatever the misaligned read ran into.
+    pub(crate) fn in_list(self, declared: NBTKind, offset: u64) -> Self {
+        match self {
+            Error::Eof | Error::InvalidTagId => Error::MalformedList { declared, offset },
+            Error::IoError(ref err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData
+                ) =>
+            {
+                Error::MalformedList { declared, offset }
+            }
+            err => err,
+        }
+    }
 }
 
 impl ser::Error for Error {
@@ -42,15 +78,93 @@ impl Display for Error {
                 formatter.write_fmt(format_args!("Expected a boolean value but got {}", byte))
             }
             Error::Eof => formatter.write_str("unexpected end of input"),
-            _ => todo!("Fill out errors: {}", self),
+            Error::IoError(err) => Display::fmt(err, formatter),
+            Error::ExpectedRootCompound => formatter.write_str("expected a root compound tag"),
+            Error::InvalidTagId => formatter.write_str("invalid tag id"),
+            Error::UnknownKind(name) => {
+                formatter.write_fmt(format_args!("{:?} is not the name of a tag kind", name))
+            }
+            Error::Unrepresentable => formatter.write_str("value cannot be represented as NBT"),
+            Error::DuplicateKey(key) => {
+                formatter.write_fmt(format_args!("compound contains the key {:?} twice", key))
+            }
+            Error::MalformedList { declared, offset } => formatter.write_fmt(format_args!(
+                "list of {} at offset {} does not match its contents",
+                declared, offset
+            )),
+            Error::NonEmptyEndList(length) => formatter.write_fmt(format_args!(
+                "list of End declares {} elements, only empty lists may be of type End",
+                length
+            )),
+            Error::InvalidLength(length) => formatter.write_fmt(format_args!(
+                "length {} is negative or longer than the rest of the input",
+                length
+            )),
+            Error::InvalidPath(path) => {
+                formatter.write_fmt(format_args!("{:?} is not a valid NBT path", path))
+            }
+            Error::PathNotFound(path) => {
+                formatter.write_fmt(format_args!("document has no value at {}", path))
+            }
+            Error::StringTooLong(length) => formatter.write_fmt(format_args!(
+                "string of {} bytes is longer than a String tag can hold",
+                length
+            )),
+            Error::TruncatedGzip => formatter.write_str("gzip stream ended before its trailer"),
+            Error::InvalidBlockState(state) => {
+                formatter.write_fmt(format_args!("{:?} is not a valid block state", state))
+            }
+            Error::InvalidResourceLocation(id) => {
+                formatter.write_fmt(format_args!("{:?} is not a valid resource location", id))
+            }
+            Error::Cancelled => formatter.write_str("operation was cancelled"),
+            Error::BudgetExceeded => {
+                formatter.write_str("document decodes to more values than the budget allows")
+            }
+            Error::SessionLocked => {
+                formatter.write_str("the world is in use by another game, server or tool")
+            }
+            Error::UnsupportedDataVersion(version) => formatter.write_fmt(format_args!(
+                "chunks with DataVersion {} are not supported",
+                version
+            )),
+            Error::ChunkOutsideRegion(x, z) => formatter.write_fmt(format_args!(
+                "chunk ({}, {}) is outside the region, whose chunks are numbered 0 to 31",
+                x, z
+            )),
+            Error::MalformedPalettedData { palette, longs } => formatter.write_fmt(format_args!(
+                "{} longs of packed data do not fit a palette of {} entries",
+                longs, palette
+            )),
+            Error::UnknownCompression(scheme) => {
+                formatter.write_fmt(format_args!("unknown chunk compression scheme {}", scheme))
+            }
+            Error::SizeLimitExceeded(limit) => formatter.write_fmt(format_args!(
+                "NBT data exceeds the limit of {} bytes",
+                limit
+            )),
         }
     }
 }
 
 impl From<io::Error> for Error {
     fn from(value: io::Error) -> Self {
+        // Errors raised by the crate's own reader adapters have to travel through `io::Read`,
+        // unwrap them again so callers can match on them.
+        if value.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = value.into_inner().expect("payload was checked above");
+            return *inner
+                .downcast::<Error>()
+                .expect("payload was checked above");
+        }
         Error::IoError(value)
     }
 }
 
-impl std::error::Error for Error {}
+impl From<fmt::Error> for Error {
+    fn from(value: fmt::Error) -> Self {
+        Error::Message(value.to_string())
+    }
+}
+
+impl core::error::Error for Error {}
@@ -0,0 +1,313 @@
+//! The model behind an interactive browser in the style of NBTExplorer: a tree whose compounds
+//! and lists expand and collapse, with search and copy-as-SNBT. It has no terminal or GUI code of
+//! its own; a front end draws [`Explorer::rows`] and calls back into it as the user moves around,
+//! like `nbt explore`, the command built with the `ratatui` feature. The chunks of a region file
+//! are only read once they are first expanded.
+use std::{
+    collections::HashSet,
+    io::{Read, Seek},
+};
+
+use crate::{
+    error::{self, Error},
+    region::RegionFile,
+    tag::{glob_match, NBTPath, NBTValue, PathSegment},
+    tree::write_line,
+};
+
+#[test]
+fn test_explore_value() {
+    let value = NBTValue::Compound(crate::CompoundMap::from_iter([
+        ("name".to_owned(), NBTValue::String("world".into())),
+        (
+            "pos".to_owned(),
+            NBTValue::List(vec![NBTValue::Double(1.5), NBTValue::Double(-2.0)]),
+        ),
+        (
+            "empty".to_owned(),
+            NBTValue::Compound(crate::CompoundMap::default()),
+        ),
+    ]));
+    let mut explorer = Explorer::new("", value);
+    let root = (0, NBTPath::default());
+    let pos = (0, "pos".parse::<NBTPath>().unwrap());
+    let row = |explorer: &Explorer, node: &NodeId| {
+        let rows = explorer.rows();
+        rows.into_iter().find(|row| &row.node == node)
+    };
+
+    let rows = explorer.rows();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].label, r#"TAG_Compound(""): 3 entries"#);
+    assert!(rows[0].expandable && !rows[0].expanded);
+
+    explorer.toggle(&root).unwrap();
+    assert_eq!(explorer.rows().len(), 4);
+    let pos_row = row(&explorer, &pos).unwrap();
+    assert_eq!(pos_row.depth, 1);
+    assert_eq!(pos_row.label, r#"TAG_List("pos"): 2 entries of TAG_Double"#);
+    assert!(pos_row.expandable);
+    let empty = (0, "empty".parse().unwrap());
+    assert!(!row(&explorer, &empty).unwrap().expandable);
+    assert_eq!(explorer.snbt(&pos).unwrap(), "[1.5d,-2.0d]");
+
+    explorer.toggle(&root).unwrap();
+    assert_eq!(explorer.rows().len(), 1);
+
+    // Searching opens up everything above the matches.
+    let found = explorer.search("-2*");
+    let second = (0, "pos[1]".parse().unwrap());
+    assert_eq!(found, std::slice::from_ref(&second));
+    let second_row = row(&explorer, &second).unwrap();
+    assert_eq!(second_row.depth, 2);
+    assert_eq!(second_row.label, "TAG_Double(None): -2");
+
+    let missing = (0, "missing".parse().unwrap());
+    assert!(matches!(
+        explorer.toggle(&missing),
+        Err(Error::PathNotFound(path)) if path == "missing"
+    ));
+    assert_eq!(explorer.snbt(&missing), None);
+}
+
+#[test]
+fn test_explore_region() {
+    use crate::region::{test_chunk, test_region};
+
+    let chunk = |x| crate::to_bytes(&test_chunk(x, 0)).unwrap();
+    let region = test_region(&[(0, 0, chunk(0)), (1, 0, chunk(1))]);
+    let region = RegionFile::new(std::io::Cursor::new(region)).unwrap();
+    let mut explorer = Explorer::from_region(region);
+    let first = (0, NBTPath::default());
+
+    let labels: Vec<_> = explorer.rows().into_iter().map(|row| row.label).collect();
+    assert_eq!(
+        labels,
+        ["Chunk [0, 0] (not loaded)", "Chunk [1, 0] (not loaded)"]
+    );
+    assert_eq!(explorer.snbt(&first), None);
+    assert!(explorer.search("xPos").is_empty());
+
+    explorer.toggle(&first).unwrap();
+    let rows = explorer.rows();
+    assert!(rows[0].label.starts_with(r#"TAG_Compound("Chunk [0, 0]")"#));
+    assert!(rows[0].expanded);
+    assert_eq!(rows.last().unwrap().label, "Chunk [1, 0] (not loaded)");
+    assert!(explorer.snbt(&first).unwrap().contains("xPos:0"));
+    assert_eq!(explorer.search("xPos"), [(0, "xPos".parse().unwrap())]);
+}
+
+/// A node of the explorer: the index of the document or chunk it is in, and its path within it.
+pub type NodeId = (usize, NBTPath);
+
+/// One line of the explorer, as it is currently expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub node: NodeId,
+    /// How deeply the node is nested, 0 for documents and chunks.
+    pub depth: usize,
+    /// The node's line as [`NBTValue::tree`] renders it, without indentation.
+    pub label: String,
+    /// Whether the node has children to show, or is a chunk that hasn't been read yet.
+    pub expandable: bool,
+    pub expanded: bool,
+}
+
+/// A tree of documents, or of the chunks of a region file, that is browsed by expanding and
+/// collapsing its nodes.
+pub struct Explorer {
+    roots: Vec<Root>,
+    expanded: HashSet<NodeId>,
+    read_chunk: Option<ChunkReader>,
+}
+
+type ChunkReader = Box<dyn FnMut(usize, usize) -> error::Result<Option<Vec<u8>>>>;
+
+struct Root {
+    name: String,
+    content: Content,
+}
+
+enum Content {
+    Loaded(NBTValue),
+    Chunk(usize, usize),
+}
+
+impl Explorer {
+    /// Explores the document `value`, named `name`.
+    pub fn new<S: Into<String>>(name: S, value: NBTValue) -> Self {
+        Self {
+            roots: vec![Root {
+                name: name.into(),
+                content: Content::Loaded(value),
+            }],
+            expanded: HashSet::new(),
+            read_chunk: None,
+        }
+    }
+
+    /// Explores the chunks of `region`, in the order of its location table. Each chunk is read,
+    /// with [`RegionFile::read_chunk`], when it is first expanded.
+    pub fn from_region<R: Read + Seek + 'static>(mut region: RegionFile<R>) -> Self {
+        let roots = region
+            .chunks()
+            .map(|(x, z)| Root {
+                name: format!("Chunk [{}, {}]", x, z),
+                content: Content::Chunk(x, z),
+            })
+            .collect();
+        Self {
+            roots,
+            expanded: HashSet::new(),
+            read_chunk: Some(Box::new(move |x, z| region.read_chunk(x, z))),
+        }
+    }
+
+    /// The lines to show: every document or chunk, followed by the children of those nodes that
+    /// are expanded.
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (index, root) in self.roots.iter().enumerate() {
+            match &root.content {
+                Content::Loaded(value) => {
+                    let mut path = NBTPath::default();
+                    self.push_rows(&mut rows, index, &mut path, Some(&root.name), value);
+                }
+                Content::Chunk(..) => rows.push(Row {
+                    node: (index, NBTPath::default()),
+                    depth: 0,
+                    label: format!("{} (not loaded)", root.name),
+                    expandable: true,
+                    expanded: false,
+                }),
+            }
+        }
+        rows
+    }
+
+    fn push_rows(
+        &self,
+        rows: &mut Vec<Row>,
+        root: usize,
+        path: &mut NBTPath,
+        name: Option<&str>,
+        value: &NBTValue,
+    ) {
+        let mut label = String::new();
+        // Writing to a `String` can't fail.
+        let _ = write_line(&mut label, name, value, 0, false);
+        label.truncate(label.trim_end().len());
+        let expandable = match value {
+            NBTValue::List(list) => !list.is_empty(),
+            NBTValue::Compound(map) => !map.is_empty(),
+            _ => false,
+        };
+        let node = (root, path.clone());
+        let expanded = expandable && self.expanded.contains(&node);
+        rows.push(Row {
+            node,
+            depth: path.0.len(),
+            label,
+            expandable,
+            expanded,
+        });
+        if !expanded {
+            return;
+        }
+        match value {
+            NBTValue::List(list) => {
+                for (i, element) in list.iter().enumerate() {
+                    path.0.push(PathSegment::Index(i));
+                    self.push_rows(rows, root, path, None, element);
+                    path.0.pop();
+                }
+            }
+            NBTValue::Compound(map) => {
+                for (key, value) in map {
+                    path.0.push(PathSegment::Key(key.clone()));
+                    self.push_rows(rows, root, path, Some(key), value);
+                    path.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Expands `node` if it is collapsed and collapses it otherwise. A chunk is read and parsed
+    /// the first time it is expanded, and stays collapsed if that fails. Nodes that don't exist
+    /// are reported as [`Error::PathNotFound`].
+    pub fn toggle(&mut self, node: &NodeId) -> error::Result<()> {
+        let (index, path) = node;
+        let not_found = || Error::PathNotFound(path.to_string());
+        let root = self.roots.get_mut(*index).ok_or_else(not_found)?;
+        if let Content::Chunk(x, z) = root.content {
+            let read_chunk = self.read_chunk.as_mut().ok_or_else(not_found)?;
+            let bytes = read_chunk(x, z)?.ok_or_else(not_found)?;
+            root.content = Content::Loaded(crate::from_slice(&bytes)?);
+        }
+        if self.value(node).is_none() {
+            return Err(not_found());
+        }
+        if !self.expanded.remove(node) {
+            self.expanded.insert(node.clone());
+        }
+        Ok(())
+    }
+
+    /// Finds the nodes whose key, or the text of whose string or number, matches the glob
+    /// `pattern`, as in [`NBTValue::find_matching`], and expands the nodes above them so they
+    /// are among the rows. Chunks that haven't been read yet aren't searched.
+    pub fn search(&mut self, pattern: &str) -> Vec<NodeId> {
+        let mut found = Vec::new();
+        for (index, root) in self.roots.iter().enumerate() {
+            let Content::Loaded(value) = &root.content else {
+                continue;
+            };
+            let matches = value.find_all(|path, value| {
+                path.key().is_some_and(|key| glob_match(pattern, key))
+                    || text(value).is_some_and(|text| glob_match(pattern, &text))
+            });
+            found.extend(matches.into_iter().map(|(path, _)| (index, path)));
+        }
+        for (index, path) in &found {
+            for depth in 0..path.0.len() {
+                let parent = NBTPath(path.0[..depth].to_vec());
+                self.expanded.insert((*index, parent));
+            }
+        }
+        found
+    }
+
+    /// The value at `node` as SNBT, for copying, or `None` if there is no such node or its chunk
+    /// hasn't been read yet.
+    pub fn snbt(&self, node: &NodeId) -> Option<String> {
+        self.value(node).map(NBTValue::to_snbt)
+    }
+
+    fn value(&self, (index, path): &NodeId) -> Option<&NBTValue> {
+        let Content::Loaded(value) = &self.roots.get(*index)?.content else {
+            return None;
+        };
+        path.0
+            .iter()
+            .try_fold(value, |value, segment| match (value, segment) {
+                (NBTValue::Compound(map), PathSegment::Key(key)) => map.get(key),
+                (NBTValue::List(list), PathSegment::Index(i)) => list.get(*i),
+                _ => None,
+            })
+    }
+}
+
+// The text search matches against, for strings and numbers.
+fn text(value: &NBTValue) -> Option<String> {
+    match value {
+        NBTValue::String(string) => Some(string.clone()),
+        NBTValue::Byte(n) => Some(n.to_string()),
+        NBTValue::Short(n) => Some(n.to_string()),
+        NBTValue::Int(n) => Some(n.to_string()),
+        NBTValue::Long(n) => Some(n.to_string()),
+        NBTValue::Float(n) => Some(n.to_string()),
+        NBTValue::Double(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,238 @@
+//! Reading and writing whole NBT files in one call.
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::GzEncoder,
+    write::ZlibEncoder,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    de::{from_buf_reader_with_options, from_gzip_reader_with_options, DeserializerOptions},
+    error,
+    ser::to_writer,
+};
+
+#[test]
+fn test_file_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Level {
+        name: String,
+        seed: i64,
+    }
+
+    let level = Level {
+        name: "world".into(),
+        seed: -7,
+    };
+    let dir = tempfile::tempdir().unwrap();
+    for compression in [Compression::None, Compression::Gzip, Compression::Zlib] {
+        let path = dir.path().join("level.dat");
+        to_file(&path, &level, compression).unwrap();
+        let read: Level = from_file(&path).unwrap();
+        assert_eq!(read, level);
+    }
+}
+
+#[test]
+fn test_atomic_save_keeps_old() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Level {
+        version: i32,
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("level.dat");
+    to_file_atomic(&path, &Level { version: 1 }, Compression::Gzip, true).unwrap();
+    assert!(!dir.path().join("level.dat_old").exists());
+    to_file_atomic(&path, &Level { version: 2 }, Compression::Gzip, true).unwrap();
+
+    let current: Level = from_file(&path).unwrap();
+    let old: Level = from_file(dir.path().join("level.dat_old")).unwrap();
+    assert_eq!((current.version, old.version), (2, 1));
+    assert!(!dir.path().join("level.dat.tmp").exists());
+
+    // An existing backup is replaced by the file saved over.
+    to_file_atomic(&path, &Level { version: 3 }, Compression::Gzip, true).unwrap();
+    let old: Level = from_file(dir.path().join("level.dat_old")).unwrap();
+    assert_eq!(old.version, 2);
+}
+
+/// How the contents of a file are compressed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Uncompressed NBT, as used by `servers.dat`.
+    None,
+    /// Gzip, as used by `level.dat` and player data.
+    #[default]
+    Gzip,
+    /// Zlib, as used by chunks inside region files.
+    Zlib,
+}
+
+impl Compression {
+    /// Guesses the compression from the first bytes of a file.
+    pub fn detect(header: &[u8]) -> Self {
+        match header {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            // The compression method is deflate and the two header bytes are a multiple of 31.
+            [cmf, flg, ..] if cmf & 0x0f == 8 && u16::from_be_bytes([*cmf, *flg]) % 31 == 0 => {
+                Compression::Zlib
+            }
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Reads a file, detecting whether it is gzip, zlib or uncompressed.
+pub fn from_file<'a, T, P>(path: P) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    from_file_with_options(path, DeserializerOptions::default())
+}
+
+pub fn from_file_with_options<'a, T, P>(path: P, options: DeserializerOptions) -> error::Result<T>
+where
+    T: Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let mut reader = io::BufReader::new(File::open(path)?);
+    match Compression::detect(reader.fill_buf()?) {
+        Compression::None => from_buf_reader_with_options(reader, options),
+        Compression::Gzip => from_gzip_reader_with_options(reader, options),
+        Compression::Zlib => {
+            from_buf_reader_with_options(io::BufReader::new(ZlibDecoder::new(reader)), options)
+        }
+    }
+}
+
+/// Opens the file at `path` for reading its uncompressed contents, along with the compression
+/// that was detected.
+pub(crate) fn open_decompressed(path: &Path) -> error::Result<(Box<dyn Read>, Compression)> {
+    let mut reader = io::BufReader::new(File::open(path)?);
+    let compression = Compression::detect(reader.fill_buf()?);
+    let reader: Box<dyn Read> = match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(io::BufReader::new(GzDecoder::new(reader))),
+        Compression::Zlib => Box::new(io::BufReader::new(ZlibDecoder::new(reader))),
+    };
+    Ok((reader, compression))
+}
+
+/// Writes `value` to a new file at `path`, replacing any file already there.
+pub fn to_file<T, P>(path: P, value: &T, compression: Compression) -> error::Result<()>
+where
+    T: Serialize,
+    P: AsRef<Path>,
+{
+    let mut file = io::BufWriter::new(File::create(path)?);
+    to_compressed_writer(&mut file, compression, |w| to_writer(w, value))?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Like [`to_file`], but writes to a temporary file next to `path` first and only renames it over
+/// `path` once it is complete, so an interrupted save never leaves a partially written file.
+/// With `keep_old`, the file being replaced is first copied to `<name>_old`, like vanilla's
+/// `level.dat_old`.
+pub fn to_file_atomic<T, P>(
+    path: P,
+    value: &T,
+    compression: Compression,
+    keep_old: bool,
+) -> error::Result<()>
+where
+    T: Serialize,
+    P: AsRef<Path>,
+{
+    write_file_atomic(path.as_ref(), compression, keep_old, |w| {
+        to_writer(w, value)
+    })
+}
+
+/// Saves a file the way [`to_file_atomic`] does, with `write` producing its uncompressed contents.
+pub(crate) fn write_file_atomic<F>(
+    path: &Path,
+    compression: Compression,
+    keep_old: bool,
+    write: F,
+) -> error::Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> error::Result<()>,
+{
+    let temp = sibling(path, ".tmp");
+    let result = (|| {
+        let mut file = io::BufWriter::new(File::create(&temp)?);
+        to_compressed_writer(&mut file, compression, write)?;
+        file.into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        // The old file is copied rather than moved, so `path` exists at every point in between.
+        if keep_old && path.exists() {
+            let old = sibling(path, "_old");
+            fs::copy(path, &old)?;
+            File::open(&old)?.sync_all()?;
+        }
+        fs::rename(&temp, path)?;
+        sync_parent(path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+// Makes the renames in the directory holding `path` durable.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> error::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()?;
+    Ok(())
+}
+
+// Directories can't be opened for syncing elsewhere.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> error::Result<()> {
+    Ok(())
+}
+
+// `path` with `suffix` appended to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn to_compressed_writer<W, F>(mut w: W, compression: Compression, write: F) -> error::Result<()>
+where
+    W: io::Write,
+    F: FnOnce(&mut dyn Write) -> error::Result<()>,
+{
+    let level = flate2::Compression::default();
+    match compression {
+        Compression::None => write(&mut w),
+        Compression::Gzip => {
+            let mut gzip = GzEncoder::new(w, level);
+            write(&mut gzip)?;
+            gzip.finish()?;
+            Ok(())
+        }
+        Compression::Zlib => {
+            let mut zlib = ZlibEncoder::new(w, level);
+            write(&mut zlib)?;
+            zlib.finish()?;
+            Ok(())
+        }
+    }
+}
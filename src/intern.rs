@@ -0,0 +1,199 @@
+//! Sharing of repeated strings, such as compound names, across many deserialized documents.
+use std::{
+    borrow::Borrow,
+    cell::RefCell,
+    collections::HashSet,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    ops::Deref,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[test]
+fn test_interned_keys() {
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Facing {
+        facing: &'static str,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Written {
+        name: &'static str,
+        properties: Facing,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Block {
+        name: InternedStr,
+        properties: BTreeMap<InternedStr, InternedStr>,
+    }
+
+    let bytes = crate::to_bytes(&Written {
+        name: "minecraft:chest",
+        properties: Facing { facing: "north" },
+    })
+    .unwrap();
+
+    let mut interner = Interner::new();
+    let (a, b): (Block, Block) = interner.scope(|| {
+        (
+            crate::from_slice(&bytes).unwrap(),
+            crate::from_slice(&bytes).unwrap(),
+        )
+    });
+    assert!(Arc::ptr_eq(&a.name.0, &b.name.0));
+    assert_eq!(&*b.properties["facing"], "north");
+    assert_eq!(interner.len(), 3);
+
+    // Outside of a scope every string gets its own allocation.
+    let c: Block = crate::from_slice(&bytes).unwrap();
+    assert!(!Arc::ptr_eq(&a.name.0, &c.name.0));
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<HashSet<Arc<str>>>> = const { RefCell::new(None) };
+}
+
+/// Set of shared strings. While [`Interner::scope`] runs, every [`InternedStr`] deserialized on
+/// the current thread reuses the interner's allocation for its text.
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of `string`, adding it if it isn't known yet.
+    pub fn intern(&mut self, string: &str) -> Arc<str> {
+        intern_into(&mut self.strings, string)
+    }
+
+    /// Number of distinct strings interned so far.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Runs `f` with this interner active on the current thread. Scopes can be nested, the
+    /// innermost one is used.
+    pub fn scope<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        // Hands the strings back, and restores the enclosing scope, even if `f` panics.
+        struct Restore<'a> {
+            interner: &'a mut Interner,
+            outer: Option<HashSet<Arc<str>>>,
+        }
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                let outer = self.outer.take();
+                let strings = ACTIVE.with(|active| mem::replace(&mut *active.borrow_mut(), outer));
+                self.interner.strings = strings.unwrap_or_default();
+            }
+        }
+
+        let strings = mem::take(&mut self.strings);
+        let outer = ACTIVE.with(|active| active.borrow_mut().replace(strings));
+        let _restore = Restore {
+            interner: self,
+            outer,
+        };
+        f()
+    }
+}
+
+fn intern_into(strings: &mut HashSet<Arc<str>>, string: &str) -> Arc<str> {
+    if let Some(interned) = strings.get(string) {
+        return interned.clone();
+    }
+    let interned: Arc<str> = Arc::from(string);
+    strings.insert(interned.clone());
+    interned
+}
+
+/// String that is shared with equal strings deserialized in the same [`Interner::scope`]. Reads
+/// and writes as a String tag, and works as a map key for compounds.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InternedStr(pub Arc<str>);
+
+impl From<&str> for InternedStr {
+    fn from(string: &str) -> Self {
+        let interned = ACTIVE.with(|active| {
+            active
+                .borrow_mut()
+                .as_mut()
+                .map(|strings| intern_into(strings, string))
+        });
+        InternedStr(interned.unwrap_or_else(|| Arc::from(string)))
+    }
+}
+
+impl Deref for InternedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for InternedStr {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Hash for InternedStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl fmt::Debug for InternedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for InternedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for InternedStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for InternedStr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = InternedStr;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, string: &str) -> Result<Self::Value, E> {
+                Ok(InternedStr::from(string))
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
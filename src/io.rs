@@ -0,0 +1,224 @@
+//! Byte sources and sinks for the tag codec. With the `std` feature, which is on by default, these
+//! are the traits and types of `std::io`. Without it they are small stand-ins with the same names
+//! and signatures, implemented for `&[u8]` and `Vec<u8>`, so the codec only needs `alloc`.
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Read, Result, Write};
+
+#[cfg(not(feature = "std"))]
+pub use self::alloc_only::{Error, ErrorKind, Read, Result, Write};
+
+#[cfg(not(feature = "std"))]
+mod alloc_only {
+    use alloc::{boxed::Box, vec::Vec};
+    use core::{error, fmt};
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// What went wrong, the subset of `std::io::ErrorKind` the codec uses.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        UnexpectedEof,
+        InvalidData,
+        WriteZero,
+        Interrupted,
+        Other,
+    }
+
+    impl fmt::Display for ErrorKind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(match self {
+                ErrorKind::UnexpectedEof => "unexpected end of file",
+                ErrorKind::InvalidData => "invalid data",
+                ErrorKind::WriteZero => "write zero",
+                ErrorKind::Interrupted => "operation interrupted",
+                ErrorKind::Other => "other error",
+            })
+        }
+    }
+
+    type Payload = Box<dyn error::Error + Send + Sync>;
+
+    /// An I/O error, with an optional payload like `std::io::Error`.
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+        payload: Option<Payload>,
+    }
+
+    impl Error {
+        pub fn new<E: Into<Payload>>(kind: ErrorKind, error: E) -> Self {
+            Self {
+                kind,
+                payload: Some(error.into()),
+            }
+        }
+
+        pub fn other<E: Into<Payload>>(error: E) -> Self {
+            Self::new(ErrorKind::Other, error)
+        }
+
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+
+        pub fn get_ref(&self) -> Option<&(dyn error::Error + Send + Sync + 'static)> {
+            self.payload.as_deref()
+        }
+
+        pub fn into_inner(self) -> Option<Payload> {
+            self.payload
+        }
+    }
+
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Self {
+            Self {
+                kind,
+                payload: None,
+            }
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.payload {
+                Some(payload) => payload.fmt(f),
+                None => self.kind.fmt(f),
+            }
+        }
+    }
+
+    impl error::Error for Error {}
+
+    /// A source of bytes, like `std::io::Read`.
+    pub trait Read {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.read(buf) {
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(read) => buf = &mut buf[read..],
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// A sink for bytes, like `std::io::Write`.
+    pub trait Write {
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        fn flush(&mut self) -> Result<()>;
+
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.write(buf) {
+                    Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                    Ok(written) => buf = &buf[written..],
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl Read for &[u8] {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let read = buf.len().min(self.len());
+            let (taken, rest) = self.split_at(read);
+            buf[..read].copy_from_slice(taken);
+            *self = rest;
+            Ok(read)
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for Box<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl Write for Vec<u8> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            (**self).flush()
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for Box<W> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            (**self).flush()
+        }
+    }
+}
+
+/// Reads `count` bytes from `reader` into `bytes`, returning how many there were before the end
+/// of the input.
+pub(crate) fn read_up_to<R: Read + ?Sized>(
+    reader: &mut R,
+    count: u64,
+    bytes: &mut alloc::vec::Vec<u8>,
+) -> Result<u64> {
+    copy_up_to(reader, count, |chunk| bytes.extend_from_slice(chunk))
+}
+
+/// Reads and drops `count` bytes from `reader`, returning how many there were before the end of
+/// the input.
+pub(crate) fn skip_up_to<R: Read + ?Sized>(reader: &mut R, count: u64) -> Result<u64> {
+    copy_up_to(reader, count, |_| {})
+}
+
+// The buffer is only as large as the reads, so a length read from a malformed document doesn't
+// allocate anything up front.
+fn copy_up_to<R, F>(reader: &mut R, count: u64, mut sink: F) -> Result<u64>
+where
+    R: Read + ?Sized,
+    F: FnMut(&[u8]),
+{
+    let mut buffer = [0; 4096];
+    let mut copied = 0;
+    while copied < count {
+        let len = buffer
+            .len()
+            .min((count - copied).try_into().unwrap_or(usize::MAX));
+        match reader.read(&mut buffer[..len]) {
+            Ok(0) => break,
+            Ok(read) => {
+                sink(&buffer[..read]);
+                copied += read as u64;
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(copied)
+}
@@ -0,0 +1,146 @@
+//! Pieces of item data shared between item stacks and the entities that carry them, in both the
+//! tag form used up to 1.20.4 and the component form used from 1.20.5.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{resource::ResourceLocation, tag::NBTValue};
+
+#[test]
+fn test_item_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct LegacyTag {
+        #[serde(rename = "Enchantments")]
+        enchantments: Vec<Enchantment>,
+        #[serde(rename = "AttributeModifiers")]
+        attribute_modifiers: Vec<AttributeModifier>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Components {
+        #[serde(rename = "minecraft:enchantments")]
+        enchantments: EnchantmentsComponent,
+        #[serde(rename = "minecraft:attribute_modifiers")]
+        attribute_modifiers: AttributeModifiersComponent,
+    }
+
+    let uuid = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+    let legacy = LegacyTag {
+        enchantments: vec![Enchantment {
+            id: "minecraft:sharpness".parse().unwrap(),
+            level: 5,
+        }],
+        attribute_modifiers: vec![AttributeModifier {
+            attribute_name: Some("generic.attack_damage".into()),
+            name: "Weapon modifier".into(),
+            amount: 6.0,
+            operation: 0,
+            uuid,
+            slot: Some("mainhand".into()),
+        }],
+    };
+    let bytes = crate::to_bytes(&legacy).unwrap();
+    assert_eq!(crate::from_slice::<LegacyTag>(&bytes).unwrap(), legacy);
+
+    let components = Components {
+        enchantments: EnchantmentsComponent {
+            levels: [("minecraft:sharpness".parse().unwrap(), 5)].into(),
+            show_in_tooltip: Some(false),
+        },
+        attribute_modifiers: AttributeModifiersComponent {
+            modifiers: vec![ComponentAttributeModifier {
+                attribute: "minecraft:generic.attack_damage".parse().unwrap(),
+                name: "Weapon modifier".into(),
+                amount: 6.0,
+                operation: "add_value".into(),
+                uuid,
+                slot: None,
+            }],
+            show_in_tooltip: None,
+        },
+    };
+    let bytes = crate::to_bytes(&components).unwrap();
+    assert_eq!(crate::from_slice::<Components>(&bytes).unwrap(), components);
+}
+
+/// One entry of the `Enchantments` or `StoredEnchantments` list in an item's tag, up to 1.20.4.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Enchantment {
+    /// Enchantment, such as `minecraft:sharpness`.
+    pub id: ResourceLocation,
+    #[serde(rename = "lvl")]
+    pub level: i16,
+}
+
+/// The `minecraft:enchantments` and `minecraft:stored_enchantments` components, from 1.20.5.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnchantmentsComponent {
+    /// Level of each enchantment, keyed by its id.
+    pub levels: BTreeMap<ResourceLocation, i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_in_tooltip: Option<bool>,
+}
+
+/// A modifier in an item's `AttributeModifiers` list or an entity attribute's `Modifiers` list, up
+/// to 1.20.4.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeModifier {
+    /// The attribute being modified. Only set on items, since entity modifiers are listed under
+    /// their attribute.
+    #[serde(
+        rename = "AttributeName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub attribute_name: Option<String>,
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Amount")]
+    pub amount: f64,
+    /// 0 adds the amount, 1 multiplies the base value and 2 multiplies the total.
+    #[serde(rename = "Operation")]
+    pub operation: i32,
+    #[serde(rename = "UUID", with = "crate::with::u128_as_int_array")]
+    pub uuid: u128,
+    /// Equipment slot the item must be in for the modifier to apply. Only set on items.
+    #[serde(rename = "Slot", default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<String>,
+}
+
+/// The `minecraft:attribute_modifiers` component, from 1.20.5.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeModifiersComponent {
+    pub modifiers: Vec<ComponentAttributeModifier>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_in_tooltip: Option<bool>,
+}
+
+/// A modifier in the `minecraft:attribute_modifiers` component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentAttributeModifier {
+    /// Attribute, such as `minecraft:generic.attack_damage`.
+    #[serde(rename = "type")]
+    pub attribute: ResourceLocation,
+    pub name: String,
+    pub amount: f64,
+    /// One of `add_value`, `add_multiplied_base` or `add_multiplied_total`.
+    pub operation: String,
+    #[serde(with = "crate::with::u128_as_int_array")]
+    pub uuid: u128,
+    /// Equipment slot group, such as `mainhand` or `armor`. Defaults to `any`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<String>,
+}
+
+/// A stack of items in an inventory, container or trade, up to 1.20.4.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// Item, such as `minecraft:diamond_sword`.
+    pub id: ResourceLocation,
+    #[serde(rename = "Count")]
+    pub count: i8,
+    /// Everything else about the item, such as its `Enchantments`, `AttributeModifiers` and
+    /// `display` name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<NBTValue>,
+}
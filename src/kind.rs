@@ -1,66 +1,162 @@
-use std::fmt::Display;
+use alloc::borrow::ToOwned;
+use core::{fmt::Display, str::FromStr};
 
+use crate::error::Error;
+
+#[test]
+fn test_kind_names() {
+    for id in 0..=NBTKind::LONG_ARRAY {
+        let kind = NBTKind::try_from(id).unwrap();
+        assert_eq!(kind.name().parse::<NBTKind>().unwrap(), kind);
+        assert_eq!(kind.tag_name().parse::<NBTKind>().unwrap(), kind);
+    }
+    assert_eq!(NBTKind::INT_ARRAY, 11);
+    assert_eq!(NBTKind::IntArray.name(), "IntArray");
+    assert_eq!(NBTKind::IntArray.tag_name(), "TAG_Int_Array");
+    assert_eq!(NBTKind::LongArray.to_string(), "LongArray");
+    assert!(matches!(
+        "Int_Array".parse::<NBTKind>(),
+        Err(Error::UnknownKind(name)) if name == "Int_Array"
+    ));
+}
+
+/// Type of an NBT tag. The discriminants are the tag ids used on the wire, so `kind as u8` and
+/// [`NBTKind::try_from`] convert between the two.
 #[repr(u8)]
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum NBTKind {
-    End,
-    Byte,
-    Short,
-    Int,
-    Long,
-    Float,
-    Double,
-    ByteArray,
-    String,
-    List,
-    Compound,
-    IntArray,
-    LongArray,
+    End = 0,
+    Byte = 1,
+    Short = 2,
+    Int = 3,
+    Long = 4,
+    Float = 5,
+    Double = 6,
+    ByteArray = 7,
+    String = 8,
+    List = 9,
+    Compound = 10,
+    IntArray = 11,
+    LongArray = 12,
 }
 
 impl NBTKind {
-    pub(crate) fn header_byte(&self) -> u8 {
+    pub const END: u8 = 0;
+    pub const BYTE: u8 = 1;
+    pub const SHORT: u8 = 2;
+    pub const INT: u8 = 3;
+    pub const LONG: u8 = 4;
+    pub const FLOAT: u8 = 5;
+    pub const DOUBLE: u8 = 6;
+    pub const BYTE_ARRAY: u8 = 7;
+    pub const STRING: u8 = 8;
+    pub const LIST: u8 = 9;
+    pub const COMPOUND: u8 = 10;
+    pub const INT_ARRAY: u8 = 11;
+    pub const LONG_ARRAY: u8 = 12;
+
+    /// The tag id written in front of tags of this kind.
+    pub fn header_byte(&self) -> u8 {
         match self {
-            NBTKind::End => 0,
-            NBTKind::Byte => 1,
-            NBTKind::Short => 2,
-            NBTKind::Int => 3,
-            NBTKind::Long => 4,
-            NBTKind::Float => 5,
-            NBTKind::Double => 6,
-            NBTKind::ByteArray => 7,
-            NBTKind::String => 8,
-            NBTKind::List => 9,
-            NBTKind::Compound => 10,
-            NBTKind::IntArray => 11,
-            NBTKind::LongArray => 12,
+            NBTKind::End => NBTKind::END,
+            NBTKind::Byte => NBTKind::BYTE,
+            NBTKind::Short => NBTKind::SHORT,
+            NBTKind::Int => NBTKind::INT,
+            NBTKind::Long => NBTKind::LONG,
+            NBTKind::Float => NBTKind::FLOAT,
+            NBTKind::Double => NBTKind::DOUBLE,
+            NBTKind::ByteArray => NBTKind::BYTE_ARRAY,
+            NBTKind::String => NBTKind::STRING,
+            NBTKind::List => NBTKind::LIST,
+            NBTKind::Compound => NBTKind::COMPOUND,
+            NBTKind::IntArray => NBTKind::INT_ARRAY,
+            NBTKind::LongArray => NBTKind::LONG_ARRAY,
         }
     }
-}
 
-impl From<u8> for NBTKind {
-    fn from(value: u8) -> Self {
-        match value {
-            0 => NBTKind::End,
-            1 => NBTKind::Byte,
-            2 => NBTKind::Short,
-            3 => NBTKind::Int,
-            4 => NBTKind::Long,
-            5 => NBTKind::Float,
-            6 => NBTKind::Double,
-            7 => NBTKind::ByteArray,
-            8 => NBTKind::String,
-            9 => NBTKind::List,
-            10 => NBTKind::Compound,
-            11 => NBTKind::IntArray,
-            12 => NBTKind::LongArray,
-            _ => unreachable!("Unknown ID value for NBTTag {}.", value),
+    /// The name of the variant, such as `IntArray`. This is also what `Display` prints.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NBTKind::End => "End",
+            NBTKind::Byte => "Byte",
+            NBTKind::Short => "Short",
+            NBTKind::Int => "Int",
+            NBTKind::Long => "Long",
+            NBTKind::Float => "Float",
+            NBTKind::Double => "Double",
+            NBTKind::ByteArray => "ByteArray",
+            NBTKind::String => "String",
+            NBTKind::List => "List",
+            NBTKind::Compound => "Compound",
+            NBTKind::IntArray => "IntArray",
+            NBTKind::LongArray => "LongArray",
+        }
+    }
+
+    /// The name the NBT specification uses, such as `TAG_Int_Array`.
+    pub fn tag_name(&self) -> &'static str {
+        match self {
+            NBTKind::End => "TAG_End",
+            NBTKind::Byte => "TAG_Byte",
+            NBTKind::Short => "TAG_Short",
+            NBTKind::Int => "TAG_Int",
+            NBTKind::Long => "TAG_Long",
+            NBTKind::Float => "TAG_Float",
+            NBTKind::Double => "TAG_Double",
+            NBTKind::ByteArray => "TAG_Byte_Array",
+            NBTKind::String => "TAG_String",
+            NBTKind::List => "TAG_List",
+            NBTKind::Compound => "TAG_Compound",
+            NBTKind::IntArray => "TAG_Int_Array",
+            NBTKind::LongArray => "TAG_Long_Array",
         }
     }
 }
 
+impl TryFrom<u8> for NBTKind {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            NBTKind::END => NBTKind::End,
+            NBTKind::BYTE => NBTKind::Byte,
+            NBTKind::SHORT => NBTKind::Short,
+            NBTKind::INT => NBTKind::Int,
+            NBTKind::LONG => NBTKind::Long,
+            NBTKind::FLOAT => NBTKind::Float,
+            NBTKind::DOUBLE => NBTKind::Double,
+            NBTKind::BYTE_ARRAY => NBTKind::ByteArray,
+            NBTKind::STRING => NBTKind::String,
+            NBTKind::LIST => NBTKind::List,
+            NBTKind::COMPOUND => NBTKind::Compound,
+            NBTKind::INT_ARRAY => NBTKind::IntArray,
+            NBTKind::LONG_ARRAY => NBTKind::LongArray,
+            _ => return Err(Error::InvalidTagId),
+        })
+    }
+}
+
+/// Parses either form of a kind's name, `IntArray` as printed by [`NBTKind::name`] or
+/// `TAG_Int_Array` as printed by [`NBTKind::tag_name`].
+impl FromStr for NBTKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (NBTKind::END..=NBTKind::LONG_ARRAY)
+            .filter_map(|id| NBTKind::try_from(id).ok())
+            .find(|kind| kind.name() == s || kind.tag_name() == s)
+            .ok_or_else(|| Error::UnknownKind(s.to_owned()))
+    }
+}
+
+impl From<NBTKind> for u8 {
+    fn from(kind: NBTKind) -> Self {
+        kind.header_byte()
+    }
+}
+
 impl Display for NBTKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{:#?}", self))
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
     }
-}
\ No newline at end of file
+}
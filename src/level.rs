@@ -0,0 +1,253 @@
+//! Editing `level.dat` in place, changing a few well known fields while keeping everything else,
+//! including tags added by mods that no typed model knows about.
+use std::path::Path;
+
+use crate::{
+    error,
+    file::{open_decompressed, write_file_atomic, Compression},
+    stream::{write_document, NBTStreamReader},
+    tag::{CompoundMap, NBTValue},
+};
+
+#[test]
+fn test_level_dat_editor() {
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Data {
+        level_name: String,
+        random_seed: i64,
+        spawn_x: i32,
+        spawn_y: i32,
+        spawn_z: i32,
+        difficulty: i8,
+        game_rules: BTreeMap<String, String>,
+        #[serde(rename = "forge:ids", serialize_with = "crate::int_array")]
+        forge_ids: Vec<i32>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Level {
+        #[serde(rename = "Data")]
+        data: Data,
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("level.dat");
+    let level = Level {
+        data: Data {
+            level_name: "world".into(),
+            random_seed: 42,
+            spawn_x: 0,
+            spawn_y: 64,
+            spawn_z: 0,
+            difficulty: 2,
+            game_rules: [("keepInventory".to_owned(), "false".to_owned())].into(),
+            forge_ids: vec![1, 2, 3],
+        },
+    };
+    crate::to_file(&path, &level, Compression::Gzip).unwrap();
+
+    let mut editor = LevelDatEditor::open(&path).unwrap();
+    assert_eq!(editor.compression(), Compression::Gzip);
+    assert_eq!(editor.seed(), Some(42));
+    assert_eq!(editor.spawn(), Some([0, 64, 0]));
+    assert_eq!(editor.difficulty(), Some(Difficulty::Normal));
+    assert_eq!(editor.gamerule("keepInventory"), Some("false"));
+    editor.set_seed(-7);
+    editor.set_spawn([100, 70, -20]);
+    editor.set_difficulty(Difficulty::Hard);
+    editor.set_gamerule("keepInventory", "true");
+    editor.save(&path).unwrap();
+
+    let saved: Level = crate::from_file(&path).unwrap();
+    assert_eq!(
+        saved.data,
+        Data {
+            random_seed: -7,
+            spawn_x: 100,
+            spawn_y: 70,
+            spawn_z: -20,
+            difficulty: 3,
+            game_rules: [("keepInventory".to_owned(), "true".to_owned())].into(),
+            ..level.data
+        }
+    );
+    assert_eq!(&std::fs::read(&path).unwrap()[..2], [0x1f, 0x8b]);
+}
+
+/// How hard the game is, as stored in `Difficulty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    fn from_byte(byte: i8) -> Option<Self> {
+        match byte {
+            0 => Some(Difficulty::Peaceful),
+            1 => Some(Difficulty::Easy),
+            2 => Some(Difficulty::Normal),
+            3 => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+/// A loaded `level.dat`. The whole file is kept as an [`NBTValue`], so saving it writes back
+/// every tag that was read, in the compression it was read with.
+#[derive(Debug, Clone)]
+pub struct LevelDatEditor {
+    name: String,
+    root: NBTValue,
+    compression: Compression,
+}
+
+impl LevelDatEditor {
+    /// Reads the file at `path`, detecting whether it is gzip, zlib or uncompressed.
+    pub fn open<P: AsRef<Path>>(path: P) -> error::Result<Self> {
+        let (reader, compression) = open_decompressed(path.as_ref())?;
+        let (name, root) = NBTStreamReader::new(reader).read_document()?;
+        Ok(Self {
+            name,
+            root,
+            compression,
+        })
+    }
+
+    /// Writes the file to `path` in the compression it was read with. Like the game, the file
+    /// being replaced is kept as `level.dat_old` and the new one is only moved into place once it
+    /// has been written completely.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> error::Result<()> {
+        write_file_atomic(path.as_ref(), self.compression, true, |w| {
+            write_document(w, &self.name, &self.root)
+        })
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// The whole file, for fields without a getter of their own.
+    pub fn root(&self) -> &NBTValue {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut NBTValue {
+        &mut self.root
+    }
+
+    /// The world seed, from `WorldGenSettings` since 1.16 and `RandomSeed` before.
+    pub fn seed(&self) -> Option<i64> {
+        let settings = self.get("WorldGenSettings").and_then(as_compound);
+        match settings.and_then(|settings| settings.get("seed")) {
+            Some(NBTValue::Long(seed)) => Some(*seed),
+            _ => match self.get("RandomSeed")? {
+                NBTValue::Long(seed) => Some(*seed),
+                _ => None,
+            },
+        }
+    }
+
+    /// Sets the seed where [`seed`](Self::seed) reads it from.
+    pub fn set_seed(&mut self, seed: i64) {
+        match self.data_mut().get_mut("WorldGenSettings") {
+            Some(NBTValue::Compound(settings)) => {
+                settings.insert("seed".to_owned(), NBTValue::Long(seed));
+            }
+            _ => self.set("RandomSeed", NBTValue::Long(seed)),
+        }
+    }
+
+    /// The world spawn as x, y and z.
+    pub fn spawn(&self) -> Option<[i32; 3]> {
+        let mut spawn = [0; 3];
+        for (coordinate, key) in spawn.iter_mut().zip(["SpawnX", "SpawnY", "SpawnZ"]) {
+            match self.get(key)? {
+                NBTValue::Int(n) => *coordinate = *n,
+                _ => return None,
+            }
+        }
+        Some(spawn)
+    }
+
+    pub fn set_spawn(&mut self, spawn: [i32; 3]) {
+        for (coordinate, key) in spawn.into_iter().zip(["SpawnX", "SpawnY", "SpawnZ"]) {
+            self.set(key, NBTValue::Int(coordinate));
+        }
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        match self.get("Difficulty")? {
+            NBTValue::Byte(byte) => Difficulty::from_byte(*byte),
+            _ => None,
+        }
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.set("Difficulty", NBTValue::Byte(difficulty as i8));
+    }
+
+    /// The value of the game rule `name`, such as `"true"` for `keepInventory`. Game rules are
+    /// stored as strings whatever their type.
+    pub fn gamerule(&self, name: &str) -> Option<&str> {
+        match self.get("GameRules").and_then(as_compound)?.get(name)? {
+            NBTValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn set_gamerule(&mut self, name: &str, value: impl Into<String>) {
+        let rules = self
+            .data_mut()
+            .entry("GameRules".to_owned())
+            .or_insert_with(|| NBTValue::Compound(CompoundMap::default()));
+        if let NBTValue::Compound(rules) = rules {
+            rules.insert(name.to_owned(), NBTValue::String(value.into()));
+        }
+    }
+
+    // An entry of the `Data` compound that holds everything in the file.
+    fn get(&self, key: &str) -> Option<&NBTValue> {
+        as_compound(&self.root)?
+            .get("Data")
+            .and_then(as_compound)?
+            .get(key)
+    }
+
+    fn set(&mut self, key: &str, value: NBTValue) {
+        self.data_mut().insert(key.to_owned(), value);
+    }
+
+    // The `Data` compound, created if the file doesn't have one.
+    fn data_mut(&mut self) -> &mut CompoundMap {
+        if !matches!(self.root, NBTValue::Compound(_)) {
+            self.root = NBTValue::Compound(CompoundMap::default());
+        }
+        let NBTValue::Compound(root) = &mut self.root else {
+            unreachable!("the root was made a compound above");
+        };
+        let data = root
+            .entry("Data".to_owned())
+            .or_insert_with(|| NBTValue::Compound(CompoundMap::default()));
+        if !matches!(data, NBTValue::Compound(_)) {
+            *data = NBTValue::Compound(CompoundMap::default());
+        }
+        match data {
+            NBTValue::Compound(data) => data,
+            _ => unreachable!("`Data` was made a compound above"),
+        }
+    }
+}
+
+fn as_compound(value: &NBTValue) -> Option<&CompoundMap> {
+    match value {
+        NBTValue::Compound(entries) => Some(entries),
+        _ => None,
+    }
+}
@@ -1,13 +1,118 @@
-mod debug;
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
+// Fails to compile unless every listed type is `Send + Sync`.
+macro_rules! assert_send_sync {
+    ($($ty:ty),* $(,)?) => {
+        const _: () = {
+            fn assert_send_sync<T: Send + Sync>() {}
+            $(let _ = assert_send_sync::<$ty>;)*
+        };
+    };
+}
+
 mod kind;
 mod parser;
 mod writer;
 
+#[cfg(all(feature = "std", any(test, feature = "debug")))]
+pub mod debug;
+pub mod io;
 pub mod tag;
 pub mod error;
 pub mod de;
 pub mod ser;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "bytemuck")]
+pub mod view;
+pub mod with;
+#[cfg(feature = "std")]
+pub mod timestamp;
+#[cfg(feature = "std")]
+pub mod resource;
+#[cfg(feature = "std")]
+pub mod intern;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod recover;
+#[cfg(feature = "std")]
+pub mod tree;
+#[cfg(feature = "std")]
+pub mod block;
+#[cfg(feature = "std")]
+pub mod structure;
+#[cfg(feature = "std")]
+pub mod item;
+#[cfg(feature = "std")]
+pub mod villager;
+#[cfg(feature = "std")]
+pub mod chunk;
+#[cfg(feature = "std")]
+pub mod migrate;
+pub mod snbt;
+#[cfg(feature = "text")]
+pub mod text;
+#[cfg(feature = "compression")]
+pub mod file;
+#[cfg(feature = "compression")]
+pub mod region;
+#[cfg(feature = "compression")]
+pub mod entity;
+#[cfg(feature = "compression")]
+pub mod source;
+#[cfg(feature = "compression")]
+pub mod world;
+#[cfg(feature = "compression")]
+pub mod level;
+#[cfg(feature = "compression")]
+pub mod explore;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use error::{Error, Result};
-pub use de::{from_gzip_reader, from_reader, from_slice, from_zlib_reader};
-pub use ser::{to_writer, to_bytes, byte_array, int_array, long_array};
\ No newline at end of file
+pub use kind::NBTKind;
+pub use tag::{
+    approx_eq, approx_eq_with, ApproxOptions, CompoundBuilder, CompoundMap, HashableNbt, NBTPath,
+    NBTValue, PathSegment,
+};
+#[cfg(feature = "std")]
+pub use block::BlockState;
+#[cfg(feature = "std")]
+pub use resource::ResourceLocation;
+#[cfg(feature = "std")]
+pub use timestamp::timestamp_millis;
+pub use with::{as_byte, as_float, as_long, as_short};
+#[cfg(feature = "std")]
+pub use validate::{validate_bytes, NbtSummary};
+#[cfg(feature = "std")]
+pub use recover::{from_reader_recovering, from_slice_recovering, scan_for_compounds};
+#[cfg(feature = "std")]
+pub use stream::{bedrock_to_java, copy_filtered, java_to_bedrock, CopyRule};
+#[cfg(feature = "compression")]
+pub use file::{from_file, from_file_with_options, to_file, to_file_atomic, Compression};
+pub use de::{
+    from_reader, from_reader_at, from_reader_le, from_reader_limited, from_reader_seed,
+    from_reader_with_options, from_reader_with_report, from_slice, from_slice_at, from_slice_le,
+    from_slice_seed, from_slice_with_options, from_slice_with_report,
+    BooleanPolicy, DeserializeReport, DeserializerOptions, DuplicateKeyPolicy, KeyMap,
+    LengthPolicy, NBTDeserializer, UnknownTagHook,
+};
+#[cfg(feature = "std")]
+pub use de::{from_buf_reader, from_buf_reader_with_options, OwnedDeserializer};
+#[cfg(feature = "compression")]
+pub use de::{from_gzip_reader, from_gzip_reader_with_options, from_zlib_reader};
+#[cfg(feature = "zstd")]
+pub use de::from_zstd_reader;
+pub use ser::{
+    to_writer, to_writer_le, to_writer_with_options, to_bytes, to_bytes_into, to_bytes_with_options,
+    to_writer_length_prefixed, byte_array, int_array, long_array, is_empty_tag, SerializerOptions,
+};
+#[cfg(feature = "zstd")]
+pub use ser::to_zstd_writer;
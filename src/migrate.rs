@@ -0,0 +1,238 @@
+//! Upgrades of NBT data from one `DataVersion` to a later one, the way the game's data fixers
+//! upgrade data saved by older versions when they load it.
+use std::{fmt, ops::Range};
+
+use crate::{
+    chunk::{PalettedContainer, ALIGNED_PACKING, FLATTENING, NO_LEVEL_WRAPPER},
+    error::{self, Error},
+    tag::{CompoundMap, NBTValue},
+};
+
+#[test]
+fn test_migrate_chunk() {
+    use crate::{
+        block::BlockState,
+        chunk::{Chunk, LegacyChunk, LegacyLevel, LegacySection},
+    };
+
+    let states: Vec<BlockState> = (0..20)
+        .map(|i| BlockState::new(format!("minecraft:block_{}", i)))
+        .collect();
+    let values: Vec<BlockState> = (0..4096).map(|i| states[i * 7 % 20].clone()).collect();
+    let packed = PalettedContainer::pack(&values, 4, true);
+    let chunk = LegacyChunk {
+        data_version: 1976,
+        level: LegacyLevel {
+            x_pos: 3,
+            z_pos: -2,
+            status: "full".into(),
+            last_update: 100,
+            inhabited_time: 20,
+            sections: vec![LegacySection {
+                y: 0,
+                palette: packed.palette,
+                block_states: packed.data,
+            }],
+            tile_entities: vec![NBTValue::String("chest".into())],
+        },
+    };
+    let mut value: NBTValue = crate::from_slice(&crate::to_bytes(&chunk).unwrap()).unwrap();
+
+    assert_eq!(
+        migrate_to(&mut value, ALIGNED_PACKING).unwrap(),
+        ALIGNED_PACKING
+    );
+    let legacy: LegacyChunk = crate::from_slice(&crate::to_bytes(&value).unwrap()).unwrap();
+    assert_eq!(legacy.data_version, ALIGNED_PACKING);
+    assert_eq!(legacy.level.sections[0].block_states.len(), 342);
+
+    assert_eq!(migrate_to(&mut value, 3465).unwrap(), NO_LEVEL_WRAPPER);
+    let migrated = Chunk::from_slice(&crate::to_bytes(&value).unwrap()).unwrap();
+    assert_eq!(migrated.data_version, NO_LEVEL_WRAPPER);
+    assert_eq!((migrated.x, migrated.z), (3, -2));
+    assert_eq!(migrated.status, "minecraft:full");
+    assert_eq!(migrated.block_entities, chunk.level.tile_entities);
+    assert_eq!(
+        migrated.sections[0].block_states.unpack(4096).unwrap(),
+        values
+    );
+
+    let mut migrator = Migrator::new();
+    migrator.register(NO_LEVEL_WRAPPER..3000, |root| {
+        if let Some(status) = root.remove("Status") {
+            root.insert("status".into(), status);
+        }
+        Ok(())
+    });
+    assert_eq!(migrator.migrate_to(&mut value, 3465).unwrap(), 3000);
+    let NBTValue::Compound(root) = &value else {
+        panic!("chunk is not a compound");
+    };
+    assert_eq!(root["status"], NBTValue::String("minecraft:full".into()));
+    assert_eq!(root["DataVersion"], NBTValue::Int(3000));
+}
+
+type MigrationFn = dyn Fn(&mut CompoundMap) -> error::Result<()> + Send + Sync;
+
+struct Migration {
+    versions: Range<i32>,
+    apply: Box<MigrationFn>,
+}
+
+/// A set of migrations, each of which upgrades data with a `DataVersion` in some range to the
+/// end of that range.
+#[derive(Default)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The migrations that ship with the crate, which upgrade chunks:
+    ///
+    /// - From the flattening to [`ALIGNED_PACKING`], block states are packed again without
+    ///   spanning longs.
+    /// - From there to [`NO_LEVEL_WRAPPER`], the `Level` compound is unwrapped, its keys get
+    ///   their 1.18 names and sections get a `block_states` container. `Biomes` keeps its
+    ///   numeric ids, naming them needs the game's biome registry.
+    pub fn chunks() -> Self {
+        let mut migrator = Self::new();
+        migrator
+            .register(FLATTENING..ALIGNED_PACKING, repack_block_states)
+            .register(ALIGNED_PACKING..NO_LEVEL_WRAPPER, unwrap_level);
+        migrator
+    }
+
+    /// Registers `migration` for data with a `DataVersion` in `versions`. It gets the root
+    /// compound and doesn't need to update `DataVersion`, which is set to `versions.end` after it
+    /// succeeds.
+    pub fn register<F>(&mut self, versions: Range<i32>, migration: F) -> &mut Self
+    where
+        F: Fn(&mut CompoundMap) -> error::Result<()> + Send + Sync + 'static,
+    {
+        assert!(!versions.is_empty(), "empty version range {:?}", versions);
+        self.migrations.push(Migration {
+            versions,
+            apply: Box::new(migration),
+        });
+        self
+    }
+
+    /// Applies migrations to `value` one after the other, up to but not beyond `target`, and
+    /// returns the `DataVersion` it ends at. That is below `target` when no migration registered
+    /// covers the version reached, or one would go past `target`. Data without a `DataVersion`
+    /// is treated as version 0.
+    ///
+    /// When a migration fails, `value` is left with the changes made up to then.
+    pub fn migrate_to(&self, value: &mut NBTValue, target: i32) -> error::Result<i32> {
+        let NBTValue::Compound(root) = value else {
+            return Err(Error::ExpectedRootCompound);
+        };
+        let mut version = match root.get("DataVersion") {
+            Some(NBTValue::Int(version)) => *version,
+            _ => 0,
+        };
+        while let Some(migration) = self.migrations.iter().find(|migration| {
+            migration.versions.contains(&version) && migration.versions.end <= target
+        }) {
+            (migration.apply)(root)?;
+            version = migration.versions.end;
+            root.insert("DataVersion".into(), NBTValue::Int(version));
+        }
+        Ok(version)
+    }
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.migrations.iter().map(|migration| &migration.versions))
+            .finish()
+    }
+}
+
+/// Upgrades `value` towards `target` with the migrations of [`Migrator::chunks`], see
+/// [`Migrator::migrate_to`].
+pub fn migrate_to(value: &mut NBTValue, target: i32) -> error::Result<i32> {
+    Migrator::chunks().migrate_to(value, target)
+}
+
+fn repack_block_states(root: &mut CompoundMap) -> error::Result<()> {
+    let Some(NBTValue::Compound(level)) = root.get_mut("Level") else {
+        return Ok(());
+    };
+    let Some(NBTValue::List(sections)) = level.get_mut("Sections") else {
+        return Ok(());
+    };
+    for section in sections {
+        let NBTValue::Compound(section) = section else {
+            continue;
+        };
+        let (Some(NBTValue::List(palette)), Some(NBTValue::LongArray(data))) =
+            (section.get("Palette"), section.get("BlockStates"))
+        else {
+            continue;
+        };
+        let container = PalettedContainer {
+            palette: palette.clone(),
+            data: data.clone(),
+        };
+        let repacked = PalettedContainer::pack(&container.unpack(4096)?, 4, false);
+        section.insert("Palette".into(), NBTValue::List(repacked.palette));
+        section.insert("BlockStates".into(), NBTValue::LongArray(repacked.data));
+    }
+    Ok(())
+}
+
+// Keys of the `Level` compound that were renamed when it was unwrapped.
+const LEVEL_RENAMES: &[(&str, &str)] = &[
+    ("Sections", "sections"),
+    ("TileEntities", "block_entities"),
+    ("TileTicks", "block_ticks"),
+    ("LiquidTicks", "fluid_ticks"),
+    ("Structures", "structures"),
+    ("CarvingMasks", "carving_masks"),
+];
+
+fn unwrap_level(root: &mut CompoundMap) -> error::Result<()> {
+    let Some(NBTValue::Compound(level)) = root.remove("Level") else {
+        return Ok(());
+    };
+    for (key, mut value) in level {
+        let key = LEVEL_RENAMES
+            .iter()
+            .find(|(old, _)| *old == key)
+            .map_or(key, |(_, new)| new.to_string());
+        match (key.as_str(), &mut value) {
+            ("Status", NBTValue::String(status)) if !status.contains(':') => {
+                status.insert_str(0, "minecraft:");
+            }
+            ("sections", NBTValue::List(sections)) => {
+                sections.iter_mut().for_each(nest_block_states);
+            }
+            _ => {}
+        }
+        root.insert(key, value);
+    }
+    // The world started at y 0 until 1.18.
+    root.entry("yPos".into()).or_insert(NBTValue::Int(0));
+    Ok(())
+}
+
+fn nest_block_states(section: &mut NBTValue) {
+    let NBTValue::Compound(section) = section else {
+        return;
+    };
+    let Some(palette) = section.remove("Palette") else {
+        return;
+    };
+    let mut block_states = CompoundMap::default();
+    block_states.insert("palette".into(), palette);
+    if let Some(data) = section.remove("BlockStates") {
+        block_states.insert("data".into(), data);
+    }
+    section.insert("block_states".into(), NBTValue::Compound(block_states));
+}
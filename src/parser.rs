@@ -1,46 +1,515 @@
-use crate::kind::NBTKind;
-use byteorder::ReadBytesExt;
-use std::io;
+use crate::{
+    de::{DeserializerOptions, LengthPolicy, ReportState},
+    error, io,
+    kind::NBTKind,
+    tag::NBTValue,
+};
+use alloc::{borrow::Cow, borrow::ToOwned, string::String, vec::Vec};
+use byteorder::{BigEndian, ByteOrder};
+use core::{marker::PhantomData, str};
+#[cfg(feature = "std")]
+use std::io::BufRead;
 
 // Macro for generating parsing function implementations of number types
 macro_rules! parse_number_types {
     ($($typ:ident),+) => {
         paste::item! {
             $(pub(crate)  fn [<parse_ $typ>](&mut self) -> io::Result<$typ> {
-                self.reader.[<read_ $typ>]::<byteorder::BigEndian>()
+                let mut buf = [0; core::mem::size_of::<$typ>()];
+                self.reader.read_exact(&mut buf)?;
+                Ok(O::[<read_ $typ>](&buf))
             })*
         }
     };
 }
 
-pub(crate) struct Parser<R: io::Read> {
+/// Source of bytes for the [`Parser`].
+/// Buffered sources can decode strings straight out of their internal buffer instead of copying
+/// them into a temporary allocation first, and in-memory sources can lend out data for `'de`.
+pub trait Input<'de>: io::Read {
+    /// Reads `len` bytes of UTF-8 and hands them to `visit` as a borrowed string.
+    fn read_str<T, F>(&mut self, len: usize, visit: F) -> error::Result<T>
+    where
+        F: FnOnce(&str) -> error::Result<T>;
+
+    /// Number of bytes consumed so far.
+    fn position(&self) -> u64;
+
+    /// Consumes `len` bytes and returns them borrowed from the underlying data, or returns `None`
+    /// without consuming anything if the source can't lend out data.
+    fn borrow_bytes(&mut self, _len: usize) -> error::Result<Option<&'de [u8]>> {
+        Ok(None)
+    }
+
+    /// Number of bytes left, if the source knows.
+    fn remaining(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A string read by [`Parser::parse_borrowed_str`], which lives for `'de` if the input could lend
+/// it out and only as long as the visit otherwise.
+pub(crate) enum Str<'de, 's> {
+    Borrowed(&'de str),
+    Transient(&'s str),
+}
+
+/// Kind of a compound entry, see [`Parser::parse_entry`].
+pub(crate) enum Entry {
+    Known(NBTKind),
+    /// An entry of a tag id the spec doesn't define, with its name and the value the unknown tag
+    /// hook read for it, if any.
+    Unknown(String, Option<NBTValue>),
+}
+
+/// Turns the `i32` length of a list or array whose elements take at least `element_size` bytes
+/// each into a count, rejecting negative lengths unless `policy` allows them and lengths that
+/// need more than the `remaining` bytes.
+pub(crate) fn checked_length(
+    length: i32,
+    element_size: u64,
+    remaining: Option<u64>,
+    policy: LengthPolicy,
+) -> error::Result<usize> {
+    let invalid = || error::Error::InvalidLength(length as i64);
+    let count = match u64::try_from(length) {
+        Ok(count) => count,
+        Err(_) if policy == LengthPolicy::Lenient => 0,
+        Err(_) => return Err(invalid()),
+    };
+    if remaining.is_some_and(|remaining| count.saturating_mul(element_size) > remaining) {
+        return Err(invalid());
+    }
+    usize::try_from(count).map_err(|_| invalid())
+}
+
+/// The fewest bytes a payload of type `kind` can take up.
+pub(crate) fn min_payload_size(kind: NBTKind) -> u64 {
+    match kind {
+        NBTKind::End => 0,
+        NBTKind::Byte | NBTKind::Compound => 1,
+        NBTKind::Short | NBTKind::String => 2,
+        NBTKind::Int | NBTKind::Float => 4,
+        NBTKind::ByteArray | NBTKind::IntArray | NBTKind::LongArray => 4,
+        NBTKind::Long | NBTKind::Double => 8,
+        NBTKind::List => 5,
+    }
+}
+
+/// Input over any reader. Strings are copied into a scratch buffer, which is kept around for the
+/// next string, before being decoded.
+pub struct IoRead<R: io::Read> {
+    reader: R,
+    position: u64,
+    scratch: Vec<u8>,
+}
+
+impl<R: io::Read> IoRead<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            position: 0,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Starts over on `reader`, keeping the scratch buffer. Returns the previous reader.
+    pub(crate) fn reset(&mut self, reader: R) -> R {
+        self.position = 0;
+        core::mem::replace(&mut self.reader, reader)
+    }
+}
+
+/// Input over a buffered reader. Strings that are already in the reader's buffer are decoded in
+/// place, everything else falls back to copying.
+#[cfg(feature = "std")]
+pub struct BufIoRead<R: BufRead> {
     reader: R,
+    position: u64,
 }
 
-impl<R: io::Read> Parser<R> {
+#[cfg(feature = "std")]
+impl<R: BufRead> BufIoRead<R> {
     pub(crate) fn new(reader: R) -> Self {
-        Self { reader }
+        Self {
+            reader,
+            position: 0,
+        }
+    }
+}
+
+/// Input over a slice that is entirely in memory. Strings are decoded in place and payloads can be
+/// borrowed for the lifetime of the slice.
+pub struct SliceRead<'a> {
+    slice: &'a [u8],
+    position: u64,
+}
+
+impl<'a> SliceRead<'a> {
+    pub(crate) fn new(slice: &'a [u8]) -> Self {
+        Self { slice, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> error::Result<&'a [u8]> {
+        if self.slice.len() < len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let (taken, rest) = self.slice.split_at(len);
+        self.slice = rest;
+        self.position += len as u64;
+        Ok(taken)
+    }
+}
+
+fn invalid_utf8(err: str::Utf8Error) -> error::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err).into()
+}
+
+#[cfg(feature = "std")]
+fn read_str_copied<R, T, F>(reader: &mut R, len: usize, visit: F) -> error::Result<T>
+where
+    R: io::Read,
+    F: FnOnce(&str) -> error::Result<T>,
+{
+    let mut buffer = alloc::vec![0u8; len];
+    reader.read_exact(&mut buffer)?;
+    visit(str::from_utf8(&buffer).map_err(invalid_utf8)?)
+}
+
+impl<R: io::Read> io::Read for IoRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<'de, R: io::Read> Input<'de> for IoRead<R> {
+    fn read_str<T, F>(&mut self, len: usize, visit: F) -> error::Result<T>
+    where
+        F: FnOnce(&str) -> error::Result<T>,
+    {
+        self.scratch.resize(len, 0);
+        self.reader.read_exact(&mut self.scratch)?;
+        self.position += len as u64;
+        visit(str::from_utf8(&self.scratch).map_err(invalid_utf8)?)
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> io::Read for BufIoRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, R: BufRead> Input<'de> for BufIoRead<R> {
+    fn read_str<T, F>(&mut self, len: usize, visit: F) -> error::Result<T>
+    where
+        F: FnOnce(&str) -> error::Result<T>,
+    {
+        let buffer = self.reader.fill_buf()?;
+        if buffer.len() < len {
+            return read_str_copied(self, len, visit);
+        }
+        let result = str::from_utf8(&buffer[..len])
+            .map_err(invalid_utf8)
+            .and_then(visit);
+        self.reader.consume(len);
+        self.position += len as u64;
+        result
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+impl io::Read for SliceRead<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.slice.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<'de> Input<'de> for SliceRead<'de> {
+    fn read_str<T, F>(&mut self, len: usize, visit: F) -> error::Result<T>
+    where
+        F: FnOnce(&str) -> error::Result<T>,
+    {
+        visit(str::from_utf8(self.take(len)?).map_err(invalid_utf8)?)
     }
 
-    parse_number_types!(i16, i32, i64, f32, f64);
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn borrow_bytes(&mut self, len: usize) -> error::Result<Option<&'de [u8]>> {
+        self.take(len).map(Some)
+    }
+
+    fn remaining(&self) -> Option<u64> {
+        Some(self.slice.len() as u64)
+    }
+}
+
+/// Reads NBT primitives from `R`. Multi-byte numbers are decoded with the byte order `O`, which is
+/// big-endian for Java Edition and little-endian for Bedrock Edition.
+pub(crate) struct Parser<R, O: ByteOrder = BigEndian> {
+    reader: R,
+    options: DeserializerOptions,
+    // What the current document has used of the budget in `options`.
+    elements: u64,
+    decoded_bytes: u64,
+    // Where the deserializer is and what it has noticed, when a report was asked for.
+    pub(crate) report: Option<ReportState>,
+    byte_order: PhantomData<O>,
+}
+
+impl<'de, R: Input<'de>, O: ByteOrder> Parser<R, O> {
+    pub(crate) fn new(reader: R, options: DeserializerOptions) -> Self {
+        Self {
+            reader,
+            options,
+            elements: 0,
+            decoded_bytes: 0,
+            report: None,
+            byte_order: PhantomData,
+        }
+    }
+
+    /// Starts the budget over for the next document.
+    pub(crate) fn reset_budget(&mut self) {
+        self.elements = 0;
+        self.decoded_bytes = 0;
+    }
+
+    /// Counts `elements` values taking up `bytes` against the budget, before they are decoded.
+    fn charge(&mut self, elements: u64, bytes: u64) -> error::Result<()> {
+        self.elements += elements;
+        self.decoded_bytes += bytes;
+        let options = &self.options;
+        if options.max_elements.is_some_and(|max| self.elements > max)
+            || options
+                .max_decoded_bytes
+                .is_some_and(|max| self.decoded_bytes > max)
+        {
+            return Err(error::Error::BudgetExceeded);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn options(&self) -> &DeserializerOptions {
+        &self.options
+    }
+
+    pub(crate) fn input_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    parse_number_types!(u16, i16, i32, i64, f32, f64);
+
+    pub(crate) fn parse_kind(&mut self) -> error::Result<NBTKind> {
+        NBTKind::try_from(self.parse_u8()?)
+    }
+
+    /// Parses the kind of a compound entry. With an unknown tag hook set, entries whose tag id is
+    /// unknown have their name read here and their payload read by the hook.
+    pub(crate) fn parse_entry(&mut self) -> error::Result<Entry> {
+        let id = self.parse_u8()?;
+        let err = match NBTKind::try_from(id) {
+            Ok(kind) => return Ok(Entry::Known(kind)),
+            Err(err) => err,
+        };
+        let Some(hook) = self.options.unknown_tags.clone() else {
+            return Err(err);
+        };
+        let name = self.parse_str(|name| Ok(name.to_owned()))?;
+        let value = hook.read(id, &mut self.reader)?;
+        Ok(Entry::Unknown(name, value))
+    }
+
+    /// Parses the element kind and length that prefix a list payload. Negative lengths are read as
+    /// empty lists, and only empty lists may have elements of type End.
+    pub(crate) fn parse_list_header(&mut self) -> error::Result<(NBTKind, usize)> {
+        let kind = self.parse_kind()?;
+        let length = self.parse_i32()?;
+        if kind == NBTKind::End && length > 0 {
+            return Err(error::Error::NonEmptyEndList(length));
+        }
+        let length = self.checked_length(length, min_payload_size(kind))?;
+        self.charge(length as u64, 0)?;
+        Ok((kind, length))
+    }
+
+    /// Parses the length that prefixes an array payload, in elements of `element_size` bytes.
+    pub(crate) fn parse_array_length(&mut self, element_size: u64) -> error::Result<usize> {
+        let length = self.parse_i32()?;
+        let length = self.checked_length(length, element_size)?;
+        self.charge(length as u64, length as u64 * element_size)?;
+        Ok(length)
+    }
+
+    fn checked_length(&self, length: i32, element_size: u64) -> error::Result<usize> {
+        let remaining = self.reader.remaining();
+        checked_length(length, element_size, remaining, self.options.lengths)
+    }
+
+    /// Number of bytes consumed so far.
+    pub(crate) fn position(&self) -> u64 {
+        self.reader.position()
+    }
 
-    pub(crate) fn parse_kind(&mut self) -> io::Result<NBTKind> {
-        Ok(NBTKind::from(self.reader.read_u8()?))
+    /// Like [`parse_str`](Self::parse_str), but hands the string out borrowed for `'de` when the
+    /// input can lend it.
+    pub(crate) fn parse_borrowed_str<T, F>(&mut self, visit: F) -> error::Result<T>
+    where
+        F: FnOnce(Str<'de, '_>) -> error::Result<T>,
+    {
+        let length = self.parse_u16()?;
+        self.charge(1, length as u64)?;
+        if let Some(bytes) = self.reader.borrow_bytes(length as usize)? {
+            return visit(Str::Borrowed(str::from_utf8(bytes).map_err(invalid_utf8)?));
+        }
+        self.reader
+            .read_str(length as usize, |string| visit(Str::Transient(string)))
     }
 
-    pub(crate) fn parse_string(&mut self) -> io::Result<String> {
+    /// Parses a string and hands it to `visit` without requiring an owned copy.
+    pub(crate) fn parse_str<T, F>(&mut self, visit: F) -> error::Result<T>
+    where
+        F: FnOnce(&str) -> error::Result<T>,
+    {
         // The first byte in a tag is the tag type (ID)
         // (Note TAG_End is not named and does not contain the extra 2 bytes;
         // the name is assumed to be empty).
-        // followed by a two byte big-endian unsigned integer for the length of the name
-        let name_length = self.reader.read_u16::<byteorder::BigEndian>()?;
-        let mut buffer = vec![0u8; name_length as usize];
-        self.reader.read_exact(&mut buffer)?;
-        Ok(String::from_utf8(buffer).unwrap())
+        // followed by a two byte unsigned integer for the length of the name
+        let name_length = self.parse_u16()?;
+        self.charge(1, name_length as u64)?;
+        self.reader.read_str(name_length as usize, visit)
+    }
+
+    /// Skips over the payload of a tag of type `kind` without decoding it.
+    pub(crate) fn skip_payload(&mut self, kind: NBTKind) -> error::Result<()> {
+        match kind {
+            NBTKind::End => Ok(()),
+            NBTKind::Byte => self.skip_bytes(1),
+            NBTKind::Short => self.skip_bytes(2),
+            NBTKind::Int | NBTKind::Float => self.skip_bytes(4),
+            NBTKind::Long | NBTKind::Double => self.skip_bytes(8),
+            NBTKind::ByteArray => self.skip_array(1),
+            NBTKind::IntArray => self.skip_array(4),
+            NBTKind::LongArray => self.skip_array(8),
+            NBTKind::String => {
+                let length = self.parse_u16()?;
+                self.skip_bytes(length as u64)
+            }
+            NBTKind::List => {
+                let offset = self.position();
+                let (element_kind, length) = self.parse_list_header()?;
+                for _ in 0..length {
+                    self.skip_payload(element_kind)
+                        .map_err(|err| err.in_list(element_kind, offset))?;
+                }
+                Ok(())
+            }
+            NBTKind::Compound => loop {
+                let kind = match self.parse_entry()? {
+                    Entry::Known(NBTKind::End) => return Ok(()),
+                    Entry::Known(kind) => kind,
+                    Entry::Unknown(..) => continue,
+                };
+                // Names are encoded the same way as string payloads.
+                self.skip_payload(NBTKind::String)?;
+                self.skip_payload(kind)?;
+            },
+        }
+    }
+
+    /// Reads the payload of an array as raw bytes in the byte order of the input. In-memory inputs
+    /// lend the payload out instead of copying it.
+    pub(crate) fn parse_array_bytes(&mut self, element_size: u64) -> error::Result<Cow<'de, [u8]>> {
+        let length = self.parse_array_length(element_size)? as u64 * element_size;
+        if let Some(bytes) = self.reader.borrow_bytes(length as usize)? {
+            return Ok(Cow::Borrowed(bytes));
+        }
+        let mut bytes = Vec::new();
+        if io::read_up_to(&mut self.reader, length, &mut bytes)? < length {
+            return Err(error::Error::Eof);
+        }
+        Ok(Cow::Owned(bytes))
+    }
+
+    fn skip_array(&mut self, element_size: u64) -> error::Result<()> {
+        let length = self.parse_i32()?;
+        let length = self.checked_length(length, element_size)?;
+        self.skip_bytes(length as u64 * element_size)
+    }
+
+    pub(crate) fn skip_bytes(&mut self, count: u64) -> error::Result<()> {
+        if io::skip_up_to(&mut self.reader, count)? < count {
+            return Err(error::Error::Eof);
+        }
+        Ok(())
+    }
+
+    /// Reads raw payload bytes, leaving them in the byte order of the input.
+    #[cfg(feature = "std")]
+    pub(crate) fn read_raw(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
     }
 
     // Separated from the number type macro since a single byte does not have an endianess.
     pub(crate) fn parse_i8(&mut self) -> io::Result<i8> {
-        self.reader.read_i8()
+        self.parse_u8().map(|n| n as i8)
+    }
+
+    fn parse_u8(&mut self) -> io::Result<u8> {
+        let mut buf = [0];
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+}
+
+/// Reader adapter that fails once more than `limit` bytes have been consumed.
+pub(crate) struct Limited<R: io::Read> {
+    reader: R,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<R: io::Read> Limited<R> {
+    pub(crate) fn new(reader: R, limit: u64) -> Self {
+        Self {
+            reader,
+            limit,
+            remaining: limit,
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            let err = error::Error::SizeLimitExceeded(self.limit);
+            return Err(io::Error::other(err));
+        }
+        let max = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..max])?;
+        self.remaining -= read as u64;
+        Ok(read)
     }
 }
@@ -0,0 +1,392 @@
+//! Best-effort reading of damaged documents, for tools that would rather have part of a file than
+//! none of it.
+use std::{borrow::Cow, io};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{
+    de::DeserializerOptions,
+    error::{self, Error},
+    kind::NBTKind,
+    parser::{Input, IoRead, Parser, SliceRead},
+    tag::{CompoundMap, NBTPath, NBTValue, PathSegment},
+};
+
+#[test]
+fn test_recover() {
+    use crate::CompoundBuilder;
+
+    let document = CompoundBuilder::new()
+        .string("a", "first")
+        .list(
+            "b",
+            [NBTValue::String("ok".into()), NBTValue::String("xx".into())],
+        )
+        .int("c", 3)
+        .build();
+    let bytes = crate::to_bytes(&document).unwrap();
+    let recovered = from_slice_recovering(&bytes).unwrap();
+    assert_eq!(recovered.value, document);
+    assert!(recovered.errors.is_empty());
+
+    // Invalid UTF-8 has a known length, so reading carries on after it.
+    let mut damaged = bytes.clone();
+    let at = bytes.windows(2).position(|window| window == b"xx").unwrap();
+    damaged[at] = 0xff;
+    let recovered = from_slice_recovering(&damaged).unwrap();
+    let NBTValue::Compound(map) = &recovered.value else {
+        unreachable!()
+    };
+    assert_eq!(map["c"], NBTValue::Int(3));
+    assert_eq!(recovered.errors.len(), 1);
+    assert_eq!(recovered.errors[0].path.to_string(), "b[1]");
+    assert_eq!(recovered.errors[0].offset, at as u64 - 2);
+
+    // An unknown tag id leaves no way to find the next entry, everything before it is kept.
+    let mut damaged = bytes.clone();
+    let at = bytes
+        .windows(2)
+        .position(|window| window == [3, 0])
+        .unwrap();
+    damaged[at] = 0x42;
+    let recovered = from_slice_recovering(&damaged).unwrap();
+    let (NBTValue::Compound(original), NBTValue::Compound(map)) = (&document, &recovered.value)
+    else {
+        unreachable!()
+    };
+    assert!(!map.contains_key("c"));
+    assert!(map.iter().all(|(key, value)| original[key] == *value));
+    assert_eq!(recovered.errors.len(), 1);
+    assert_eq!(recovered.errors[0].offset, at as u64);
+
+    let recovered = from_reader_recovering(&bytes[..bytes.len() - 3]).unwrap();
+    assert_eq!(recovered.errors.len(), 1);
+}
+
+#[test]
+fn test_scan_for_compounds() {
+    use crate::CompoundBuilder;
+
+    let first = CompoundBuilder::new()
+        .compound("Data", CompoundBuilder::new().long("Seed", 7))
+        .build();
+    let second = CompoundBuilder::new()
+        .string("id", "minecraft:chest")
+        .build();
+    let mut blob = vec![0x0a, 0xff, 0xff, 0x00];
+    blob.extend(crate::to_bytes(&first).unwrap());
+    // A cut-off copy of the second document, then the whole of it.
+    let bytes = crate::to_bytes(&second).unwrap();
+    blob.extend(&bytes[..bytes.len() - 4]);
+    blob.extend([0xff; 8]);
+    let offset = blob.len();
+    blob.extend(&bytes);
+    blob.extend([0x0a, 0x00, 0x00, 0x00, 0x0a]);
+
+    let found: Vec<_> = scan_for_compounds(&blob).collect();
+    assert_eq!(found.len(), 2);
+    assert_eq!((found[0].offset, &found[0].value), (4, &first));
+    assert_eq!((found[1].offset, &found[1].value), (offset, &second));
+    assert_eq!(found[1].size, bytes.len());
+}
+
+/// A part of a document that couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corruption {
+    /// Path of the value that couldn't be read, or of the compound whose entry name couldn't be.
+    pub path: NBTPath,
+    /// Offset into the document where the damaged value starts.
+    pub offset: u64,
+    pub message: String,
+}
+
+/// What [`from_slice_recovering`] could make out of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovered {
+    pub name: String,
+    pub value: NBTValue,
+    /// Every place the document was damaged, in the order they were found. Empty if the whole
+    /// document was read.
+    pub errors: Vec<Corruption>,
+}
+
+/// Reads a document into an [`NBTValue`], keeping whatever can be read when parts of it are
+/// damaged rather than failing as a whole. Only a root that isn't a compound is an error.
+///
+/// Damage that doesn't change how long a value is, such as invalid UTF-8 or a list of End with
+/// elements, is recorded and reading carries on with the next value: strings are decoded lossily
+/// and such lists are read as empty. Unknown tag ids and truncation leave no way to tell where the
+/// next value starts, so the value is left out and so is everything after it, but every value read
+/// before it is kept.
+pub fn from_slice_recovering(bytes: &[u8]) -> error::Result<Recovered> {
+    Recovery::new(SliceRead::new(bytes)).read()
+}
+
+/// Like [`from_slice_recovering`], for a document read from `r`.
+pub fn from_reader_recovering<R: io::Read>(r: R) -> error::Result<Recovered> {
+    Recovery::new(IoRead::new(r)).read()
+}
+
+// Something that couldn't be read, along with what could be read of it.
+type Lost = Option<NBTValue>;
+
+struct Recovery<R> {
+    parser: Parser<R, BigEndian>,
+    path: NBTPath,
+    errors: Vec<Corruption>,
+}
+
+impl<'de, R: Input<'de>> Recovery<R> {
+    fn new(input: R) -> Self {
+        Self {
+            parser: Parser::new(input, DeserializerOptions::default()),
+            path: NBTPath::default(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn read(mut self) -> error::Result<Recovered> {
+        if self.parser.parse_kind()? != NBTKind::Compound {
+            return Err(Error::ExpectedRootCompound);
+        }
+        let offset = self.parser.position();
+        let (name, value) = match self.string(offset) {
+            // Compounds always give back what they read before the damage.
+            Ok(name) => match self.compound() {
+                Ok(value) | Err(Some(value)) => (name, value),
+                Err(None) => unreachable!(),
+            },
+            Err(_) => (String::new(), NBTValue::Compound(CompoundMap::default())),
+        };
+        Ok(Recovered {
+            name,
+            value,
+            errors: self.errors,
+        })
+    }
+
+    fn record(&mut self, offset: u64, err: Error) {
+        self.errors.push(Corruption {
+            path: self.path.clone(),
+            offset,
+            message: err.to_string(),
+        });
+    }
+
+    // Reads a length-prefixed string, lossily if it isn't valid UTF-8.
+    fn string(&mut self, offset: u64) -> Result<String, Lost> {
+        let mut length = [0; 2];
+        let mut bytes = Vec::new();
+        let read = self
+            .parser
+            .input_mut()
+            .read_exact(&mut length)
+            .and_then(|_| {
+                bytes.resize(BigEndian::read_u16(&length) as usize, 0);
+                self.parser.input_mut().read_exact(&mut bytes)
+            });
+        if let Err(err) = read {
+            self.record(offset, err.into());
+            return Err(None);
+        }
+        match String::from_utf8_lossy(&bytes) {
+            Cow::Borrowed(string) => Ok(string.to_owned()),
+            Cow::Owned(string) => {
+                let err = io::Error::new(io::ErrorKind::InvalidData, "string is not valid UTF-8");
+                self.record(offset, err.into());
+                Ok(string)
+            }
+        }
+    }
+
+    // Reads a payload, or returns what could be read of it if its end couldn't be found.
+    fn payload(&mut self, kind: NBTKind) -> Result<NBTValue, Lost> {
+        let offset = self.parser.position();
+        match kind {
+            NBTKind::String => self.string(offset).map(NBTValue::String),
+            NBTKind::List => self.list(offset),
+            NBTKind::Compound => self.compound(),
+            kind => self.primitive(kind).map_err(|err| {
+                self.record(offset, err);
+                None
+            }),
+        }
+    }
+
+    fn primitive(&mut self, kind: NBTKind) -> error::Result<NBTValue> {
+        let parser = &mut self.parser;
+        Ok(match kind {
+            NBTKind::Byte => NBTValue::Byte(parser.parse_i8()?),
+            NBTKind::Short => NBTValue::Short(parser.parse_i16()?),
+            NBTKind::Int => NBTValue::Int(parser.parse_i32()?),
+            NBTKind::Long => NBTValue::Long(parser.parse_i64()?),
+            NBTKind::Float => NBTValue::Float(parser.parse_f32()?),
+            NBTKind::Double => NBTValue::Double(parser.parse_f64()?),
+            NBTKind::ByteArray => {
+                let raw = parser.parse_array_bytes(1)?;
+                NBTValue::ByteArray(raw.iter().map(|&b| b as i8).collect())
+            }
+            NBTKind::IntArray => {
+                let raw = parser.parse_array_bytes(4)?;
+                NBTValue::IntArray(raw.chunks_exact(4).map(BigEndian::read_i32).collect())
+            }
+            NBTKind::LongArray => {
+                let raw = parser.parse_array_bytes(8)?;
+                NBTValue::LongArray(raw.chunks_exact(8).map(BigEndian::read_i64).collect())
+            }
+            _ => return Err(Error::InvalidTagId),
+        })
+    }
+
+    fn list(&mut self, offset: u64) -> Result<NBTValue, Lost> {
+        let (kind, length) = match self.parser.parse_list_header() {
+            Ok(header) => header,
+            // End payloads are empty, so the elements take up no room and can be dropped.
+            Err(err @ Error::NonEmptyEndList(_)) => {
+                self.record(offset, err);
+                return Ok(NBTValue::List(Vec::new()));
+            }
+            Err(err) => {
+                self.record(offset, err);
+                return Err(None);
+            }
+        };
+        let mut elements = Vec::with_capacity(length.min(1024));
+        for i in 0..length {
+            self.path.0.push(PathSegment::Index(i));
+            let element = self.payload(kind);
+            self.path.0.pop();
+            match element {
+                Ok(element) => elements.push(element),
+                Err(partial) => {
+                    elements.extend(partial);
+                    return Err(Some(NBTValue::List(elements)));
+                }
+            }
+        }
+        Ok(NBTValue::List(elements))
+    }
+
+    fn compound(&mut self) -> Result<NBTValue, Lost> {
+        let mut entries = CompoundMap::default();
+        loop {
+            let offset = self.parser.position();
+            let kind = match self.parser.parse_kind() {
+                Ok(NBTKind::End) => return Ok(NBTValue::Compound(entries)),
+                Ok(kind) => kind,
+                Err(err) => {
+                    self.record(offset, err);
+                    return Err(Some(NBTValue::Compound(entries)));
+                }
+            };
+            let Ok(name) = self.string(offset + 1) else {
+                return Err(Some(NBTValue::Compound(entries)));
+            };
+            self.path.0.push(PathSegment::Key(name.clone()));
+            let value = self.payload(kind);
+            self.path.0.pop();
+            match value {
+                Ok(value) => {
+                    entries.insert(name, value);
+                }
+                Err(partial) => {
+                    if let Some(partial) = partial {
+                        entries.insert(name, partial);
+                    }
+                    return Err(Some(NBTValue::Compound(entries)));
+                }
+            }
+        }
+    }
+}
+
+/// A document found by [`scan_for_compounds`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoundDocument {
+    /// Offset of the document's first byte in the scanned data.
+    pub offset: usize,
+    /// Number of bytes the document takes up.
+    pub size: usize,
+    pub name: String,
+    pub value: NBTValue,
+}
+
+/// Looks for uncompressed big-endian documents anywhere in `bytes`, such as a damaged file or a
+/// disk image, and yields every one that decodes. A candidate is a Compound tag id followed by a
+/// root name of at most [`MAX_NAME_LENGTH`] bytes of UTF-8 without control characters, and a
+/// body that passes [`validate_bytes`](crate::validate_bytes) and holds at least one entry.
+/// Scanning resumes after the end of each document found, so compounds nested in it aren't
+/// reported on their own.
+///
+/// Region files store chunks compressed, so their chunks are only found once decompressed, e.g.
+/// by `RegionFile::repair` for those it can still locate.
+///
+/// ```
+/// let document = nbt::CompoundBuilder::new().int("x", 1).build();
+/// let mut blob = vec![0xff; 100];
+/// blob.extend(nbt::to_bytes(&document).unwrap());
+/// blob.extend([0x0a, 0x00, 0x00, 0x00]);
+///
+/// let found: Vec<_> = nbt::scan_for_compounds(&blob).collect();
+/// assert_eq!(found.len(), 1);
+/// assert_eq!((found[0].offset, found[0].value.clone()), (100, document));
+/// ```
+pub fn scan_for_compounds(bytes: &[u8]) -> CompoundScan<'_> {
+    CompoundScan { bytes, offset: 0 }
+}
+
+/// Longest root name [`scan_for_compounds`] accepts. Real documents have short or empty root
+/// names, so this rules out most random data early.
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// Iterator returned by [`scan_for_compounds`].
+pub struct CompoundScan<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl CompoundScan<'_> {
+    // Decodes the document starting at `offset`, if there is a plausible one.
+    fn document_at(&self, offset: usize) -> Option<FoundDocument> {
+        let bytes = &self.bytes[offset..];
+        let name_length = BigEndian::read_u16(bytes.get(1..3)?) as usize;
+        let name = bytes.get(3..3 + name_length)?;
+        if name_length > MAX_NAME_LENGTH
+            || std::str::from_utf8(name).map_or(true, |name| name.chars().any(char::is_control))
+        {
+            return None;
+        }
+        let summary = crate::validate_bytes(bytes).ok()?;
+        if summary.tags < 2 {
+            return None;
+        }
+        let size = summary.size;
+        let value = crate::from_slice(&bytes[..size]).ok()?;
+        Some(FoundDocument {
+            offset,
+            size,
+            name: summary.root_name,
+            value,
+        })
+    }
+}
+
+impl Iterator for CompoundScan<'_> {
+    type Item = FoundDocument;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let compound = NBTKind::Compound.header_byte();
+        while self.offset < self.bytes.len() {
+            let offset = self.offset;
+            self.offset += 1;
+            if self.bytes[offset] != compound {
+                continue;
+            }
+            if let Some(found) = self.document_at(offset) {
+                self.offset = offset + found.size;
+                return Some(found);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,957 @@
+//! Reading chunks out of region files, which store the 32×32 chunks of one region each compressed
+//! on its own. Both Anvil (`.mca`) and the older McRegion (`.mcr`) files are supported.
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{
+    read::{DeflateDecoder, GzDecoder, ZlibDecoder},
+    write::ZlibEncoder,
+};
+
+use crate::{
+    chunk::RawChunk,
+    error::{self, Error},
+    file::Compression,
+    timestamp::Timestamp,
+};
+
+#[test]
+fn test_read_chunk() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Chunk {
+        x_pos: i32,
+        z_pos: i32,
+    }
+
+    // Chunk (-1, 33) sits at (31, 1) in region (-1, 1).
+    let chunk = Chunk {
+        x_pos: -1,
+        z_pos: 33,
+    };
+    let region = test_region(&[(31, 1, crate::to_bytes(&chunk).unwrap())]);
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("region")).unwrap();
+    std::fs::write(dir.path().join("region/r.-1.1.mca"), &region).unwrap();
+
+    let (block_x, block_z) = (-5, 530);
+    assert_eq!(chunk_at_block(block_x, block_z), (-1, 33));
+    assert_eq!(region_at_chunk(-1, 33), (-1, 1));
+    let bytes = read_chunk_at_block(dir.path(), block_x, block_z)
+        .unwrap()
+        .unwrap();
+    assert_eq!(crate::from_slice::<Chunk>(&bytes).unwrap(), chunk);
+
+    let mut file = RegionFile::new(io::Cursor::new(region)).unwrap();
+    assert_eq!(file.chunks().collect::<Vec<_>>(), [(31, 1)]);
+    assert_eq!(file.timestamp(31, 1).unwrap(), Some(1));
+    assert_eq!(
+        file.modified(31, 1).unwrap(),
+        Some(UNIX_EPOCH + std::time::Duration::from_secs(1))
+    );
+    assert!(file.read_chunk(0, 0).unwrap().is_none());
+    assert!(matches!(
+        file.read_chunk(32, 0),
+        Err(Error::ChunkOutsideRegion(32, 0))
+    ));
+    assert!(matches!(
+        file.timestamp(32, 0),
+        Err(Error::ChunkOutsideRegion(32, 0))
+    ));
+    assert!(read_chunk_at_block(dir.path(), 0, 0).unwrap().is_none());
+}
+
+#[test]
+fn test_mcregion() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("r.0.0.mcr");
+    std::fs::write(&path, test_region(&[(2, 3, vec![10, 0, 0, 0])])).unwrap();
+
+    let mut file = RegionFile::open(&path).unwrap();
+    assert_eq!(file.format(), RegionFormat::McRegion);
+    assert_eq!(file.read_chunk(2, 3).unwrap().unwrap(), [10, 0, 0, 0]);
+    assert_eq!(RegionFormat::McRegion.extension(), "mcr");
+}
+
+#[test]
+fn test_compression_fallbacks() {
+    use flate2::{write::DeflateEncoder, Compression};
+    use std::io::Write;
+
+    let nbt = [10, 0, 0, 0];
+    // A region whose only chunk is stored with `scheme` as `payload`.
+    let region_with = |scheme: u8, payload: &[u8]| {
+        let mut region = test_region(&[(0, 0, nbt.to_vec())]);
+        region.truncate(2 * SECTOR_SIZE);
+        region.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        region.push(scheme);
+        region.extend_from_slice(payload);
+        region.resize(3 * SECTOR_SIZE, 0);
+        RegionFile::new(io::Cursor::new(region)).unwrap()
+    };
+
+    let mut deflate = DeflateEncoder::new(Vec::new(), Compression::default());
+    deflate.write_all(&nbt).unwrap();
+    let mut file = region_with(2, &deflate.finish().unwrap());
+    assert_eq!(file.read_chunk(0, 0).unwrap().unwrap(), nbt);
+
+    let reversed: Vec<u8> = nbt.iter().rev().copied().collect();
+    let mut file = region_with(42, &reversed);
+    assert!(matches!(
+        file.read_chunk(0, 0),
+        Err(Error::UnknownCompression(42))
+    ));
+    file.set_decompressor(42, |bytes| Ok(bytes.iter().rev().copied().collect()));
+    assert_eq!(file.read_chunk(0, 0).unwrap().unwrap(), nbt);
+}
+
+#[test]
+fn test_verify_and_repair() {
+    let chunk = |x: i32| {
+        let chunk = crate::CompoundBuilder::new()
+            .int("xPos", x)
+            .int("zPos", 0)
+            .build();
+        crate::to_bytes(&chunk).unwrap()
+    };
+    let mut region = test_region(&[(0, 0, chunk(0)), (1, 0, chunk(1)), (2, 0, chunk(2))]);
+    // (1, 0) claims the sector of (0, 0), and (2, 0) holds garbage.
+    let location_0 = region[0..4].to_vec();
+    region[4..8].copy_from_slice(&location_0);
+    let sector_2 = u32::from_be_bytes(region[8..12].try_into().unwrap()) >> 8;
+    let start = sector_2 as usize * SECTOR_SIZE;
+    region[start + 5..start + 9].copy_from_slice(&[1, 2, 3, 4]);
+    // (3, 0) points past the end of the file.
+    region[12..16].copy_from_slice(&(100u32 << 8 | 1).to_be_bytes());
+
+    let mut file = RegionFile::new(io::Cursor::new(region)).unwrap();
+    let mut reports = Vec::new();
+    let issues = file
+        .verify_with_progress(|progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    let done: Vec<_> = reports
+        .iter()
+        .map(|progress| (progress.chunks_done, progress.chunks_total))
+        .collect();
+    assert_eq!(done, [(1, 4), (2, 4), (3, 4), (4, 4)]);
+    // (3, 0) was never read.
+    assert_eq!(reports[3].bytes_processed, reports[2].bytes_processed);
+    let kinds: Vec<_> = issues
+        .iter()
+        .map(|issue| ((issue.x, issue.z), &issue.kind))
+        .collect();
+    assert_eq!(kinds.len(), 3, "{:?}", kinds);
+    assert_eq!(kinds[0], ((1, 0), &ChunkIssueKind::Overlaps { x: 0, z: 0 }));
+    assert!(matches!(kinds[1], ((2, 0), ChunkIssueKind::Undecodable(_))));
+    assert_eq!(
+        kinds[2],
+        (
+            (3, 0),
+            &ChunkIssueKind::OutOfBounds {
+                sector: 100,
+                sectors: 1
+            }
+        )
+    );
+
+    let mut repaired = io::Cursor::new(Vec::new());
+    let dropped = file.repair(&mut repaired).unwrap();
+    // The data (1, 0) points to is that of (0, 0), so only (0, 0) keeps it.
+    let positions: Vec<_> = dropped.iter().map(|issue| (issue.x, issue.z)).collect();
+    assert_eq!(positions, [(1, 0), (2, 0), (3, 0)]);
+    assert_eq!(dropped[0], issues[0]);
+    let mut repaired = RegionFile::new(repaired).unwrap();
+    assert!(repaired.verify().unwrap().is_empty());
+    assert_eq!(repaired.chunks().collect::<Vec<_>>(), [(0, 0)]);
+    let kept = repaired.read_chunk(0, 0).unwrap().unwrap();
+    assert_eq!(
+        crate::from_slice::<crate::NBTValue>(&kept).unwrap(),
+        crate::from_slice::<crate::NBTValue>(&chunk(0)).unwrap()
+    );
+    assert_eq!(repaired.timestamp(0, 0).unwrap(), Some(1));
+
+    let cancelled =
+        file.repair_with_progress(io::Cursor::new(Vec::new()), |progress| {
+            match progress.chunks_done {
+                1 => ControlFlow::Continue(()),
+                _ => ControlFlow::Break(()),
+            }
+        });
+    assert!(matches!(cancelled, Err(Error::Cancelled)));
+}
+
+#[test]
+fn test_optimize_into() {
+    use crate::tag::{CompoundBuilder, NBTValue};
+
+    let state = |name: &str| CompoundBuilder::new().string("Name", name).build();
+    // Only air, packed at 4 bits per entry with an unused entry in the palette.
+    let section = CompoundBuilder::new()
+        .byte("Y", 0)
+        .compound(
+            "block_states",
+            CompoundBuilder::new()
+                .list("palette", [state("minecraft:air"), state("minecraft:dirt")])
+                .long_array("data", vec![0; 256]),
+        )
+        .build();
+    let chunk = CompoundBuilder::new()
+        .int("DataVersion", 3465)
+        .int("xPos", 0)
+        .int("yPos", 0)
+        .int("zPos", 0)
+        .string("Status", "minecraft:full")
+        .long("LastUpdate", 0)
+        .long("InhabitedTime", 0)
+        .string("Unknown", "kept")
+        .list("sections", [section])
+        .build();
+    let chunk = crate::to_bytes(&chunk).unwrap();
+    let old = crate::to_bytes(&CompoundBuilder::new().int("DataVersion", 100).build()).unwrap();
+    let mut region = RegionFile::new(io::Cursor::new(test_region(&[
+        (0, 0, chunk),
+        (1, 0, old.clone()),
+    ])))
+    .unwrap();
+
+    let cancelled =
+        region.optimize_into_with_progress(io::Cursor::new(Vec::new()), |_| ControlFlow::Break(()));
+    assert!(matches!(cancelled, Err(Error::Cancelled)));
+    let mut optimized = io::Cursor::new(Vec::new());
+    region.optimize_into(&mut optimized).unwrap();
+    let mut optimized = RegionFile::new(optimized).unwrap();
+    assert_eq!(optimized.timestamp(1, 0).unwrap(), Some(1));
+    assert_eq!(optimized.read_chunk(1, 0).unwrap().unwrap(), old);
+    let raw = RawChunk::from_slice(&optimized.read_chunk(0, 0).unwrap().unwrap()).unwrap();
+    assert_eq!(raw.root()["Unknown"], NBTValue::String("kept".into()));
+    let chunk = raw.chunk().unwrap();
+    assert_eq!(
+        chunk.sections[0].block_states.palette,
+        [crate::BlockState::new("minecraft:air")]
+    );
+    assert!(chunk.sections[0].block_states.data.is_empty());
+}
+
+/// Builds a region file holding each of `chunks`, given as their position and uncompressed NBT.
+#[cfg(test)]
+pub(crate) fn test_region(chunks: &[(usize, usize, Vec<u8>)]) -> Vec<u8> {
+    let mut region = vec![0; 2 * SECTOR_SIZE];
+    for (x, z, nbt) in chunks {
+        let compressed = StoredChunk::compress(nbt).unwrap().data;
+
+        let index = 4 * chunk_index(*x, *z).unwrap();
+        let sector = (region.len() / SECTOR_SIZE) as u32;
+        let sectors = (compressed.len() + 5).div_ceil(SECTOR_SIZE) as u32;
+        region[index..index + 4].copy_from_slice(&(sector << 8 | sectors).to_be_bytes());
+        region[SECTOR_SIZE + index..SECTOR_SIZE + index + 4].copy_from_slice(&1u32.to_be_bytes());
+        region.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+        region.push(2);
+        region.extend_from_slice(&compressed);
+        region.resize((sector + sectors) as usize * SECTOR_SIZE, 0);
+    }
+    region
+}
+
+/// An empty chunk at `(x, z)` as saved by 1.20, to be filled in with struct update syntax.
+#[cfg(test)]
+pub(crate) fn test_chunk(x: i32, z: i32) -> crate::chunk::ModernChunk {
+    crate::chunk::ModernChunk {
+        data_version: 3465,
+        x_pos: x,
+        y_pos: -4,
+        z_pos: z,
+        status: "minecraft:full".into(),
+        last_update: 0,
+        inhabited_time: 0,
+        sections: Vec::new(),
+        block_entities: Vec::new(),
+    }
+}
+
+/// A world in a temporary folder, built up one folder or region file at a time.
+#[cfg(test)]
+pub(crate) struct TestWorld(tempfile::TempDir);
+
+#[cfg(test)]
+impl TestWorld {
+    pub(crate) fn new() -> Self {
+        TestWorld(tempfile::tempdir().unwrap())
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        self.0.path()
+    }
+
+    /// Creates `folder`, relative to the world, and any missing parents.
+    pub(crate) fn folder(&self, folder: &str) -> &Self {
+        std::fs::create_dir_all(self.path().join(folder)).unwrap();
+        self
+    }
+
+    /// Writes the region file at `path`, relative to the world, built by [`test_region`].
+    pub(crate) fn region(&self, path: &str, chunks: &[(usize, usize, Vec<u8>)]) -> &Self {
+        let path = self.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, test_region(chunks)).unwrap();
+        self
+    }
+
+    pub(crate) fn open(&self) -> crate::world::World {
+        crate::world::World::open(self.path()).unwrap()
+    }
+}
+
+const SECTOR_SIZE: usize = 4096;
+
+/// How far an operation over many chunks has come, passed to its progress callback after each
+/// chunk. The callback returns [`ControlFlow::Break`](std::ops::ControlFlow::Break) to cancel
+/// the operation, which then fails with [`Error::Cancelled`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub chunks_done: usize,
+    pub chunks_total: usize,
+    /// Bytes read from region files so far, as stored on disk.
+    pub bytes_processed: u64,
+}
+
+impl Progress {
+    // Counts one more chunk of `bytes` stored bytes done and reports it to `callback`, failing
+    // with `Error::Cancelled` if it asks to stop.
+    pub(crate) fn advance<G>(&mut self, bytes: u64, callback: &mut G) -> error::Result<()>
+    where
+        G: FnMut(Progress) -> ControlFlow<()>,
+    {
+        self.chunks_done += 1;
+        self.bytes_processed += bytes;
+        match callback(*self) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(Error::Cancelled),
+        }
+    }
+}
+
+/// Chunk that contains the block at `x`, `z`.
+pub fn chunk_at_block(x: i32, z: i32) -> (i32, i32) {
+    (x >> 4, z >> 4)
+}
+
+/// Region that contains the chunk at `x`, `z`.
+pub fn region_at_chunk(x: i32, z: i32) -> (i32, i32) {
+    (x >> 5, z >> 5)
+}
+
+/// Region coordinates of a region file named like `r.<x>.<z>.<extension>`, with the extension of
+/// `format`.
+pub(crate) fn parse_region_name(path: &Path, format: RegionFormat) -> Option<(i32, i32)> {
+    let name = path.file_name()?.to_str()?;
+    let (x, z) = name
+        .strip_prefix("r.")?
+        .strip_suffix(format.extension())?
+        .strip_suffix('.')?
+        .split_once('.')?;
+    Some((x.parse().ok()?, z.parse().ok()?))
+}
+
+/// Path of the region file holding region `x`, `z` of the dimension stored in `dir`.
+pub fn region_path<P: AsRef<Path>>(dir: P, x: i32, z: i32) -> PathBuf {
+    dir.as_ref()
+        .join("region")
+        .join(format!("r.{}.{}.mca", x, z))
+}
+
+/// Reads the chunk containing the block at `x`, `z` from the dimension stored in `dir`, such as a
+/// world's directory for the overworld. Returns the chunk's uncompressed NBT, or `None` if the
+/// chunk or its whole region hasn't been generated.
+pub fn read_chunk_at_block<P: AsRef<Path>>(
+    dir: P,
+    x: i32,
+    z: i32,
+) -> error::Result<Option<Vec<u8>>> {
+    let (chunk_x, chunk_z) = chunk_at_block(x, z);
+    let (region_x, region_z) = region_at_chunk(chunk_x, chunk_z);
+    let mut region = match RegionFile::open(region_path(dir, region_x, region_z)) {
+        Ok(region) => region,
+        Err(Error::IoError(err)) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    region.read_chunk((chunk_x & 31) as usize, (chunk_z & 31) as usize)
+}
+
+/// The generation of region file, which decides the file extension and the compression schemes
+/// chunks may use.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RegionFormat {
+    /// Anvil (`.mca`), used since 1.2.1.
+    #[default]
+    Anvil,
+    /// McRegion (`.mcr`), used from Beta 1.3 up to 1.2.1. The header is the same as Anvil's, but
+    /// chunks are always gzip or zlib compressed and hold the old `Level` layout with 128 block
+    /// high columns.
+    McRegion,
+}
+
+impl RegionFormat {
+    /// Extension of region files in this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            RegionFormat::Anvil => "mca",
+            RegionFormat::McRegion => "mcr",
+        }
+    }
+
+    /// Guesses the format from the extension of `path`, falling back to Anvil.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension() {
+            Some(extension) if extension == "mcr" => RegionFormat::McRegion,
+            _ => RegionFormat::Anvil,
+        }
+    }
+}
+
+/// Decompresses a chunk stored with a compression scheme the crate doesn't know, see
+/// [`RegionFile::set_decompressor`].
+pub type Decompressor = Box<dyn Fn(&[u8]) -> io::Result<Vec<u8>> + Send + Sync>;
+
+/// An open region file. Chunks are addressed by their position within the region, from 0 to 31
+/// on each axis.
+pub struct RegionFile<R> {
+    inner: R,
+    format: RegionFormat,
+    decompressors: HashMap<u8, Decompressor>,
+    // Per chunk, the first sector in the upper three bytes and the sector count in the lowest.
+    locations: Box<[u32; 1024]>,
+    timestamps: Box<[u32; 1024]>,
+}
+
+// Custom decompressors have to be `Send + Sync` so that region files can be read from a thread
+// pool.
+assert_send_sync!(RegionFile<File>);
+
+impl RegionFile<File> {
+    /// Opens the region file at `path`, in the format its extension names.
+    pub fn open<P: AsRef<Path>>(path: P) -> error::Result<Self> {
+        let format = RegionFormat::from_path(&path);
+        Self::with_format(File::open(path)?, format)
+    }
+
+    /// Opens the region file at `path` for reading and writing, creating an empty one if it
+    /// doesn't exist yet.
+    pub fn open_writable<P: AsRef<Path>>(path: P) -> error::Result<Self> {
+        let format = RegionFormat::from_path(&path);
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(&[0; 2 * SECTOR_SIZE])?;
+        }
+        Self::with_format(file, format)
+    }
+}
+
+impl<R: Read + Seek> RegionFile<R> {
+    /// Reads the location and timestamp tables from the start of `reader`, which holds an Anvil
+    /// region file.
+    pub fn new(reader: R) -> error::Result<Self> {
+        Self::with_format(reader, RegionFormat::Anvil)
+    }
+
+    pub fn with_format(mut inner: R, format: RegionFormat) -> error::Result<Self> {
+        let mut locations = Box::new([0; 1024]);
+        let mut timestamps = Box::new([0; 1024]);
+        inner.seek(SeekFrom::Start(0))?;
+        inner.read_u32_into::<BigEndian>(&mut locations[..])?;
+        inner.read_u32_into::<BigEndian>(&mut timestamps[..])?;
+        Ok(Self {
+            inner,
+            format,
+            decompressors: HashMap::new(),
+            locations,
+            timestamps,
+        })
+    }
+
+    pub fn format(&self) -> RegionFormat {
+        self.format
+    }
+
+    /// Decompresses chunks stored with compression `scheme` using `decompress`, which gets the
+    /// stored bytes and returns the chunk's NBT. This is how chunks written by tools with their own
+    /// schemes, or with LZ4 as allowed since 1.20.5, can be read. A decompressor for one of the
+    /// built in schemes replaces it.
+    pub fn set_decompressor<F>(&mut self, scheme: u8, decompress: F)
+    where
+        F: Fn(&[u8]) -> io::Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.decompressors.insert(scheme, Box::new(decompress));
+    }
+
+    /// Positions of the chunks that are present, in the order of the location table.
+    pub fn chunks(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..1024)
+            .filter(|&i| self.locations[i] != 0)
+            .map(|i| (i % 32, i / 32))
+    }
+
+    /// When the chunk at `x`, `z` was last saved, in seconds since the Unix epoch, or `None` if
+    /// it hasn't been generated.
+    pub fn timestamp(&self, x: usize, z: usize) -> error::Result<Option<u32>> {
+        let index = chunk_index(x, z)?;
+        Ok((self.locations[index] != 0).then_some(self.timestamps[index]))
+    }
+
+    /// Like [`timestamp`](Self::timestamp), as a [`Timestamp`] such as `SystemTime`.
+    pub fn modified<T: Timestamp>(&self, x: usize, z: usize) -> error::Result<Option<T>> {
+        Ok(self
+            .timestamp(x, z)?
+            .and_then(|timestamp| T::from_unix_millis(timestamp as i64 * 1000)))
+    }
+
+    /// Reads and decompresses the chunk at `x`, `z`, returning its NBT, or `None` if it hasn't been
+    /// generated. Chunks marked as zlib that hold a raw deflate stream instead, as some tools
+    /// write them, are read too. Schemes without a decompressor are reported as
+    /// [`Error::UnknownCompression`], and positions past 31 as [`Error::ChunkOutsideRegion`].
+    pub fn read_chunk(&mut self, x: usize, z: usize) -> error::Result<Option<Vec<u8>>> {
+        match self.read_stored_chunk(x, z)? {
+            Some(stored) => self.decompress(&stored).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Decompresses with the custom decompressor for the chunk's scheme if there is one, and with
+    // the built in schemes of the file's format otherwise.
+    fn decompress(&self, stored: &StoredChunk) -> error::Result<Vec<u8>> {
+        if let Some(decompress) = self.decompressors.get(&stored.compression) {
+            return Ok(decompress(&stored.data)?);
+        }
+        if stored.compression == 3 && self.format == RegionFormat::McRegion {
+            return Err(Error::UnknownCompression(3));
+        }
+        stored.decompress()
+    }
+
+    /// Checks every chunk in the location table, returning the problems found. The sectors of
+    /// each chunk must lie after the header and within the file without overlapping another
+    /// chunk's, the stored length must fit in those sectors, and the chunk must decompress into a
+    /// well formed document.
+    pub fn verify(&mut self) -> error::Result<Vec<ChunkIssue>> {
+        self.verify_with_progress(|_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`verify`](Self::verify), but calls `progress` after each chunk that was checked.
+    pub fn verify_with_progress<G>(&mut self, mut progress: G) -> error::Result<Vec<ChunkIssue>>
+    where
+        G: FnMut(Progress) -> ControlFlow<()> + Send,
+    {
+        let file_sectors = self
+            .inner
+            .seek(SeekFrom::End(0))?
+            .div_ceil(SECTOR_SIZE as u64);
+        // The chunk each sector was first claimed by.
+        let mut owners: HashMap<u64, (usize, usize)> = HashMap::new();
+        let mut issues = Vec::new();
+        let positions: Vec<_> = self.chunks().collect();
+        let mut done = Progress {
+            chunks_total: positions.len(),
+            ..Progress::default()
+        };
+        for (x, z) in positions {
+            let location = self.locations[chunk_index(x, z)?];
+            let (first, count) = ((location >> 8) as u64, (location & 0xff) as u64);
+            let mut bytes = 0;
+            let mut issue = |kind| issues.push(ChunkIssue { x, z, kind });
+            if first < 2 || count == 0 || first + count > file_sectors {
+                issue(ChunkIssueKind::OutOfBounds {
+                    sector: first as u32,
+                    sectors: count as u8,
+                });
+            } else {
+                for sector in first..first + count {
+                    if let Some(&other) = owners.get(&sector) {
+                        issue(ChunkIssueKind::Overlaps {
+                            x: other.0,
+                            z: other.1,
+                        });
+                        break;
+                    }
+                    owners.insert(sector, (x, z));
+                }
+                if let Some(kind) = self.check_chunk(x, z, location, &mut bytes) {
+                    issue(kind);
+                }
+            }
+            done.advance(bytes, &mut progress)?;
+        }
+        Ok(issues)
+    }
+
+    // Reads and decodes the chunk at `x`, `z`, whose entry in the location table is `location`,
+    // adding the size it is stored with to `bytes` once its length has been read.
+    fn check_chunk(
+        &mut self,
+        x: usize,
+        z: usize,
+        location: u32,
+        bytes: &mut u64,
+    ) -> Option<ChunkIssueKind> {
+        let offset = (location >> 8) as u64 * SECTOR_SIZE as u64;
+        let sectors = ((location & 0xff) as u64).max(1);
+        let length = match self
+            .inner
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.inner.read_u32::<BigEndian>())
+        {
+            Ok(length) => length,
+            Err(err) => return Some(ChunkIssueKind::Undecodable(err.to_string())),
+        };
+        if length == 0 || length as u64 + 4 > sectors * SECTOR_SIZE as u64 {
+            return Some(ChunkIssueKind::LengthExceedsSectors {
+                length,
+                sectors: sectors as u8,
+            });
+        }
+        *bytes += length as u64 + 4;
+        let stored = match self.read_stored_chunk(x, z) {
+            Ok(stored) => stored?,
+            Err(err) => return Some(ChunkIssueKind::Undecodable(err.to_string())),
+        };
+        match self.decompress(&stored) {
+            Err(Error::UnknownCompression(scheme)) => {
+                Some(ChunkIssueKind::UnknownCompression(scheme))
+            }
+            Err(err) => Some(ChunkIssueKind::Undecodable(err.to_string())),
+            Ok(nbt) => crate::validate::validate_bytes(&nbt)
+                .err()
+                .map(|err| ChunkIssueKind::Undecodable(err.to_string())),
+        }
+    }
+
+    /// Copies every chunk that reads and decodes into a new region file written to `out`, which
+    /// should be empty, keeping the chunks' timestamps. Chunks that don't are left out and
+    /// returned along with what is wrong with them. Chunks that share sectors with another are
+    /// only copied if the position saved in them matches their place in the region, so the data
+    /// of one chunk isn't duplicated into the slot of another. The new file has the same format
+    /// as this one.
+    pub fn repair<W: Read + Write + Seek>(&mut self, out: W) -> error::Result<Vec<ChunkIssue>> {
+        self.repair_with_progress(out, |_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`repair`](Self::repair), but calls `progress` after each chunk that was copied or left
+    /// out.
+    pub fn repair_with_progress<W, G>(
+        &mut self,
+        mut out: W,
+        mut progress: G,
+    ) -> error::Result<Vec<ChunkIssue>>
+    where
+        W: Read + Write + Seek,
+        G: FnMut(Progress) -> ControlFlow<()> + Send,
+    {
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&[0; 2 * SECTOR_SIZE])?;
+        let mut repaired = RegionFile::with_format(out, self.format)?;
+        let mut dropped = Vec::new();
+        let positions: Vec<_> = self.chunks().collect();
+
+        // A chunk that shares a sector with each chunk that does.
+        let mut owners: HashMap<u64, (usize, usize)> = HashMap::new();
+        let mut overlapping: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        for &(x, z) in &positions {
+            let location = self.locations[chunk_index(x, z)?];
+            for sector in (location >> 8) as u64..(location >> 8) as u64 + (location & 0xff) as u64
+            {
+                if let Some(&other) = owners.get(&sector) {
+                    overlapping.entry((x, z)).or_insert(other);
+                    overlapping.entry(other).or_insert((x, z));
+                } else {
+                    owners.insert(sector, (x, z));
+                }
+            }
+        }
+
+        let mut done = Progress {
+            chunks_total: positions.len(),
+            ..Progress::default()
+        };
+        for (x, z) in positions {
+            let location = self.locations[chunk_index(x, z)?];
+            let mut bytes = 0;
+            let kind = match self.check_chunk(x, z, location, &mut bytes) {
+                Some(kind) => Some(kind),
+                None => overlapping
+                    .get(&(x, z))
+                    .filter(|_| !self.is_in_place(x, z))
+                    .map(|&(other_x, other_z)| ChunkIssueKind::Overlaps {
+                        x: other_x,
+                        z: other_z,
+                    }),
+            };
+            if let Some(kind) = kind {
+                dropped.push(ChunkIssue { x, z, kind });
+            } else {
+                let stored = self
+                    .read_stored_chunk(x, z)?
+                    .expect("the chunk was just checked");
+                repaired.write_chunk_with_timestamp(
+                    x,
+                    z,
+                    &stored,
+                    self.timestamps[chunk_index(x, z)?],
+                )?;
+            }
+            done.advance(bytes, &mut progress)?;
+        }
+        repaired.inner.flush()?;
+        Ok(dropped)
+    }
+
+    // Whether the chunk at `x`, `z` saves a position that lies at `x`, `z` within a region: its
+    // `xPos` and `zPos`, in the `Level` compound before 1.18, or the `Position` of entity chunks.
+    fn is_in_place(&mut self, x: usize, z: usize) -> bool {
+        #[derive(serde::Deserialize)]
+        struct Saved {
+            #[serde(rename = "xPos")]
+            x: Option<i32>,
+            #[serde(rename = "zPos")]
+            z: Option<i32>,
+            #[serde(rename = "Position")]
+            position: Option<Vec<i32>>,
+            #[serde(rename = "Level")]
+            level: Option<Box<Saved>>,
+        }
+
+        let Ok(Some(nbt)) = self.read_chunk(x, z) else {
+            return false;
+        };
+        let Ok(mut saved) = crate::from_slice::<Saved>(&nbt) else {
+            return false;
+        };
+        if let Some(level) = saved.level.take() {
+            saved = *level;
+        }
+        let position = match (saved.x, saved.z, saved.position.as_deref()) {
+            (Some(saved_x), Some(saved_z), _) | (_, _, Some(&[saved_x, saved_z])) => {
+                (saved_x, saved_z)
+            }
+            _ => return false,
+        };
+        ((position.0 & 31) as usize, (position.1 & 31) as usize) == (x, z)
+    }
+
+    /// Copies every chunk into a new region file written to `out`, which should be empty, with
+    /// the palettes of its sections optimized, see [`RawChunk::optimize`]. Chunks are written
+    /// back to back, so the new file also drops the sectors left free by chunks that grew and
+    /// moved. Timestamps are kept, and chunks from before the flattening are copied as stored.
+    pub fn optimize_into<W: Read + Write + Seek>(&mut self, out: W) -> error::Result<()> {
+        self.optimize_into_with_progress(out, |_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`optimize_into`](Self::optimize_into), but calls `progress` after each chunk that was
+    /// copied.
+    pub fn optimize_into_with_progress<W, G>(
+        &mut self,
+        mut out: W,
+        mut progress: G,
+    ) -> error::Result<()>
+    where
+        W: Read + Write + Seek,
+        G: FnMut(Progress) -> ControlFlow<()> + Send,
+    {
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&[0; 2 * SECTOR_SIZE])?;
+        let mut optimized = RegionFile::with_format(out, self.format)?;
+        let positions: Vec<_> = self.chunks().collect();
+        let mut done = Progress {
+            chunks_total: positions.len(),
+            ..Progress::default()
+        };
+        for (x, z) in positions {
+            let Some(read) = self.read_stored_chunk(x, z)? else {
+                continue;
+            };
+            let mut chunk = RawChunk::from_slice(&self.decompress(&read)?)?;
+            let bytes = read.data.len() as u64 + 5;
+            let stored = match chunk.optimize() {
+                Ok(()) => StoredChunk::compress(&chunk.to_bytes()?)?,
+                Err(Error::UnsupportedDataVersion(_)) => read,
+                Err(err) => return Err(err),
+            };
+            let timestamp = self.timestamps[chunk_index(x, z)?];
+            optimized.write_chunk_with_timestamp(x, z, &stored, timestamp)?;
+            done.advance(bytes, &mut progress)?;
+        }
+        optimized.inner.flush()?;
+        Ok(())
+    }
+
+    /// Reads the chunk at `x`, `z` as it is stored, without decompressing it.
+    pub fn read_stored_chunk(&mut self, x: usize, z: usize) -> error::Result<Option<StoredChunk>> {
+        let location = self.locations[chunk_index(x, z)?];
+        if location == 0 {
+            return Ok(None);
+        }
+        let offset = (location >> 8) as u64 * SECTOR_SIZE as u64;
+        self.inner.seek(SeekFrom::Start(offset))?;
+        // The length counts the compression byte that follows it, and has to fit in the sectors
+        // allocated to the chunk along with the length itself.
+        let length = self.inner.read_u32::<BigEndian>()?;
+        let sectors = (location & 0xff) as u64;
+        if length == 0 || length as u64 + 4 > sectors * SECTOR_SIZE as u64 {
+            return Err(Error::InvalidLength(length as i64));
+        }
+        let compression = self.inner.read_u8()?;
+        let mut data = vec![0; length as usize - 1];
+        self.inner.read_exact(&mut data)?;
+        Ok(Some(StoredChunk { compression, data }))
+    }
+}
+
+impl<R: Read + Write + Seek> RegionFile<R> {
+    /// Stores `chunk` at `x`, `z` and sets its timestamp to now. The chunk is written over its
+    /// previous sectors if it still fits in them, and at the end of the file otherwise.
+    pub fn write_stored_chunk(
+        &mut self,
+        x: usize,
+        z: usize,
+        chunk: &StoredChunk,
+    ) -> error::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as u32);
+        self.write_chunk_with_timestamp(x, z, chunk, timestamp)
+    }
+
+    /// Compresses the chunk NBT `nbt` with zlib and stores it at `x`, `z`, see
+    /// [`write_stored_chunk`](Self::write_stored_chunk).
+    pub fn write_chunk(&mut self, x: usize, z: usize, nbt: &[u8]) -> error::Result<()> {
+        self.write_stored_chunk(x, z, &StoredChunk::compress(nbt)?)
+    }
+
+    pub(crate) fn write_chunk_with_timestamp(
+        &mut self,
+        x: usize,
+        z: usize,
+        chunk: &StoredChunk,
+        timestamp: u32,
+    ) -> error::Result<()> {
+        let index = chunk_index(x, z)?;
+        let sectors = (chunk.data.len() + 5).div_ceil(SECTOR_SIZE);
+        if sectors > 0xff {
+            return Err(Error::Message(format!(
+                "chunk ({}, {}) needs {} sectors, region files hold at most 255",
+                x, z, sectors
+            )));
+        }
+        let location = self.locations[index];
+        let sector = if location != 0 && (location & 0xff) as usize >= sectors {
+            location >> 8
+        } else {
+            let end = self.inner.seek(SeekFrom::End(0))?;
+            (end.div_ceil(SECTOR_SIZE as u64) as u32).max(2)
+        };
+
+        self.inner
+            .seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE as u64))?;
+        self.inner
+            .write_u32::<BigEndian>(chunk.data.len() as u32 + 1)?;
+        self.inner.write_u8(chunk.compression)?;
+        self.inner.write_all(&chunk.data)?;
+        let padding = sectors * SECTOR_SIZE - (chunk.data.len() + 5);
+        self.inner.write_all(&vec![0; padding])?;
+
+        self.locations[index] = sector << 8 | sectors as u32;
+        self.timestamps[index] = timestamp;
+        self.inner.seek(SeekFrom::Start(4 * index as u64))?;
+        self.inner.write_u32::<BigEndian>(self.locations[index])?;
+        self.inner
+            .seek(SeekFrom::Start((SECTOR_SIZE + 4 * index) as u64))?;
+        self.inner.write_u32::<BigEndian>(timestamp)?;
+        Ok(())
+    }
+}
+
+/// A problem with one chunk of a region file, found by [`RegionFile::verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkIssue {
+    /// Position of the chunk within the region.
+    pub x: usize,
+    pub z: usize,
+    pub kind: ChunkIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkIssueKind {
+    /// The location table points into the header or past the end of the file, or gives no
+    /// sectors at all.
+    OutOfBounds { sector: u32, sectors: u8 },
+    /// Some of the chunk's sectors were already claimed by the chunk at `x`, `z`.
+    Overlaps { x: usize, z: usize },
+    /// The length in front of the chunk is zero or more than its sectors hold.
+    LengthExceedsSectors { length: u32, sectors: u8 },
+    /// The compression byte names a scheme without a decompressor.
+    UnknownCompression(u8),
+    /// The chunk couldn't be read, decompressed or parsed, for the given reason.
+    Undecodable(String),
+}
+
+/// A chunk as stored in a region file, before decompression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredChunk {
+    /// 1 for gzip, 2 for zlib and 3 for uncompressed. Other values are only understood by
+    /// decompressors set with [`RegionFile::set_decompressor`].
+    pub compression: u8,
+    pub data: Vec<u8>,
+}
+
+impl StoredChunk {
+    /// Compresses the NBT of a chunk with zlib, the scheme the game writes.
+    pub fn compress(nbt: &[u8]) -> error::Result<Self> {
+        let mut zlib = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(nbt)?;
+        Ok(Self {
+            compression: 2,
+            data: zlib.finish()?,
+        })
+    }
+
+    /// Decompresses the chunk with one of the built in schemes, returning its NBT.
+    pub fn decompress(&self) -> error::Result<Vec<u8>> {
+        let compressed = self.data.as_slice();
+        let mut chunk = Vec::new();
+        match self.compression {
+            1 => GzDecoder::new(compressed).read_to_end(&mut chunk)?,
+            2 if Compression::detect(compressed) != Compression::Zlib => {
+                DeflateDecoder::new(compressed).read_to_end(&mut chunk)?
+            }
+            2 => ZlibDecoder::new(compressed).read_to_end(&mut chunk)?,
+            3 => return Ok(self.data.clone()),
+            scheme => return Err(Error::UnknownCompression(scheme)),
+        };
+        Ok(chunk)
+    }
+}
+
+// Chunks are numbered 0 to 31 along each axis within a region.
+fn chunk_index(x: usize, z: usize) -> error::Result<usize> {
+    if x < 32 && z < 32 {
+        Ok(x + z * 32)
+    } else {
+        let coordinate = |n: usize| i32::try_from(n).unwrap_or(i32::MAX);
+        Err(Error::ChunkOutsideRegion(coordinate(x), coordinate(z)))
+    }
+}
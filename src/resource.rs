@@ -0,0 +1,126 @@
+//! Namespaced identifiers such as `minecraft:stone`, which name blocks, items, dimensions and
+//! everything else registered with the game.
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{self, Error};
+
+#[test]
+fn test_resource_location() {
+    let stone: ResourceLocation = "stone".parse().unwrap();
+    assert_eq!((stone.namespace(), stone.path()), ("minecraft", "stone"));
+    assert_eq!(stone, "minecraft:stone");
+    assert_eq!(
+        ResourceLocation::new("mymod", "ores/ruby")
+            .unwrap()
+            .to_string(),
+        "mymod:ores/ruby"
+    );
+    for invalid in ["Stone", "minecraft:", "a:b:c", "my mod:x", "ns/sub:x"] {
+        assert!(
+            matches!(invalid.parse::<ResourceLocation>(), Err(Error::InvalidResourceLocation(id)) if id == invalid),
+            "{:?}",
+            invalid
+        );
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: ResourceLocation,
+    }
+    let bytes = crate::to_bytes(&Item { id: stone.clone() }).unwrap();
+    assert_eq!(crate::from_slice::<Item>(&bytes).unwrap().id, stone);
+}
+
+/// A namespace and a path, written as `namespace:path`. Identifiers without a namespace are in
+/// the `minecraft` one. Namespaces may hold lowercase letters, digits and `_-.`, and paths may
+/// hold `/` as well.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ResourceLocation {
+    namespace: String,
+    path: String,
+}
+
+impl ResourceLocation {
+    /// The namespace used when none is given.
+    pub const DEFAULT_NAMESPACE: &'static str = "minecraft";
+
+    pub fn new<N: Into<String>, P: Into<String>>(namespace: N, path: P) -> error::Result<Self> {
+        let (namespace, path) = (namespace.into(), path.into());
+        let valid = !namespace.is_empty()
+            && !path.is_empty()
+            && namespace.chars().all(is_namespace_char)
+            && path.chars().all(|c| is_namespace_char(c) || c == '/');
+        if !valid {
+            return Err(Error::InvalidResourceLocation(format!(
+                "{}:{}",
+                namespace, path
+            )));
+        }
+        Ok(Self { namespace, path })
+    }
+
+    /// `path` in the `minecraft` namespace.
+    pub fn minecraft<P: Into<String>>(path: P) -> error::Result<Self> {
+        Self::new(Self::DEFAULT_NAMESPACE, path)
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+fn is_namespace_char(c: char) -> bool {
+    matches!(c, 'a'..='z' | '0'..='9' | '_' | '-' | '.')
+}
+
+impl fmt::Display for ResourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+impl FromStr for ResourceLocation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (namespace, path) = match s.split_once(':') {
+            // An empty namespace is the default one, as in the game.
+            Some(("", path)) => (Self::DEFAULT_NAMESPACE, path),
+            Some((namespace, path)) => (namespace, path),
+            None => (Self::DEFAULT_NAMESPACE, s),
+        };
+        Self::new(namespace, path).map_err(|_| Error::InvalidResourceLocation(s.to_owned()))
+    }
+}
+
+/// Compares against the written form, so `"stone"` is not equal to `minecraft:stone`.
+impl PartialEq<str> for ResourceLocation {
+    fn eq(&self, other: &str) -> bool {
+        other.split_once(':') == Some((self.namespace.as_str(), self.path.as_str()))
+    }
+}
+
+impl PartialEq<&str> for ResourceLocation {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl Serialize for ResourceLocation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceLocation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        id.parse().map_err(de::Error::custom)
+    }
+}
@@ -1,9 +1,18 @@
 use crate::{
     error,
     error::Error,
+    io,
     kind::NBTKind,
-    writer::{DelayedHeader, Writer},
+    writer::{DelayedHeader, Writer, MAX_STRING_LEN},
 };
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use core::borrow::Borrow;
 use serde::{
     ser::{
         self, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple, SerializeTupleStruct,
@@ -11,132 +20,1309 @@ use serde::{
     },
     Serialize, Serializer,
 };
-use std::{borrow::Borrow, io};
 
-// TODO: Implement tag specific array serializers (IntArray, LongArray, etc.)
-#[test]
-fn test_write() {
-    use crate::debug;
-    use std::fs::File;
-    #[derive(Debug, Serialize)]
-    struct Server {
-        ip: String,
-        name: String,
+// TODO: Implement tag specific array serializers (IntArray, LongArray, etc.)
+#[cfg(feature = "std")]
+#[test]
+fn test_write() {
+    use crate::debug;
+    use std::fs::File;
+    #[derive(Debug, Serialize)]
+    struct Server {
+        ip: String,
+        name: String,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct Servers {
+        servers: Vec<Server>,
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("test.nbt");
+    let file = File::create(&path).unwrap();
+    to_writer(
+        file,
+        &Servers {
+            servers: vec![Server {
+                ip: "loucaskreger.com".into(),
+                name: "Minecraft Server".into(),
+            }],
+        },
+    )
+    .unwrap();
+
+    debug::dump_nbt(path.to_str().unwrap()).unwrap();
+
+    #[derive(Debug, Serialize)]
+    struct Test {
+        #[serde(serialize_with = "crate::byte_array")]
+        after: Vec<i8>,
+    }
+
+    let file = File::create(dir.path().join("output2.nbt")).unwrap();
+    to_writer(file, &Test { after: vec![1, 2] }).unwrap();
+}
+
+#[test]
+fn test_struct_name_as_root() {
+    #[derive(Serialize)]
+    struct Level {
+        version: i32,
+    }
+
+    let options = SerializerOptions {
+        struct_name_as_root: true,
+        ..Default::default()
+    };
+    let bytes = to_bytes_with_options(&Level { version: 1 }, options).unwrap();
+    assert_eq!(&bytes[..8], &[10, 0, 5, b'L', b'e', b'v', b'e', b'l']);
+
+    // The default keeps the nameless root compound.
+    let bytes = to_bytes(&Level { version: 1 }).unwrap();
+    assert_eq!(&bytes[..3], &[10, 0, 0]);
+}
+
+#[test]
+fn test_little_endian_round_trip() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Level {
+        version: i32,
+        name: String,
+    }
+
+    let level = Level {
+        version: 1,
+        name: "world".into(),
+    };
+    let mut bytes = Vec::new();
+    to_writer_le(&mut bytes, &level).unwrap();
+    // Root compound, empty name, then an Int tag named "version" with a little-endian length.
+    assert_eq!(&bytes[..6], &[10, 0, 0, 3, 7, 0]);
+    assert_eq!(&bytes[13..17], &[1, 0, 0, 0]);
+
+    let decoded: Level = crate::de::from_slice_le(&bytes).unwrap();
+    assert_eq!(decoded, level);
+}
+
+#[test]
+fn test_serde_bytes_as_byte_array() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Section {
+        #[serde(with = "serde_bytes")]
+        light: Vec<u8>,
+    }
+
+    let section = Section {
+        light: vec![0, 127, 128, 255],
+    };
+    let bytes = to_bytes(&section).unwrap();
+    // Root header, then a ByteArray tag named "light" with a length of 4.
+    assert_eq!(&bytes[3..6], &[NBTKind::ByteArray.header_byte(), 0, 5]);
+    assert_eq!(&bytes[11..19], &[0, 0, 0, 4, 0, 127, 128, 255]);
+
+    let decoded: Section = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, section);
+}
+
+#[test]
+fn test_canonicalize_floats() {
+    #[derive(Serialize)]
+    struct Motion {
+        x: f64,
+        y: f32,
+    }
+
+    let motion = Motion {
+        x: -0.0,
+        y: f32::from_bits(0x7fc0_1234),
+    };
+    let options = SerializerOptions {
+        canonicalize_floats: true,
+        ..Default::default()
+    };
+    let bytes = to_bytes_with_options(&motion, options).unwrap();
+    let canonical = to_bytes(&Motion {
+        x: 0.0,
+        y: f32::NAN,
+    })
+    .unwrap();
+    assert_eq!(bytes, canonical);
+    assert_ne!(to_bytes(&motion).unwrap(), canonical);
+}
+
+#[test]
+fn test_strict() {
+    use crate::tag::NBTValue;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Marker;
+
+    #[derive(Serialize)]
+    struct Entry<T> {
+        value: T,
+    }
+
+    let strict = || SerializerOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let mixed = Entry {
+        value: NBTValue::List(vec![NBTValue::Long(1), NBTValue::List(Vec::new())]),
+    };
+    assert!(to_bytes(&mixed).is_ok());
+    assert!(matches!(
+        to_bytes_with_options(&mixed, strict()),
+        Err(Error::MismatchedTag(NBTKind::List, NBTKind::Long))
+    ));
+
+    // A sequence of unknown length is written as an empty list, followed by its elements.
+    struct Unsized;
+    impl Serialize for Unsized {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq((0..3).filter(|n: &i32| *n > 0))
+        }
+    }
+    assert!(to_bytes_with_options(&Entry { value: Unsized }, strict()).is_err());
+    assert!(to_bytes_with_options(&Entry { value: Marker }, strict()).is_err());
+
+    let fine = Entry {
+        value: BTreeMap::from([("list", vec![vec![1i8], vec![]])]),
+    };
+    assert_eq!(
+        to_bytes_with_options(&fine, strict()).unwrap(),
+        to_bytes(&fine).unwrap()
+    );
+
+    // With `booleans_as_strings` a bool is a String element like any other.
+    let flags = Entry {
+        value: (true, "maybe"),
+    };
+    let strings = SerializerOptions {
+        booleans_as_strings: true,
+        ..strict()
+    };
+    assert!(to_bytes_with_options(&flags, strings).is_ok());
+    assert!(matches!(
+        to_bytes_with_options(&flags, strict()),
+        Err(Error::MismatchedTag(NBTKind::String, NBTKind::Byte))
+    ));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_round_trip() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Backup {
+        name: String,
+        #[serde(serialize_with = "long_array")]
+        heightmap: Vec<i64>,
+    }
+
+    let backup = Backup {
+        name: "world".into(),
+        heightmap: vec![7; 37],
+    };
+    let mut compressed = Vec::new();
+    to_zstd_writer(&mut compressed, &backup).unwrap();
+    assert_eq!(&compressed[..4], [0x28, 0xb5, 0x2f, 0xfd]);
+    let read: Backup = crate::from_zstd_reader(compressed.as_slice()).unwrap();
+    assert_eq!(read, backup);
+}
+
+#[test]
+fn test_map_round_trip() {
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Block {
+        name: String,
+        properties: BTreeMap<String, String>,
+    }
+
+    let block = Block {
+        name: "minecraft:oak_stairs".into(),
+        properties: BTreeMap::from([
+            ("facing".to_owned(), "east".to_owned()),
+            ("half".to_owned(), "top".to_owned()),
+        ]),
+    };
+    let bytes = to_bytes(&block).unwrap();
+    // A Compound named "properties" holding a String named "facing".
+    assert_eq!(&bytes[32..45], b"\x0a\x00\x0aproperties");
+    assert_eq!(&bytes[45..54], b"\x08\x00\x06facing");
+    assert_eq!(crate::from_slice::<Block>(&bytes).unwrap(), block);
+
+    let root = BTreeMap::from([("level".to_owned(), 1i32)]);
+    let bytes = to_bytes(&root).unwrap();
+    assert_eq!(
+        crate::from_slice::<BTreeMap<String, i32>>(&bytes).unwrap(),
+        root
+    );
+    assert!(to_bytes(&BTreeMap::from([(1, 2)])).is_err());
+}
+
+#[test]
+fn test_length_prefixed() {
+    #[derive(Serialize)]
+    struct Motd {
+        text: String,
+    }
+
+    let motd = Motd {
+        text: "a".repeat(200),
+    };
+    let mut framed = Vec::new();
+    to_writer_length_prefixed(&mut framed, &motd).unwrap();
+    // 3 + 1 + 2 + 4 + 2 + 200 + 1 = 213 bytes, which takes two VarInt bytes.
+    assert_eq!(&framed[..2], &[213, 1]);
+    assert_eq!(&framed[2..], to_bytes(&motd).unwrap());
+}
+
+#[test]
+fn test_narrow_integers() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Counts {
+        small: i32,
+        medium: i64,
+        large: i64,
+        #[serde(with = "crate::as_short")]
+        forced: i32,
+        list: Vec<i32>,
+    }
+
+    let counts = Counts {
+        small: 3,
+        medium: 300,
+        large: 1 << 40,
+        forced: 5,
+        list: vec![1, 100_000],
+    };
+    let options = SerializerOptions {
+        narrow_integers: true,
+        ..Default::default()
+    };
+    let bytes = to_bytes_with_options(&counts, options).unwrap();
+    let value: crate::NBTValue = crate::from_slice(&bytes).unwrap();
+    let crate::NBTValue::Compound(map) = value else {
+        panic!("root is not a compound");
+    };
+    let kinds: Vec<_> = ["small", "medium", "large", "forced", "list"]
+        .map(|key| map[key].kind())
+        .into();
+    assert_eq!(
+        kinds,
+        [
+            NBTKind::Byte,
+            NBTKind::Short,
+            NBTKind::Long,
+            NBTKind::Byte,
+            NBTKind::List
+        ]
+    );
+    assert_eq!(
+        map["list"],
+        crate::NBTValue::List(vec![crate::NBTValue::Int(1), crate::NBTValue::Int(100_000)])
+    );
+    let read: Counts = crate::from_slice(&bytes).unwrap();
+    assert_eq!(read, counts);
+
+    // Without the policy the helper still decides the tag, and reads any integer in range.
+    let bytes = to_bytes(&counts).unwrap();
+    let value: crate::NBTValue = crate::from_slice(&bytes).unwrap();
+    let crate::NBTValue::Compound(map) = value else {
+        panic!("root is not a compound");
+    };
+    assert_eq!(map["forced"], crate::NBTValue::Short(5));
+    assert_eq!(crate::from_slice::<Counts>(&bytes).unwrap(), counts);
+    let too_large = Counts {
+        forced: 40_000,
+        ..counts
+    };
+    assert!(to_bytes(&too_large).is_err());
+}
+
+#[test]
+fn test_long_strings() {
+    use crate::de::{from_slice_with_options, DeserializerOptions};
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Book {
+        title: String,
+        text: String,
+        pages: Vec<String>,
+    }
+
+    // Two bytes per char, so the first piece has to end a byte early.
+    let book = Book {
+        title: "Notes".into(),
+        text: "é".repeat(40_000),
+        pages: vec!["short".into()],
+    };
+    assert!(matches!(to_bytes(&book), Err(Error::StringTooLong(80_000))));
+
+    let options = SerializerOptions {
+        long_strings: true,
+        ..Default::default()
+    };
+    let bytes = to_bytes_with_options(&book, options.clone()).unwrap();
+    let read = |long_strings| {
+        let options = DeserializerOptions {
+            long_strings,
+            ..Default::default()
+        };
+        from_slice_with_options::<Book>(&bytes, options)
+    };
+    assert_eq!(read(true).unwrap(), book);
+    assert!(read(false).is_err());
+    let crate::NBTValue::Compound(value) = crate::from_slice(&bytes).unwrap() else {
+        panic!("document is not a compound");
+    };
+    let crate::NBTValue::List(pieces) = &value["text"] else {
+        panic!("long string is not a list");
+    };
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(value["title"], crate::NBTValue::String("Notes".into()));
+
+    let in_list = Book {
+        pages: vec![book.text.clone()],
+        ..book
+    };
+    assert!(matches!(
+        to_bytes_with_options(&in_list, options),
+        Err(Error::StringTooLong(80_000))
+    ));
+}
+
+#[test]
+fn test_smart_pointers() {
+    use crate::NBTValue;
+    use serde::Deserialize;
+    use std::{borrow::Cow, rc::Rc, sync::Arc};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Node {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent: Option<Box<Node>>,
+        children: Vec<Node>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Shared<'a> {
+        tree: Box<Node>,
+        data: Rc<NBTValue>,
+        name: Arc<str>,
+        tags: Arc<Vec<String>>,
+        #[serde(borrow)]
+        label: Cow<'a, str>,
+        owned: Cow<'a, [i32]>,
+        value: Box<NBTValue>,
+    }
+
+    let leaf = |name: &str| Node {
+        name: name.into(),
+        parent: None,
+        children: Vec::new(),
+    };
+    let tree = Node {
+        name: "root".into(),
+        parent: Some(Box::new(leaf("up"))),
+        children: vec![
+            leaf("a"),
+            Node {
+                children: vec![leaf("c")],
+                ..leaf("b")
+            },
+        ],
+    };
+    let data = Rc::new(
+        crate::CompoundBuilder::new()
+            .int("x", 1)
+            .list("l", [NBTValue::Byte(2)])
+            .build(),
+    );
+    let shared = Shared {
+        tree: Box::new(tree),
+        data: Rc::clone(&data),
+        name: "shared".into(),
+        tags: Arc::new(vec!["one".into(), "two".into()]),
+        label: Cow::Borrowed("label"),
+        owned: Cow::Owned(vec![1, 2, 3]),
+        value: Box::new(NBTValue::LongArray(vec![4, 5])),
+    };
+
+    let bytes = to_bytes(&shared).unwrap();
+    let read: Shared = crate::from_slice(&bytes).unwrap();
+    assert_eq!(read, shared);
+    // Strings are borrowed from the input when the field allows it.
+    assert!(matches!(read.label, Cow::Borrowed("label")));
+}
+
+#[test]
+fn test_skip_empty() {
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Inner {
+        items: Vec<i32>,
+    }
+
+    #[derive(Serialize)]
+    struct Entity {
+        id: String,
+        tags: Vec<String>,
+        data: BTreeMap<String, i32>,
+        inner: Inner,
+        #[serde(serialize_with = "crate::int_array")]
+        uuid: Vec<i32>,
+        #[serde(skip_serializing_if = "crate::is_empty_tag")]
+        passengers: Vec<Inner>,
+        nested: Vec<Vec<i32>>,
+    }
+
+    let entity = Entity {
+        id: "minecraft:pig".into(),
+        tags: Vec::new(),
+        data: BTreeMap::new(),
+        inner: Inner { items: Vec::new() },
+        uuid: Vec::new(),
+        passengers: Vec::new(),
+        nested: vec![Vec::new()],
+    };
+    let options = SerializerOptions {
+        skip_empty: true,
+        ..Default::default()
+    };
+    let bytes = to_bytes_with_options(&entity, options).unwrap();
+    let crate::NBTValue::Compound(map) = crate::from_slice(&bytes).unwrap() else {
+        panic!("root is not a compound");
+    };
+    let mut keys: Vec<_> = map.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, ["id", "nested", "uuid"]);
+
+    // Without the option only the field using the helper is left out.
+    let crate::NBTValue::Compound(map) = crate::from_slice(&to_bytes(&entity).unwrap()).unwrap()
+    else {
+        panic!("root is not a compound");
+    };
+    assert_eq!(map.len(), 6);
+    assert!(!map.contains_key("passengers"));
+}
+
+#[test]
+fn test_length_overflow() {
+    use serde::ser::SerializeSeq;
+
+    const TOO_LONG: usize = i32::MAX as usize + 1;
+
+    struct HugeArray;
+
+    impl Serialize for HugeArray {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer
+                .serialize_tuple_struct(INT_ARRAY_NAME, TOO_LONG)?
+                .end()
+        }
+    }
+
+    struct HugeList;
+
+    impl Serialize for HugeList {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(TOO_LONG))?;
+            seq.serialize_element(&1i32)?;
+            seq.end()
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    assert!(matches!(
+        to_bytes(&Wrapper { value: HugeArray }),
+        Err(Error::InvalidLength(length)) if length == TOO_LONG as i64
+    ));
+    assert!(matches!(
+        to_bytes(&Wrapper { value: HugeList }),
+        Err(Error::InvalidLength(length)) if length == TOO_LONG as i64
+    ));
+}
+
+/// Options that change how values are written by the serializer.
+#[derive(Debug, Default, Clone)]
+pub struct SerializerOptions {
+    /// Use the name given to `serialize_struct` (the Rust struct's name) as the name of the root
+    /// compound instead of the empty string. Some third-party parsers expect a named root.
+    pub struct_name_as_root: bool,
+    /// Write every NaN as the same quiet NaN and negative zero as zero, so equal documents encode
+    /// to the same bytes regardless of where their floats came from.
+    pub canonicalize_floats: bool,
+    /// Check every list before writing it: all elements must be of the same kind, there must be
+    /// as many of them as the list's declared length, and unit structs may not stand in for a
+    /// value. A violation is returned as an error instead of producing a corrupt document. Costs
+    /// an extra pass over every list element.
+    pub strict: bool,
+    /// Write each integer as the narrowest of Byte, Short, Int and Long that holds its value, so
+    /// an `i32` holding 3 becomes a Byte. List and array elements keep the tag of their type,
+    /// since all elements have to share one. Such documents read back as usual, but not with
+    /// [`DeserializerOptions::strict_numbers`](crate::de::DeserializerOptions::strict_numbers).
+    pub narrow_integers: bool,
+    /// Write `bool`s as the Strings `"true"` and `"false"` instead of a Byte of 1 or 0, for data
+    /// that expects them. Read them back with
+    /// [`BooleanPolicy::Lenient`](crate::de::BooleanPolicy::Lenient).
+    pub booleans_as_strings: bool,
+    /// Allow values that aren't compounds as the root, such as a list or a number. They are
+    /// written as a tag with an empty name, like a compound entry. Read them back with
+    /// [`DeserializerOptions::any_root`](crate::de::DeserializerOptions::any_root).
+    pub any_root: bool,
+    /// Leave out compound entries that are empty Lists or Compounds, as the game does in several
+    /// files. Compounds whose entries would all be left out count as empty too. List elements and
+    /// the root are always written. Costs an extra pass over every compound entry.
+    pub skip_empty: bool,
+    /// Write strings longer than the 65535 bytes a String tag holds as a List of Strings, each
+    /// holding a piece of at most that many bytes split at a char boundary, instead of failing
+    /// with [`Error::StringTooLong`]. Strings that are list elements can't be split, since every
+    /// element of a list has to be of the same kind. Read them back with
+    /// [`DeserializerOptions::long_strings`](crate::de::DeserializerOptions::long_strings).
+    pub long_strings: bool,
+}
+
+pub fn to_writer<T, W>(w: W, value: &T) -> error::Result<()>
+where
+    T: Serialize,
+    W: io::Write,
+{
+    to_writer_with_options(w, value, SerializerOptions::default())
+}
+
+pub fn to_writer_with_options<T, W>(
+    w: W,
+    value: &T,
+    options: SerializerOptions,
+) -> error::Result<()>
+where
+    T: Serialize,
+    W: io::Write,
+{
+    let mut serializer = NBTSerializer {
+        writer: Writer::<_, BigEndian>::new(w, options),
+    };
+    value.serialize(&mut serializer)
+}
+
+/// Like [`to_writer`], but writes the little-endian NBT used by Bedrock Edition.
+pub fn to_writer_le<T, W>(w: W, value: &T) -> error::Result<()>
+where
+    T: Serialize,
+    W: io::Write,
+{
+    let mut serializer = NBTSerializer {
+        writer: Writer::<_, LittleEndian>::new(w, SerializerOptions::default()),
+    };
+    value.serialize(&mut serializer)
+}
+
+pub fn to_bytes<T>(value: &T) -> error::Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    to_bytes_with_options(value, SerializerOptions::default())
+}
+
+pub fn to_bytes_with_options<T>(value: &T, options: SerializerOptions) -> error::Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut result = Vec::new();
+    to_writer_with_options(&mut result, value, options)?;
+    Ok(result)
+}
+
+/// Like [`to_bytes`], but writes into `buffer` after clearing it, so its allocation can be reused
+/// across calls.
+pub fn to_bytes_into<T>(buffer: &mut Vec<u8>, value: &T) -> error::Result<()>
+where
+    T: Serialize,
+{
+    buffer.clear();
+    to_writer(buffer, value)
+}
+
+/// Writes `value` compressed with zstd at its default level.
+#[cfg(feature = "zstd")]
+pub fn to_zstd_writer<T, W>(w: W, value: &T) -> error::Result<()>
+where
+    T: Serialize,
+    W: io::Write,
+{
+    let mut zstd = zstd::stream::write::Encoder::new(w, 0)?;
+    to_writer(&mut zstd, value)?;
+    zstd.finish()?;
+    Ok(())
+}
+
+/// Writes `value` preceded by its length in bytes as a VarInt, the framing the Minecraft protocol
+/// uses for packets. The value is serialized into a temporary buffer first to learn its length.
+pub fn to_writer_length_prefixed<T, W>(mut w: W, value: &T) -> error::Result<()>
+where
+    T: Serialize,
+    W: io::Write,
+{
+    let bytes = to_bytes(value)?;
+    write_varint(&mut w, bytes.len() as u32)?;
+    w.write_all(&bytes)?;
+    Ok(())
+}
+
+// Seven bits at a time, least significant group first, with the high bit set on all but the last.
+fn write_varint<W: io::Write>(w: &mut W, mut value: u32) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+struct NBTSerializer<W: io::Write, O: ByteOrder> {
+    writer: Writer<W, O>,
+}
+
+macro_rules! unrepresentable {
+    ($name:ident, $typ:ty) => {
+        fn $name(self, _: $typ) -> Result<Self::Ok, Self::Error> {
+            Err(Error::Unrepresentable)
+        }
+    };
+}
+
+macro_rules! no_root_compound {
+    ($name:ident, $typ:ty) => {
+        fn $name(self, v: $typ) -> Result<Self::Ok, Self::Error> {
+            self.any_root()?.$name(v)
+        }
+    };
+}
+
+impl<W: io::Write, O: ByteOrder> NBTSerializer<W, O> {
+    // The serializer for a root that isn't a compound, which is only allowed with `any_root`.
+    fn any_root(&mut self) -> error::Result<NBTSerializerImpl<'_, W, O>> {
+        if !self.writer.options().any_root {
+            return Err(Error::ExpectedRootCompound);
+        }
+        Ok(NBTSerializerImpl::with_deferred_header(
+            &mut self.writer,
+            Some(DelayedHeader::MapKey("")),
+        ))
+    }
+}
+
+impl<'a, W: io::Write, O: ByteOrder> Serializer for &'a mut NBTSerializer<W, O> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = NBTSeqSerializer<'a, W, O>;
+    type SerializeTuple = NBTSeqSerializer<'a, W, O>;
+    type SerializeTupleStruct = NBTSeqSerializer<'a, W, O>;
+    type SerializeTupleVariant = NBTSeqSerializer<'a, W, O>;
+    type SerializeMap = NBTMapSerializer<'a, W, O>;
+    type SerializeStruct = NBTStructSerializer<'a, W, O>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    unrepresentable!(serialize_bool, bool);
+    unrepresentable!(serialize_u8, u8);
+    unrepresentable!(serialize_u16, u16);
+    unrepresentable!(serialize_u32, u32);
+    unrepresentable!(serialize_u64, u64);
+    unrepresentable!(serialize_char, char);
+
+    no_root_compound!(serialize_i8, i8);
+    no_root_compound!(serialize_i16, i16);
+    no_root_compound!(serialize_i32, i32);
+    no_root_compound!(serialize_i64, i64);
+    no_root_compound!(serialize_f32, f32);
+    no_root_compound!(serialize_f64, f64);
+    no_root_compound!(serialize_str, &str);
+    no_root_compound!(serialize_bytes, &[u8]);
+    no_root_compound!(serialize_unit_struct, &'static str);
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.any_root()?.serialize_none()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.any_root()?.serialize_some(value)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.any_root()?.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.any_root()?
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.any_root()?
+            .serialize_newtype_variant(name, variant_index, variant, value)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.any_root()?.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.any_root()?
+            .serialize_tuple_variant(name, variant_index, variant, len)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.writer.write_tag_header(NBTKind::Compound, None)?;
+        self.writer.write_string("")?;
+        Ok(NBTMapSerializer::new(&mut self.writer))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let root_name = if self.writer.options().struct_name_as_root {
+            name
+        } else {
+            ""
+        };
+        self.writer.write_tag_header(NBTKind::Compound, None)?;
+        self.writer.write_string(root_name)?;
+        Ok(NBTStructSerializer::new(&mut self.writer))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Error::ExpectedRootCompound)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.any_root()?.serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.any_root()?.serialize_tuple(len)
+    }
+}
+
+struct NBTMapSerializer<'a, W: io::Write, O: ByteOrder> {
+    writer: &'a mut Writer<W, O>,
+    // The name of the entry being written, which goes into the header in front of its value.
+    key: String,
+}
+
+impl<'a, W: io::Write, O: ByteOrder> NBTMapSerializer<'a, W, O> {
+    pub fn new(writer: &'a mut Writer<W, O>) -> Self {
+        Self {
+            writer,
+            key: String::new(),
+        }
+    }
+}
+
+impl<'a, W: io::Write, O: ByteOrder> SerializeMap for NBTMapSerializer<'a, W, O> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = key.serialize(MapKeySerializer)?;
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.writer.options().skip_empty && value.serialize(EmptySerializer)? {
+            return Ok(());
+        }
+        value.serialize(NBTSerializerImpl::with_deferred_header(
+            self.writer,
+            Some(DelayedHeader::MapKey(&self.key)),
+        ))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_tag_header(NBTKind::End, None)?;
+        Ok(())
+    }
+}
+
+/// Turns map keys into compound entry names. Only strings, and enum variants without data, can
+/// be names.
+struct MapKeySerializer;
+
+impl Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    unrepresentable!(serialize_bool, bool);
+    unrepresentable!(serialize_i8, i8);
+    unrepresentable!(serialize_i16, i16);
+    unrepresentable!(serialize_i32, i32);
+    unrepresentable!(serialize_i64, i64);
+    unrepresentable!(serialize_u8, u8);
+    unrepresentable!(serialize_u16, u16);
+    unrepresentable!(serialize_u32, u32);
+    unrepresentable!(serialize_u64, u64);
+    unrepresentable!(serialize_f32, f32);
+    unrepresentable!(serialize_f64, f64);
+    unrepresentable!(serialize_bytes, &[u8]);
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+}
+
+/// Finds the kind of tag a value would be written as, without writing it. Containers report
+/// their own kind without looking at their contents, and `None` is reported for values that
+/// aren't written at all.
+struct KindSerializer {
+    // `bool`s are written as Strings rather than Bytes.
+    booleans_as_strings: bool,
+}
+
+macro_rules! kind {
+    ($name:ident, $typ:ty, $kind:expr) => {
+        fn $name(self, _: $typ) -> Result<Self::Ok, Self::Error> {
+            Ok(Some($kind))
+        }
+    };
+}
+
+impl Serializer for KindSerializer {
+    type Ok = Option<NBTKind>;
+    type Error = Error;
+    type SerializeSeq = SkipContents;
+    type SerializeTuple = SkipContents;
+    type SerializeTupleStruct = SkipContents;
+    type SerializeTupleVariant = SkipContents;
+    type SerializeMap = SkipContents;
+    type SerializeStruct = SkipContents;
+    type SerializeStructVariant = ser::Impossible<Option<NBTKind>, Error>;
+
+    unrepresentable!(serialize_u8, u8);
+    unrepresentable!(serialize_u16, u16);
+    unrepresentable!(serialize_u32, u32);
+    unrepresentable!(serialize_u64, u64);
+    unrepresentable!(serialize_char, char);
+
+    kind!(serialize_i8, i8, NBTKind::Byte);
+    kind!(serialize_i16, i16, NBTKind::Short);
+    kind!(serialize_i32, i32, NBTKind::Int);
+    kind!(serialize_i64, i64, NBTKind::Long);
+    kind!(serialize_f32, f32, NBTKind::Float);
+    kind!(serialize_f64, f64, NBTKind::Double);
+    kind!(serialize_str, &str, NBTKind::String);
+    kind!(serialize_bytes, &[u8], NBTKind::ByteArray);
+    kind!(serialize_unit_struct, &'static str, NBTKind::End);
+
+    fn serialize_bool(self, _: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(if self.booleans_as_strings {
+            NBTKind::String
+        } else {
+            NBTKind::Byte
+        }))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SkipContents(NBTKind::List))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(SkipContents(NBTKind::List))
     }
 
-    #[derive(Debug, Serialize)]
-    struct Servers {
-        servers: Vec<Server>,
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        match name {
+            BYTE_ARRAY_NAME => Ok(SkipContents(NBTKind::ByteArray)),
+            INT_ARRAY_NAME => Ok(SkipContents(NBTKind::IntArray)),
+            LONG_ARRAY_NAME => Ok(SkipContents(NBTKind::LongArray)),
+            _ => Err(Error::Unrepresentable),
+        }
     }
 
-    let file = File::create("test.nbt").unwrap();
-    to_writer(
-        file,
-        &Servers {
-            servers: vec![Server {
-                ip: "loucaskreger.com".into(),
-                name: "Minecraft Server".into(),
-            }],
-        },
-    )
-    .unwrap();
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(SkipContents(NBTKind::List))
+    }
 
-    debug::dump_nbt("test.nbt").unwrap();
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SkipContents(NBTKind::Compound))
+    }
 
-    #[derive(Debug, Serialize)]
-    struct Test {
-        #[serde(serialize_with = "crate::byte_array")]
-        after: Vec<i8>,
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(SkipContents(NBTKind::Compound))
     }
 
-    let file = File::create("output2.nbt").unwrap();
-    to_writer(file, &Test { after: vec![1, 2] }).unwrap();
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Error::Unrepresentable)
+    }
 }
 
-pub fn to_writer<T, W>(w: W, value: &T) -> error::Result<()>
-where
-    T: Serialize,
-    W: io::Write,
-{
-    let mut serializer = NBTSerializer {
-        writer: Writer::new(w),
+/// The contents of a container whose kind [`KindSerializer`] already knows.
+struct SkipContents(NBTKind);
+
+macro_rules! skip_contents {
+    ($trait:ident, $method:ident) => {
+        impl $trait for SkipContents {
+            type Ok = Option<NBTKind>;
+            type Error = Error;
+
+            fn $method<T>(&mut self, _value: &T) -> Result<(), Self::Error>
+            where
+                T: ?Sized + Serialize,
+            {
+                Ok(())
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                Ok(Some(self.0))
+            }
+        }
     };
-    value.serialize(&mut serializer)
 }
 
-pub fn to_bytes<T>(value: &T) -> error::Result<Vec<u8>>
-where
-    T: Serialize,
-{
-    let mut result = Vec::new();
-    let mut serializer = NBTSerializer {
-        writer: Writer::new(&mut result),
-    };
-    value.serialize(&mut serializer)?;
-    Ok(result)
+skip_contents!(SerializeSeq, serialize_element);
+skip_contents!(SerializeTuple, serialize_element);
+skip_contents!(SerializeTupleStruct, serialize_field);
+skip_contents!(SerializeTupleVariant, serialize_field);
+
+impl SerializeMap for SkipContents {
+    type Ok = Option<NBTKind>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(self.0))
+    }
 }
 
-struct NBTSerializer<W: io::Write> {
-    writer: Writer<W>,
+impl SerializeStruct for SkipContents {
+    type Ok = Option<NBTKind>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(self.0))
+    }
 }
 
-macro_rules! unrepresentable {
-    ($name:ident, $typ:ty) => {
-        fn $name(self, _: $typ) -> Result<Self::Ok, Self::Error> {
-            Err(Error::Unrepresentable)
-        }
-    };
+/// Whether `value` is an empty List or Compound, or a Compound holding only such values, which
+/// [`SerializerOptions::skip_empty`] leaves out. Use it as
+/// `#[serde(skip_serializing_if = "nbt::is_empty_tag")]` to leave out single fields.
+pub fn is_empty_tag<T: ?Sized + Serialize>(value: &T) -> bool {
+    value.serialize(EmptySerializer).unwrap_or(false)
 }
 
-macro_rules! no_root_compound {
-    ($name:ident, $typ:ty) => {
-        fn $name(self, _: $typ) -> Result<Self::Ok, Self::Error> {
-            Err(Error::ExpectedRootCompound)
-        }
+/// Finds whether a value is empty in the sense of [`is_empty_tag`], without writing it.
+struct EmptySerializer;
+
+macro_rules! not_empty {
+    ($($name:ident: $typ:ty),*) => {
+        $(fn $name(self, _: $typ) -> Result<Self::Ok, Self::Error> {
+            Ok(false)
+        })*
     };
 }
 
-impl<'a, W: io::Write> Serializer for &'a mut NBTSerializer<W> {
-    type Ok = ();
+impl Serializer for EmptySerializer {
+    type Ok = bool;
     type Error = Error;
-    type SerializeSeq = ser::Impossible<(), Error>;
-    type SerializeTuple = ser::Impossible<(), Error>;
-    type SerializeTupleStruct = ser::Impossible<(), Error>;
-    type SerializeTupleVariant = ser::Impossible<(), Error>;
-    type SerializeMap = NBTMapSerializer<'a, W>;
-    type SerializeStruct = NBTStructSerializer<'a, W>;
-    type SerializeStructVariant = ser::Impossible<(), Error>;
-
-    unrepresentable!(serialize_bool, bool);
-    unrepresentable!(serialize_u8, u8);
-    unrepresentable!(serialize_u16, u16);
-    unrepresentable!(serialize_u32, u32);
-    unrepresentable!(serialize_u64, u64);
-    unrepresentable!(serialize_char, char);
-
-    no_root_compound!(serialize_i8, i8);
-    no_root_compound!(serialize_i16, i16);
-    no_root_compound!(serialize_i32, i32);
-    no_root_compound!(serialize_i64, i64);
-    no_root_compound!(serialize_f32, f32);
-    no_root_compound!(serialize_f64, f64);
-    no_root_compound!(serialize_str, &str);
-    no_root_compound!(serialize_bytes, &[u8]);
-    no_root_compound!(serialize_unit_struct, &'static str);
+    type SerializeSeq = Emptiness;
+    type SerializeTuple = Emptiness;
+    type SerializeTupleStruct = Emptiness;
+    type SerializeTupleVariant = Emptiness;
+    type SerializeMap = Emptiness;
+    type SerializeStruct = Emptiness;
+    type SerializeStructVariant = ser::Impossible<bool, Error>;
+
+    not_empty!(
+        serialize_bool: bool, serialize_i8: i8, serialize_i16: i16, serialize_i32: i32,
+        serialize_i64: i64, serialize_u8: u8, serialize_u16: u16, serialize_u32: u32,
+        serialize_u64: u64, serialize_f32: f32, serialize_f64: f64, serialize_char: char,
+        serialize_str: &str, serialize_bytes: &[u8], serialize_unit_struct: &'static str
+    );
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        Err(Error::ExpectedRootCompound)
+        Ok(false)
     }
 
-    fn serialize_some<T: ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        Err(Error::ExpectedRootCompound)
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Err(Error::ExpectedRootCompound)
+        Ok(false)
     }
 
     fn serialize_unit_variant(
@@ -145,39 +1331,48 @@ impl<'a, W: io::Write> Serializer for &'a mut NBTSerializer<W> {
         _variant_index: u32,
         _variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(Error::ExpectedRootCompound)
+        Ok(false)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
         _variant: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        Err(Error::ExpectedRootCompound)
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(Emptiness(true))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(Emptiness(true))
     }
 
+    // Arrays are never left out.
     fn serialize_tuple_struct(
         self,
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Err(Error::ExpectedRootCompound)
+        Ok(Emptiness(false))
     }
 
     fn serialize_tuple_variant(
@@ -187,13 +1382,11 @@ impl<'a, W: io::Write> Serializer for &'a mut NBTSerializer<W> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Err(Error::ExpectedRootCompound)
+        Ok(Emptiness(true))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        self.writer.write_tag_header(NBTKind::Compound, None)?;
-        self.writer.write_string("")?;
-        Ok(NBTMapSerializer::new(&mut self.writer))
+        Ok(Emptiness(true))
     }
 
     fn serialize_struct(
@@ -201,9 +1394,7 @@ impl<'a, W: io::Write> Serializer for &'a mut NBTSerializer<W> {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.writer.write_tag_header(NBTKind::Compound, None)?;
-        self.writer.write_string("")?;
-        Ok(NBTStructSerializer::new(&mut self.writer))
+        Ok(Emptiness(true))
     }
 
     fn serialize_struct_variant(
@@ -213,76 +1404,105 @@ impl<'a, W: io::Write> Serializer for &'a mut NBTSerializer<W> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Err(Error::ExpectedRootCompound)
-    }
-
-    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(Error::ExpectedRootCompound)
-    }
-
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Err(Error::ExpectedRootCompound)
+        Err(Error::Unrepresentable)
     }
 }
 
-struct NBTMapSerializer<'a, W: io::Write> {
-    writer: &'a mut Writer<W>,
+/// Whether a container seen by [`EmptySerializer`] is empty so far. Any list element makes a
+/// list non-empty, while compound entries only do if they aren't empty themselves.
+struct Emptiness(bool);
+
+macro_rules! emptiness {
+    ($trait:ident, $method:ident) => {
+        impl $trait for Emptiness {
+            type Ok = bool;
+            type Error = Error;
+
+            fn $method<T>(&mut self, _value: &T) -> Result<(), Self::Error>
+            where
+                T: ?Sized + Serialize,
+            {
+                self.0 = false;
+                Ok(())
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                Ok(self.0)
+            }
+        }
+    };
 }
 
-impl<'a, W: io::Write> NBTMapSerializer<'a, W> {
-    pub fn new(writer: &'a mut Writer<W>) -> Self {
-        Self { writer }
-    }
-}
+emptiness!(SerializeSeq, serialize_element);
+emptiness!(SerializeTuple, serialize_element);
+emptiness!(SerializeTupleStruct, serialize_field);
+emptiness!(SerializeTupleVariant, serialize_field);
 
-impl<'a, W: io::Write> SerializeMap for NBTMapSerializer<'a, W> {
-    type Ok = ();
+impl SerializeMap for Emptiness {
+    type Ok = bool;
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        key.serialize(NBTSerializerImpl::from_writer(&mut self.writer))
+        Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(NBTSerializerImpl::from_writer(&mut self.writer))
+        if self.0 && !value.serialize(EmptySerializer)? {
+            self.0 = false;
+        }
+        Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.writer.write_tag_header(NBTKind::End, None)?;
-        Ok(())
+        Ok(self.0)
+    }
+}
+
+impl SerializeStruct for Emptiness {
+    type Ok = bool;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        SerializeMap::serialize_value(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.0)
     }
 }
 
-struct NBTStructSerializer<'a, W: io::Write> {
-    writer: &'a mut Writer<W>,
+struct NBTStructSerializer<'a, W: io::Write, O: ByteOrder> {
+    writer: &'a mut Writer<W, O>,
 }
 
-impl<'a, W: io::Write> NBTStructSerializer<'a, W> {
-    pub fn new(writer: &'a mut Writer<W>) -> Self {
+impl<'a, W: io::Write, O: ByteOrder> NBTStructSerializer<'a, W, O> {
+    pub fn new(writer: &'a mut Writer<W, O>) -> Self {
         Self { writer }
     }
 }
 
-impl<'a, W: io::Write> SerializeStruct for NBTStructSerializer<'a, W> {
+impl<'a, W: io::Write, O: ByteOrder> SerializeStruct for NBTStructSerializer<'a, W, O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
+        if self.writer.options().skip_empty && value.serialize(EmptySerializer)? {
+            return Ok(());
+        }
         value.serialize(NBTSerializerImpl::with_deferred_header(
-            &mut self.writer,
+            self.writer,
             Some(DelayedHeader::MapKey(key)),
         ))
     }
@@ -293,133 +1513,185 @@ impl<'a, W: io::Write> SerializeStruct for NBTStructSerializer<'a, W> {
     }
 }
 
-struct NBTSeqSerializer<'a, W: io::Write> {
-    writer: &'a mut Writer<W>,
-    deferred_header: Option<DelayedHeader>,
+struct NBTSeqSerializer<'a, W: io::Write, O: ByteOrder> {
+    writer: &'a mut Writer<W, O>,
+    deferred_header: Option<DelayedHeader<'a>>,
     skip_header: bool,
+    // Only used in strict mode: the length written in front of the elements, how many have been
+    // written since, and the kind they all have to be.
+    length: usize,
+    written: usize,
+    element_kind: Option<NBTKind>,
 }
 
-impl<'a, W: io::Write> NBTSeqSerializer<'a, W> {
-    pub fn from_writer(writer: &'a mut Writer<W>) -> Self {
-        Self::new(writer, None, false)
+impl<'a, W: io::Write, O: ByteOrder> NBTSeqSerializer<'a, W, O> {
+    pub fn from_writer(writer: &'a mut Writer<W, O>) -> Self {
+        Self::new(writer, None, false, 0, None)
     }
 
     pub fn with_deferred_header(
-        writer: &'a mut Writer<W>,
-        deferred_header: Option<DelayedHeader>,
+        writer: &'a mut Writer<W, O>,
+        deferred_header: Option<DelayedHeader<'a>>,
+        length: usize,
     ) -> Self {
-        Self::new(writer, deferred_header, false)
+        Self::new(writer, deferred_header, false, length, None)
     }
 
     pub fn new(
-        writer: &'a mut Writer<W>,
-        deferred_header: Option<DelayedHeader>,
+        writer: &'a mut Writer<W, O>,
+        deferred_header: Option<DelayedHeader<'a>>,
         skip_header: bool,
+        length: usize,
+        element_kind: Option<NBTKind>,
     ) -> Self {
         Self {
             writer,
             deferred_header,
             skip_header,
+            length,
+            written: 0,
+            element_kind,
         }
     }
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
+        if self.writer.options().strict {
+            self.check_element(value)?;
+        }
         value.serialize(NBTSerializerImpl::new(
-            &mut self.writer,
+            self.writer,
             self.deferred_header,
             self.skip_header,
+        ))?;
+        // The element type and length of a list are only written once, before the first element.
+        self.deferred_header = None;
+        self.skip_header = true;
+        Ok(())
+    }
+
+    // Elements after the first are written without a header, so they have to be of the kind the
+    // list declared, and there can't be more of them than the declared length.
+    fn check_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let kinds = KindSerializer {
+            booleans_as_strings: self.writer.options().booleans_as_strings,
+        };
+        let kind = match value.serialize(kinds)? {
+            Some(NBTKind::End) | None => return Err(Error::Unrepresentable),
+            Some(kind) => kind,
+        };
+        match self.element_kind {
+            Some(expected) if kind != expected => return Err(Error::MismatchedTag(kind, expected)),
+            _ => self.element_kind = Some(kind),
+        }
+        self.written += 1;
+        if self.written > self.length {
+            return Err(self.length_mismatch());
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        if self.writer.options().strict && self.written != self.length {
+            return Err(self.length_mismatch());
+        }
+        Ok(())
+    }
+
+    fn length_mismatch(&self) -> Error {
+        Error::Message(format!(
+            "list declared {} elements but at least {} were written",
+            self.length, self.written
         ))
     }
 }
 
-impl<'a, W: io::Write> SerializeSeq for NBTSeqSerializer<'a, W> {
+impl<'a, W: io::Write, O: ByteOrder> SerializeSeq for NBTSeqSerializer<'a, W, O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.serialize_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        NBTSeqSerializer::end(self)
     }
 }
 
-impl<'a, W: io::Write> SerializeTuple for NBTSeqSerializer<'a, W> {
+impl<'a, W: io::Write, O: ByteOrder> SerializeTuple for NBTSeqSerializer<'a, W, O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.serialize_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        NBTSeqSerializer::end(self)
     }
 }
 
-impl<'a, W: io::Write> SerializeTupleStruct for NBTSeqSerializer<'a, W> {
+impl<'a, W: io::Write, O: ByteOrder> SerializeTupleStruct for NBTSeqSerializer<'a, W, O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.serialize_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        NBTSeqSerializer::end(self)
     }
 }
 
-impl<'a, W: io::Write> SerializeTupleVariant for NBTSeqSerializer<'a, W> {
+impl<'a, W: io::Write, O: ByteOrder> SerializeTupleVariant for NBTSeqSerializer<'a, W, O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.serialize_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        NBTSeqSerializer::end(self)
     }
 }
 
-struct NBTSerializerImpl<'a, W: io::Write> {
-    writer: &'a mut Writer<W>,
-    deferred_header: Option<DelayedHeader>,
+struct NBTSerializerImpl<'a, W: io::Write, O: ByteOrder> {
+    writer: &'a mut Writer<W, O>,
+    deferred_header: Option<DelayedHeader<'a>>,
     skip_header: bool,
 }
 
-impl<'a, W: io::Write> NBTSerializerImpl<'a, W> {
-    pub fn from_writer(writer: &'a mut Writer<W>) -> Self {
-        Self::new(writer, None, false)
-    }
-
+impl<'a, W: io::Write, O: ByteOrder> NBTSerializerImpl<'a, W, O> {
     pub fn with_deferred_header(
-        writer: &'a mut Writer<W>,
-        deferred_header: Option<DelayedHeader>,
+        writer: &'a mut Writer<W, O>,
+        deferred_header: Option<DelayedHeader<'a>>,
     ) -> Self {
         Self::new(writer, deferred_header, false)
     }
 
     pub fn new(
-        writer: &'a mut Writer<W>,
-        delayed_header: Option<DelayedHeader>,
+        writer: &'a mut Writer<W, O>,
+        delayed_header: Option<DelayedHeader<'a>>,
         skip_header: bool,
     ) -> Self {
         Self {
@@ -435,17 +1707,43 @@ impl<'a, W: io::Write> NBTSerializerImpl<'a, W> {
         }
         Ok(())
     }
+
+    fn in_list(&self) -> bool {
+        // The first element of a list writes the list's element type, the others no header.
+        self.skip_header || matches!(self.deferred_header, Some(DelayedHeader::List(_)))
+    }
+
+    /// Writes `v`, which came from a value of the integer type `kind`.
+    fn serialize_integer(mut self, v: i64, kind: NBTKind) -> error::Result<()> {
+        let kind = if self.writer.options().narrow_integers && !self.in_list() {
+            match v {
+                _ if i8::try_from(v).is_ok() => NBTKind::Byte,
+                _ if i16::try_from(v).is_ok() => NBTKind::Short,
+                _ if i32::try_from(v).is_ok() => NBTKind::Int,
+                _ => NBTKind::Long,
+            }
+        } else {
+            kind
+        };
+        self.write(kind)?;
+        match kind {
+            NBTKind::Byte => self.writer.write_i8(v as i8),
+            NBTKind::Short => self.writer.write_i16(v as i16),
+            NBTKind::Int => self.writer.write_i32(v as i32),
+            _ => self.writer.write_i64(v),
+        }
+    }
 }
 
-impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
+impl<'a, W: io::Write, O: ByteOrder> Serializer for NBTSerializerImpl<'a, W, O> {
     type Ok = ();
     type Error = Error;
-    type SerializeSeq = NBTSeqSerializer<'a, W>;
-    type SerializeTuple = NBTSeqSerializer<'a, W>;
-    type SerializeTupleStruct = NBTSeqSerializer<'a, W>;
-    type SerializeTupleVariant = NBTSeqSerializer<'a, W>;
-    type SerializeMap = NBTMapSerializer<'a, W>;
-    type SerializeStruct = NBTStructSerializer<'a, W>;
+    type SerializeSeq = NBTSeqSerializer<'a, W, O>;
+    type SerializeTuple = NBTSeqSerializer<'a, W, O>;
+    type SerializeTupleStruct = NBTSeqSerializer<'a, W, O>;
+    type SerializeTupleVariant = NBTSeqSerializer<'a, W, O>;
+    type SerializeMap = NBTMapSerializer<'a, W, O>;
+    type SerializeStruct = NBTStructSerializer<'a, W, O>;
     type SerializeStructVariant = ser::Impossible<(), Error>;
 
     unrepresentable!(serialize_u8, u8);
@@ -455,64 +1753,61 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
     unrepresentable!(serialize_char, char);
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        if self.writer.options().booleans_as_strings {
+            return self.serialize_str(if v { "true" } else { "false" });
+        }
         self.serialize_i8(v as i8)
     }
 
-    fn serialize_i8(mut self, v: i8) -> Result<Self::Ok, Self::Error> {
-        self.write(NBTKind::Byte)?;
-        // if self.skip_header {
-        //     self.writer.write_tag_header(NBTKind::Byte, self.deferred_header)?;
-        // }
-        self.writer.write_i8(v)
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_integer(v as i64, NBTKind::Byte)
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::Short, self.deferred_header)?;
-        self.writer.write_i16(v)
+        self.serialize_integer(v as i64, NBTKind::Short)
     }
 
     fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::Int, self.deferred_header)?;
-        self.writer.write_i32(v)
+        self.serialize_integer(v as i64, NBTKind::Int)
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::List, self.deferred_header)?;
-        self.writer.write_i64(v)
+        self.serialize_integer(v, NBTKind::Long)
     }
 
-    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::Float, self.deferred_header)?;
+    fn serialize_f32(mut self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.write(NBTKind::Float)?;
         self.writer.write_f32(v)
     }
 
-    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::Double, self.deferred_header)?;
+    fn serialize_f64(mut self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.write(NBTKind::Double)?;
         self.writer.write_f64(v)
     }
 
-    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::String, self.deferred_header)?;
+    fn serialize_str(mut self, v: &str) -> Result<Self::Ok, Self::Error> {
+        if v.len() > MAX_STRING_LEN && self.writer.options().long_strings && !self.in_list() {
+            self.write(NBTKind::List)?;
+            return self.writer.write_long_string(v);
+        }
+        self.write(NBTKind::String)?;
         self.writer.write_string(v)
     }
 
-    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unrepresentable)
+    // Reached through `serde_bytes` or a `serialize_bytes` impl. The bytes are written as-is, so
+    // values above 127 come back as negative `i8`s when read as a plain `Vec<i8>`.
+    fn serialize_bytes(mut self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.write(NBTKind::ByteArray)?;
+        self.writer.write_bytes(v)
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         Ok(())
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -522,6 +1817,10 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        // An end tag in place of a value ends the compound around it early.
+        if self.writer.options().strict {
+            return Err(Error::Unrepresentable);
+        }
         self.writer
             .write_tag_header(NBTKind::End, self.deferred_header)
     }
@@ -535,18 +1834,18 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
         Err(Error::Unrepresentable)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -554,22 +1853,17 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        let length = match len {
-            Some(len) => len,
-            None => 0,
-        };
-        self.serialize_tuple(length)
+        self.serialize_tuple(len.unwrap_or_default())
     }
 
-    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::List, self.deferred_header)?;
+    fn serialize_tuple(mut self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.write(NBTKind::List)?;
         if len == 0 {
             self.writer.write_tag_header(NBTKind::End, None)?;
             self.writer.write_i32(0)?;
@@ -579,12 +1873,13 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
             Ok(NBTSeqSerializer::with_deferred_header(
                 self.writer,
                 Some(header),
+                len,
             ))
         }
     }
 
     fn serialize_tuple_struct(
-        self,
+        mut self,
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
@@ -594,14 +1889,21 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
             LONG_ARRAY_NAME => NBTKind::LongArray,
             _ => return Err(Error::Unrepresentable),
         };
-        self.writer.write_tag_header(kind, self.deferred_header)?;
-        if len == 0 {
-            self.writer.write_tag_header(NBTKind::End, None)?;
-            self.writer.write_i32(0)?;
-        } else {
-            self.writer.write_i32(len as i32)?;
-        }
-        Ok(NBTSeqSerializer::new(self.writer, None, true))
+        self.write(kind)?;
+        // Unlike lists, arrays don't declare an element type, only the length.
+        self.writer.write_length(len)?;
+        let element_kind = match kind {
+            NBTKind::ByteArray => NBTKind::Byte,
+            NBTKind::IntArray => NBTKind::Int,
+            _ => NBTKind::Long,
+        };
+        Ok(NBTSeqSerializer::new(
+            self.writer,
+            None,
+            true,
+            len,
+            Some(element_kind),
+        ))
     }
 
     fn serialize_tuple_variant(
@@ -614,17 +1916,17 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
         self.serialize_seq(Some(len))
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+    fn serialize_map(mut self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.write(NBTKind::Compound)?;
         Ok(NBTMapSerializer::new(self.writer))
     }
 
     fn serialize_struct(
-        self,
+        mut self,
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.writer
-            .write_tag_header(NBTKind::Compound, self.deferred_header)?;
+        self.write(NBTKind::Compound)?;
         Ok(NBTStructSerializer::new(self.writer))
     }
 
@@ -639,45 +1941,9 @@ impl<'a, W: io::Write> Serializer for NBTSerializerImpl<'a, W> {
     }
 }
 
-const BYTE_ARRAY_NAME: &'static str = "__nbt_byte_array__";
-const INT_ARRAY_NAME: &'static str = "__nbt_int_array__";
-const LONG_ARRAY_NAME: &'static str = "__nbt_long_array__";
-
-fn serialize_array<T, S>(
-    array: T,
-    serializer: S,
-    array_type: &'static str,
-) -> Result<S::Ok, S::Error>
-where
-    T: IntoIterator,
-    <T as IntoIterator>::Item: std::borrow::Borrow<i8>,
-    S: Serializer,
-{
-    let mut iter = array.into_iter();
-    let (length, max_length) = iter.size_hint();
-
-    let error_message =
-        "array serializer can only be used for fixed-length collections.".to_string();
-
-    if max_length.is_none() || length != max_length.unwrap() {
-        return Err(serde::ser::Error::custom(&error_message));
-    }
-
-    let mut se = serializer.serialize_tuple_struct(array_type, length)?;
-    for _ in 0..length {
-        se.serialize_field(
-            iter.next()
-                .ok_or_else(|| serde::ser::Error::custom(&error_message))?
-                .borrow(),
-        )?;
-    }
-
-    if iter.next().is_some() {
-        Err(serde::ser::Error::custom(error_message))
-    } else {
-        se.end()
-    }
-}
+pub(crate) const BYTE_ARRAY_NAME: &str = "__nbt_byte_array__";
+pub(crate) const INT_ARRAY_NAME: &str = "__nbt_int_array__";
+pub(crate) const LONG_ARRAY_NAME: &str = "__nbt_long_array__";
 
 macro_rules! serialize_array {
     ($array: ident, $serializer: ident, $array_type: expr) => {{
@@ -711,7 +1977,7 @@ macro_rules! serialize_array {
 pub fn byte_array<T, S>(array: T, serializer: S) -> Result<S::Ok, S::Error>
 where
     T: IntoIterator,
-    <T as IntoIterator>::Item: std::borrow::Borrow<i8>,
+    <T as IntoIterator>::Item: Borrow<i8>,
     S: Serializer,
 {
     serialize_array!(array, serializer, BYTE_ARRAY_NAME)
@@ -720,7 +1986,7 @@ where
 pub fn int_array<T, S>(array: T, serializer: S) -> Result<S::Ok, S::Error>
 where
     T: IntoIterator,
-    <T as IntoIterator>::Item: std::borrow::Borrow<i32>,
+    <T as IntoIterator>::Item: Borrow<i32>,
     S: Serializer,
 {
     serialize_array!(array, serializer, INT_ARRAY_NAME)
@@ -729,7 +1995,7 @@ where
 pub fn long_array<T, S>(array: T, serializer: S) -> Result<S::Ok, S::Error>
 where
     T: IntoIterator,
-    <T as IntoIterator>::Item: std::borrow::Borrow<i64>,
+    <T as IntoIterator>::Item: Borrow<i64>,
     S: Serializer,
 {
     serialize_array!(array, serializer, LONG_ARRAY_NAME)
@@ -0,0 +1,119 @@
+//! Stringified NBT, the text form of NBT used by commands such as `/data` and `/give`.
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Write};
+
+use crate::tag::NBTValue;
+
+// Entries are only in a predictable order when compounds are sorted.
+#[cfg(not(feature = "fast-hash"))]
+#[test]
+fn test_to_snbt() {
+    use crate::tag::CompoundBuilder;
+
+    let value = CompoundBuilder::new()
+        .byte("Count", 1)
+        .short("Damage", 3)
+        .long("Time", -5)
+        .float("Speed", 0.5)
+        .double("Health", 20.0)
+        .string("id", "minecraft:stone")
+        .string("display name", "say \"hi\"\\")
+        .int_array("UUID", [1, -2])
+        .byte_array("Bytes", [])
+        .long_array("Longs", [7])
+        .list("Pos", [NBTValue::Int(0), NBTValue::Int(64)])
+        .compound("tag", CompoundBuilder::new())
+        .build();
+    assert_eq!(
+        value.to_snbt(),
+        concat!(
+            r#"{Bytes:[B;],Count:1b,Damage:3s,Health:20.0d,Longs:[L;7L],Pos:[0,64],Speed:0.5f,"#,
+            r#"Time:-5L,UUID:[I;1,-2],"display name":"say \"hi\"\\",id:"minecraft:stone",tag:{}}"#
+        )
+    );
+}
+
+impl NBTValue {
+    /// Formats the value as SNBT, on a single line. Keys are quoted only when they contain
+    /// characters that unquoted keys can't, and compounds list their entries in the iteration
+    /// order of [`CompoundMap`](crate::CompoundMap).
+    pub fn to_snbt(&self) -> String {
+        Snbt(self).to_string()
+    }
+}
+
+struct Snbt<'a>(&'a NBTValue);
+
+impl Display for Snbt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            NBTValue::Byte(n) => write!(f, "{}b", n),
+            NBTValue::Short(n) => write!(f, "{}s", n),
+            NBTValue::Int(n) => write!(f, "{}", n),
+            NBTValue::Long(n) => write!(f, "{}L", n),
+            NBTValue::Float(n) => write!(f, "{:?}f", n),
+            NBTValue::Double(n) => write!(f, "{:?}d", n),
+            NBTValue::String(string) => write_quoted(f, string),
+            NBTValue::ByteArray(bytes) => write_array(f, "B", bytes.iter().map(|n| (n, "b"))),
+            NBTValue::IntArray(ints) => write_array(f, "I", ints.iter().map(|n| (n, ""))),
+            NBTValue::LongArray(longs) => write_array(f, "L", longs.iter().map(|n| (n, "L"))),
+            NBTValue::List(elements) => {
+                f.write_char('[')?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    Snbt(element).fmt(f)?;
+                }
+                f.write_char(']')
+            }
+            NBTValue::Compound(entries) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    if !key.is_empty() && key.chars().all(is_unquoted_char) {
+                        f.write_str(key)?;
+                    } else {
+                        write_quoted(f, key)?;
+                    }
+                    f.write_char(':')?;
+                    Snbt(value).fmt(f)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+// Characters allowed in keys and strings without quotes.
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, string: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in string.chars() {
+        if matches!(c, '"' | '\\') {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+fn write_array<'a, T, I>(f: &mut fmt::Formatter<'_>, prefix: &str, elements: I) -> fmt::Result
+where
+    T: Display + 'a,
+    I: Iterator<Item = (&'a T, &'static str)>,
+{
+    write!(f, "[{};", prefix)?;
+    for (i, (element, suffix)) in elements.enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        write!(f, "{}{}", element, suffix)?;
+    }
+    f.write_char(']')
+}
@@ -0,0 +1,492 @@
+//! Storage that chunks can be read from and written to by their coordinates, so code working with
+//! chunks doesn't need to care whether they live in region files or somewhere else.
+//!
+//! A [`World`](crate::world::World) reads and writes its chunks through a source for each folder
+//! of chunks, [`RegionDirectory`] unless it was opened with
+//! [`World::with_sources`](crate::world::World::with_sources).
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{self, Error},
+    region::{parse_region_name, region_at_chunk, RegionFile, RegionFormat, StoredChunk},
+    tag::{NBTPath, NBTValue},
+};
+
+#[test]
+fn test_chunk_sources() {
+    fn round_trip<S: ChunkSource>(source: &mut S) {
+        let chunk = StoredChunk {
+            compression: 3,
+            data: vec![10, 0, 0, 0],
+        };
+        assert_eq!(source.get_chunk(-1, 2).unwrap(), None);
+        source.put_chunk(-1, 2, chunk.clone()).unwrap();
+        assert_eq!(source.get_chunk(-1, 2).unwrap(), Some(chunk));
+        assert_eq!(source.read_chunk(-1, 2).unwrap().unwrap(), [10, 0, 0, 0]);
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    round_trip(&mut RegionDirectory::new(dir.path().join("region")));
+    round_trip(&mut ChunkDirectory::new(dir.path().join("chunks")));
+    round_trip(&mut HashMap::new());
+
+    // Loose chunk files keep their scheme in the extension, so LZ4 isn't mistaken for raw NBT.
+    let mut loose = ChunkDirectory::new(dir.path().join("chunks"));
+    let lz4 = StoredChunk {
+        compression: 4,
+        data: vec![1, 2, 3],
+    };
+    loose.put_chunk(-1, 2, lz4.clone()).unwrap();
+    assert_eq!(loose.get_chunk(-1, 2).unwrap(), Some(lz4));
+    assert!(!dir.path().join("chunks/c.-1.2.nbt").exists());
+    let custom = StoredChunk {
+        compression: 127,
+        data: vec![1, 2, 3],
+    };
+    assert!(matches!(
+        loose.put_chunk(0, 0, custom),
+        Err(Error::UnknownCompression(127))
+    ));
+
+    let mut region = RegionFile::open(dir.path().join("region/r.-1.0.mca")).unwrap();
+    assert!(region.read_chunk(31, 2).unwrap().is_some());
+
+    let chest = |x: i32| {
+        let chest = crate::CompoundBuilder::new().string("id", "minecraft:chest");
+        let chunk = crate::CompoundBuilder::new()
+            .int("xPos", x)
+            .list("block_entities", [chest.build()])
+            .build();
+        StoredChunk::compress(&crate::to_bytes(&chunk).unwrap()).unwrap()
+    };
+    let mut chunks = HashMap::from([((3, -4), chest(3)), ((0, 0), chest(0))]);
+    let found: Vec<_> = chunks
+        .find_matching("id", Some("*:chest"))
+        .unwrap()
+        .into_iter()
+        .map(|(position, path)| (position, path.to_string()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ((0, 0), "block_entities[0].id".to_owned()),
+            ((3, -4), "block_entities[0].id".to_owned())
+        ]
+    );
+    assert!(chunks.find_matching("xPos", Some("7")).unwrap().is_empty());
+
+    // A single region file only has slots for its own chunks.
+    assert!(matches!(
+        region.get_chunk(-1, 2),
+        Err(Error::ChunkOutsideRegion(-1, 2))
+    ));
+    assert!(matches!(
+        region.get_chunk(32, 0),
+        Err(Error::ChunkOutsideRegion(32, 0))
+    ));
+
+    // Reads go through the region's own decompressors.
+    region.set_decompressor(3, |_| Ok(vec![0]));
+    assert_eq!(
+        ChunkSource::read_chunk(&mut region, 31, 2)
+            .unwrap()
+            .unwrap(),
+        [0]
+    );
+
+    // Chunks that outgrow their sectors move to the end of the region file.
+    let mut region = RegionFile::open_writable(dir.path().join("r.0.0.mca")).unwrap();
+    let small = StoredChunk {
+        compression: 3,
+        data: vec![1; 10],
+    };
+    let large = StoredChunk {
+        compression: 3,
+        data: vec![2; 5000],
+    };
+    region.put_chunk(0, 0, large.clone()).unwrap();
+    region.put_chunk(1, 0, small.clone()).unwrap();
+    region.put_chunk(1, 0, large.clone()).unwrap();
+    region.put_chunk(0, 0, small.clone()).unwrap();
+    let mut region = RegionFile::open(dir.path().join("r.0.0.mca")).unwrap();
+    assert_eq!(region.get_chunk(0, 0).unwrap(), Some(small));
+    assert_eq!(region.get_chunk(1, 0).unwrap(), Some(large));
+}
+
+/// Somewhere chunks are kept as they are stored on disk, addressed by their chunk coordinates.
+pub trait ChunkSource {
+    /// Reads the stored chunk at `x`, `z`, or `None` if there is none.
+    fn get_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<StoredChunk>>;
+
+    /// Stores `chunk` at `x`, `z`, replacing any chunk already there.
+    fn put_chunk(&mut self, x: i32, z: i32, chunk: StoredChunk) -> error::Result<()>;
+
+    /// The coordinates of every chunk there is, in no particular order.
+    fn positions(&mut self) -> error::Result<Vec<(i32, i32)>>;
+
+    /// Reads and decompresses the chunk at `x`, `z`, returning its NBT.
+    fn read_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<Vec<u8>>> {
+        self.get_chunk(x, z)?
+            .map(|chunk| chunk.decompress())
+            .transpose()
+    }
+
+    /// When the chunk at `x`, `z` was last saved, in seconds since the Unix epoch, or `None` if
+    /// there is no such chunk or the source doesn't keep track.
+    fn timestamp(&mut self, _x: i32, _z: i32) -> error::Result<Option<u32>> {
+        Ok(None)
+    }
+
+    /// Stores `chunk` at `x`, `z` as saved at `timestamp`, for copying chunks between sources.
+    /// Sources that don't keep timestamps store it like [`put_chunk`](Self::put_chunk).
+    fn put_chunk_with_timestamp(
+        &mut self,
+        x: i32,
+        z: i32,
+        chunk: StoredChunk,
+        _timestamp: u32,
+    ) -> error::Result<()> {
+        self.put_chunk(x, z, chunk)
+    }
+
+    /// Searches every chunk for the compound entries whose key matches the glob `key` and, if
+    /// given, whose value matches the glob `value`, see [`NBTValue::find_matching`]. Returns the
+    /// coordinates of the chunk each match is in along with the path to it within the chunk.
+    fn find_matching(
+        &mut self,
+        key: &str,
+        value: Option<&str>,
+    ) -> error::Result<Vec<((i32, i32), NBTPath)>> {
+        let mut positions = self.positions()?;
+        positions.sort();
+        let mut found = Vec::new();
+        for (x, z) in positions {
+            let Some(bytes) = self.read_chunk(x, z)? else {
+                continue;
+            };
+            let chunk: NBTValue = crate::from_slice(&bytes)?;
+            let matches = chunk.find_matching(key, value).into_iter();
+            found.extend(matches.map(|(path, _)| ((x, z), path)));
+        }
+        Ok(found)
+    }
+}
+
+/// A single region file. A region file doesn't know where it lies in the world, so chunks are
+/// addressed by their position within it, from 0 to 31, and other coordinates are rejected with
+/// [`Error::ChunkOutsideRegion`]. Use a [`RegionDirectory`] to address chunks by their world
+/// coordinates.
+impl<R: Read + Write + Seek> ChunkSource for RegionFile<R> {
+    fn get_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<StoredChunk>> {
+        let (x, z) = region_slot(x, z)?;
+        self.read_stored_chunk(x, z)
+    }
+
+    fn put_chunk(&mut self, x: i32, z: i32, chunk: StoredChunk) -> error::Result<()> {
+        let (x, z) = region_slot(x, z)?;
+        self.write_stored_chunk(x, z, &chunk)
+    }
+
+    fn positions(&mut self) -> error::Result<Vec<(i32, i32)>> {
+        Ok(self.chunks().map(|(x, z)| (x as i32, z as i32)).collect())
+    }
+
+    fn read_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<Vec<u8>>> {
+        let (x, z) = region_slot(x, z)?;
+        RegionFile::read_chunk(self, x, z)
+    }
+
+    fn timestamp(&mut self, x: i32, z: i32) -> error::Result<Option<u32>> {
+        let (x, z) = region_slot(x, z)?;
+        RegionFile::timestamp(self, x, z)
+    }
+
+    fn put_chunk_with_timestamp(
+        &mut self,
+        x: i32,
+        z: i32,
+        chunk: StoredChunk,
+        timestamp: u32,
+    ) -> error::Result<()> {
+        let (x, z) = region_slot(x, z)?;
+        self.write_chunk_with_timestamp(x, z, &chunk, timestamp)
+    }
+}
+
+fn region_slot(x: i32, z: i32) -> error::Result<(usize, usize)> {
+    match (usize::try_from(x), usize::try_from(z)) {
+        (Ok(slot_x), Ok(slot_z)) if slot_x < 32 && slot_z < 32 => Ok((slot_x, slot_z)),
+        _ => Err(Error::ChunkOutsideRegion(x, z)),
+    }
+}
+
+/// A folder of region files named `r.<x>.<z>.<extension>`, such as a dimension's `region`
+/// folder, addressed by world chunk coordinates. The region file used last is kept open, and
+/// region files are created on the first write to them. Writing to the folder through anything
+/// else while the source is in use isn't noticed.
+pub struct RegionDirectory {
+    dir: PathBuf,
+    format: RegionFormat,
+    // The region file used last, by its region coordinates, and whether it is open for writing.
+    open: Option<((i32, i32), bool, RegionFile<fs::File>)>,
+}
+
+impl RegionDirectory {
+    /// Uses the Anvil region files in `dir`.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self::with_format(dir, RegionFormat::Anvil)
+    }
+
+    pub fn with_format<P: Into<PathBuf>>(dir: P, format: RegionFormat) -> Self {
+        Self {
+            dir: dir.into(),
+            format,
+            open: None,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn format(&self) -> RegionFormat {
+        self.format
+    }
+
+    /// The region files in the folder, with their region coordinates.
+    pub fn regions(&self) -> error::Result<Vec<((i32, i32), PathBuf)>> {
+        Ok(files_in(&self.dir)?
+            .into_iter()
+            .filter_map(|path| Some((parse_region_name(&path, self.format)?, path)))
+            .collect())
+    }
+
+    // The region holding the chunk at `x`, `z`, opened for writing if `write` is set, in which
+    // case it is created if needed. `None` if there is nothing to read.
+    fn region(
+        &mut self,
+        x: i32,
+        z: i32,
+        write: bool,
+    ) -> error::Result<Option<&mut RegionFile<fs::File>>> {
+        let position = region_at_chunk(x, z);
+        let reusable = matches!(
+            &self.open,
+            Some((open, writable, _)) if *open == position && (*writable || !write)
+        );
+        if !reusable {
+            self.open = None;
+            let path = self.dir.join(format!(
+                "r.{}.{}.{}",
+                position.0,
+                position.1,
+                self.format.extension()
+            ));
+            let region = if write {
+                fs::create_dir_all(&self.dir)?;
+                RegionFile::open_writable(&path)?
+            } else {
+                match fs::File::open(&path) {
+                    Ok(file) => RegionFile::with_format(file, self.format)?,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                    Err(err) => return Err(Error::IoError(err)),
+                }
+            };
+            self.open = Some((position, write, region));
+        }
+        Ok(self.open.as_mut().map(|(_, _, region)| region))
+    }
+}
+
+impl Clone for RegionDirectory {
+    fn clone(&self) -> Self {
+        Self::with_format(self.dir.clone(), self.format)
+    }
+}
+
+impl fmt::Debug for RegionDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegionDirectory")
+            .field("dir", &self.dir)
+            .field("format", &self.format)
+            .finish_non_exhaustive()
+    }
+}
+
+// The position of a chunk within its region.
+fn slot(x: i32, z: i32) -> (usize, usize) {
+    ((x & 31) as usize, (z & 31) as usize)
+}
+
+impl ChunkSource for RegionDirectory {
+    fn get_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<StoredChunk>> {
+        let (slot_x, slot_z) = slot(x, z);
+        match self.region(x, z, false)? {
+            Some(region) => region.read_stored_chunk(slot_x, slot_z),
+            None => Ok(None),
+        }
+    }
+
+    fn put_chunk(&mut self, x: i32, z: i32, chunk: StoredChunk) -> error::Result<()> {
+        let (slot_x, slot_z) = slot(x, z);
+        let region = self.region(x, z, true)?.expect("opened for writing");
+        region.write_stored_chunk(slot_x, slot_z, &chunk)
+    }
+
+    fn positions(&mut self) -> error::Result<Vec<(i32, i32)>> {
+        let mut positions = Vec::new();
+        for ((region_x, region_z), path) in self.regions()? {
+            let region = RegionFile::with_format(fs::File::open(path)?, self.format)?;
+            positions.extend(
+                region
+                    .chunks()
+                    .map(|(x, z)| (region_x * 32 + x as i32, region_z * 32 + z as i32)),
+            );
+        }
+        Ok(positions)
+    }
+
+    fn read_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<Vec<u8>>> {
+        let (slot_x, slot_z) = slot(x, z);
+        match self.region(x, z, false)? {
+            Some(region) => RegionFile::read_chunk(region, slot_x, slot_z),
+            None => Ok(None),
+        }
+    }
+
+    fn timestamp(&mut self, x: i32, z: i32) -> error::Result<Option<u32>> {
+        let (slot_x, slot_z) = slot(x, z);
+        match self.region(x, z, false)? {
+            Some(region) => RegionFile::timestamp(region, slot_x, slot_z),
+            None => Ok(None),
+        }
+    }
+
+    fn put_chunk_with_timestamp(
+        &mut self,
+        x: i32,
+        z: i32,
+        chunk: StoredChunk,
+        timestamp: u32,
+    ) -> error::Result<()> {
+        let (slot_x, slot_z) = slot(x, z);
+        let region = self.region(x, z, true)?.expect("opened for writing");
+        region.write_chunk_with_timestamp(slot_x, slot_z, &chunk, timestamp)
+    }
+}
+
+/// Chunks kept in memory, for tests and for worlds that are built up before being saved.
+impl ChunkSource for HashMap<(i32, i32), StoredChunk> {
+    fn get_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<StoredChunk>> {
+        Ok(self.get(&(x, z)).cloned())
+    }
+
+    fn put_chunk(&mut self, x: i32, z: i32, chunk: StoredChunk) -> error::Result<()> {
+        self.insert((x, z), chunk);
+        Ok(())
+    }
+
+    fn positions(&mut self) -> error::Result<Vec<(i32, i32)>> {
+        Ok(self.keys().copied().collect())
+    }
+}
+
+/// A directory holding each chunk in a file of its own, named `c.<x>.<z>.<extension>` after its
+/// compression scheme: `gz` for gzip, `zlib`, `nbt` for uncompressed NBT and `lz4`. Chunks
+/// compressed with other schemes can't be stored and are reported as
+/// [`Error::UnknownCompression`].
+#[derive(Debug, Clone)]
+pub struct ChunkDirectory {
+    dir: PathBuf,
+}
+
+// The compression schemes a `ChunkDirectory` can store, with the extension of their files.
+const CHUNK_FILE_EXTENSIONS: [(u8, &str); 4] = [(1, "gz"), (2, "zlib"), (3, "nbt"), (4, "lz4")];
+
+impl ChunkDirectory {
+    /// Uses `dir`, which is created on the first write if it doesn't exist.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, x: i32, z: i32, extension: &str) -> PathBuf {
+        self.dir.join(format!("c.{}.{}.{}", x, z, extension))
+    }
+}
+
+impl ChunkSource for ChunkDirectory {
+    fn get_chunk(&mut self, x: i32, z: i32) -> error::Result<Option<StoredChunk>> {
+        for (compression, extension) in CHUNK_FILE_EXTENSIONS {
+            match fs::read(self.path(x, z, extension)) {
+                Ok(data) => return Ok(Some(StoredChunk { compression, data })),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(Error::IoError(err)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn put_chunk(&mut self, x: i32, z: i32, chunk: StoredChunk) -> error::Result<()> {
+        let Some(&(_, extension)) = CHUNK_FILE_EXTENSIONS
+            .iter()
+            .find(|(compression, _)| *compression == chunk.compression)
+        else {
+            return Err(Error::UnknownCompression(chunk.compression));
+        };
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(x, z, extension), chunk.data)?;
+        // A chunk stored before with another scheme would otherwise shadow this one.
+        for (_, other) in CHUNK_FILE_EXTENSIONS {
+            if other != extension {
+                match fs::remove_file(self.path(x, z, other)) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                        return Err(Error::IoError(err))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn positions(&mut self) -> error::Result<Vec<(i32, i32)>> {
+        let mut positions = Vec::new();
+        for path in files_in(&self.dir)? {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some((position, extension)) = name
+                .strip_prefix("c.")
+                .and_then(|name| name.rsplit_once('.'))
+            else {
+                continue;
+            };
+            let Some((x, z)) = position.split_once('.') else {
+                continue;
+            };
+            let known = CHUNK_FILE_EXTENSIONS
+                .iter()
+                .any(|(_, known)| *known == extension);
+            if let (true, Ok(x), Ok(z)) = (known, x.parse(), z.parse()) {
+                positions.push((x, z));
+            }
+        }
+        Ok(positions)
+    }
+}
+
+// The paths of the entries of `dir`, none if it doesn't exist.
+fn files_in(dir: &Path) -> error::Result<Vec<PathBuf>> {
+    match fs::read_dir(dir) {
+        Ok(entries) => Ok(entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(Error::IoError(err)),
+    }
+}
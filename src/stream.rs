@@ -0,0 +1,739 @@
+//! Pull-based reading of NBT documents, one tag at a time, without building the whole document
+//! in memory.
+use std::io::{self, Read};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::{
+    de::DeserializerOptions,
+    error::{self, Error},
+    kind::NBTKind,
+    parser::{IoRead, Parser},
+    tag::{glob_match, CompoundMap, NBTPath, NBTValue, PathSegment},
+    writer::Writer,
+};
+
+#[test]
+fn test_stream_events() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Section {
+        #[serde(serialize_with = "crate::long_array")]
+        states: Vec<i64>,
+        #[serde(serialize_with = "crate::byte_array")]
+        light: Vec<i8>,
+        y: i8,
+        tags: Vec<String>,
+    }
+
+    let bytes = crate::to_bytes(&Section {
+        states: vec![1; 64],
+        light: vec![-1, 2, 3],
+        y: 4,
+        tags: vec!["a".into()],
+    })
+    .unwrap();
+
+    let mut reader = NBTStreamReader::new(bytes.as_slice());
+    let mut seen = Vec::new();
+    while let Some(event) = reader.next_event().unwrap() {
+        seen.push(match event {
+            NBTEvent::CompoundStart { name } => format!("start {:?}", name),
+            NBTEvent::CompoundEnd => "end".to_owned(),
+            NBTEvent::ListStart { name, length, .. } => format!("list {:?} {}", name, length),
+            NBTEvent::ListEnd => "list end".to_owned(),
+            NBTEvent::Value { name, value } => format!("{:?} = {:?}", name, value),
+            // The first array is dropped unread, the second is copied out.
+            NBTEvent::Array { name, array } if name.as_deref() == Some("states") => {
+                format!("{} of {}", array.kind(), array.len())
+            }
+            NBTEvent::Array { mut array, .. } => {
+                let mut raw = Vec::new();
+                array.copy_to(&mut raw).unwrap();
+                format!("{:?}", raw)
+            }
+        });
+    }
+    assert_eq!(
+        seen,
+        [
+            r#"start Some("")"#,
+            "LongArray of 64",
+            "[255, 2, 3]",
+            r#"Some("y") = Byte(4)"#,
+            r#"list Some("tags") 1"#,
+            r#"None = String("a")"#,
+            "list end",
+            "end",
+        ]
+    );
+}
+
+#[test]
+fn test_convert_byte_order() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Pos {
+        x: f64,
+        y: i16,
+    }
+
+    #[derive(Serialize)]
+    struct Level {
+        name: String,
+        #[serde(serialize_with = "crate::int_array")]
+        biomes: Vec<i32>,
+        #[serde(serialize_with = "crate::long_array")]
+        states: Vec<i64>,
+        #[serde(serialize_with = "crate::byte_array")]
+        light: Vec<i8>,
+        positions: Vec<Pos>,
+        seed: i64,
+    }
+
+    let level = Level {
+        name: "world".into(),
+        biomes: (0..2000).collect(),
+        states: vec![i64::MIN, 1],
+        light: vec![1, -2],
+        positions: vec![Pos { x: 0.5, y: -3 }],
+        seed: -42,
+    };
+    let java = crate::to_bytes(&level).unwrap();
+    let mut bedrock = Vec::new();
+    crate::to_writer_le(&mut bedrock, &level).unwrap();
+
+    let mut converted = Vec::new();
+    java_to_bedrock(java.as_slice(), &mut converted).unwrap();
+    assert_eq!(converted, bedrock);
+    converted.clear();
+    bedrock_to_java(bedrock.as_slice(), &mut converted).unwrap();
+    assert_eq!(converted, java);
+}
+
+#[test]
+fn test_copy_filtered() {
+    use crate::CompoundBuilder;
+
+    let entity = |id: &str| {
+        CompoundBuilder::new()
+            .string("id", id)
+            .list("Pos", [NBTValue::Double(1.0), NBTValue::Double(2.0)])
+            .compound(
+                "Paper",
+                CompoundBuilder::new().int_array("Origin", [1, 2, 3]),
+            )
+            .build()
+    };
+    let chunk = CompoundBuilder::new()
+        .int("DataVersion", 3465)
+        .long_array("Heightmap", [1, 2])
+        .list(
+            "Entities",
+            [
+                entity("minecraft:cow"),
+                entity("minecraft:item"),
+                entity("minecraft:pig"),
+            ],
+        )
+        .build();
+    let bytes = crate::to_bytes(&chunk).unwrap();
+
+    let rules = [
+        ("*Paper.Origin", CopyRule::Drop),
+        ("Entities[1]", CopyRule::Drop),
+        (
+            "Entities[*].Pos[0]",
+            CopyRule::Replace(NBTValue::Double(0.5)),
+        ),
+        ("DataVersion", CopyRule::Replace(NBTValue::Short(1))),
+        ("Heightmap", CopyRule::Rename("Heightmaps".into())),
+    ];
+    let mut copy = Vec::new();
+    assert_eq!(
+        copy_filtered(bytes.as_slice(), &mut copy, &rules).unwrap(),
+        7
+    );
+    let copy: NBTValue = crate::from_slice(&copy).unwrap();
+
+    let entity = |id: &str| {
+        CompoundBuilder::new()
+            .string("id", id)
+            .list("Pos", [NBTValue::Double(0.5), NBTValue::Double(2.0)])
+            .compound("Paper", CompoundBuilder::new())
+            .build()
+    };
+    let expected = CompoundBuilder::new()
+        .value("DataVersion", NBTValue::Short(1))
+        .long_array("Heightmaps", [1, 2])
+        .list(
+            "Entities",
+            [entity("minecraft:cow"), entity("minecraft:pig")],
+        )
+        .build();
+    assert_eq!(copy, expected);
+
+    // List elements can't change kind.
+    let rules = [("Entities[0]", CopyRule::Replace(NBTValue::Int(0)))];
+    assert!(copy_filtered(bytes.as_slice(), io::sink(), &rules).is_err());
+}
+
+/// A single step through a document, as returned by [`NBTStreamReader::next_event`]. `name` is
+/// the entry's name inside its compound and `None` for list elements.
+pub enum NBTEvent<'a, R: io::Read, O: ByteOrder = BigEndian> {
+    CompoundStart {
+        name: Option<String>,
+    },
+    CompoundEnd,
+    ListStart {
+        name: Option<String>,
+        element_kind: NBTKind,
+        length: usize,
+    },
+    ListEnd,
+    /// Any tag other than a compound, list or array.
+    Value {
+        name: Option<String>,
+        value: NBTValue,
+    },
+    /// A ByteArray, IntArray or LongArray whose payload hasn't been read yet.
+    Array {
+        name: Option<String>,
+        array: ArrayReader<'a, R, O>,
+    },
+}
+
+pub(crate) enum Frame {
+    Compound,
+    List { kind: NBTKind, remaining: usize },
+}
+
+/// Reads a document as a sequence of [`NBTEvent`]s.
+pub struct NBTStreamReader<R: io::Read, O: ByteOrder = BigEndian> {
+    parser: Parser<IoRead<R>, O>,
+    stack: Vec<Frame>,
+    started: bool,
+    // Bytes of the last array payload that its `ArrayReader` left unread.
+    pending: u64,
+}
+
+assert_send_sync!(NBTStreamReader<std::fs::File>);
+
+impl<R: io::Read> NBTStreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_byte_order(reader)
+    }
+}
+
+impl<R: io::Read> NBTStreamReader<R, LittleEndian> {
+    /// Like [`NBTStreamReader::new`], but reads the little-endian NBT used by Bedrock Edition.
+    pub fn new_le(reader: R) -> Self {
+        Self::with_byte_order(reader)
+    }
+}
+
+impl<R: io::Read, O: ByteOrder> NBTStreamReader<R, O> {
+    fn with_byte_order(reader: R) -> Self {
+        Self {
+            parser: Parser::new(IoRead::new(reader), DeserializerOptions::default()),
+            stack: Vec::new(),
+            started: false,
+            pending: 0,
+        }
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn position(&self) -> u64 {
+        self.parser.position()
+    }
+
+    /// Reads the next event, or `None` once the root compound has been closed.
+    pub fn next_event(&mut self) -> error::Result<Option<NBTEvent<'_, R, O>>> {
+        self.parser.skip_bytes(self.pending)?;
+        self.pending = 0;
+
+        if !self.started {
+            self.started = true;
+            if self.parser.parse_kind()? != NBTKind::Compound {
+                return Err(Error::ExpectedRootCompound);
+            }
+            let name = self.parser.parse_str(|name| Ok(name.to_owned()))?;
+            self.stack.push(Frame::Compound);
+            return Ok(Some(NBTEvent::CompoundStart { name: Some(name) }));
+        }
+
+        match self.stack.last_mut() {
+            None => Ok(None),
+            Some(Frame::Compound) => {
+                let kind = self.parser.parse_kind()?;
+                if kind == NBTKind::End {
+                    self.stack.pop();
+                    return Ok(Some(NBTEvent::CompoundEnd));
+                }
+                let name = self.parser.parse_str(|name| Ok(name.to_owned()))?;
+                self.payload(kind, Some(name)).map(Some)
+            }
+            Some(Frame::List { kind, remaining }) => {
+                if *remaining == 0 {
+                    self.stack.pop();
+                    return Ok(Some(NBTEvent::ListEnd));
+                }
+                *remaining -= 1;
+                let kind = *kind;
+                self.payload(kind, None).map(Some)
+            }
+        }
+    }
+
+    /// Skips the rest of the compound or list that was started last, along with its end event.
+    /// Nothing inside it is decoded.
+    pub fn skip_container(&mut self) -> error::Result<()> {
+        self.parser.skip_bytes(self.pending)?;
+        self.pending = 0;
+        match self.stack.pop() {
+            None => Ok(()),
+            Some(Frame::Compound) => self.parser.skip_payload(NBTKind::Compound),
+            Some(Frame::List { kind, remaining }) => {
+                (0..remaining).try_for_each(|_| self.parser.skip_payload(kind))
+            }
+        }
+    }
+
+    /// Reads the rest of the document into memory, returning the root compound's name and value.
+    /// Call this on a fresh reader to load a whole document.
+    pub fn read_document(mut self) -> error::Result<(String, NBTValue)> {
+        enum Open {
+            Compound(CompoundMap),
+            List(Vec<NBTValue>),
+        }
+
+        // Containers that have been started but not ended, with their names.
+        let mut open: Vec<(Option<String>, Open)> = Vec::new();
+        loop {
+            let (name, value) = match self.next_event()? {
+                None => return Err(Error::Eof),
+                Some(NBTEvent::CompoundStart { name }) => {
+                    open.push((name, Open::Compound(CompoundMap::default())));
+                    continue;
+                }
+                Some(NBTEvent::ListStart { name, length, .. }) => {
+                    open.push((name, Open::List(Vec::with_capacity(length.min(1024)))));
+                    continue;
+                }
+                Some(NBTEvent::CompoundEnd | NBTEvent::ListEnd) => {
+                    let (name, container) = open.pop().expect("events are balanced");
+                    let value = match container {
+                        Open::Compound(entries) => NBTValue::Compound(entries),
+                        Open::List(elements) => NBTValue::List(elements),
+                    };
+                    if open.is_empty() {
+                        return Ok((name.unwrap_or_default(), value));
+                    }
+                    (name, value)
+                }
+                Some(NBTEvent::Value { name, value }) => (name, value),
+                Some(NBTEvent::Array { name, array }) => (name, array.into_value()?),
+            };
+            match open.last_mut() {
+                Some((_, Open::Compound(entries))) => {
+                    entries.insert(name.unwrap_or_default(), value);
+                }
+                Some((_, Open::List(elements))) => elements.push(value),
+                None => unreachable!("the root compound is returned when it ends"),
+            }
+        }
+    }
+
+    fn payload(
+        &mut self,
+        kind: NBTKind,
+        name: Option<String>,
+    ) -> error::Result<NBTEvent<'_, R, O>> {
+        let value = match kind {
+            NBTKind::Compound => {
+                self.stack.push(Frame::Compound);
+                return Ok(NBTEvent::CompoundStart { name });
+            }
+            NBTKind::List => {
+                let (element_kind, length) = self.parser.parse_list_header()?;
+                self.stack.push(Frame::List {
+                    kind: element_kind,
+                    remaining: length,
+                });
+                return Ok(NBTEvent::ListStart {
+                    name,
+                    element_kind,
+                    length,
+                });
+            }
+            NBTKind::ByteArray | NBTKind::IntArray | NBTKind::LongArray => {
+                let len = self.parser.parse_array_length(element_size(kind))?;
+                self.pending = len as u64 * element_size(kind);
+                let array = ArrayReader {
+                    reader: self,
+                    kind,
+                    len,
+                };
+                return Ok(NBTEvent::Array { name, array });
+            }
+            NBTKind::Byte => NBTValue::Byte(self.parser.parse_i8()?),
+            NBTKind::Short => NBTValue::Short(self.parser.parse_i16()?),
+            NBTKind::Int => NBTValue::Int(self.parser.parse_i32()?),
+            NBTKind::Long => NBTValue::Long(self.parser.parse_i64()?),
+            NBTKind::Float => NBTValue::Float(self.parser.parse_f32()?),
+            NBTKind::Double => NBTValue::Double(self.parser.parse_f64()?),
+            NBTKind::String => {
+                NBTValue::String(self.parser.parse_str(|string| Ok(string.to_owned()))?)
+            }
+            NBTKind::End => return Err(Error::InvalidTagId),
+        };
+        Ok(NBTEvent::Value { name, value })
+    }
+}
+
+fn element_size(kind: NBTKind) -> u64 {
+    match kind {
+        NBTKind::IntArray => 4,
+        NBTKind::LongArray => 8,
+        _ => 1,
+    }
+}
+
+/// Handle to the payload of an array tag. The payload can be read in pieces through
+/// [`io::Read`], copied out, or ignored; whatever is left unread is skipped by the next call to
+/// [`NBTStreamReader::next_event`].
+pub struct ArrayReader<'a, R: io::Read, O: ByteOrder> {
+    reader: &'a mut NBTStreamReader<R, O>,
+    kind: NBTKind,
+    len: usize,
+}
+
+impl<'a, R: io::Read, O: ByteOrder> ArrayReader<'a, R, O> {
+    /// ByteArray, IntArray or LongArray.
+    pub fn kind(&self) -> NBTKind {
+        self.kind
+    }
+
+    /// Number of elements in the array.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copies the rest of the raw payload to `w`, in the byte order of the document. Returns the
+    /// number of bytes copied.
+    pub fn copy_to<W: io::Write>(&mut self, w: &mut W) -> error::Result<u64> {
+        let expected = self.reader.pending;
+        let copied = io::copy(self, w)?;
+        if copied < expected {
+            return Err(Error::Eof);
+        }
+        Ok(copied)
+    }
+
+    /// Reads the rest of the array into a value.
+    pub fn into_value(mut self) -> error::Result<NBTValue> {
+        let mut raw = Vec::new();
+        self.copy_to(&mut raw)?;
+        Ok(match self.kind {
+            NBTKind::ByteArray => NBTValue::ByteArray(raw.into_iter().map(|b| b as i8).collect()),
+            NBTKind::IntArray => NBTValue::IntArray(raw.chunks_exact(4).map(O::read_i32).collect()),
+            _ => NBTValue::LongArray(raw.chunks_exact(8).map(O::read_i64).collect()),
+        })
+    }
+}
+
+impl<'a, R: io::Read, O: ByteOrder> Read for ArrayReader<'a, R, O> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = buf
+            .len()
+            .min(self.reader.pending.try_into().unwrap_or(usize::MAX));
+        let read = self.reader.parser.read_raw(&mut buf[..max])?;
+        self.reader.pending -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Writes `value`, which has to be a compound, as a document whose root is named `name`. This is
+/// the counterpart of [`NBTStreamReader::read_document`].
+pub fn write_document<W: io::Write>(w: W, name: &str, value: &NBTValue) -> error::Result<()> {
+    Writer::<_, BigEndian>::new(w, Default::default()).write_document(name, value)
+}
+
+/// Converts a big-endian Java Edition document read from `r` into the little-endian encoding used
+/// by Bedrock Edition, one tag at a time, so the document is never held in memory as a whole.
+pub fn java_to_bedrock<R: io::Read, W: io::Write>(r: R, w: W) -> error::Result<()> {
+    let reader = NBTStreamReader::new(r);
+    convert_byte_order(
+        reader,
+        Writer::<_, LittleEndian>::new(w, Default::default()),
+    )
+}
+
+/// The reverse of [`java_to_bedrock`].
+pub fn bedrock_to_java<R: io::Read, W: io::Write>(r: R, w: W) -> error::Result<()> {
+    let reader = NBTStreamReader::new_le(r);
+    convert_byte_order(reader, Writer::<_, BigEndian>::new(w, Default::default()))
+}
+
+fn convert_byte_order<R, I, W, O>(
+    mut reader: NBTStreamReader<R, I>,
+    mut writer: Writer<W, O>,
+) -> error::Result<()>
+where
+    R: io::Read,
+    I: ByteOrder,
+    W: io::Write,
+    O: ByteOrder,
+{
+    let swap = I::read_u16(&[0, 1]) != O::read_u16(&[0, 1]);
+    while let Some(event) = reader.next_event()? {
+        match event {
+            NBTEvent::CompoundStart { name } => header(&mut writer, NBTKind::Compound, name)?,
+            NBTEvent::CompoundEnd => writer.write_tag_header(NBTKind::End, None)?,
+            NBTEvent::ListStart {
+                name,
+                element_kind,
+                length,
+            } => {
+                header(&mut writer, NBTKind::List, name)?;
+                writer.write_tag_header(element_kind, None)?;
+                writer.write_length(length)?;
+            }
+            NBTEvent::ListEnd => {}
+            NBTEvent::Value { name, value } => {
+                header(&mut writer, value.kind(), name)?;
+                match value {
+                    NBTValue::Byte(n) => writer.write_i8(n)?,
+                    NBTValue::Short(n) => writer.write_i16(n)?,
+                    NBTValue::Int(n) => writer.write_i32(n)?,
+                    NBTValue::Long(n) => writer.write_i64(n)?,
+                    NBTValue::Float(n) => writer.write_f32(n)?,
+                    NBTValue::Double(n) => writer.write_f64(n)?,
+                    NBTValue::String(string) => writer.write_string(&string)?,
+                    _ => unreachable!("compounds, lists and arrays have events of their own"),
+                }
+            }
+            NBTEvent::Array { name, array } => {
+                header(&mut writer, array.kind(), name)?;
+                copy_array(array, &mut writer, swap)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Writes the id and name of a tag, list elements have neither.
+fn header<W: io::Write, O: ByteOrder>(
+    writer: &mut Writer<W, O>,
+    kind: NBTKind,
+    name: Option<String>,
+) -> error::Result<()> {
+    match name {
+        Some(name) => {
+            writer.write_tag_header(kind, None)?;
+            writer.write_string(&name)
+        }
+        None => Ok(()),
+    }
+}
+
+// Writes the length and payload of an array, swapping the byte order of each element if `swap`.
+fn copy_array<R, I, W, O>(
+    mut array: ArrayReader<'_, R, I>,
+    writer: &mut Writer<W, O>,
+    swap: bool,
+) -> error::Result<()>
+where
+    R: io::Read,
+    I: ByteOrder,
+    W: io::Write,
+    O: ByteOrder,
+{
+    writer.write_length(array.len())?;
+    let size = element_size(array.kind()) as usize;
+    let mut remaining = array.len() * size;
+    // A multiple of every element size, so no element is split across reads.
+    let mut buffer = [0; 4096];
+    while remaining > 0 {
+        let chunk = &mut buffer[..remaining.min(4096)];
+        array.read_exact(chunk)?;
+        if swap {
+            chunk.chunks_exact_mut(size).for_each(<[u8]>::reverse);
+        }
+        writer.write_raw(chunk)?;
+        remaining -= chunk.len();
+    }
+    Ok(())
+}
+
+/// What [`copy_filtered`] does with a value whose path matches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopyRule {
+    /// Leaves the value out, along with everything inside it.
+    Drop,
+    /// Writes the value under another name. List elements have no name and are copied as is.
+    Rename(String),
+    /// Writes this value in its place, skipping the original unread. In a list it has to be of
+    /// the list's element kind.
+    Replace(NBTValue),
+}
+
+/// Copies a document from `r` to `w` one tag at a time, applying the first of `rules` whose path
+/// matches each value on the way, and returns how many values a rule was applied to. Paths are
+/// written like [`NBTPath`]s and may contain `*` and `?`, so `*Paper.Origin` matches that tag in
+/// every entity of a chunk. The root compound itself is never matched.
+///
+/// Like [`java_to_bedrock`], this never holds more than a single value in memory, so it can strip
+/// data out of arbitrarily large documents.
+///
+/// ```
+/// use nbt::{copy_filtered, CompoundBuilder, CopyRule, NBTValue};
+///
+/// let entity = |id: &str| {
+///     CompoundBuilder::new()
+///         .string("id", id)
+///         .compound("Paper", CompoundBuilder::new().list("Origin", [NBTValue::Double(0.0)]))
+///         .build()
+/// };
+/// let chunk = CompoundBuilder::new().list("Entities", [entity("minecraft:cow")]).build();
+/// let mut copy = Vec::new();
+/// let rules = [("*Paper.Origin", CopyRule::Drop)];
+/// copy_filtered(nbt::to_bytes(&chunk).unwrap().as_slice(), &mut copy, &rules).unwrap();
+///
+/// let copy: NBTValue = nbt::from_slice(&copy).unwrap();
+/// let stripped = CompoundBuilder::new().string("id", "minecraft:cow").compound("Paper", CompoundBuilder::new());
+/// assert_eq!(copy, CompoundBuilder::new().list("Entities", [stripped.build()]).build());
+/// ```
+pub fn copy_filtered<R: io::Read, W: io::Write>(
+    r: R,
+    w: W,
+    rules: &[(&str, CopyRule)],
+) -> error::Result<usize> {
+    // Lists remember how many elements they've had so far, to build the paths of the next one.
+    enum Open {
+        Compound,
+        List(usize),
+    }
+
+    let rule = |path: &NBTPath| {
+        let path = path.to_string();
+        rules
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, &path))
+            .map(|(_, rule)| rule)
+    };
+
+    let mut reader = NBTStreamReader::new(r);
+    let mut writer = Writer::<_, BigEndian>::new(w, Default::default());
+    let mut open: Vec<Open> = Vec::new();
+    let mut path = NBTPath::default();
+    let mut applied = 0;
+    while let Some(event) = reader.next_event()? {
+        let (name, kind) = match &event {
+            NBTEvent::CompoundEnd | NBTEvent::ListEnd => {
+                open.pop();
+                if open.is_empty() {
+                    writer.write_tag_header(NBTKind::End, None)?;
+                    continue;
+                }
+                if let NBTEvent::CompoundEnd = event {
+                    writer.write_tag_header(NBTKind::End, None)?;
+                }
+                path.0.pop();
+                continue;
+            }
+            NBTEvent::CompoundStart { name } => (name.clone(), NBTKind::Compound),
+            NBTEvent::ListStart { name, .. } => (name.clone(), NBTKind::List),
+            NBTEvent::Value { name, value } => (name.clone(), value.kind()),
+            NBTEvent::Array { name, array } => (name.clone(), array.kind()),
+        };
+
+        // The root compound.
+        let Some(parent) = open.last_mut() else {
+            open.push(Open::Compound);
+            header(&mut writer, kind, name)?;
+            continue;
+        };
+        let list_kind = match parent {
+            Open::Compound => {
+                path.0
+                    .push(PathSegment::Key(name.clone().unwrap_or_default()));
+                None
+            }
+            Open::List(next) => {
+                path.0.push(PathSegment::Index(*next));
+                *next += 1;
+                Some(kind)
+            }
+        };
+        let container = matches!(kind, NBTKind::Compound | NBTKind::List);
+
+        let (name, event) = match rule(&path) {
+            Some(CopyRule::Drop) => {
+                applied += 1;
+                if container {
+                    reader.skip_container()?;
+                }
+                path.0.pop();
+                continue;
+            }
+            Some(CopyRule::Replace(value)) => {
+                applied += 1;
+                if list_kind.is_some_and(|kind| kind != value.kind()) {
+                    return Err(Error::MismatchedTag(value.kind(), kind));
+                }
+                if container {
+                    reader.skip_container()?;
+                }
+                header(&mut writer, value.kind(), name)?;
+                writer.write_value(value)?;
+                path.0.pop();
+                continue;
+            }
+            Some(CopyRule::Rename(to)) if name.is_some() => {
+                applied += 1;
+                (Some(to.clone()), event)
+            }
+            _ => (name, event),
+        };
+
+        header(&mut writer, kind, name)?;
+        match event {
+            NBTEvent::CompoundStart { .. } => open.push(Open::Compound),
+            NBTEvent::ListStart {
+                element_kind,
+                length,
+                ..
+            } => {
+                // The length comes first, so dropped elements have to be counted up front.
+                let dropped = (0..length)
+                    .filter(|&i| {
+                        path.0.push(PathSegment::Index(i));
+                        let dropped = matches!(rule(&path), Some(CopyRule::Drop));
+                        path.0.pop();
+                        dropped
+                    })
+                    .count();
+                writer.write_tag_header(element_kind, None)?;
+                writer.write_length(length - dropped)?;
+                open.push(Open::List(0));
+            }
+            NBTEvent::Value { value, .. } => {
+                writer.write_value(&value)?;
+                path.0.pop();
+            }
+            NBTEvent::Array { array, .. } => {
+                copy_array(array, &mut writer, false)?;
+                path.0.pop();
+            }
+            NBTEvent::CompoundEnd | NBTEvent::ListEnd => unreachable!("handled above"),
+        }
+    }
+    Ok(applied)
+}
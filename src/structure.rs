@@ -0,0 +1,672 @@
+//! Structure files as written by structure blocks and used by data packs for generated features.
+use std::collections::{btree_map::Entry, BTreeMap};
+#[cfg(feature = "compression")]
+use std::{fmt::Write, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    block::BlockState,
+    chunk::{Chunk, PalettedContainer, Section, ALIGNED_PACKING},
+    error,
+    tag::NBTValue,
+};
+#[cfg(feature = "compression")]
+use crate::{error::Error, file::Compression, resource::ResourceLocation};
+
+#[test]
+fn test_structure_transforms() {
+    let stairs = |facing: &str, shape: &str| {
+        format!("minecraft:oak_stairs[facing={},shape={}]", facing, shape)
+            .parse::<BlockState>()
+            .unwrap()
+    };
+    let mut structure = StructureFile {
+        data_version: 3465,
+        size: [2, 1, 3],
+        palette: vec![stairs("north", "inner_left")],
+        palettes: Vec::new(),
+        blocks: vec![StructureBlock {
+            pos: [1, 0, 0],
+            state: 0,
+            nbt: None,
+        }],
+        entities: vec![StructureEntity {
+            pos: [1.5, 0.0, 0.25],
+            block_pos: [1, 0, 0],
+            nbt: None,
+        }],
+    };
+    let bytes = crate::to_bytes(&structure).unwrap();
+    assert_eq!(
+        crate::from_slice::<StructureFile>(&bytes).unwrap(),
+        structure
+    );
+
+    structure.rotate_90();
+    assert_eq!(structure.size, [3, 1, 2]);
+    assert_eq!(structure.blocks[0].pos, [2, 0, 1]);
+    assert_eq!(structure.entities[0].pos, [2.75, 0.0, 1.5]);
+    assert_eq!(structure.entities[0].block_pos, [2, 0, 1]);
+    assert_eq!(structure.palette, [stairs("east", "inner_left")]);
+
+    structure.mirror_x();
+    assert_eq!(structure.blocks[0].pos, [0, 0, 1]);
+    assert_eq!(structure.palette, [stairs("west", "inner_right")]);
+    structure.mirror_z();
+    assert_eq!(structure.blocks[0].pos, [0, 0, 0]);
+    assert_eq!(structure.entities[0].pos, [0.25, 0.0, 0.5]);
+
+    structure.translate([100, 64, -20]);
+    assert_eq!(structure.blocks[0].pos, [100, 64, -20]);
+    assert_eq!(structure.entities[0].pos, [100.25, 64.0, -19.5]);
+}
+
+#[test]
+fn test_paste_into() {
+    use crate::tag::CompoundBuilder;
+
+    let stone = BlockState::new("minecraft:stone");
+    let chest = BlockState::new("minecraft:chest");
+    let air = BlockState::new("minecraft:air");
+    let structure = StructureFile {
+        data_version: 3465,
+        size: [2, 2, 1],
+        palette: vec![stone.clone(), chest.clone()],
+        palettes: Vec::new(),
+        blocks: vec![
+            StructureBlock {
+                pos: [0, 0, 0],
+                state: 0,
+                nbt: None,
+            },
+            StructureBlock {
+                pos: [1, 1, 0],
+                state: 1,
+                nbt: Some(
+                    CompoundBuilder::new()
+                        .string("id", "minecraft:chest")
+                        .build(),
+                ),
+            },
+        ],
+        entities: vec![StructureEntity {
+            pos: [0.5, 1.0, 0.5],
+            block_pos: [0, 1, 0],
+            nbt: Some(
+                CompoundBuilder::new()
+                    .string("id", "minecraft:pig")
+                    .int_array("UUID", vec![1, 2, 3, 4])
+                    .long("UUIDMost", 1)
+                    .long("UUIDLeast", 2)
+                    .build(),
+            ),
+        }],
+    };
+    let mut chunk = Chunk {
+        data_version: 3465,
+        x: 1,
+        z: 0,
+        status: "minecraft:full".into(),
+        last_update: 0,
+        inhabited_time: 0,
+        sections: Vec::new(),
+        block_entities: Vec::new(),
+    };
+
+    let block_entity = |id: &str, [x, y, z]: [i32; 3]| {
+        CompoundBuilder::new()
+            .string("id", id)
+            .int("x", x)
+            .int("y", y)
+            .int("z", z)
+            .build()
+    };
+    let index = |[x, y, z]: [i32; 3]| (((y & 15) * 16 + (z & 15)) * 16 + (x & 15)) as usize;
+    chunk
+        .block_entities
+        .push(block_entity("minecraft:furnace", [17, 15, 4]));
+
+    // The structure straddles chunks 0 and 1, only its chest lands in this chunk.
+    let entities = structure.paste_into(&mut chunk, [15, 15, 4]).unwrap();
+    assert!(entities.is_empty());
+    assert_eq!(chunk.sections.len(), 1);
+    assert_eq!(chunk.sections[0].y, 1);
+    let blocks = chunk.sections[0].block_states.unpack(4096).unwrap();
+    assert_eq!(blocks[index([16, 16, 4])], chest);
+    assert_eq!(blocks.iter().filter(|&block| *block != air).count(), 1);
+
+    let entities = structure.paste_into(&mut chunk, [16, 14, 4]).unwrap();
+    assert_eq!(chunk.sections.len(), 2);
+    assert_eq!(chunk.sections[0].y, 0);
+    let blocks = chunk.sections[0].block_states.unpack(4096).unwrap();
+    assert_eq!(blocks[index([16, 14, 4])], stone);
+    assert_eq!(blocks[index([17, 15, 4])], chest);
+    // The second chest replaced the furnace.
+    assert_eq!(
+        chunk.block_entities,
+        [
+            block_entity("minecraft:chest", [16, 16, 4]),
+            block_entity("minecraft:chest", [17, 15, 4]),
+        ]
+    );
+    // The pig's UUIDs are dropped, so pasting it again doesn't duplicate them.
+    assert_eq!(
+        entities,
+        [CompoundBuilder::new()
+            .string("id", "minecraft:pig")
+            .list("Pos", [16.5, 15.0, 4.5].map(NBTValue::Double))
+            .build()]
+    );
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_paste_and_save() {
+    use std::io::Cursor;
+
+    use crate::{chunk::RawChunk, region::RegionFile, tag::CompoundBuilder};
+
+    let section = CompoundBuilder::new()
+        .byte("Y", 0)
+        .compound(
+            "block_states",
+            CompoundBuilder::new().list(
+                "palette",
+                [CompoundBuilder::new()
+                    .string("Name", "minecraft:air")
+                    .build()],
+            ),
+        )
+        .compound(
+            "biomes",
+            CompoundBuilder::new().list("palette", [NBTValue::String("minecraft:plains".into())]),
+        )
+        .byte_array("SkyLight", vec![-1; 2048])
+        .build();
+    let chunk = CompoundBuilder::new()
+        .int("DataVersion", 3465)
+        .int("xPos", 0)
+        .int("yPos", 0)
+        .int("zPos", 0)
+        .string("Status", "minecraft:full")
+        .long("LastUpdate", 0)
+        .long("InhabitedTime", 0)
+        .compound("Heightmaps", CompoundBuilder::new())
+        .list("sections", [section])
+        .build();
+    let region = crate::region::test_region(&[(0, 0, crate::to_bytes(&chunk).unwrap())]);
+    let mut region = RegionFile::new(Cursor::new(region)).unwrap();
+
+    let structure = StructureFile {
+        data_version: 3465,
+        size: [1, 2, 1],
+        palette: vec![
+            BlockState::new("minecraft:stone"),
+            BlockState::new("minecraft:chest"),
+        ],
+        palettes: Vec::new(),
+        blocks: vec![
+            StructureBlock {
+                pos: [0, 0, 0],
+                state: 0,
+                nbt: None,
+            },
+            StructureBlock {
+                pos: [0, 1, 0],
+                state: 1,
+                nbt: Some(
+                    CompoundBuilder::new()
+                        .string("id", "minecraft:chest")
+                        .build(),
+                ),
+            },
+        ],
+        entities: Vec::new(),
+    };
+    let mut raw = RawChunk::from_slice(&region.read_chunk(0, 0).unwrap().unwrap()).unwrap();
+    let mut chunk = raw.chunk().unwrap();
+    structure.paste_into(&mut chunk, [2, 15, 3]).unwrap();
+    raw.update(&chunk).unwrap();
+    region.write_chunk(0, 0, &raw.to_bytes().unwrap()).unwrap();
+
+    let saved = RawChunk::from_slice(&region.read_chunk(0, 0).unwrap().unwrap()).unwrap();
+    assert_eq!(saved.chunk().unwrap(), chunk);
+    assert!(saved.root().contains_key("Heightmaps"));
+    let NBTValue::List(sections) = &saved.root()["sections"] else {
+        panic!("sections is not a list");
+    };
+    assert_eq!(sections.len(), 2);
+    let NBTValue::Compound(first) = &sections[0] else {
+        panic!("section is not a compound");
+    };
+    assert!(first.contains_key("biomes") && first.contains_key("SkyLight"));
+    let NBTValue::List(block_entities) = &saved.root()["block_entities"] else {
+        panic!("block entities is not a list");
+    };
+    assert_eq!(block_entities.len(), 1);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_structure_pack() {
+    let structure = StructureFile {
+        data_version: 3465,
+        size: [1, 1, 1],
+        palette: vec![BlockState::new("minecraft:stone")],
+        palettes: Vec::new(),
+        blocks: vec![StructureBlock {
+            pos: [0, 0, 0],
+            state: 0,
+            nbt: None,
+        }],
+        entities: Vec::new(),
+    };
+    let mut pack = StructurePack {
+        pack_format: 15,
+        description: "Houses \"and\" more".into(),
+        structures: vec![
+            ("houses:small".parse().unwrap(), structure.clone()),
+            ("houses:tall/tower".parse().unwrap(), structure.clone()),
+        ],
+    };
+    let dir = tempfile::tempdir().unwrap();
+    pack.write(dir.path()).unwrap();
+
+    let mcmeta = fs::read_to_string(dir.path().join("pack.mcmeta")).unwrap();
+    assert!(mcmeta.contains(r#""pack_format": 15"#));
+    assert!(mcmeta.contains(r#""description": "Houses \"and\" more""#));
+    let path = dir.path().join("data/houses/structures/tall/tower.nbt");
+    let bytes = fs::read(&path).unwrap();
+    assert_eq!(Compression::detect(&bytes), Compression::Gzip);
+    let read: StructureFile = crate::from_file(&path).unwrap();
+    assert_eq!(read, structure);
+
+    // 1.21 renamed the directory.
+    pack.pack_format = 48;
+    pack.write(dir.path()).unwrap();
+    assert!(dir.path().join("data/houses/structure/small.nbt").exists());
+
+    pack.structures = vec![("houses:../escape".parse().unwrap(), structure)];
+    assert!(matches!(
+        pack.write(dir.path()),
+        Err(Error::InvalidResourceLocation(_))
+    ));
+}
+
+/// The contents of a structure `.nbt` file. Read and write it with [`from_file`](crate::from_file)
+/// and [`to_file`](crate::to_file) using gzip compression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructureFile {
+    #[serde(rename = "DataVersion")]
+    pub data_version: i32,
+    /// Extent along x, y and z in blocks.
+    pub size: [i32; 3],
+    /// Block states that `blocks` refer to by index.
+    #[serde(default)]
+    pub palette: Vec<BlockState>,
+    /// Alternative palettes, used instead of `palette` by structures such as shipwrecks that pick
+    /// one at random when placed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palettes: Vec<Vec<BlockState>>,
+    pub blocks: Vec<StructureBlock>,
+    pub entities: Vec<StructureEntity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructureBlock {
+    /// Position relative to the structure's origin.
+    pub pos: [i32; 3],
+    /// Index into the palette.
+    pub state: i32,
+    /// Block entity data, without its position.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbt: Option<NBTValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructureEntity {
+    /// Exact position relative to the structure's origin.
+    pub pos: [f64; 3],
+    /// The block the entity is in, relative to the structure's origin.
+    #[serde(rename = "blockPos")]
+    pub block_pos: [i32; 3],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbt: Option<NBTValue>,
+}
+
+impl StructureFile {
+    /// Rotates the structure a quarter turn clockwise, seen from above, around its origin corner
+    /// so that it keeps covering positive coordinates.
+    pub fn rotate_90(&mut self) {
+        let [size_x, size_y, size_z] = self.size;
+        self.transform(
+            Transform::Rotate90,
+            |[x, y, z]| [size_z - 1 - z, y, x],
+            |[x, y, z]| [size_z as f64 - z, y, x],
+        );
+        self.size = [size_z, size_y, size_x];
+    }
+
+    /// Mirrors the structure so that east and west swap places.
+    pub fn mirror_x(&mut self) {
+        let size_x = self.size[0];
+        self.transform(
+            Transform::MirrorX,
+            |[x, y, z]| [size_x - 1 - x, y, z],
+            |[x, y, z]| [size_x as f64 - x, y, z],
+        );
+    }
+
+    /// Mirrors the structure so that north and south swap places.
+    pub fn mirror_z(&mut self) {
+        let size_z = self.size[2];
+        self.transform(
+            Transform::MirrorZ,
+            |[x, y, z]| [x, y, size_z - 1 - z],
+            |[x, y, z]| [x, y, size_z as f64 - z],
+        );
+    }
+
+    /// Moves every block and entity by `offset`, for instance to world coordinates before pasting.
+    /// The size stays the same.
+    pub fn translate(&mut self, offset: [i32; 3]) {
+        let [dx, dy, dz] = offset;
+        for block in &mut self.blocks {
+            let [x, y, z] = block.pos;
+            block.pos = [x + dx, y + dy, z + dz];
+        }
+        for entity in &mut self.entities {
+            let [x, y, z] = entity.pos;
+            entity.pos = [x + dx as f64, y + dy as f64, z + dz as f64];
+            let [x, y, z] = entity.block_pos;
+            entity.block_pos = [x + dx, y + dy, z + dz];
+        }
+    }
+
+    /// Places the structure into `chunk` with its origin at the block `pos`, in world
+    /// coordinates. Only the part of the structure inside the chunk is placed: its blocks replace
+    /// the chunk's, merging into the palettes of the sections, which are created as air where
+    /// missing. Block entities at replaced positions are removed and those of the structure are
+    /// added at their new positions. Entities are kept apart from chunks since 1.17, so the
+    /// entities that land in the chunk are returned with their `Pos` moved and their UUID removed,
+    /// for storing in the chunk's entity region file. Blocks are taken from `palette`, or the first of `palettes`
+    /// when it is empty.
+    ///
+    /// To save the result, read the chunk with [`RawChunk::chunk`](crate::chunk::RawChunk::chunk)
+    /// and store it back with [`RawChunk::update`](crate::chunk::RawChunk::update), which keeps
+    /// the fields `Chunk` leaves out. The light saved with the chunk is out of date afterwards,
+    /// see [`RawChunk::strip_light`](crate::chunk::RawChunk::strip_light).
+    pub fn paste_into(&self, chunk: &mut Chunk, pos: [i32; 3]) -> error::Result<Vec<NBTValue>> {
+        let palette = match (self.palette.is_empty(), self.palettes.first()) {
+            (true, Some(palette)) => palette,
+            _ => &self.palette,
+        };
+        let (min_x, min_z) = (chunk.x * 16, chunk.z * 16);
+        let in_chunk = |[x, _, z]: [i32; 3]| {
+            (min_x..min_x + 16).contains(&x) && (min_z..min_z + 16).contains(&z)
+        };
+        let spanning = chunk.data_version < ALIGNED_PACKING;
+
+        // Unpacked block states of the sections being written to, by section y.
+        let mut sections: BTreeMap<i8, Vec<BlockState>> = BTreeMap::new();
+        let mut placed = Vec::new();
+        for block in &self.blocks {
+            let [x, y, z] = [0, 1, 2].map(|i| block.pos[i] + pos[i]);
+            let Some(state) = usize::try_from(block.state)
+                .ok()
+                .and_then(|state| palette.get(state))
+            else {
+                continue;
+            };
+            if !in_chunk([x, y, z]) {
+                continue;
+            }
+            let Ok(section_y) = i8::try_from(y >> 4) else {
+                continue;
+            };
+            let states = match sections.entry(section_y) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let existing = chunk.sections.iter().find(|section| section.y == section_y);
+                    entry.insert(match existing {
+                        Some(section) => section.block_states.unpack(4096)?,
+                        None => vec![BlockState::new("minecraft:air"); 4096],
+                    })
+                }
+            };
+            states[(((y & 15) * 16 + (z & 15)) * 16 + (x & 15)) as usize] = state.clone();
+            placed.push(([x, y, z], block.nbt.as_ref()));
+        }
+
+        for (y, states) in sections {
+            let block_states = PalettedContainer::pack(&states, 4, spanning);
+            match chunk.sections.iter_mut().find(|section| section.y == y) {
+                Some(section) => section.block_states = block_states,
+                None => {
+                    let at = chunk.sections.partition_point(|section| section.y < y);
+                    chunk.sections.insert(at, Section { y, block_states });
+                }
+            }
+        }
+
+        chunk.block_entities.retain(|entity| {
+            !block_entity_pos(entity).is_some_and(|at| placed.iter().any(|(pos, _)| *pos == at))
+        });
+        for ([x, y, z], nbt) in placed {
+            if let Some(NBTValue::Compound(nbt)) = nbt {
+                let mut nbt = nbt.clone();
+                for (key, value) in [("x", x), ("y", y), ("z", z)] {
+                    nbt.insert(key.into(), NBTValue::Int(value));
+                }
+                chunk.block_entities.push(NBTValue::Compound(nbt));
+            }
+        }
+
+        let mut entities = Vec::new();
+        for entity in &self.entities {
+            let Some(NBTValue::Compound(nbt)) = &entity.nbt else {
+                continue;
+            };
+            let moved = [0, 1, 2].map(|i| entity.pos[i] + pos[i] as f64);
+            if !in_chunk(moved.map(|coordinate| coordinate.floor() as i32)) {
+                continue;
+            }
+            let mut nbt = nbt.clone();
+            // The game gives placed entities new UUIDs, so pasting twice doesn't duplicate them.
+            for key in ["UUID", "UUIDMost", "UUIDLeast"] {
+                nbt.remove(key);
+            }
+            nbt.insert(
+                "Pos".into(),
+                NBTValue::List(moved.map(NBTValue::Double).into()),
+            );
+            entities.push(NBTValue::Compound(nbt));
+        }
+        Ok(entities)
+    }
+
+    fn transform(
+        &mut self,
+        transform: Transform,
+        block: impl Fn([i32; 3]) -> [i32; 3],
+        entity: impl Fn([f64; 3]) -> [f64; 3],
+    ) {
+        for structure_block in &mut self.blocks {
+            structure_block.pos = block(structure_block.pos);
+        }
+        for structure_entity in &mut self.entities {
+            structure_entity.pos = entity(structure_entity.pos);
+            structure_entity.block_pos = block(structure_entity.block_pos);
+        }
+        let palettes = self.palettes.iter_mut().flatten();
+        for state in self.palette.iter_mut().chain(palettes) {
+            transform.apply(&mut state.properties);
+        }
+    }
+}
+
+/// A data pack that holds nothing but structures, for the game to load by id, e.g. with
+/// `/place template` or from jigsaw pools.
+#[cfg(feature = "compression")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructurePack {
+    /// The `pack_format` of `pack.mcmeta`, which tells the game which versions the pack is for.
+    pub pack_format: i32,
+    pub description: String,
+    /// Structures by id. `mypack:houses/small` is stored at
+    /// `data/mypack/structures/houses/small.nbt`.
+    pub structures: Vec<(ResourceLocation, StructureFile)>,
+}
+
+/// First data pack format that keeps structures in a `structure` directory instead of
+/// `structures` (24w21a, during 1.21).
+#[cfg(feature = "compression")]
+pub const SINGULAR_DIRECTORIES: i32 = 45;
+
+#[cfg(feature = "compression")]
+impl StructurePack {
+    /// Writes the pack into the directory `dir`, creating it if needed: `pack.mcmeta` and every
+    /// structure gzip compressed in the directory its `pack_format` expects. Files already in
+    /// `dir` are left alone unless they are overwritten. Ids whose path would leave the
+    /// namespace's directory, through `.` or `..`, are reported as
+    /// [`Error::InvalidResourceLocation`].
+    pub fn write<P: AsRef<Path>>(&self, dir: P) -> error::Result<()> {
+        let dir = dir.as_ref();
+        let kind = if self.pack_format >= SINGULAR_DIRECTORIES {
+            "structure"
+        } else {
+            "structures"
+        };
+        for (id, _) in &self.structures {
+            if id
+                .path()
+                .split('/')
+                .any(|part| matches!(part, "" | "." | ".."))
+            {
+                return Err(Error::InvalidResourceLocation(id.to_string()));
+            }
+        }
+
+        fs::create_dir_all(dir)?;
+        let mut description = String::new();
+        for c in self.description.chars() {
+            match c {
+                '"' | '\\' => write!(description, "\\{}", c)?,
+                c if c.is_control() => write!(description, "\\u{:04x}", c as u32)?,
+                c => description.push(c),
+            }
+        }
+        fs::write(
+            dir.join("pack.mcmeta"),
+            format!(
+                "{{\n  \"pack\": {{\n    \"pack_format\": {},\n    \"description\": \"{}\"\n  }}\n}}\n",
+                self.pack_format, description
+            ),
+        )?;
+
+        for (id, structure) in &self.structures {
+            let path = dir
+                .join("data")
+                .join(id.namespace())
+                .join(kind)
+                .join(format!("{}.nbt", id.path()));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            crate::to_file(path, structure, Compression::Gzip)?;
+        }
+        Ok(())
+    }
+}
+
+// The `x`, `y` and `z` of a block entity.
+pub(crate) fn block_entity_pos(entity: &NBTValue) -> Option<[i32; 3]> {
+    let NBTValue::Compound(entity) = entity else {
+        return None;
+    };
+    let mut pos = [0; 3];
+    for (coordinate, key) in pos.iter_mut().zip(["x", "y", "z"]) {
+        match entity.get(key) {
+            Some(NBTValue::Int(value)) => *coordinate = *value,
+            _ => return None,
+        }
+    }
+    Some(pos)
+}
+
+#[derive(Clone, Copy)]
+enum Transform {
+    Rotate90,
+    MirrorX,
+    MirrorZ,
+}
+
+const HORIZONTAL: [&str; 4] = ["north", "east", "south", "west"];
+
+impl Transform {
+    fn direction(self, direction: &str) -> Option<&'static str> {
+        let index = HORIZONTAL.iter().position(|&d| d == direction)?;
+        Some(match self {
+            Transform::Rotate90 => HORIZONTAL[(index + 1) % 4],
+            Transform::MirrorX if index % 2 == 1 => HORIZONTAL[(index + 2) % 4],
+            Transform::MirrorZ if index % 2 == 0 => HORIZONTAL[(index + 2) % 4],
+            _ => HORIZONTAL[index],
+        })
+    }
+
+    // The 16 step `rotation` of signs, banners and heads, counting clockwise from south.
+    fn rotation(self, rotation: u8) -> u8 {
+        match self {
+            Transform::Rotate90 => (rotation + 4) % 16,
+            Transform::MirrorX => (16 - rotation) % 16,
+            Transform::MirrorZ => (24 - rotation) % 16,
+        }
+    }
+
+    /// Updates the block state properties that depend on the orientation of the block.
+    fn apply(self, properties: &mut BTreeMap<String, String>) {
+        // Fences, walls and the like name their connections after directions.
+        let connections: Vec<_> = HORIZONTAL
+            .iter()
+            .filter_map(|&d| properties.remove_entry(d))
+            .collect();
+        for (direction, value) in connections {
+            properties.insert(self.direction(&direction).unwrap().to_owned(), value);
+        }
+
+        for (key, value) in properties.iter_mut() {
+            let mapped = match key.as_str() {
+                "facing" => self.direction(value).map(str::to_owned),
+                // Jigsaws and crafters combine two directions, such as `north_up`.
+                "orientation" => value.split_once('_').map(|(front, top)| {
+                    let front = self.direction(front).unwrap_or(front);
+                    let top = self.direction(top).unwrap_or(top);
+                    format!("{}_{}", front, top)
+                }),
+                "axis" if matches!(self, Transform::Rotate90) => match value.as_str() {
+                    "x" => Some("z".to_owned()),
+                    "z" => Some("x".to_owned()),
+                    _ => None,
+                },
+                "rotation" => value
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|&rotation| rotation < 16)
+                    .map(|rotation| self.rotation(rotation).to_string()),
+                // Mirroring turns left into right, for stair corners, door hinges and double chests.
+                "shape" | "hinge" | "type" if !matches!(self, Transform::Rotate90) => {
+                    if value.contains("left") {
+                        Some(value.replace("left", "right"))
+                    } else {
+                        Some(value.replace("right", "left"))
+                    }
+                }
+                _ => None,
+            };
+            if let Some(mapped) = mapped {
+                *value = mapped;
+            }
+        }
+    }
+}
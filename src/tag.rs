@@ -1,11 +1,488 @@
-use std::collections::BTreeMap;
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+#[cfg(feature = "compression")]
+use std::path::Path;
 
-use serde::{Deserialize, Serialize};
+use byteorder::BigEndian;
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
+#[cfg(feature = "std")]
+use crate::stream::NBTStreamReader;
+use crate::{
+    error::{self, Error},
+    io,
+    kind::NBTKind,
+    ser::{BYTE_ARRAY_NAME, INT_ARRAY_NAME, LONG_ARRAY_NAME},
+    writer::{canonical_f32, canonical_f64, DelayedHeader, Writer},
+};
+
+#[cfg(feature = "std")]
+#[test]
+fn test_nbt_tag() {
+    let mut tag = NBTTag::new(None);
+    tag.insert("Name", NBTValue::String("Steve".into()));
+    tag.insert("Pos", NBTValue::List(vec![NBTValue::Double(0.5); 3]));
+    assert_eq!(
+        tag.insert("Name", NBTValue::String("Alex".into())),
+        Some(NBTValue::String("Steve".into()))
+    );
+
+    let mut bytes = Vec::new();
+    tag.to_writer(&mut bytes).unwrap();
+    assert_eq!(NBTTag::from_reader(bytes.as_slice()).unwrap(), tag);
+
+    tag.set_title("player");
+    assert_eq!(
+        tag.remove("Pos"),
+        Some(NBTValue::List(vec![NBTValue::Double(0.5); 3]))
+    );
+    #[cfg(feature = "compression")]
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("player.dat");
+        tag.save(&path, crate::file::Compression::Gzip).unwrap();
+        let loaded = NBTTag::load(&path).unwrap();
+        assert_eq!(loaded.title(), "player");
+        assert_eq!(loaded.get("Name"), Some(&NBTValue::String("Alex".into())));
+    }
+}
+
+#[test]
+fn test_value_kind() {
+    let value = NBTValue::List(vec![NBTValue::Int(1)]);
+    assert_eq!(value.kind(), NBTKind::List);
+    assert_eq!(u8::from(value.kind()), 9);
+    assert_eq!(NBTKind::try_from(9).unwrap(), NBTKind::List);
+    assert!(NBTKind::try_from(13).is_err());
+    assert_eq!(NBTKind::LongArray.to_string(), "LongArray");
+}
+
+#[test]
+fn test_walk() {
+    let mut level = NBTValue::Compound(CompoundMap::from_iter([
+        ("name".to_owned(), NBTValue::String("world".into())),
+        (
+            "spawn".to_owned(),
+            NBTValue::List(vec![NBTValue::Int(0), NBTValue::Int(64)]),
+        ),
+    ]));
+
+    let mut paths = Vec::new();
+    level.walk(|path, _| paths.push(path.to_string()));
+    // Compounds only iterate in key order without `fast-hash`.
+    paths.sort();
+    assert_eq!(paths, ["", "name", "spawn", "spawn[0]", "spawn[1]"]);
+
+    level.walk_mut(|_, value| {
+        if let NBTValue::Int(n) = value {
+            *n += 1;
+        }
+    });
+    let ints = level.find_all(|_, value| value.kind() == NBTKind::Int);
+    assert_eq!(ints.len(), 2);
+    assert_eq!(ints[1].0.to_string(), "spawn[1]");
+    assert_eq!(ints[1].1, &NBTValue::Int(65));
+}
+
+#[test]
+fn test_map_values_and_retain() {
+    let block = |name: &str| {
+        NBTValue::Compound(CompoundMap::from_iter([
+            ("Name".to_owned(), NBTValue::String(name.into())),
+            ("Paper.Origin".to_owned(), NBTValue::Int(0)),
+        ]))
+    };
+    let mut palette = NBTValue::List(vec![
+        block("minecraft:grass"),
+        block("minecraft:stone"),
+        block("minecraft:grass"),
+    ]);
+
+    let renamed = palette.map_values(|_, value| match value {
+        NBTValue::String(name) if name == "minecraft:grass" => {
+            Some(NBTValue::String("minecraft:short_grass".into()))
+        }
+        _ => None,
+    });
+    assert_eq!(renamed, 2);
+
+    let stripped =
+        palette.retain(|path, _| !path.key().is_some_and(|key| key.starts_with("Paper.")));
+    assert_eq!(stripped, 3);
+    assert_eq!(
+        palette,
+        NBTValue::List(vec![
+            NBTValue::Compound(CompoundMap::from_iter([(
+                "Name".to_owned(),
+                NBTValue::String("minecraft:short_grass".into())
+            )])),
+            NBTValue::Compound(CompoundMap::from_iter([(
+                "Name".to_owned(),
+                NBTValue::String("minecraft:stone".into())
+            )])),
+            NBTValue::Compound(CompoundMap::from_iter([(
+                "Name".to_owned(),
+                NBTValue::String("minecraft:short_grass".into())
+            )])),
+        ])
+    );
+}
+
+#[test]
+fn test_encoded_size() {
+    let player = NBTValue::Compound(CompoundMap::from_iter([
+        ("Health".to_owned(), NBTValue::Float(20.0)),
+        (
+            "Inventory".to_owned(),
+            NBTValue::List(vec![NBTValue::LongArray(vec![0; 16])]),
+        ),
+    ]));
+
+    // 1 + 2 + 6 + 4 for Health, 1 + 2 + 9 + 5 + 4 + 128 for Inventory and 1 for the End tag.
+    assert_eq!(player.encoded_size(), 163);
+    assert_eq!(
+        player.encoded_size_by_key().unwrap(),
+        [("Inventory", 149), ("Health", 13)]
+    );
+    assert!(NBTValue::Int(0).encoded_size_by_key().is_none());
+}
+
+#[test]
+fn test_find_matching() {
+    let chest = |x: i32| {
+        NBTValue::Compound(CompoundMap::from_iter([
+            ("id".to_owned(), NBTValue::String("minecraft:chest".into())),
+            ("x".to_owned(), NBTValue::Int(x)),
+        ]))
+    };
+    let chunk = NBTValue::Compound(CompoundMap::from_iter([(
+        "block_entities".to_owned(),
+        NBTValue::List(vec![chest(16), chest(-3)]),
+    )]));
+
+    let paths = |found: Vec<(NBTPath, &NBTValue)>| {
+        let mut paths: Vec<_> = found.iter().map(|(path, _)| path.to_string()).collect();
+        paths.sort();
+        paths
+    };
+    assert_eq!(
+        paths(chunk.find_matching("id", Some("*:chest"))),
+        ["block_entities[0].id", "block_entities[1].id"]
+    );
+    assert_eq!(
+        paths(chunk.find_matching("?", Some("-*"))),
+        ["block_entities[1].x"]
+    );
+    assert_eq!(
+        paths(chunk.find_matching("block_*", None)),
+        ["block_entities"]
+    );
+    assert!(chunk.find_matching("id", Some("chest")).is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_value_serde() {
+    let compound = |entries: &[(&str, NBTValue)]| {
+        NBTValue::Compound(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    };
+    let value = compound(&[
+        ("byte", NBTValue::Byte(-1)),
+        ("short", NBTValue::Short(300)),
+        ("int", NBTValue::Int(70000)),
+        ("long", NBTValue::Long(1 << 40)),
+        ("float", NBTValue::Float(0.5)),
+        ("double", NBTValue::Double(-2.25)),
+        ("string", NBTValue::String("hello".into())),
+        ("bytes", NBTValue::ByteArray(vec![1, -2, 3])),
+        ("ints", NBTValue::IntArray(vec![1, 2])),
+        ("longs", NBTValue::LongArray(vec![])),
+        (
+            "list",
+            NBTValue::List(vec![NBTValue::Byte(1), NBTValue::Byte(2)]),
+        ),
+        ("empty", NBTValue::List(vec![])),
+        (
+            "arrays",
+            NBTValue::List(vec![
+                NBTValue::IntArray(vec![4]),
+                NBTValue::IntArray(vec![]),
+            ]),
+        ),
+        (
+            "nested",
+            compound(&[("uuid", NBTValue::IntArray(vec![1, 2, 3, 4]))]),
+        ),
+    ]);
+
+    let mut document = Vec::new();
+    crate::stream::write_document(&mut document, "", &value).unwrap();
+    assert_eq!(crate::from_slice::<NBTValue>(&document).unwrap(), value);
+    assert_eq!(crate::to_bytes(&value).unwrap(), document);
+
+    // Self-describing formats keep the structure, and unsigned integers take the narrowest kind.
+    let json = compound(&[
+        ("byte", NBTValue::Byte(5)),
+        ("short", NBTValue::Short(300)),
+        ("int", NBTValue::Int(70000)),
+        ("long", NBTValue::Long(1 << 40)),
+        ("double", NBTValue::Double(-2.25)),
+        ("string", NBTValue::String("hello".into())),
+        (
+            "list",
+            NBTValue::List(vec![NBTValue::Byte(1), NBTValue::Byte(2)]),
+        ),
+        ("nested", compound(&[("empty", NBTValue::List(vec![]))])),
+    ]);
+    let text = serde_json::to_string(&json).unwrap();
+    assert_eq!(serde_json::from_str::<NBTValue>(&text).unwrap(), json);
+    assert!(serde_json::from_str::<NBTValue>("18446744073709551615").is_err());
+    assert!(serde_json::from_str::<NBTValue>("null").is_err());
+
+    // Values nested in typed structs keep their kinds too.
+    let item = crate::item::ItemStack {
+        id: "minecraft:player_head".parse().unwrap(),
+        count: 1,
+        tag: Some(compound(&[("Id", NBTValue::IntArray(vec![1, 2, 3, 4]))])),
+    };
+    let bytes = crate::to_bytes(&item).unwrap();
+    assert_eq!(
+        crate::from_slice::<crate::item::ItemStack>(&bytes).unwrap(),
+        item
+    );
+}
+
+#[test]
+fn test_compound_builder() {
+    let built = CompoundBuilder::new()
+        .boolean("Hardcore", false)
+        .string("LevelName", "world")
+        .list("ServerBrands", [NBTValue::String("vanilla".into())])
+        .int_array("UUID", [1, 2, 3, 4])
+        .compound("Version", CompoundBuilder::new().int("Id", 3465))
+        .build();
+
+    let mut version = CompoundMap::default();
+    version.insert("Id".to_owned(), NBTValue::Int(3465));
+    let mut expected = CompoundMap::default();
+    expected.insert("Hardcore".to_owned(), NBTValue::Byte(0));
+    expected.insert("LevelName".to_owned(), NBTValue::String("world".into()));
+    expected.insert(
+        "ServerBrands".to_owned(),
+        NBTValue::List(vec![NBTValue::String("vanilla".into())]),
+    );
+    expected.insert("UUID".to_owned(), NBTValue::IntArray(vec![1, 2, 3, 4]));
+    expected.insert("Version".to_owned(), NBTValue::Compound(version));
+    assert_eq!(built, NBTValue::Compound(expected));
+}
+
+#[test]
+fn test_hashable_nbt() {
+    use std::collections::HashSet;
+
+    let value = |x: f64| {
+        NBTValue::Compound(CompoundMap::from_iter([
+            ("id".to_owned(), NBTValue::String("minecraft:chest".into())),
+            ("x".to_owned(), NBTValue::Double(x)),
+        ]))
+    };
+
+    let mut seen = HashSet::new();
+    assert!(seen.insert(HashableNbt(value(f64::NAN))));
+    assert!(!seen.insert(HashableNbt(value(f64::NAN))));
+    // Bit-level comparison tells zero and negative zero apart.
+    assert!(seen.insert(HashableNbt(value(0.0))));
+    assert!(seen.insert(HashableNbt(value(-0.0))));
+    assert_eq!(seen.len(), 3);
+}
+
+#[test]
+fn test_approx_eq() {
+    let item = |slot: i8, id: &str| CompoundBuilder::new().byte("Slot", slot).string("id", id);
+    let player = |x: f64, items: [(i8, &str); 2]| {
+        CompoundBuilder::new()
+            .list("Pos", [NBTValue::Double(x), NBTValue::Double(64.0)])
+            .list("Inventory", items.map(|(slot, id)| item(slot, id).build()))
+            .build()
+    };
+
+    let a = player(0.5, [(0, "minecraft:stone"), (1, "minecraft:dirt")]);
+    let b = player(0.5 + 1e-12, [(1, "minecraft:dirt"), (0, "minecraft:stone")]);
+    assert!(!approx_eq(&a, &b, 1e-9));
+    let options = ApproxOptions {
+        epsilon: 1e-9,
+        unordered_lists: vec!["Inventory".into()],
+    };
+    assert!(approx_eq_with(&a, &b, &options));
+    assert!(!approx_eq_with(
+        &a,
+        &player(0.6, [(1, "minecraft:dirt"), (0, "minecraft:stone")]),
+        &options
+    ));
+    assert!(!approx_eq_with(
+        &a,
+        &player(0.5, [(0, "minecraft:dirt"), (1, "minecraft:dirt")]),
+        &options
+    ));
+    assert!(approx_eq(
+        &NBTValue::Float(f32::NAN),
+        &NBTValue::Float(f32::NAN),
+        0.0
+    ));
+    assert!(!approx_eq(
+        &NBTValue::Float(1.0),
+        &NBTValue::Double(1.0),
+        0.0
+    ));
+}
+
+#[test]
+fn test_sort_lists() {
+    let item = |slot: i8, id: &str| CompoundBuilder::new().byte("Slot", slot).string("id", id);
+    let mut player = CompoundBuilder::new()
+        .list(
+            "Inventory",
+            [
+                item(9, "minecraft:dirt").build(),
+                CompoundBuilder::new().string("id", "minecraft:air").build(),
+                item(-106, "minecraft:shield").build(),
+                item(0, "minecraft:stone").build(),
+            ],
+        )
+        .list("Pos", [NBTValue::Double(2.0), NBTValue::Double(1.0)])
+        .build();
+
+    assert_eq!(player.sort_lists(&[("Inventory", "Slot")]), 1);
+    let NBTValue::Compound(map) = &player else {
+        unreachable!()
+    };
+    let NBTValue::List(items) = &map["Inventory"] else {
+        unreachable!()
+    };
+    let ids: Vec<_> = items
+        .iter()
+        .map(|item| match item {
+            NBTValue::Compound(item) => item["id"].clone(),
+            _ => unreachable!(),
+        })
+        .collect();
+    let expected =
+        ["shield", "stone", "dirt", "air"].map(|id| NBTValue::String(format!("minecraft:{id}")));
+    assert_eq!(ids, expected);
+    // Lists that weren't asked for keep their order.
+    assert_eq!(
+        map["Pos"],
+        NBTValue::List(vec![NBTValue::Double(2.0), NBTValue::Double(1.0)])
+    );
+    assert!(!NBTValue::Int(1).sort_list_by_key("Slot"));
+}
+
+#[test]
+fn test_compound_iterators() {
+    let mut value = CompoundBuilder::new()
+        .int("b", 2)
+        .int("a", 1)
+        .string("c", "three")
+        .build();
+
+    let mut keys: Vec<_> = value.keys().cloned().collect();
+    #[cfg(not(feature = "fast-hash"))]
+    assert_eq!(keys, ["a", "b", "c"]);
+    keys.sort();
+    assert_eq!(keys, ["a", "b", "c"]);
+    assert_eq!(value.values().count(), 3);
+
+    for (_, entry) in &mut value {
+        if let NBTValue::Int(n) = entry {
+            *n *= 10;
+        }
+    }
+    let mut total = 0;
+    for (key, entry) in &value {
+        if let NBTValue::Int(n) = entry {
+            assert_ne!(key, "c");
+            total += n;
+        }
+    }
+    assert_eq!(total, 30);
+    let entries: CompoundMap = value.into_iter().collect();
+    assert_eq!(entries["b"], NBTValue::Int(20));
+
+    let list = NBTValue::List(vec![NBTValue::Int(1)]);
+    assert_eq!(list.iter().count(), 0);
+    assert_eq!(list.into_iter().size_hint(), (0, Some(0)));
+}
+
+// Whether all of `text` matches `pattern`, where `*` matches any run of characters and `?` any
+// single one. On a mismatch the last `*` is retried one character further along.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Map type behind [`NBTValue::Compound`]. A `BTreeMap` by default, or a hashbrown map with ahash
+/// when the `fast-hash` feature is enabled.
+///
+/// By default entries iterate, and are written, in the byte-wise order of their keys. With
+/// `fast-hash` the order is unspecified and can differ from one run to the next. Neither keeps
+/// the order entries had in the document they were read from.
+#[cfg(not(feature = "fast-hash"))]
+pub type CompoundMap = alloc::collections::BTreeMap<String, NBTValue>;
+
+/// Map type behind [`NBTValue::Compound`]. A `BTreeMap` by default, or a hashbrown map with ahash
+/// when the `fast-hash` feature is enabled.
+///
+/// By default entries iterate, and are written, in the byte-wise order of their keys. With
+/// `fast-hash` the order is unspecified and can differ from one run to the next. Neither keeps
+/// the order entries had in the document they were read from.
+#[cfg(feature = "fast-hash")]
+pub type CompoundMap = hashbrown::HashMap<String, NBTValue, ahash::RandomState>;
+
+/// A whole NBT document: the root compound along with its name, which is usually empty.
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct NBTTag {
     title: String,
-    payload: BTreeMap<String, NBTValue>,
+    payload: CompoundMap,
 }
 
 impl NBTTag {
@@ -20,9 +497,91 @@ impl NBTTag {
             payload: Default::default(),
         }
     }
+
+    /// A document named `title` holding `payload`.
+    pub fn with_payload<S: Into<String>>(title: S, payload: CompoundMap) -> Self {
+        Self {
+            title: title.into(),
+            payload,
+        }
+    }
+
+    /// Reads an uncompressed document from `r`.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: io::Read>(r: R) -> error::Result<Self> {
+        match NBTStreamReader::new(r).read_document()? {
+            (title, NBTValue::Compound(payload)) => Ok(Self { title, payload }),
+            _ => Err(Error::ExpectedRootCompound),
+        }
+    }
+
+    /// Writes the document to `w` without compression.
+    pub fn to_writer<W: io::Write>(&self, w: W) -> error::Result<()> {
+        Writer::<_, BigEndian>::new(w, Default::default()).write_root(&self.title, &self.payload)
+    }
+
+    /// Reads the file at `path`, detecting whether it is gzip, zlib or uncompressed.
+    #[cfg(feature = "compression")]
+    pub fn load<P: AsRef<Path>>(path: P) -> error::Result<Self> {
+        let (reader, _) = crate::file::open_decompressed(path.as_ref())?;
+        Self::from_reader(reader)
+    }
+
+    /// Writes the document to `path` with `compression`. Like
+    /// [`to_file_atomic`](crate::file::to_file_atomic), the file only replaces what is at `path`
+    /// once it has been written completely.
+    #[cfg(feature = "compression")]
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        path: P,
+        compression: crate::file::Compression,
+    ) -> error::Result<()> {
+        crate::file::write_file_atomic(path.as_ref(), compression, false, |w| self.to_writer(w))
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn set_title<S: Into<String>>(&mut self, title: S) {
+        self.title = title.into();
+    }
+
+    /// The entries of the root compound.
+    pub fn payload(&self) -> &CompoundMap {
+        &self.payload
+    }
+
+    pub fn payload_mut(&mut self) -> &mut CompoundMap {
+        &mut self.payload
+    }
+
+    pub fn into_payload(self) -> CompoundMap {
+        self.payload
+    }
+
+    /// The entry `key` of the root compound.
+    pub fn get(&self, key: &str) -> Option<&NBTValue> {
+        self.payload.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut NBTValue> {
+        self.payload.get_mut(key)
+    }
+
+    /// Sets the entry `key` of the root compound, returning the value it replaced.
+    pub fn insert<S: Into<String>>(&mut self, key: S, value: NBTValue) -> Option<NBTValue> {
+        self.payload.insert(key.into(), value)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<NBTValue> {
+        self.payload.remove(key)
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+/// A single tag's payload. Serializing it writes the tag it stands for, and deserializing it
+/// keeps the kind of every tag read, including the difference between arrays and lists.
+#[derive(Debug, PartialEq, Clone)]
 pub enum NBTValue {
     Byte(i8),
     Short(i16),
@@ -33,7 +592,922 @@ pub enum NBTValue {
     ByteArray(Vec<i8>),
     String(String),
     List(Vec<NBTValue>),
-    Compound(BTreeMap<String, NBTValue>),
+    Compound(CompoundMap),
     IntArray(Vec<i32>),
     LongArray(Vec<i64>),
 }
+
+impl NBTValue {
+    /// Names of the entries of a compound, in the order of [`CompoundMap`]. Other kinds of values
+    /// have none.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Values of the entries of a compound, in the order of [`CompoundMap`]. Other kinds of values
+    /// have none.
+    pub fn values(&self) -> impl Iterator<Item = &NBTValue> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Entries of a compound, in the order of [`CompoundMap`]. Other kinds of values have none,
+    /// lists included.
+    pub fn iter(&self) -> Iter<'_> {
+        match self {
+            NBTValue::Compound(entries) => Iter(Some(entries.iter())),
+            _ => Iter(None),
+        }
+    }
+
+    /// Like [`iter`](Self::iter), with mutable values.
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        match self {
+            NBTValue::Compound(entries) => IterMut(Some(entries.iter_mut())),
+            _ => IterMut(None),
+        }
+    }
+
+    /// The kind of tag this value is written as.
+    pub fn kind(&self) -> NBTKind {
+        match self {
+            NBTValue::Byte(_) => NBTKind::Byte,
+            NBTValue::Short(_) => NBTKind::Short,
+            NBTValue::Int(_) => NBTKind::Int,
+            NBTValue::Long(_) => NBTKind::Long,
+            NBTValue::Float(_) => NBTKind::Float,
+            NBTValue::Double(_) => NBTKind::Double,
+            NBTValue::ByteArray(_) => NBTKind::ByteArray,
+            NBTValue::String(_) => NBTKind::String,
+            NBTValue::List(_) => NBTKind::List,
+            NBTValue::Compound(_) => NBTKind::Compound,
+            NBTValue::IntArray(_) => NBTKind::IntArray,
+            NBTValue::LongArray(_) => NBTKind::LongArray,
+        }
+    }
+
+    /// Writes this value as a tag named `name`: its id, then the name, then the payload. For a
+    /// compound that is a whole document, the same one [`crate::stream::write_document`] writes.
+    pub fn write_to<W: io::Write>(&self, w: &mut W, name: &str) -> error::Result<()> {
+        let mut writer = Writer::<_, BigEndian>::new(w, Default::default());
+        writer.write_tag_header(self.kind(), Some(DelayedHeader::MapKey(name)))?;
+        writer.write_value(self)
+    }
+
+    /// Calls `visit` on this value and then on everything nested in it, depth-first and in the
+    /// iteration order of [`CompoundMap`], along with the path that leads to each value.
+    pub fn walk<F>(&self, mut visit: F)
+    where
+        F: FnMut(&NBTPath, &NBTValue),
+    {
+        self.walk_at(&mut NBTPath::default(), &mut visit);
+    }
+
+    fn walk_at<F>(&self, path: &mut NBTPath, visit: &mut F)
+    where
+        F: FnMut(&NBTPath, &NBTValue),
+    {
+        visit(path, self);
+        match self {
+            NBTValue::List(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    path.0.push(PathSegment::Index(i));
+                    value.walk_at(path, visit);
+                    path.0.pop();
+                }
+            }
+            NBTValue::Compound(map) => {
+                for (key, value) in map {
+                    path.0.push(PathSegment::Key(key.clone()));
+                    value.walk_at(path, visit);
+                    path.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Like [`NBTValue::walk`], but hands out mutable references. Children are visited after
+    /// `visit` returns, so values it replaces are walked in their new form.
+    pub fn walk_mut<F>(&mut self, mut visit: F)
+    where
+        F: FnMut(&NBTPath, &mut NBTValue),
+    {
+        self.walk_mut_at(&mut NBTPath::default(), &mut visit);
+    }
+
+    fn walk_mut_at<F>(&mut self, path: &mut NBTPath, visit: &mut F)
+    where
+        F: FnMut(&NBTPath, &mut NBTValue),
+    {
+        visit(path, self);
+        match self {
+            NBTValue::List(values) => {
+                for (i, value) in values.iter_mut().enumerate() {
+                    path.0.push(PathSegment::Index(i));
+                    value.walk_mut_at(path, visit);
+                    path.0.pop();
+                }
+            }
+            NBTValue::Compound(map) => {
+                for (key, value) in map {
+                    path.0.push(PathSegment::Key(key.clone()));
+                    value.walk_mut_at(path, visit);
+                    path.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Collects every value in the tree, this one included, that `predicate` accepts.
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<(NBTPath, &NBTValue)>
+    where
+        F: FnMut(&NBTPath, &NBTValue) -> bool,
+    {
+        let mut found = Vec::new();
+        self.find_at(&mut NBTPath::default(), &mut predicate, &mut found);
+        found
+    }
+
+    /// Finds the compound entries whose key matches the glob `key` and, if given, whose value
+    /// matches the glob `value`. In both, `*` matches any run of characters and `?` any single
+    /// character. Values are matched against the text of strings and the decimal form of numbers,
+    /// so compounds, lists and arrays only match when `value` is `None`.
+    pub fn find_matching(&self, key: &str, value: Option<&str>) -> Vec<(NBTPath, &NBTValue)> {
+        self.find_all(|path, found| {
+            let Some(found_key) = path.key() else {
+                return false;
+            };
+            if !glob_match(key, found_key) {
+                return false;
+            }
+            let Some(pattern) = value else {
+                return true;
+            };
+            match found {
+                NBTValue::String(string) => glob_match(pattern, string),
+                NBTValue::Byte(n) => glob_match(pattern, &n.to_string()),
+                NBTValue::Short(n) => glob_match(pattern, &n.to_string()),
+                NBTValue::Int(n) => glob_match(pattern, &n.to_string()),
+                NBTValue::Long(n) => glob_match(pattern, &n.to_string()),
+                NBTValue::Float(n) => glob_match(pattern, &n.to_string()),
+                NBTValue::Double(n) => glob_match(pattern, &n.to_string()),
+                _ => false,
+            }
+        })
+    }
+
+    // Separate from `walk_at` since the matches borrow from `self` rather than the callback.
+    fn find_at<'a, F>(
+        &'a self,
+        path: &mut NBTPath,
+        predicate: &mut F,
+        found: &mut Vec<(NBTPath, &'a NBTValue)>,
+    ) where
+        F: FnMut(&NBTPath, &NBTValue) -> bool,
+    {
+        if predicate(path, self) {
+            found.push((path.clone(), self));
+        }
+        match self {
+            NBTValue::List(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    path.0.push(PathSegment::Index(i));
+                    value.find_at(path, predicate, found);
+                    path.0.pop();
+                }
+            }
+            NBTValue::Compound(map) => {
+                for (key, value) in map {
+                    path.0.push(PathSegment::Key(key.clone()));
+                    value.find_at(path, predicate, found);
+                    path.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces every value for which `f` returns a replacement and returns how many were
+    /// replaced. Replacements are walked in turn, so `f` must not keep matching its own output.
+    pub fn map_values<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&NBTPath, &NBTValue) -> Option<NBTValue>,
+    {
+        let mut changed = 0;
+        self.walk_mut(|path, value| {
+            if let Some(replacement) = f(path, value) {
+                *value = replacement;
+                changed += 1;
+            }
+        });
+        changed
+    }
+
+    /// Removes every list element and compound entry nested in this value that `keep` rejects,
+    /// and returns how many were removed. Nothing inside a removed value is visited.
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(&NBTPath, &NBTValue) -> bool,
+    {
+        let mut removed = 0;
+        self.walk_mut(|path, value| {
+            let mut child_path = path.clone();
+            let mut keep_child = |segment, child: &NBTValue| {
+                child_path.0.push(segment);
+                let kept = keep(&child_path, child);
+                child_path.0.pop();
+                if !kept {
+                    removed += 1;
+                }
+                kept
+            };
+            match value {
+                NBTValue::List(values) => {
+                    let mut i = 0;
+                    values.retain(|child| {
+                        i += 1;
+                        keep_child(PathSegment::Index(i - 1), child)
+                    });
+                }
+                NBTValue::Compound(map) => {
+                    map.retain(|key, child| keep_child(PathSegment::Key(key.clone()), child))
+                }
+                _ => {}
+            }
+        });
+        removed
+    }
+
+    /// Number of bytes the payload of this value takes up when written, not counting the tag id
+    /// and name in front of it.
+    pub fn encoded_size(&self) -> usize {
+        match self {
+            NBTValue::Byte(_) => 1,
+            NBTValue::Short(_) => 2,
+            NBTValue::Int(_) | NBTValue::Float(_) => 4,
+            NBTValue::Long(_) | NBTValue::Double(_) => 8,
+            NBTValue::ByteArray(bytes) => 4 + bytes.len(),
+            NBTValue::String(string) => 2 + string.len(),
+            // Element kind and length, then the bare payloads.
+            NBTValue::List(values) => 5 + values.iter().map(NBTValue::encoded_size).sum::<usize>(),
+            // Named entries followed by the End tag.
+            NBTValue::Compound(map) => {
+                1 + map
+                    .iter()
+                    .map(|(key, value)| entry_size(key, value))
+                    .sum::<usize>()
+            }
+            NBTValue::IntArray(ints) => 4 + ints.len() * 4,
+            NBTValue::LongArray(longs) => 4 + longs.len() * 8,
+        }
+    }
+
+    /// For compounds, the encoded size of each entry including its tag id and name, largest
+    /// first. Returns `None` for every other kind of value.
+    pub fn encoded_size_by_key(&self) -> Option<Vec<(&str, usize)>> {
+        let NBTValue::Compound(map) = self else {
+            return None;
+        };
+        let mut sizes: Vec<_> = map
+            .iter()
+            .map(|(key, value)| (key.as_str(), entry_size(key, value)))
+            .collect();
+        sizes.sort_by_key(|&(_, size)| core::cmp::Reverse(size));
+        Some(sizes)
+    }
+
+    /// Replaces every NaN in the tree with the same quiet NaN and negative zero with zero, so
+    /// values that only differ in those bit patterns encode to the same bytes.
+    pub fn canonicalize_floats(&mut self) {
+        match self {
+            NBTValue::Float(n) => *n = canonical_f32(*n),
+            NBTValue::Double(n) => *n = canonical_f64(*n),
+            NBTValue::List(values) => values.iter_mut().for_each(NBTValue::canonicalize_floats),
+            NBTValue::Compound(map) => map.values_mut().for_each(NBTValue::canonicalize_floats),
+            _ => {}
+        }
+    }
+
+    /// Stably sorts this list of compounds by the value each has at `key`, e.g. `Slot` for an
+    /// inventory. Numbers compare by value whatever their kind and strings lexicographically.
+    /// Elements without the key go last. Returns `false` if this isn't a list.
+    pub fn sort_list_by_key(&mut self, key: &str) -> bool {
+        let NBTValue::List(values) = self else {
+            return false;
+        };
+        values.sort_by(|a, b| match (list_field(a, key), list_field(b, key)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        });
+        true
+    }
+
+    /// Sorts every list whose path matches one of `lists` with [`NBTValue::sort_list_by_key`],
+    /// so that diffs don't show entries the game merely reordered. Each entry is a path that may
+    /// contain `*` and `?` along with the key to sort by, e.g. `("Inventory", "Slot")`. Returns
+    /// how many lists were sorted.
+    ///
+    /// Sorting a block state palette this way leaves the packed indices pointing at the wrong
+    /// entries, so only do that on copies made for comparison.
+    pub fn sort_lists(&mut self, lists: &[(&str, &str)]) -> usize {
+        let mut sorted = 0;
+        self.walk_mut(|path, value| {
+            if !matches!(value, NBTValue::List(_)) {
+                return;
+            }
+            let path = path.to_string();
+            if let Some((_, key)) = lists.iter().find(|(pattern, _)| glob_match(pattern, &path)) {
+                value.sort_list_by_key(key);
+                sorted += 1;
+            }
+        });
+        sorted
+    }
+}
+
+// What lists are sorted by: integers exactly, then floats and strings. Numbers of every kind
+// compare with each other, and anything else ties.
+#[derive(PartialEq, PartialOrd)]
+enum SortKey<'a> {
+    Integer(i64),
+    Float(f64),
+    String(&'a str),
+    Other,
+}
+
+impl SortKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SortKey::Integer(a), SortKey::Float(b)) => (*a as f64).total_cmp(b),
+            (SortKey::Float(a), SortKey::Integer(b)) => a.total_cmp(&(*b as f64)),
+            (SortKey::Float(a), SortKey::Float(b)) => a.total_cmp(b),
+            (a, b) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+fn list_field<'a>(element: &'a NBTValue, key: &str) -> Option<SortKey<'a>> {
+    match element {
+        NBTValue::Compound(map) => map.get(key).map(sort_key),
+        _ => None,
+    }
+}
+
+fn sort_key(value: &NBTValue) -> SortKey<'_> {
+    match value {
+        NBTValue::Byte(n) => SortKey::Integer(*n as i64),
+        NBTValue::Short(n) => SortKey::Integer(*n as i64),
+        NBTValue::Int(n) => SortKey::Integer(*n as i64),
+        NBTValue::Long(n) => SortKey::Integer(*n),
+        NBTValue::Float(n) => SortKey::Float(*n as f64),
+        NBTValue::Double(n) => SortKey::Float(*n),
+        NBTValue::String(string) => SortKey::String(string),
+        _ => SortKey::Other,
+    }
+}
+
+// Tag id, length-prefixed name and payload of a compound entry.
+fn entry_size(key: &str, value: &NBTValue) -> usize {
+    1 + 2 + key.len() + value.encoded_size()
+}
+
+/// Name the deserializer is asked for when reading an [`NBTValue`], so it can hand arrays over
+/// in a form that tells them apart from lists.
+pub(crate) const VALUE_NAME: &str = "__nbt_value__";
+
+impl Serialize for NBTValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            NBTValue::Byte(n) => serializer.serialize_i8(*n),
+            NBTValue::Short(n) => serializer.serialize_i16(*n),
+            NBTValue::Int(n) => serializer.serialize_i32(*n),
+            NBTValue::Long(n) => serializer.serialize_i64(*n),
+            NBTValue::Float(n) => serializer.serialize_f32(*n),
+            NBTValue::Double(n) => serializer.serialize_f64(*n),
+            NBTValue::ByteArray(bytes) => crate::byte_array(bytes, serializer),
+            NBTValue::String(string) => serializer.serialize_str(string),
+            NBTValue::List(elements) => serializer.collect_seq(elements),
+            NBTValue::Compound(entries) => serializer.collect_map(entries),
+            NBTValue::IntArray(ints) => crate::int_array(ints, serializer),
+            NBTValue::LongArray(longs) => crate::long_array(longs, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for NBTValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(VALUE_NAME, NBTValueVisitor)
+    }
+}
+
+struct NBTValueVisitor;
+
+impl<'de> Visitor<'de> for NBTValueVisitor {
+    type Value = NBTValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an NBT tag")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<NBTValue, E> {
+        Ok(NBTValue::Byte(v as i8))
+    }
+
+    fn visit_i8<E: de::Error>(self, v: i8) -> Result<NBTValue, E> {
+        Ok(NBTValue::Byte(v))
+    }
+
+    fn visit_i16<E: de::Error>(self, v: i16) -> Result<NBTValue, E> {
+        Ok(NBTValue::Short(v))
+    }
+
+    fn visit_i32<E: de::Error>(self, v: i32) -> Result<NBTValue, E> {
+        Ok(NBTValue::Int(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<NBTValue, E> {
+        Ok(NBTValue::Long(v))
+    }
+
+    // Only other formats produce unsigned integers, which have no tag of their own. They become
+    // the narrowest kind that holds them. `visit_u8`, `visit_u16` and `visit_u32` forward here.
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<NBTValue, E> {
+        if let Ok(v) = i8::try_from(v) {
+            Ok(NBTValue::Byte(v))
+        } else if let Ok(v) = i16::try_from(v) {
+            Ok(NBTValue::Short(v))
+        } else if let Ok(v) = i32::try_from(v) {
+            Ok(NBTValue::Int(v))
+        } else if let Ok(v) = i64::try_from(v) {
+            Ok(NBTValue::Long(v))
+        } else {
+            Err(E::invalid_value(de::Unexpected::Unsigned(v), &self))
+        }
+    }
+
+    fn visit_f32<E: de::Error>(self, v: f32) -> Result<NBTValue, E> {
+        Ok(NBTValue::Float(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<NBTValue, E> {
+        Ok(NBTValue::Double(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<NBTValue, E> {
+        Ok(NBTValue::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<NBTValue, E> {
+        Ok(NBTValue::String(v))
+    }
+
+    // A present optional value of another format. Null and unit have no tag and are rejected.
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<NBTValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<NBTValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<NBTValue, A::Error> {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1024));
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+        Ok(NBTValue::List(elements))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<NBTValue, A::Error> {
+        let mut entries = CompoundMap::default();
+        let Some(key) = map.next_key::<String>()? else {
+            return Ok(NBTValue::Compound(entries));
+        };
+        // Arrays arrive as a single entry named after the kind of array.
+        match key.as_str() {
+            BYTE_ARRAY_NAME => return Ok(NBTValue::ByteArray(map.next_value()?)),
+            INT_ARRAY_NAME => return Ok(NBTValue::IntArray(map.next_value()?)),
+            LONG_ARRAY_NAME => return Ok(NBTValue::LongArray(map.next_value()?)),
+            _ => {}
+        }
+        entries.insert(key, map.next_value()?);
+        while let Some((key, value)) = map.next_entry()? {
+            entries.insert(key, value);
+        }
+        Ok(NBTValue::Compound(entries))
+    }
+}
+
+/// Iterator over the entries of a compound, see [`NBTValue::iter`].
+#[derive(Debug)]
+pub struct Iter<'a>(Option<<&'a CompoundMap as IntoIterator>::IntoIter>);
+
+/// Iterator over the entries of a compound with mutable values, see [`NBTValue::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'a>(Option<<&'a mut CompoundMap as IntoIterator>::IntoIter>);
+
+/// Iterator that moves the entries out of a compound, see [`NBTValue::into_iter`].
+#[derive(Debug)]
+pub struct IntoIter(Option<<CompoundMap as IntoIterator>::IntoIter>);
+
+macro_rules! entries_iterator {
+    ($name:ty, $item:ty) => {
+        impl<'a> Iterator for $name {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.as_mut()?.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.as_ref().map_or((0, Some(0)), Iterator::size_hint)
+            }
+        }
+    };
+}
+
+entries_iterator!(Iter<'a>, (&'a String, &'a NBTValue));
+entries_iterator!(IterMut<'a>, (&'a String, &'a mut NBTValue));
+entries_iterator!(IntoIter, (String, NBTValue));
+
+/// Moves out the entries of a compound, in the order of [`CompoundMap`]. Other kinds of values
+/// have none.
+impl IntoIterator for NBTValue {
+    type Item = (String, NBTValue);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        match self {
+            NBTValue::Compound(entries) => IntoIter(Some(entries.into_iter())),
+            _ => IntoIter(None),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a NBTValue {
+    type Item = (&'a String, &'a NBTValue);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut NBTValue {
+    type Item = (&'a String, &'a mut NBTValue);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+/// Builds an [`NBTValue::Compound`] one entry at a time. Entries added under a key that is already
+/// present replace it.
+///
+/// ```
+/// use nbt::{CompoundBuilder, NBTValue};
+///
+/// let level = CompoundBuilder::new()
+///     .byte("Hardcore", 0)
+///     .string("LevelName", "world")
+///     .list("ServerBrands", [NBTValue::String("vanilla".into())])
+///     .compound("GameRules", CompoundBuilder::new().string("keepInventory", "true"))
+///     .build();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CompoundBuilder {
+    entries: CompoundMap,
+}
+
+impl CompoundBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `value` as it is, for entries of a kind without a method of their own.
+    pub fn value<K: Into<String>>(mut self, key: K, value: NBTValue) -> Self {
+        self.entries.insert(key.into(), value);
+        self
+    }
+
+    pub fn byte<K: Into<String>>(self, key: K, value: i8) -> Self {
+        self.value(key, NBTValue::Byte(value))
+    }
+
+    /// Adds a byte that is 1 for `true` and 0 for `false`, the way the game stores flags.
+    pub fn boolean<K: Into<String>>(self, key: K, value: bool) -> Self {
+        self.value(key, NBTValue::Byte(value as i8))
+    }
+
+    pub fn short<K: Into<String>>(self, key: K, value: i16) -> Self {
+        self.value(key, NBTValue::Short(value))
+    }
+
+    pub fn int<K: Into<String>>(self, key: K, value: i32) -> Self {
+        self.value(key, NBTValue::Int(value))
+    }
+
+    pub fn long<K: Into<String>>(self, key: K, value: i64) -> Self {
+        self.value(key, NBTValue::Long(value))
+    }
+
+    pub fn float<K: Into<String>>(self, key: K, value: f32) -> Self {
+        self.value(key, NBTValue::Float(value))
+    }
+
+    pub fn double<K: Into<String>>(self, key: K, value: f64) -> Self {
+        self.value(key, NBTValue::Double(value))
+    }
+
+    pub fn string<K: Into<String>, V: Into<String>>(self, key: K, value: V) -> Self {
+        self.value(key, NBTValue::String(value.into()))
+    }
+
+    pub fn byte_array<K: Into<String>, V: Into<Vec<i8>>>(self, key: K, value: V) -> Self {
+        self.value(key, NBTValue::ByteArray(value.into()))
+    }
+
+    pub fn int_array<K: Into<String>, V: Into<Vec<i32>>>(self, key: K, value: V) -> Self {
+        self.value(key, NBTValue::IntArray(value.into()))
+    }
+
+    pub fn long_array<K: Into<String>, V: Into<Vec<i64>>>(self, key: K, value: V) -> Self {
+        self.value(key, NBTValue::LongArray(value.into()))
+    }
+
+    /// Adds a list of `elements`, which have to all be of the same kind for the compound to be
+    /// written.
+    pub fn list<K, I>(self, key: K, elements: I) -> Self
+    where
+        K: Into<String>,
+        I: IntoIterator<Item = NBTValue>,
+    {
+        self.value(key, NBTValue::List(elements.into_iter().collect()))
+    }
+
+    pub fn compound<K: Into<String>>(self, key: K, compound: CompoundBuilder) -> Self {
+        self.value(key, compound.build())
+    }
+
+    pub fn build(self) -> NBTValue {
+        NBTValue::Compound(self.entries)
+    }
+}
+
+impl From<CompoundBuilder> for NBTValue {
+    fn from(builder: CompoundBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// One step into a list or compound.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Location of a value inside an [`NBTValue`] tree. Displays compound keys joined with `.` and
+/// list indices as `[i]`, e.g. `Data.Player.Inventory[3]`.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
+pub struct NBTPath(pub Vec<PathSegment>);
+
+impl NBTPath {
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Name of the compound entry this path ends in, if it ends in one.
+    pub fn key(&self) -> Option<&str> {
+        match self.0.last() {
+            Some(PathSegment::Key(key)) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl Display for NBTPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => f.write_str(key)?,
+                PathSegment::Key(key) => write!(f, ".{}", key)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the format [`NBTPath`] displays as. Keys can't contain `.` or `[`.
+impl FromStr for NBTPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> error::Result<Self> {
+        let invalid = || Error::InvalidPath(s.to_string());
+        let mut segments = Vec::new();
+        for part in s.split('.') {
+            let (key, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+            if key.is_empty() {
+                return Err(invalid());
+            }
+            segments.push(PathSegment::Key(key.to_string()));
+            while !indices.is_empty() {
+                let (index, rest) = indices[1..].split_once(']').ok_or_else(invalid)?;
+                segments.push(PathSegment::Index(index.parse().map_err(|_| invalid())?));
+                indices = rest;
+                if !indices.is_empty() && !indices.starts_with('[') {
+                    return Err(invalid());
+                }
+            }
+        }
+        Ok(NBTPath(segments))
+    }
+}
+
+/// Wrapper that gives [`NBTValue`] `Eq` and `Hash` by comparing floats by their bits, so values
+/// can be used as keys in sets and maps. Identical NaNs are equal and `0.0` differs from `-0.0`;
+/// call [`NBTValue::canonicalize_floats`] first to treat those as the same.
+#[derive(Debug, Clone)]
+pub struct HashableNbt(pub NBTValue);
+
+impl PartialEq for HashableNbt {
+    fn eq(&self, other: &Self) -> bool {
+        bits_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for HashableNbt {}
+
+impl Hash for HashableNbt {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_bits(&self.0, state)
+    }
+}
+
+fn bits_eq(a: &NBTValue, b: &NBTValue) -> bool {
+    match (a, b) {
+        (NBTValue::Float(a), NBTValue::Float(b)) => a.to_bits() == b.to_bits(),
+        (NBTValue::Double(a), NBTValue::Double(b)) => a.to_bits() == b.to_bits(),
+        (NBTValue::List(a), NBTValue::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| bits_eq(a, b))
+        }
+        (NBTValue::Compound(a), NBTValue::Compound(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, va)| b.get(key).is_some_and(|vb| bits_eq(va, vb)))
+        }
+        // Everything else has no floats inside and compares exactly already.
+        (a, b) => a == b,
+    }
+}
+
+fn hash_bits<H: Hasher>(value: &NBTValue, state: &mut H) {
+    core::mem::discriminant(value).hash(state);
+    match value {
+        NBTValue::Byte(n) => n.hash(state),
+        NBTValue::Short(n) => n.hash(state),
+        NBTValue::Int(n) => n.hash(state),
+        NBTValue::Long(n) => n.hash(state),
+        NBTValue::Float(n) => n.to_bits().hash(state),
+        NBTValue::Double(n) => n.to_bits().hash(state),
+        NBTValue::ByteArray(bytes) => bytes.hash(state),
+        NBTValue::String(string) => string.hash(state),
+        NBTValue::List(values) => {
+            values.len().hash(state);
+            values.iter().for_each(|value| hash_bits(value, state));
+        }
+        NBTValue::Compound(map) => {
+            // Entries are hashed on their own and summed so the result doesn't depend on the
+            // order the map iterates in.
+            map.len().hash(state);
+            let entries = map.iter().fold(0u64, |sum, (key, value)| {
+                let mut entry = EntryHasher::default();
+                key.hash(&mut entry);
+                hash_bits(value, &mut entry);
+                sum.wrapping_add(entry.finish())
+            });
+            entries.hash(state);
+        }
+        NBTValue::IntArray(ints) => ints.hash(state),
+        NBTValue::LongArray(longs) => longs.hash(state),
+    }
+}
+
+// FNV-1a, which hashes compound entries on their own before they are summed. Unlike
+// `DefaultHasher` it doesn't need `std`.
+struct EntryHasher(u64);
+
+impl Default for EntryHasher {
+    fn default() -> Self {
+        EntryHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for EntryHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// How [`approx_eq_with`] compares two trees.
+#[derive(Debug, Clone, Default)]
+pub struct ApproxOptions {
+    /// Largest difference allowed between two floats or doubles.
+    pub epsilon: f64,
+    /// Lists whose order doesn't matter, as [`NBTPath`]s that may contain `*` and `?`, e.g.
+    /// `Inventory` or `sections[*].block_states.palette`.
+    pub unordered_lists: Vec<String>,
+}
+
+/// Compares two trees like `==`, except that floats and doubles only have to be within `epsilon`
+/// of each other. NaNs are equal to NaNs. Compound keys are never ordered, so their order
+/// doesn't matter either way.
+///
+/// ```
+/// use nbt::{CompoundBuilder, approx_eq};
+///
+/// let a = CompoundBuilder::new().double("x", 0.1 + 0.2).build();
+/// let b = CompoundBuilder::new().double("x", 0.3).build();
+/// assert!(a != b);
+/// assert!(approx_eq(&a, &b, 1e-9));
+/// ```
+pub fn approx_eq(a: &NBTValue, b: &NBTValue, epsilon: f64) -> bool {
+    approx_eq_with(
+        a,
+        b,
+        &ApproxOptions {
+            epsilon,
+            ..Default::default()
+        },
+    )
+}
+
+/// Like [`approx_eq`], but can also treat the lists at [`ApproxOptions::unordered_lists`] as
+/// multisets: each element has to match a different element of the other list, in any order.
+/// Elements are paired greedily, so with a large epsilon a list can be reported as different
+/// when another pairing would have matched.
+pub fn approx_eq_with(a: &NBTValue, b: &NBTValue, options: &ApproxOptions) -> bool {
+    approx_eq_at(a, b, options, &mut NBTPath::default())
+}
+
+fn approx_eq_at(a: &NBTValue, b: &NBTValue, options: &ApproxOptions, path: &mut NBTPath) -> bool {
+    let close = |a: f64, b: f64| (a.is_nan() && b.is_nan()) || (a - b).abs() <= options.epsilon;
+    match (a, b) {
+        (NBTValue::Float(a), NBTValue::Float(b)) => close(*a as f64, *b as f64),
+        (NBTValue::Double(a), NBTValue::Double(b)) => close(*a, *b),
+        (NBTValue::List(a), NBTValue::List(b)) => {
+            if a.len() != b.len() {
+                return false;
+            }
+            let unordered = !options.unordered_lists.is_empty() && {
+                let path = path.to_string();
+                options
+                    .unordered_lists
+                    .iter()
+                    .any(|pattern| glob_match(pattern, &path))
+            };
+            if !unordered {
+                return a.iter().zip(b).enumerate().all(|(i, (a, b))| {
+                    path.0.push(PathSegment::Index(i));
+                    let eq = approx_eq_at(a, b, options, path);
+                    path.0.pop();
+                    eq
+                });
+            }
+            let mut unmatched: Vec<&NBTValue> = b.iter().collect();
+            a.iter().enumerate().all(|(i, a)| {
+                path.0.push(PathSegment::Index(i));
+                let found = unmatched
+                    .iter()
+                    .position(|b| approx_eq_at(a, b, options, path));
+                path.0.pop();
+                found.map(|found| unmatched.swap_remove(found)).is_some()
+            })
+        }
+        (NBTValue::Compound(a), NBTValue::Compound(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, va)| {
+                    b.get(key).is_some_and(|vb| {
+                        path.0.push(PathSegment::Key(key.clone()));
+                        let eq = approx_eq_at(va, vb, options, path);
+                        path.0.pop();
+                        eq
+                    })
+                })
+        }
+        // Everything else has no floats inside and compares exactly.
+        (a, b) => a == b,
+    }
+}
@@ -0,0 +1,142 @@
+//! Helpers for testing that types read and write NBT without changing it, along with a few
+//! fixture files to run them on. Enabled by the `test-util` feature.
+use std::fmt::{Debug, Write};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::tag::NBTValue;
+
+#[test]
+fn test_fixtures_round_trip() {
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    struct Server {
+        #[serde(rename = "acceptTextures", skip_serializing_if = "Option::is_none")]
+        accept_textures: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        icon: Option<String>,
+        ip: String,
+        name: String,
+    }
+
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    struct Servers {
+        servers: Vec<Server>,
+    }
+
+    assert_round_trip::<Servers>(SERVERS_DAT);
+    assert_value_round_trip(SERVERS_DAT);
+    assert_value_round_trip(LEVEL_DAT);
+    #[cfg(feature = "compression")]
+    {
+        let mut region = crate::region::RegionFile::new(std::io::Cursor::new(REGION)).unwrap();
+        assert_value_round_trip(&region.read_chunk(0, 0).unwrap().unwrap());
+    }
+
+    let diff = hexdump_diff(
+        &[0; 20],
+        &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    );
+    assert!(diff.contains("first difference at 0x11"), "{}", diff);
+}
+
+/// The uncompressed contents of a `servers.dat` with two servers, one of them with every optional
+/// field set.
+pub const SERVERS_DAT: &[u8] = include_bytes!("../fixtures/servers.dat");
+
+/// The uncompressed contents of a 1.20.1 `level.dat`, trimmed down to the commonly used fields.
+pub const LEVEL_DAT: &[u8] = include_bytes!("../fixtures/level.dat");
+
+/// An Anvil region file holding a single uncompressed 1.20.1 chunk at 0, 0.
+pub const REGION: &[u8] = include_bytes!("../fixtures/r.0.0.mca");
+
+/// Asserts that reading `bytes` as a `T` and writing it again gives back the same bytes. Fields
+/// have to be declared in the order the document has them, since that is the order they are
+/// written in.
+#[track_caller]
+pub fn assert_round_trip<T>(bytes: &[u8]) -> T
+where
+    T: Serialize + DeserializeOwned + Debug,
+{
+    let value: T = match crate::from_slice(bytes) {
+        Ok(value) => value,
+        Err(err) => panic!("could not read the document: {}", err),
+    };
+    let written = match crate::to_bytes(&value) {
+        Ok(written) => written,
+        Err(err) => panic!("could not write {:?}: {}", value, err),
+    };
+    if written != bytes {
+        panic!(
+            "writing {:?} changed the document\n{}",
+            value,
+            hexdump_diff(bytes, &written)
+        );
+    }
+    value
+}
+
+/// Asserts that reading `bytes` as an [`NBTValue`] and writing it again gives back the same bytes.
+/// The compounds in the document have to list their entries in the order
+/// [`CompoundMap`](crate::CompoundMap) iterates them, which is sorted by key. With the `fast-hash`
+/// feature that order is arbitrary, so the value read back is compared instead.
+#[track_caller]
+pub fn assert_value_round_trip(bytes: &[u8]) -> NBTValue {
+    let value: NBTValue = match crate::from_slice(bytes) {
+        Ok(value) => value,
+        Err(err) => panic!("could not read the document: {}", err),
+    };
+    let written = match crate::to_bytes(&value) {
+        Ok(written) => written,
+        Err(err) => panic!("could not write the document back: {}", err),
+    };
+    if cfg!(feature = "fast-hash") {
+        let reread: NBTValue = crate::from_slice(&written).expect("written document is readable");
+        assert_eq!(reread, value, "writing the document changed it");
+    } else if written != bytes {
+        panic!(
+            "writing the document changed it\n{}",
+            hexdump_diff(bytes, &written)
+        );
+    }
+    value
+}
+
+/// Lines of both inputs around their first difference, 16 bytes to a line, with the differing
+/// bytes of each line marked below it.
+pub fn hexdump_diff(expected: &[u8], actual: &[u8]) -> String {
+    const CONTEXT_LINES: usize = 2;
+
+    let first = expected
+        .iter()
+        .zip(actual)
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+    let mut out = format!(
+        "expected {} bytes, got {}, first difference at {:#x}\n",
+        expected.len(),
+        actual.len(),
+        first
+    );
+    let start = (first / 16).saturating_sub(CONTEXT_LINES) * 16;
+    let end = (first / 16 + CONTEXT_LINES + 1) * 16;
+    for (label, bytes, other) in [("expected", expected, actual), ("actual", actual, expected)] {
+        let _ = writeln!(out, "{}:", label);
+        for line in (start..end.min(bytes.len())).step_by(16) {
+            let chunk = &bytes[line..(line + 16).min(bytes.len())];
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let marks: String = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| match other.get(line + i) {
+                    Some(other) if other == byte => "   ",
+                    _ => "^^ ",
+                })
+                .collect();
+            let _ = writeln!(out, "{:08x}  {}", line, hex.join(" "));
+            if marks.contains('^') {
+                let _ = writeln!(out, "          {}", marks.trim_end());
+            }
+        }
+    }
+    out
+}
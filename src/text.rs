@@ -0,0 +1,248 @@
+//! Chat components, the JSON text stored in string tags such as `CustomName`, sign lines and item
+//! lore. Enabled by the `text` feature.
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[test]
+fn test_text_component() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Named {
+        #[serde(rename = "CustomName", with = "option_as_json", default)]
+        custom_name: Option<TextComponent>,
+    }
+
+    let named = Named {
+        custom_name: Some(TextComponent::Text {
+            text: "Bob".into(),
+            style: Style {
+                color: Some("gold".into()),
+                bold: Some(true),
+                ..Default::default()
+            },
+            extra: vec![TextComponent::Translate {
+                key: "entity.minecraft.villager".into(),
+                with: Vec::new(),
+                style: Style::default(),
+                extra: Vec::new(),
+            }],
+        }),
+    };
+    let bytes = crate::to_bytes(&named).unwrap();
+    assert_eq!(crate::from_slice::<Named>(&bytes).unwrap(), named);
+
+    // Plain strings and arrays are shorthands for components.
+    let plain: TextComponent = serde_json::from_str(r#""Hello""#).unwrap();
+    assert_eq!(plain.to_plain_text(), "Hello");
+    let list: TextComponent = serde_json::from_str(r#"["A", {"text": "B"}, "C"]"#).unwrap();
+    assert_eq!(list.to_plain_text(), "ABC");
+    assert_eq!(list.extra().len(), 2);
+}
+
+/// A chat component. Components of other types, such as `score` and `keybind`, are read as
+/// empty text components that keep their style and children.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextComponent {
+    /// Literal text.
+    Text {
+        text: String,
+        style: Style,
+        extra: Vec<TextComponent>,
+    },
+    /// Text looked up in the game's language files by `key`, with `with` filling in its
+    /// placeholders.
+    Translate {
+        key: String,
+        with: Vec<TextComponent>,
+        style: Style,
+        extra: Vec<TextComponent>,
+    },
+}
+
+/// Formatting of a component. Unset fields are inherited from the parent component.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Style {
+    /// A named color such as `gold`, or a hex color such as `#ff8800`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    /// Text inserted into the chat box when the component is shift-clicked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insertion: Option<String>,
+}
+
+impl TextComponent {
+    /// A text component without formatting.
+    pub fn text<S: Into<String>>(text: S) -> Self {
+        TextComponent::Text {
+            text: text.into(),
+            style: Style::default(),
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn style(&self) -> &Style {
+        match self {
+            TextComponent::Text { style, .. } | TextComponent::Translate { style, .. } => style,
+        }
+    }
+
+    /// Components displayed after this one, inheriting its style.
+    pub fn extra(&self) -> &[TextComponent] {
+        match self {
+            TextComponent::Text { extra, .. } | TextComponent::Translate { extra, .. } => extra,
+        }
+    }
+
+    /// The text of this component and its children without formatting. Translated components
+    /// contribute their key, since the language files aren't available.
+    pub fn to_plain_text(&self) -> String {
+        let mut out = String::new();
+        self.push_plain_text(&mut out);
+        out
+    }
+
+    fn push_plain_text(&self, out: &mut String) {
+        match self {
+            TextComponent::Text { text, .. } => out.push_str(text),
+            TextComponent::Translate { key, .. } => out.push_str(key),
+        }
+        for child in self.extra() {
+            child.push_plain_text(out);
+        }
+    }
+}
+
+// The JSON object form shared by every component type.
+#[derive(Serialize, Deserialize)]
+struct Object {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    translate: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    with: Vec<TextComponent>,
+    #[serde(flatten)]
+    style: Style,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra: Vec<TextComponent>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Plain(String),
+    List(Vec<TextComponent>),
+    Object(Object),
+}
+
+impl Serialize for TextComponent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let object = match self.clone() {
+            TextComponent::Text { text, style, extra } => Object {
+                text: Some(text),
+                translate: None,
+                with: Vec::new(),
+                style,
+                extra,
+            },
+            TextComponent::Translate {
+                key,
+                with,
+                style,
+                extra,
+            } => Object {
+                text: None,
+                translate: Some(key),
+                with,
+                style,
+                extra,
+            },
+        };
+        object.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextComponent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Plain(text) => TextComponent::text(text),
+            // The first element is the parent of the rest.
+            Repr::List(components) => {
+                let mut components = components.into_iter();
+                let mut first = components.next().unwrap_or_else(|| TextComponent::text(""));
+                match &mut first {
+                    TextComponent::Text { extra, .. } | TextComponent::Translate { extra, .. } => {
+                        extra.extend(components)
+                    }
+                }
+                first
+            }
+            Repr::Object(object) => match object.translate {
+                Some(key) => TextComponent::Translate {
+                    key,
+                    with: object.with,
+                    style: object.style,
+                    extra: object.extra,
+                },
+                None => TextComponent::Text {
+                    text: object.text.unwrap_or_default(),
+                    style: object.style,
+                    extra: object.extra,
+                },
+            },
+        })
+    }
+}
+
+/// Stores a [`TextComponent`] in a String tag as JSON, the way the game stores names and sign
+/// text.
+pub mod as_json {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &TextComponent,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let json = serde_json::to_string(value).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<TextComponent, D::Error> {
+        let json = String::deserialize(deserializer)?;
+        serde_json::from_str(&json).map_err(de::Error::custom)
+    }
+}
+
+/// Like [`as_json`], for optional fields such as `CustomName`. Use it together with
+/// `#[serde(default)]` so a missing tag reads as `None`.
+pub mod option_as_json {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<TextComponent>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => as_json::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<TextComponent>, D::Error> {
+        as_json::deserialize(deserializer).map(Some)
+    }
+}
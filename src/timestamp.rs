@@ -0,0 +1,117 @@
+//! Points in time stored as counts since the Unix epoch, such as `LastPlayed` in `level.dat` and
+//! the chunk timestamps of region files. [`SystemTime`] is always supported, and the date types
+//! of `chrono` and `time` with the features of the same names.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+#[test]
+fn test_timestamp_millis() {
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Data {
+        #[serde(rename = "LastPlayed", with = "timestamp_millis")]
+        last_played: SystemTime,
+    }
+
+    let data = Data {
+        last_played: UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+    };
+    let bytes = crate::to_bytes(&data).unwrap();
+    assert_eq!(
+        &bytes[bytes.len() - 9..bytes.len() - 1],
+        1_700_000_000_123i64.to_be_bytes()
+    );
+    assert_eq!(crate::from_slice::<Data>(&bytes).unwrap(), data);
+
+    let before_epoch = UNIX_EPOCH - Duration::from_millis(1500);
+    assert_eq!(before_epoch.unix_millis(), -1500);
+    assert_eq!(SystemTime::from_unix_millis(-1500), Some(before_epoch));
+    #[cfg(feature = "chrono")]
+    assert_eq!(
+        chrono::DateTime::<chrono::Utc>::from_unix_millis(1_700_000_000_123)
+            .unwrap()
+            .unix_millis(),
+        1_700_000_000_123
+    );
+    #[cfg(feature = "time")]
+    assert_eq!(
+        time::OffsetDateTime::from_unix_millis(-1500)
+            .unwrap()
+            .unix_millis(),
+        -1500
+    );
+}
+
+/// A point in time that can be converted to and from milliseconds since the Unix epoch.
+pub trait Timestamp: Sized {
+    /// The time `millis` milliseconds after the epoch, or `None` if the type can't represent it.
+    fn from_unix_millis(millis: i64) -> Option<Self>;
+
+    /// Milliseconds since the epoch, negative for times before it.
+    fn unix_millis(&self) -> i64;
+}
+
+impl Timestamp for SystemTime {
+    fn from_unix_millis(millis: i64) -> Option<Self> {
+        let offset = Duration::from_millis(millis.unsigned_abs());
+        if millis < 0 {
+            UNIX_EPOCH.checked_sub(offset)
+        } else {
+            UNIX_EPOCH.checked_add(offset)
+        }
+    }
+
+    fn unix_millis(&self) -> i64 {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_millis() as i64,
+            Err(before) => -(before.duration().as_millis() as i64),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Timestamp for chrono::DateTime<chrono::Utc> {
+    fn from_unix_millis(millis: i64) -> Option<Self> {
+        chrono::DateTime::from_timestamp_millis(millis)
+    }
+
+    fn unix_millis(&self) -> i64 {
+        self.timestamp_millis()
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {
+    fn from_unix_millis(millis: i64) -> Option<Self> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000).ok()
+    }
+
+    fn unix_millis(&self) -> i64 {
+        self.unix_timestamp_nanos().div_euclid(1_000_000) as i64
+    }
+}
+
+/// Stores a [`Timestamp`] in a Long tag as milliseconds since the Unix epoch, like `LastPlayed`.
+pub mod timestamp_millis {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Timestamp,
+        S: Serializer,
+    {
+        serializer.serialize_i64(value.unix_millis())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Timestamp,
+        D: Deserializer<'de>,
+    {
+        let millis = i64::deserialize(deserializer)?;
+        T::from_unix_millis(millis)
+            .ok_or_else(|| de::Error::custom(format!("timestamp {} ms is out of range", millis)))
+    }
+}
@@ -0,0 +1,312 @@
+//! Human-readable tree rendering of documents in the style of NBTExplorer, with one tag per line
+//! and children indented below their parent.
+use std::fmt::{self, Display, Write};
+
+use byteorder::ByteOrder;
+
+use crate::{
+    error,
+    kind::NBTKind,
+    stream::{NBTEvent, NBTStreamReader},
+    tag::NBTValue,
+};
+
+#[test]
+fn test_tree() {
+    let value = NBTValue::Compound(crate::CompoundMap::from_iter([
+        ("name".to_owned(), NBTValue::String("world".into())),
+        ("heights".to_owned(), NBTValue::LongArray(vec![0; 37])),
+        (
+            "pos".to_owned(),
+            NBTValue::List(vec![NBTValue::Double(1.5), NBTValue::Double(-2.0)]),
+        ),
+    ]));
+    let tree = value.tree("").to_string();
+    let mut lines: Vec<_> = tree.lines().collect();
+    // Compound entries come in map order, which depends on the map type.
+    lines[1..].sort();
+    assert_eq!(
+        lines,
+        [
+            r#"TAG_Compound(""): 3 entries"#,
+            "    TAG_Double(None): -2",
+            "    TAG_Double(None): 1.5",
+            r#"  TAG_List("pos"): 2 entries of TAG_Double"#,
+            r#"  TAG_LongArray("heights"): [37 longs]"#,
+            r#"  TAG_String("name"): "world""#,
+        ]
+    );
+
+    #[derive(serde::Serialize)]
+    struct Level {
+        name: String,
+        #[serde(serialize_with = "crate::long_array")]
+        heights: Vec<i64>,
+        pos: Vec<f64>,
+    }
+
+    let bytes = crate::to_bytes(&Level {
+        name: "world".into(),
+        heights: vec![0; 37],
+        pos: vec![1.5, -2.0],
+    })
+    .unwrap();
+    let mut streamed = String::new();
+    NBTStreamReader::new(bytes.as_slice())
+        .write_tree(&mut streamed)
+        .unwrap();
+    let mut streamed: Vec<_> = streamed.lines().collect();
+    // The compound header leaves out the entry count, as it isn't known up front when streaming.
+    assert_eq!(streamed[0], r#"TAG_Compound("")"#);
+    streamed[1..].sort();
+    assert_eq!(
+        streamed[1..],
+        [
+            "    TAG_Double(None): -2",
+            "    TAG_Double(None): 1.5",
+            r#"  TAG_List("pos"): 2 entries of TAG_Double"#,
+            r#"  TAG_LongArray("heights"): [37 longs]"#,
+            r#"  TAG_String("name"): "world""#,
+        ]
+    );
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn test_colored_tree() {
+    let value = NBTValue::Compound(crate::CompoundMap::from_iter([(
+        "name".to_owned(),
+        NBTValue::String("world".into()),
+    )]));
+    let plain = value.tree("").to_string();
+    let colored = value.tree("").color(true).to_string();
+    assert!(colored.contains("\x1b[32m\"world\"\x1b[39m"));
+    let stripped = colored.split('\x1b').fold(String::new(), |mut out, part| {
+        out.push_str(part.split_once('m').map_or(part, |(_, rest)| rest));
+        out
+    });
+    assert_eq!(stripped, plain);
+    assert_eq!(value.tree("").color(false).to_string(), plain);
+}
+
+/// Renders a value as an indented tree, see [`NBTValue::tree`].
+pub struct Tree<'a> {
+    name: Option<&'a str>,
+    value: &'a NBTValue,
+    color: bool,
+}
+
+impl NBTValue {
+    /// Renders this value, named `name`, as a tree:
+    ///
+    /// ```text
+    /// TAG_Compound(""): 2 entries
+    ///   TAG_String("name"): "world"
+    ///   TAG_LongArray("heights"): [37 longs]
+    /// ```
+    pub fn tree<'a>(&'a self, name: &'a str) -> Tree<'a> {
+        Tree {
+            name: Some(name),
+            value: self,
+            color: false,
+        }
+    }
+}
+
+#[cfg(feature = "color")]
+impl Tree<'_> {
+    /// Highlights tag kinds, names, strings and numbers with ANSI colors.
+    pub fn color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    /// Highlights the output if stdout is a terminal and the `NO_COLOR` environment variable isn't
+    /// set.
+    pub fn color_auto(self) -> Self {
+        use std::io::IsTerminal;
+
+        let enabled = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        self.color(enabled)
+    }
+}
+
+impl Display for Tree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self.name, self.value, 0, self.color)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Part {
+    Kind,
+    Name,
+    String,
+    Number,
+}
+
+// Text that is highlighted according to its part of the tree when `color` is set.
+#[cfg_attr(not(feature = "color"), allow(dead_code))]
+struct Painted<T> {
+    text: T,
+    part: Part,
+    color: bool,
+}
+
+fn paint<T: Display>(text: T, part: Part, color: bool) -> Painted<T> {
+    Painted { text, part, color }
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "color")]
+        if self.color {
+            use owo_colors::OwoColorize;
+
+            return match self.part {
+                Part::Kind => self.text.blue().fmt(f),
+                Part::Name => self.text.cyan().fmt(f),
+                Part::String => self.text.green().fmt(f),
+                Part::Number => self.text.yellow().fmt(f),
+            };
+        }
+        self.text.fmt(f)
+    }
+}
+
+fn write_value<W: Write>(
+    w: &mut W,
+    name: Option<&str>,
+    value: &NBTValue,
+    depth: usize,
+    color: bool,
+) -> fmt::Result {
+    write_line(w, name, value, depth, color)?;
+    match value {
+        NBTValue::List(list) => {
+            for element in list {
+                write_value(w, None, element, depth + 1, color)?;
+            }
+        }
+        NBTValue::Compound(compound) => {
+            for (name, value) in compound {
+                write_value(w, Some(name), value, depth + 1, color)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Writes the line `value` gets in the tree, without the lines of its children.
+pub(crate) fn write_line<W: Write>(
+    w: &mut W,
+    name: Option<&str>,
+    value: &NBTValue,
+    depth: usize,
+    color: bool,
+) -> fmt::Result {
+    write_header(w, value.kind(), name, depth, color)?;
+    match value {
+        NBTValue::Byte(n) => write_number(w, n, color),
+        NBTValue::Short(n) => write_number(w, n, color),
+        NBTValue::Int(n) => write_number(w, n, color),
+        NBTValue::Long(n) => write_number(w, n, color),
+        NBTValue::Float(n) => write_number(w, n, color),
+        NBTValue::Double(n) => write_number(w, n, color),
+        NBTValue::String(string) => {
+            writeln!(
+                w,
+                ": {}",
+                paint(format!("{:?}", string), Part::String, color)
+            )
+        }
+        NBTValue::ByteArray(array) => write_array_summary(w, NBTKind::ByteArray, array.len()),
+        NBTValue::IntArray(array) => write_array_summary(w, NBTKind::IntArray, array.len()),
+        NBTValue::LongArray(array) => write_array_summary(w, NBTKind::LongArray, array.len()),
+        NBTValue::List(list) => {
+            write_list_summary(w, list.first().map(NBTValue::kind), list.len(), color)
+        }
+        NBTValue::Compound(compound) => writeln!(w, ": {} entries", compound.len()),
+    }
+}
+
+fn write_number<W: Write>(w: &mut W, n: impl Display, color: bool) -> fmt::Result {
+    writeln!(w, ": {}", paint(n, Part::Number, color))
+}
+
+// `TAG_Int("name")` indented to `depth`, or `TAG_Int(None)` for list elements.
+fn write_header<W: Write>(
+    w: &mut W,
+    kind: NBTKind,
+    name: Option<&str>,
+    depth: usize,
+    color: bool,
+) -> fmt::Result {
+    let kind = paint(format!("TAG_{}", kind), Part::Kind, color);
+    write!(w, "{:1$}{2}", "", depth * 2, kind)?;
+    match name {
+        Some(name) => write!(w, "({})", paint(format!("{:?}", name), Part::Name, color)),
+        None => write!(w, "(None)"),
+    }
+}
+
+fn write_list_summary<W: Write>(
+    w: &mut W,
+    kind: Option<NBTKind>,
+    len: usize,
+    color: bool,
+) -> fmt::Result {
+    match kind {
+        Some(kind) if len > 0 => {
+            let kind = paint(format!("TAG_{}", kind), Part::Kind, color);
+            writeln!(w, ": {} entries of {}", len, kind)
+        }
+        _ => writeln!(w, ": {} entries", len),
+    }
+}
+
+fn write_array_summary<W: Write>(w: &mut W, kind: NBTKind, len: usize) -> fmt::Result {
+    let unit = match kind {
+        NBTKind::ByteArray => "bytes",
+        NBTKind::IntArray => "ints",
+        _ => "longs",
+    };
+    writeln!(w, ": [{} {}]", len, unit)
+}
+
+impl<R: std::io::Read, O: ByteOrder> NBTStreamReader<R, O> {
+    /// Reads the rest of the document and renders it like [`NBTValue::tree`]. Compounds are
+    /// written before their entries have been read, so their headers leave out the entry count.
+    /// Array payloads are skipped.
+    pub fn write_tree<W: Write>(&mut self, w: &mut W) -> error::Result<()> {
+        let mut depth = 0;
+        while let Some(event) = self.next_event()? {
+            match event {
+                NBTEvent::CompoundStart { name } => {
+                    write_header(w, NBTKind::Compound, name.as_deref(), depth, false)?;
+                    writeln!(w)?;
+                    depth += 1;
+                }
+                NBTEvent::ListStart {
+                    name,
+                    element_kind,
+                    length,
+                } => {
+                    write_header(w, NBTKind::List, name.as_deref(), depth, false)?;
+                    write_list_summary(w, Some(element_kind), length, false)?;
+                    depth += 1;
+                }
+                NBTEvent::CompoundEnd | NBTEvent::ListEnd => depth -= 1,
+                NBTEvent::Value { name, value } => {
+                    write_value(w, name.as_deref(), &value, depth, false)?
+                }
+                NBTEvent::Array { name, array } => {
+                    write_header(w, array.kind(), name.as_deref(), depth, false)?;
+                    write_array_summary(w, array.kind(), array.len())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
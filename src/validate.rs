@@ -0,0 +1,113 @@
+//! Structural checks of encoded documents that don't decode any values.
+use byteorder::BigEndian;
+
+use crate::{
+    de::DeserializerOptions,
+    error::{self, Error},
+    kind::NBTKind,
+    parser::{Parser, SliceRead},
+    stream::Frame,
+};
+
+#[test]
+fn test_validate_bytes() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Chunk {
+        #[serde(serialize_with = "crate::long_array")]
+        heightmap: Vec<i64>,
+        sections: Vec<Section>,
+    }
+
+    #[derive(Serialize)]
+    struct Section {
+        y: i8,
+    }
+
+    let bytes = crate::to_bytes(&Chunk {
+        heightmap: vec![0; 4],
+        sections: vec![Section { y: 0 }, Section { y: 1 }],
+    })
+    .unwrap();
+    let summary = validate_bytes(&bytes).unwrap();
+    assert_eq!(summary.root_name, "");
+    assert_eq!(summary.size, bytes.len());
+    // The root, the heightmap, the list, both sections and their bytes.
+    assert_eq!(summary.tags, 7);
+    assert_eq!(summary.max_depth, 3);
+
+    assert!(validate_bytes(&bytes[..bytes.len() - 1]).is_err());
+    let mut invalid_utf8 = bytes.clone();
+    invalid_utf8[6] = 0xff;
+    assert!(validate_bytes(&invalid_utf8).is_err());
+}
+
+/// What [`validate_bytes`] found in a well-formed document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NbtSummary {
+    pub root_name: String,
+    /// Number of bytes the document takes up. Anything after it in the input is not checked.
+    pub size: usize,
+    /// Number of tags, including the root and every list element.
+    pub tags: usize,
+    /// Deepest nesting of compounds and lists, where the root is 1.
+    pub max_depth: usize,
+}
+
+/// Checks that `bytes` starts with a well-formed big-endian document: known tag ids, lengths that
+/// stay within the input, UTF-8 names and strings, and a closing End tag for every compound.
+/// Payloads are skipped over rather than decoded, so nothing but the root name is allocated.
+pub fn validate_bytes(bytes: &[u8]) -> error::Result<NbtSummary> {
+    let mut parser =
+        Parser::<_, BigEndian>::new(SliceRead::new(bytes), DeserializerOptions::default());
+    if parser.parse_kind()? != NBTKind::Compound {
+        return Err(Error::ExpectedRootCompound);
+    }
+    let root_name = parser.parse_str(|name| Ok(name.to_owned()))?;
+    let mut summary = NbtSummary {
+        root_name,
+        size: 0,
+        tags: 1,
+        max_depth: 1,
+    };
+
+    let mut stack = vec![Frame::Compound];
+    while let Some(frame) = stack.last_mut() {
+        let kind = match frame {
+            Frame::Compound => {
+                let kind = parser.parse_kind()?;
+                if kind == NBTKind::End {
+                    stack.pop();
+                    continue;
+                }
+                parser.parse_str(|_| Ok(()))?;
+                kind
+            }
+            Frame::List { remaining: 0, .. } => {
+                stack.pop();
+                continue;
+            }
+            Frame::List { kind, remaining } => {
+                *remaining -= 1;
+                *kind
+            }
+        };
+
+        summary.tags += 1;
+        match kind {
+            NBTKind::Compound => stack.push(Frame::Compound),
+            NBTKind::List => {
+                let (kind, remaining) = parser.parse_list_header()?;
+                stack.push(Frame::List { kind, remaining });
+            }
+            NBTKind::String => parser.parse_str(|_| Ok(()))?,
+            NBTKind::End => return Err(Error::InvalidTagId),
+            kind => parser.skip_payload(kind)?,
+        }
+        summary.max_depth = summary.max_depth.max(stack.len());
+    }
+
+    summary.size = parser.position() as usize;
+    Ok(summary)
+}
@@ -0,0 +1,174 @@
+//! Zero-copy views of IntArray and LongArray payloads. Fields of these types borrow the payload
+//! straight from the slice given to [`from_slice`](crate::from_slice) instead of decoding it into a
+//! `Vec`. Elements stay big-endian in memory and are decoded on access, so the views only apply
+//! to Java Edition documents.
+use std::fmt;
+
+use bytemuck::{Pod, Zeroable};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::ser::{INT_ARRAY_NAME, LONG_ARRAY_NAME};
+
+#[test]
+fn test_array_views() {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Owned {
+        #[serde(serialize_with = "crate::int_array")]
+        biomes: Vec<i32>,
+        #[serde(serialize_with = "crate::long_array")]
+        states: Vec<i64>,
+    }
+
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        #[serde(borrow)]
+        biomes: IntArrayView<'a>,
+        #[serde(borrow)]
+        states: LongArrayView<'a>,
+    }
+
+    let bytes = crate::to_bytes(&Owned {
+        biomes: vec![1, -2, 3],
+        states: vec![i64::MIN, 0],
+    })
+    .unwrap();
+    let view: Borrowed = crate::from_slice(&bytes).unwrap();
+    assert_eq!(view.biomes.iter().collect::<Vec<_>>(), [1, -2, 3]);
+    assert_eq!(view.states.len(), 2);
+    assert_eq!(view.states.0[0].get(), i64::MIN);
+    assert!(bytes
+        .as_ptr_range()
+        .contains(&view.states.0.as_ptr().cast()));
+
+    // Readers can't lend out their data, so there is nothing to borrow from.
+    assert!(crate::from_reader::<Borrowed, _>(bytes.as_slice()).is_err());
+
+    // The payload of another kind of array isn't reinterpreted, even when its length fits.
+    let swapped = crate::to_bytes(&Owned {
+        biomes: vec![1, 2],
+        states: vec![1],
+    })
+    .unwrap();
+    #[derive(Debug, Deserialize)]
+    struct Swapped<'a> {
+        #[serde(borrow, rename = "states")]
+        _states: IntArrayView<'a>,
+    }
+    assert!(matches!(
+        crate::from_slice::<Swapped>(&swapped),
+        Err(crate::Error::MismatchedTag(
+            crate::NBTKind::LongArray,
+            crate::NBTKind::IntArray
+        ))
+    ));
+    #[derive(Serialize)]
+    struct Bytes {
+        #[serde(serialize_with = "crate::byte_array")]
+        states: Vec<i8>,
+    }
+    #[derive(Debug, Deserialize)]
+    struct Longs<'a> {
+        #[serde(borrow, rename = "states")]
+        _states: LongArrayView<'a>,
+    }
+    let bytes = crate::to_bytes(&Bytes { states: vec![0; 8] }).unwrap();
+    assert!(matches!(
+        crate::from_slice::<Longs>(&bytes),
+        Err(crate::Error::MismatchedTag(
+            crate::NBTKind::ByteArray,
+            crate::NBTKind::LongArray
+        ))
+    ));
+}
+
+/// A big-endian `i32` as it appears in an IntArray payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct I32Be([u8; 4]);
+
+impl I32Be {
+    pub fn get(self) -> i32 {
+        i32::from_be_bytes(self.0)
+    }
+}
+
+/// A big-endian `i64` as it appears in a LongArray payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct I64Be([u8; 8]);
+
+impl I64Be {
+    pub fn get(self) -> i64 {
+        i64::from_be_bytes(self.0)
+    }
+}
+
+/// IntArray payload borrowed from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntArrayView<'a>(pub &'a [I32Be]);
+
+impl IntArrayView<'_> {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i32> + '_ {
+        self.0.iter().map(|n| n.get())
+    }
+}
+
+/// LongArray payload borrowed from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongArrayView<'a>(pub &'a [I64Be]);
+
+impl LongArrayView<'_> {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        self.0.iter().map(|n| n.get())
+    }
+}
+
+struct ViewVisitor<T>(&'static str, std::marker::PhantomData<T>);
+
+impl<'de, T: Pod> de::Visitor<'de> for ViewVisitor<T> {
+    type Value = &'de [T];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} borrowed from the input", self.0)
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, bytes: &'de [u8]) -> Result<Self::Value, E> {
+        bytemuck::try_cast_slice(bytes).map_err(|_| E::invalid_length(bytes.len(), &self))
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for IntArrayView<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = ViewVisitor("an IntArray", std::marker::PhantomData);
+        deserializer
+            .deserialize_newtype_struct(INT_ARRAY_NAME, visitor)
+            .map(IntArrayView)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for LongArrayView<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = ViewVisitor("a LongArray", std::marker::PhantomData);
+        deserializer
+            .deserialize_newtype_struct(LONG_ARRAY_NAME, visitor)
+            .map(LongArrayView)
+    }
+}
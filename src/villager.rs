@@ -0,0 +1,281 @@
+//! The trading, gossip and memory data of villagers, up to 1.20.4.
+use serde::{Deserialize, Serialize};
+
+use crate::{item::ItemStack, resource::ResourceLocation};
+
+#[test]
+fn test_villager_round_trip() {
+    let item = |id: &str, count| ItemStack {
+        id: id.parse().unwrap(),
+        count,
+        tag: None,
+    };
+    let home = GlobalPos {
+        pos: [12, 64, -40],
+        dimension: "minecraft:overworld".parse().unwrap(),
+    };
+    let villager = Villager {
+        villager_data: VillagerData {
+            level: 2,
+            profession: "minecraft:farmer".parse().unwrap(),
+            kind: "minecraft:plains".parse().unwrap(),
+        },
+        xp: 10,
+        offers: Some(Offers {
+            recipes: vec![Offer {
+                buy: item("minecraft:wheat", 20),
+                buy_b: Some(item("minecraft:air", 0)),
+                sell: item("minecraft:emerald", 1),
+                uses: 3,
+                max_uses: 16,
+                reward_exp: true,
+                xp: 2,
+                price_multiplier: 0.05,
+                special_price: -1,
+                demand: 4,
+            }],
+        }),
+        gossips: vec![Gossip {
+            kind: GossipType::MinorPositive,
+            value: 25,
+            target: u128::MAX / 3,
+        }],
+        brain: Brain {
+            memories: Memories {
+                home: Some(Memory {
+                    value: home.clone(),
+                    ttl: None,
+                }),
+                job_site: Some(Memory {
+                    value: home,
+                    ttl: None,
+                }),
+                last_slept: Some(Memory {
+                    value: 12000,
+                    ttl: None,
+                }),
+                golem_detected_recently: Some(Memory {
+                    value: true,
+                    ttl: Some(600),
+                }),
+                ..Default::default()
+            },
+        },
+        last_restock: 24000,
+        restocks_today: 1,
+        last_gossip_decay: 23000,
+    };
+    let bytes = crate::to_bytes(&villager).unwrap();
+    assert_eq!(crate::from_slice::<Villager>(&bytes).unwrap(), villager);
+}
+
+/// The villager specific fields of a villager or zombie villager. Fields shared with every entity
+/// are skipped when reading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Villager {
+    pub villager_data: VillagerData,
+    /// Experience towards the next level.
+    pub xp: i32,
+    /// Trades, missing until the villager has a profession.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offers: Option<Offers>,
+    #[serde(default)]
+    pub gossips: Vec<Gossip>,
+    #[serde(default)]
+    pub brain: Brain,
+    /// Game time of the last restock, in ticks.
+    #[serde(default)]
+    pub last_restock: i64,
+    #[serde(default)]
+    pub restocks_today: i32,
+    /// Game time gossip last decayed at, in ticks.
+    #[serde(default)]
+    pub last_gossip_decay: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VillagerData {
+    /// Career level from 1 (novice) to 5 (master).
+    pub level: i32,
+    /// Profession, such as `minecraft:farmer`.
+    pub profession: ResourceLocation,
+    /// Biome type that decides the villager's outfit, such as `minecraft:plains`.
+    #[serde(rename = "type")]
+    pub kind: ResourceLocation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Offers {
+    #[serde(rename = "Recipes")]
+    pub recipes: Vec<Offer>,
+}
+
+/// One trade.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Offer {
+    pub buy: ItemStack,
+    /// Second item wanted, stored as a stack of air by trades that only want one.
+    #[serde(rename = "buyB", default, skip_serializing_if = "Option::is_none")]
+    pub buy_b: Option<ItemStack>,
+    pub sell: ItemStack,
+    /// Times the trade has been used since the last restock.
+    pub uses: i32,
+    pub max_uses: i32,
+    /// Whether the player gets experience orbs for trading.
+    pub reward_exp: bool,
+    /// Experience the villager gets for the trade.
+    pub xp: i32,
+    /// How much `demand` raises the price of `buy`.
+    pub price_multiplier: f32,
+    /// Added to the price of `buy`, negative after the player was cured or is the hero of the
+    /// village.
+    pub special_price: i32,
+    pub demand: i32,
+}
+
+/// What the villager has heard about another entity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Gossip {
+    #[serde(rename = "Type")]
+    pub kind: GossipType,
+    /// Strength of the gossip, which decays over time.
+    pub value: i32,
+    /// UUID of the entity the gossip is about.
+    #[serde(with = "crate::with::u128_as_int_array")]
+    pub target: u128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GossipType {
+    MajorNegative,
+    MinorNegative,
+    MinorPositive,
+    MajorPositive,
+    Trading,
+}
+
+impl GossipType {
+    const NAMES: [&'static str; 5] = [
+        "major_negative",
+        "minor_negative",
+        "minor_positive",
+        "major_positive",
+        "trading",
+    ];
+
+    const ALL: [GossipType; 5] = [
+        GossipType::MajorNegative,
+        GossipType::MinorNegative,
+        GossipType::MinorPositive,
+        GossipType::MajorPositive,
+        GossipType::Trading,
+    ];
+
+    /// The name the type is stored under, such as `major_negative`.
+    pub fn as_str(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+}
+
+// Stored as a String tag, which unit variants can't be serialized to.
+impl Serialize for GossipType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for GossipType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Self::NAMES
+            .iter()
+            .position(|&n| n == name)
+            .map(|i| Self::ALL[i])
+            .ok_or_else(|| serde::de::Error::unknown_variant(&name, &Self::NAMES))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Brain {
+    #[serde(default)]
+    pub memories: Memories,
+}
+
+/// The memories a villager keeps across saves. Memories that are absent are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Memories {
+    /// The bed the villager sleeps in.
+    #[serde(
+        rename = "minecraft:home",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub home: Option<Memory<GlobalPos>>,
+    /// The workstation the villager has claimed.
+    #[serde(
+        rename = "minecraft:job_site",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub job_site: Option<Memory<GlobalPos>>,
+    /// A workstation the villager is walking to but hasn't claimed yet.
+    #[serde(
+        rename = "minecraft:potential_job_site",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub potential_job_site: Option<Memory<GlobalPos>>,
+    /// The bell the villager gathers at.
+    #[serde(
+        rename = "minecraft:meeting_point",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub meeting_point: Option<Memory<GlobalPos>>,
+    /// Game time the villager last slept at, in ticks.
+    #[serde(
+        rename = "minecraft:last_slept",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_slept: Option<Memory<i64>>,
+    #[serde(
+        rename = "minecraft:last_woken",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_woken: Option<Memory<i64>>,
+    #[serde(
+        rename = "minecraft:last_worked_at_poi",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_worked_at_poi: Option<Memory<i64>>,
+    /// Set for a while after the villager sees an iron golem, which stops it from summoning one.
+    #[serde(
+        rename = "minecraft:golem_detected_recently",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub golem_detected_recently: Option<Memory<bool>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Memory<T> {
+    pub value: T,
+    /// Ticks until the memory is forgotten, for memories that expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<i64>,
+}
+
+/// A block position in a given dimension.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobalPos {
+    #[serde(serialize_with = "crate::int_array")]
+    pub pos: [i32; 3],
+    /// Dimension, such as `minecraft:overworld`.
+    pub dimension: ResourceLocation,
+}
@@ -0,0 +1,38 @@
+//! Entry points for JavaScript, exported with `wasm-bindgen` when the `wasm` feature is enabled.
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::tag::NBTValue;
+
+/// Parses an NBT document into a JavaScript object. Compounds become plain objects, lists and
+/// arrays become arrays, and longs become `BigInt`s so they keep every bit. Gzip and zlib
+/// compressed documents are decompressed first when the `compression` feature is enabled.
+#[wasm_bindgen]
+pub fn parse(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let value = read(bytes)?;
+    let serializer = serde_wasm_bindgen::Serializer::new()
+        .serialize_maps_as_objects(true)
+        .serialize_large_number_types_as_bigints(true);
+    Ok(value.serialize(&serializer)?)
+}
+
+/// Parses an NBT document and formats it as SNBT.
+#[wasm_bindgen(js_name = toSnbt)]
+pub fn to_snbt(bytes: &[u8]) -> Result<String, JsError> {
+    Ok(read(bytes)?.to_snbt())
+}
+
+fn read(bytes: &[u8]) -> Result<NBTValue, JsError> {
+    #[cfg(feature = "compression")]
+    {
+        use crate::file::Compression;
+        use flate2::read::ZlibDecoder;
+
+        match Compression::detect(bytes) {
+            Compression::Gzip => return Ok(crate::from_gzip_reader(bytes)?),
+            Compression::Zlib => return Ok(crate::from_reader(ZlibDecoder::new(bytes))?),
+            Compression::None => {}
+        }
+    }
+    Ok(crate::from_slice(bytes)?)
+}
@@ -0,0 +1,283 @@
+//! Helpers for `#[serde(with = "...")]` that store values NBT has no tag for in the bits of one
+//! that it does, or that pick the tag a value is stored in.
+use alloc::{format, vec::Vec};
+use core::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+#[test]
+fn test_bit_pattern_strategies() {
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entity {
+        #[serde(with = "u64_as_long")]
+        seed: u64,
+        #[serde(with = "i128_as_int_array")]
+        uuid: i128,
+    }
+
+    let entity = Entity {
+        seed: u64::MAX,
+        uuid: 0x0011_2233_4455_6677_8899_aabb_ccdd_eeff,
+    };
+    let bytes = crate::to_bytes(&entity).unwrap();
+    // A Long named "seed" holding -1, followed by an IntArray of 4 named "uuid".
+    assert_eq!(&bytes[3..10], &[4, 0, 4, b's', b'e', b'e', b'd']);
+    assert_eq!(&bytes[10..18], &[0xff; 8]);
+    assert_eq!(&bytes[18..25], &[11, 0, 4, b'u', b'u', b'i', b'd']);
+    assert_eq!(&bytes[25..33], &[0, 0, 0, 4, 0x00, 0x11, 0x22, 0x33]);
+
+    let decoded: Entity = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, entity);
+}
+
+#[test]
+fn test_tag_coercion() {
+    use crate::NBTValue;
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Mob {
+        #[serde(with = "as_byte")]
+        count: u32,
+        #[serde(with = "as_long")]
+        age: i16,
+        #[serde(with = "as_float")]
+        speed: f64,
+        #[serde(with = "as_float")]
+        health: i32,
+    }
+
+    let mob = Mob {
+        count: 3,
+        age: -2,
+        speed: 0.25,
+        health: 20,
+    };
+    let bytes = crate::to_bytes(&mob).unwrap();
+    let NBTValue::Compound(map) = crate::from_slice(&bytes).unwrap() else {
+        panic!("root is not a compound");
+    };
+    assert_eq!(map["count"], NBTValue::Byte(3));
+    assert_eq!(map["age"], NBTValue::Long(-2));
+    assert_eq!(map["speed"], NBTValue::Float(0.25));
+    assert_eq!(map["health"], NBTValue::Float(20.0));
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Lenient {
+        #[serde(with = "as_byte")]
+        count: u32,
+        #[serde(with = "as_float")]
+        speed: f64,
+    }
+    #[derive(Serialize)]
+    struct Wide {
+        count: i64,
+        speed: i32,
+    }
+    let bytes = crate::to_bytes(&Wide { count: 7, speed: 2 }).unwrap();
+    assert_eq!(
+        crate::from_slice::<Lenient>(&bytes).unwrap(),
+        Lenient {
+            count: 7,
+            speed: 2.0
+        }
+    );
+    let bytes = crate::to_bytes(&Wide {
+        count: -1,
+        speed: 2,
+    })
+    .unwrap();
+    assert!(crate::from_slice::<Lenient>(&bytes).is_err());
+    let too_large = Mob { count: 200, ..mob };
+    assert!(crate::to_bytes(&too_large).is_err());
+}
+
+/// Stores a `u64` in a Long tag by reinterpreting its bits, so values above `i64::MAX` are written
+/// as negative numbers.
+pub mod u64_as_long {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(*value as i64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Ok(i64::deserialize(deserializer)? as u64)
+    }
+}
+
+/// Stores an `i128` in an IntArray of 4, most significant int first. This is the layout Minecraft
+/// uses for UUIDs.
+pub mod i128_as_int_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::int_array(to_ints(*value as u128), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+        Ok(from_ints(deserializer)? as i128)
+    }
+}
+
+/// Stores a `u128` in an IntArray of 4 in the same layout as [`i128_as_int_array`].
+pub mod u128_as_int_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::int_array(to_ints(*value), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        from_ints(deserializer)
+    }
+}
+
+fn to_ints(value: u128) -> [i32; 4] {
+    [
+        (value >> 96) as i32,
+        (value >> 64) as i32,
+        (value >> 32) as i32,
+        value as i32,
+    ]
+}
+
+fn from_ints<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    let ints = Vec::<i32>::deserialize(deserializer)?;
+    if ints.len() != 4 {
+        return Err(de::Error::invalid_length(ints.len(), &"an int array of 4"));
+    }
+    Ok(ints
+        .iter()
+        .fold(0, |value, &int| (value << 32) | int as u32 as u128))
+}
+
+macro_rules! as_integer {
+    ($name:ident, $int:ty, $serialize:ident, $tag:literal) => {
+        #[doc = concat!("Stores an integer in ", $tag, " tag whatever its Rust type, failing for values out of")]
+        /// range. Reading accepts any integer tag whose value fits the field.
+        pub mod $name {
+            use super::*;
+
+            pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                T: Copy + TryInto<$int> + fmt::Display,
+                S: Serializer,
+            {
+                let int = (*value).try_into().map_err(|_| {
+                    serde::ser::Error::custom(format!("{} does not fit in {} tag", value, $tag))
+                })?;
+                serializer.$serialize(int)
+            }
+
+            pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where
+                T: TryFrom<i64>,
+                D: Deserializer<'de>,
+            {
+                let value = deserializer.deserialize_any(IntegerVisitor)?;
+                T::try_from(value)
+                    .map_err(|_| de::Error::custom(format!("{} is out of range for the field", value)))
+            }
+        }
+    };
+}
+
+as_integer!(as_byte, i8, serialize_i8, "a Byte");
+as_integer!(as_short, i16, serialize_i16, "a Short");
+as_integer!(as_long, i64, serialize_i64, "a Long");
+
+/// Stores a number in a Float tag whatever its Rust type, rounding values that a Float can't hold
+/// exactly. Reading accepts any numeric tag, and fails for integer fields if the value isn't a
+/// whole number in range.
+pub mod as_float {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: FloatField,
+        S: Serializer,
+    {
+        serializer.serialize_f32(value.to_f32())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FloatField,
+        D: Deserializer<'de>,
+    {
+        let value = deserializer.deserialize_any(FloatVisitor)?;
+        T::from_f32(value)
+            .ok_or_else(|| de::Error::custom(format!("{} is out of range for the field", value)))
+    }
+}
+
+/// Rust numbers that [`as_float`] can store.
+pub trait FloatField: Sized {
+    fn to_f32(&self) -> f32;
+    fn from_f32(value: f32) -> Option<Self>;
+}
+
+macro_rules! float_field {
+    (float $($float:ty),*; int $($int:ty),*) => {
+        $(impl FloatField for $float {
+            fn to_f32(&self) -> f32 {
+                *self as f32
+            }
+
+            fn from_f32(value: f32) -> Option<Self> {
+                Some(value as $float)
+            }
+        })*
+        $(impl FloatField for $int {
+            fn to_f32(&self) -> f32 {
+                *self as f32
+            }
+
+            fn from_f32(value: f32) -> Option<Self> {
+                let whole = value >= <$int>::MIN as f32
+                    && value <= <$int>::MAX as f32
+                    && (value as $int) as f32 == value;
+                whole.then_some(value as $int)
+            }
+        })*
+    };
+}
+
+float_field!(float f32, f64; int i8, i16, i32, i64, u8, u16, u32, u64);
+
+// Accepts an integer of any width.
+struct IntegerVisitor;
+
+impl de::Visitor<'_> for IntegerVisitor {
+    type Value = i64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer tag")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<i64, E> {
+        Ok(v)
+    }
+}
+
+// Accepts a number of any tag.
+struct FloatVisitor;
+
+impl de::Visitor<'_> for FloatVisitor {
+    type Value = f32;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a numeric tag")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<f32, E> {
+        Ok(v as f32)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<f32, E> {
+        Ok(v as f32)
+    }
+}
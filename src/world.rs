@@ -0,0 +1,1332 @@
+//! Whole worlds, as saved in a folder holding `level.dat` and a folder of region files per
+//! dimension.
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File, TryLockError},
+    io::{Read, Seek, SeekFrom, Write},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    block::BlockState,
+    chunk::{Chunk, RawChunk},
+    error::{self, Error},
+    region::{chunk_at_block, region_at_chunk, Progress, StoredChunk},
+    source::{ChunkSource, RegionDirectory},
+    structure::{block_entity_pos, StructureBlock, StructureEntity, StructureFile},
+    tag::{NBTPath, NBTValue},
+};
+
+#[test]
+fn test_iter_chunks() {
+    use crate::region::{test_chunk, TestWorld};
+
+    let chunk = |x, z| crate::to_bytes(&test_chunk(x, z)).unwrap();
+    let dir = TestWorld::new();
+    dir.region("region/r.0.0.mca", &[(1, 2, chunk(1, 2))])
+        .region("region/r.-1.0.mca", &[(31, 0, chunk(-1, 0))])
+        .region("DIM-1/region/r.0.-1.mca", &[(0, 31, chunk(0, -1))]);
+
+    let world = dir.open();
+    let mut found: Vec<_> = world
+        .iter_chunks()
+        .unwrap()
+        .map(|result| {
+            let (dimension, position, chunk) = result.unwrap();
+            assert_eq!(position, (chunk.x, chunk.z));
+            (dimension, position)
+        })
+        .collect();
+    found.sort();
+    let expected = [
+        ("minecraft:overworld".to_owned(), (-1, 0)),
+        ("minecraft:overworld".to_owned(), (1, 2)),
+        ("minecraft:the_nether".to_owned(), (0, -1)),
+    ];
+    assert_eq!(found, expected);
+    let nether = world.dimension("minecraft:the_nether").unwrap().unwrap();
+    assert_eq!(nether.read_chunk(0, -1).unwrap().unwrap().z, -1);
+
+    #[cfg(feature = "rayon")]
+    {
+        let mut found: Vec<_> = world
+            .par_iter_chunks()
+            .unwrap()
+            .map(|result| {
+                let (dimension, position, _) = result.unwrap();
+                (dimension, position)
+            })
+            .collect();
+        found.sort();
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn test_dimensions() {
+    let dir = crate::region::TestWorld::new();
+    dir.folder("region")
+        .folder("DIM1/region")
+        .folder("dimensions/mypack/caves/deep/region")
+        .folder("dimensions/mypack/empty");
+
+    let world = dir.open();
+    let dimensions = world.dimensions().unwrap();
+    let ids: Vec<_> = dimensions.iter().map(Dimension::id).collect();
+    assert_eq!(
+        ids,
+        [
+            "minecraft:overworld",
+            "minecraft:the_end",
+            "mypack:caves/deep"
+        ]
+    );
+    assert_eq!(
+        dimensions[2].dir(),
+        dir.path().join("dimensions/mypack/caves/deep")
+    );
+    let end = world.dimension("minecraft:the_end").unwrap().unwrap();
+    assert_eq!(end.region_dir(), dir.path().join("DIM1/region"));
+    assert!(world.dimension("minecraft:the_nether").unwrap().is_none());
+    assert!(end.read_chunk(0, 0).unwrap().is_none());
+}
+
+#[test]
+fn test_chunk_sources() {
+    use crate::{region::test_chunk, source::ChunkDirectory};
+
+    // Chunks kept as loose files next to the folders the game would use.
+    let dir = crate::region::TestWorld::new();
+    dir.folder("region").folder("DIM-1/region");
+    let loose = |folder: &Path| ChunkDirectory::new(folder.join("chunks"));
+    let world = World::with_sources(dir.path(), loose).unwrap();
+    let overworld = world.dimension("minecraft:overworld").unwrap().unwrap();
+    let nether = world.dimension("minecraft:the_nether").unwrap().unwrap();
+    for (x, z) in [(0, 0), (40, -3)] {
+        let chunk = StoredChunk::compress(&crate::to_bytes(&test_chunk(x, z)).unwrap()).unwrap();
+        overworld.chunk_source().put_chunk(x, z, chunk).unwrap();
+        assert_eq!(overworld.read_chunk(x, z).unwrap().unwrap().x, x);
+        assert!(nether.read_chunk(x, z).unwrap().is_none());
+    }
+    assert!(dir.path().join("region/chunks/c.40.-3.zlib").is_file());
+
+    let mut found: Vec<_> = world
+        .iter_chunks()
+        .unwrap()
+        .map(|result| result.unwrap().1)
+        .collect();
+    found.sort();
+    assert_eq!(found, [(0, 0), (40, -3)]);
+    assert_eq!(world.analyze().unwrap()[0].chunks, 2);
+    let lock = world.lock_session().unwrap();
+    assert_eq!(world.strip_light(&lock, [0, 0], [15, 15]).unwrap(), 1);
+
+    let exported = world
+        .export_area([640, -48], [640, -48], dir.path().join("export"))
+        .unwrap();
+    let chunks: Vec<_> = exported
+        .iter_chunks()
+        .unwrap()
+        .map(|result| result.unwrap().1)
+        .collect();
+    assert_eq!(chunks, [(40, -3)]);
+}
+
+#[test]
+fn test_session_lock() {
+    let dir = tempfile::tempdir().unwrap();
+    let world = World::open(dir.path()).unwrap();
+    let lock = world.lock_session().unwrap();
+    assert!(matches!(world.lock_session(), Err(Error::SessionLocked)));
+    lock.check().unwrap();
+
+    // Another process that ignores the file lock takes over the world, as older versions did.
+    fs::write(dir.path().join("session.lock"), 1i64.to_be_bytes()).unwrap();
+    assert!(matches!(lock.check(), Err(Error::SessionLocked)));
+    drop(lock);
+    world.lock_session().unwrap();
+}
+
+#[test]
+fn test_analyze() {
+    use crate::{
+        chunk::ModernChunk,
+        entity::Entity,
+        region::{test_chunk, TestWorld},
+        tag::CompoundBuilder,
+    };
+
+    #[derive(serde::Serialize)]
+    struct EntityChunk {
+        #[serde(rename = "Entities")]
+        entities: Vec<Entity>,
+    }
+
+    let chunk = |x, inhabited_time, block_entities: &[&str]| {
+        crate::to_bytes(&ModernChunk {
+            inhabited_time,
+            block_entities: block_entities
+                .iter()
+                .map(|id| CompoundBuilder::new().string("id", *id).build())
+                .collect(),
+            ..test_chunk(x, 0)
+        })
+        .unwrap()
+    };
+    let entities = |ids: &[&str]| {
+        let entities = ids
+            .iter()
+            .map(|id| Entity {
+                id: id.to_string(),
+                pos: vec![0.0; 3],
+                uuid: 0,
+            })
+            .collect();
+        crate::to_bytes(&EntityChunk { entities }).unwrap()
+    };
+    let dir = TestWorld::new();
+    dir.region(
+        "region/r.0.0.mca",
+        &[
+            (0, 0, chunk(0, 0, &["minecraft:chest", "minecraft:chest"])),
+            (1, 0, chunk(1, 600, &["minecraft:furnace"])),
+            (2, 0, chunk(2, 100_000, &[])),
+        ],
+    )
+    .region(
+        "entities/r.0.0.mca",
+        &[
+            (0, 0, entities(&["minecraft:cow", "minecraft:cow"])),
+            (1, 0, entities(&["minecraft:zombie"])),
+        ],
+    )
+    .folder("DIM1/region");
+    let region_size = fs::metadata(dir.path().join("region/r.0.0.mca"))
+        .unwrap()
+        .len();
+
+    let mut last = Progress::default();
+    let stats = dir
+        .open()
+        .analyze_with_progress(|progress| {
+            last = progress;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!((last.chunks_done, last.chunks_total), (5, 5));
+    assert!(last.bytes_processed > 0);
+    let cancelled = dir.open().analyze_with_progress(|_| ControlFlow::Break(()));
+    assert!(matches!(cancelled, Err(Error::Cancelled)));
+    let found: Vec<_> = dir
+        .open()
+        .find_matching("id", Some("minecraft:c*"))
+        .unwrap()
+        .into_iter()
+        .map(|(id, position, path)| (id, position, path.to_string()))
+        .collect();
+    let in_overworld = |path: &str| ("minecraft:overworld".to_owned(), (0, 0), path.to_owned());
+    assert_eq!(
+        found,
+        [
+            in_overworld("block_entities[0].id"),
+            in_overworld("block_entities[1].id"),
+            in_overworld("Entities[0].id"),
+            in_overworld("Entities[1].id"),
+        ]
+    );
+    assert_eq!(stats.len(), 2);
+    let overworld = &stats[0];
+    assert_eq!(overworld.id, "minecraft:overworld");
+    assert_eq!(overworld.chunks, 3);
+    assert_eq!(overworld.inhabited_time, [1, 1, 0, 1, 0]);
+    assert_eq!(overworld.total_inhabited_time, 100_600);
+    let counts = |pairs: &[(&str, usize)]| {
+        pairs
+            .iter()
+            .map(|&(id, count)| (id.to_owned(), count))
+            .collect::<BTreeMap<_, _>>()
+    };
+    assert_eq!(
+        overworld.entities,
+        counts(&[("minecraft:cow", 2), ("minecraft:zombie", 1)])
+    );
+    assert_eq!(
+        overworld.block_entities,
+        counts(&[("minecraft:chest", 2), ("minecraft:furnace", 1)])
+    );
+    assert_eq!(
+        overworld.region_sizes,
+        BTreeMap::from([((0, 0), region_size)])
+    );
+    assert_eq!(overworld.total_region_size(), region_size);
+    assert_eq!(
+        stats[1],
+        DimensionStats {
+            id: "minecraft:the_end".into(),
+            ..DimensionStats::default()
+        }
+    );
+}
+
+#[test]
+fn test_export_area() {
+    let chunk = crate::to_bytes(
+        &crate::tag::CompoundBuilder::new()
+            .int("DataVersion", 3465)
+            .build(),
+    )
+    .unwrap();
+    let dir = crate::region::TestWorld::new();
+    fs::write(dir.path().join("level.dat"), b"level").unwrap();
+    for folder in ["region", "entities", "DIM-1/region"] {
+        dir.region(
+            &format!("{}/r.0.0.mca", folder),
+            &[
+                (0, 0, chunk.clone()),
+                (1, 0, chunk.clone()),
+                (5, 5, chunk.clone()),
+            ],
+        )
+        .region(&format!("{}/r.1.0.mca", folder), &[(0, 0, chunk.clone())]);
+    }
+
+    use crate::region::RegionFile;
+
+    let target = dir.path().join("export");
+    let world = dir.open();
+    let stop = dir.path().join("stop");
+    let cancelled = world.export_area_with_progress([0, 0], [31, 15], &stop, |progress| {
+        match progress.chunks_done {
+            5 => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        }
+    });
+    assert!(matches!(cancelled, Err(Error::Cancelled)));
+    // Two chunks from each of three folders, the nether's coming last.
+    let copied = RegionFile::open(stop.join("DIM-1/region/r.0.0.mca")).unwrap();
+    assert_eq!(copied.chunks().count(), 1);
+    let exported = world.export_area([0, 0], [31, 15], &target).unwrap();
+    assert_eq!(fs::read(target.join("level.dat")).unwrap(), b"level");
+    for folder in ["region", "entities", "DIM-1/region"] {
+        let region = RegionFile::open(target.join(folder).join("r.0.0.mca")).unwrap();
+        assert_eq!(region.chunks().collect::<Vec<_>>(), [(0, 0), (1, 0)]);
+        assert_eq!(region.timestamp(1, 0).unwrap(), Some(1));
+        assert!(!target.join(folder).join("r.1.0.mca").exists());
+    }
+    let chunks = exported.iter_chunks().unwrap().count();
+    assert_eq!(chunks, 4);
+    // The export's session was locked while it was written and released afterwards.
+    assert!(target.join("session.lock").is_file());
+    exported.lock_session().unwrap();
+
+    assert!(world.export_area([0, 0], [0, 0], &target).is_err());
+}
+
+#[test]
+fn test_extract_structure() {
+    use crate::{
+        chunk::{ModernChunk, ModernSection, PalettedContainer},
+        region::{test_chunk, TestWorld},
+        tag::CompoundBuilder,
+    };
+
+    let stone = BlockState::new("minecraft:stone");
+    let chest = BlockState::new("minecraft:chest");
+    let air = BlockState::new("minecraft:air");
+    let mut states = vec![air.clone(); 4096];
+    states[(2 * 16 + 3) * 16 + 15] = stone.clone();
+    states[(3 * 16 + 3) * 16 + 15] = chest.clone();
+    let chunk = crate::to_bytes(&ModernChunk {
+        sections: vec![ModernSection {
+            y: 4,
+            block_states: PalettedContainer::pack(&states, 4, false),
+            biomes: None,
+        }],
+        block_entities: vec![CompoundBuilder::new()
+            .string("id", "minecraft:chest")
+            .int("x", 15)
+            .int("y", 67)
+            .int("z", 3)
+            .build()],
+        ..test_chunk(0, 0)
+    })
+    .unwrap();
+    let pig = |x: f64| {
+        CompoundBuilder::new()
+            .string("id", "minecraft:pig")
+            .list("Pos", [x, 66.0, 3.5].map(NBTValue::Double))
+            .build()
+    };
+    let entities = crate::to_bytes(
+        &CompoundBuilder::new()
+            .int("DataVersion", 3465)
+            .list("Entities", [pig(15.5), pig(10.5)])
+            .build(),
+    )
+    .unwrap();
+    let dir = TestWorld::new();
+    dir.region("region/r.0.0.mca", &[(0, 0, chunk)])
+        .region("entities/r.0.0.mca", &[(0, 0, entities)]);
+
+    // The box reaches into chunk 1, which hasn't been generated.
+    let world = dir.open();
+    let structure = world.extract_structure([16, 67, 3], [14, 66, 3]).unwrap();
+    assert_eq!(structure.data_version, 3465);
+    assert_eq!(structure.size, [3, 2, 1]);
+    assert_eq!(structure.blocks.len(), 4);
+    let block = |pos: [i32; 3]| {
+        let block = structure
+            .blocks
+            .iter()
+            .find(|block| block.pos == pos)
+            .unwrap();
+        (&structure.palette[block.state as usize], block.nbt.clone())
+    };
+    assert_eq!(block([0, 0, 0]), (&air, None));
+    assert_eq!(block([1, 0, 0]), (&stone, None));
+    assert_eq!(
+        block([1, 1, 0]),
+        (
+            &chest,
+            Some(
+                CompoundBuilder::new()
+                    .string("id", "minecraft:chest")
+                    .build()
+            )
+        )
+    );
+    assert_eq!(
+        structure.entities,
+        [StructureEntity {
+            pos: [1.5, 0.0, 0.5],
+            block_pos: [1, 0, 0],
+            nbt: Some(pig(15.5)),
+        }]
+    );
+}
+
+#[test]
+fn test_strip_light() {
+    use crate::tag::CompoundBuilder;
+
+    let chunk = crate::to_bytes(
+        &CompoundBuilder::new()
+            .int("DataVersion", 3465)
+            .boolean("isLightOn", true)
+            .list(
+                "sections",
+                [CompoundBuilder::new()
+                    .byte("Y", 0)
+                    .byte_array("SkyLight", vec![-1; 2048])
+                    .build()],
+            )
+            .build(),
+    )
+    .unwrap();
+    use crate::region::RegionFile;
+
+    let dir = crate::region::TestWorld::new();
+    for folder in ["region", "DIM1/region"] {
+        let chunks = [(0, 0, chunk.clone()), (2, 0, chunk.clone())];
+        dir.region(&format!("{}/r.0.0.mca", folder), &chunks);
+    }
+
+    let world = dir.open();
+    let lock = world.lock_session().unwrap();
+    assert_eq!(world.strip_light(&lock, [0, 0], [31, 15]).unwrap(), 2);
+    for dimension in world.dimensions().unwrap() {
+        let mut region = RegionFile::open(dimension.region_dir().join("r.0.0.mca")).unwrap();
+        let stripped = RawChunk::from_slice(&region.read_chunk(0, 0).unwrap().unwrap()).unwrap();
+        assert!(!stripped.is_light_on());
+        assert!(!stripped
+            .to_bytes()
+            .unwrap()
+            .windows(8)
+            .any(|key| key == b"SkyLight"));
+        assert_eq!(region.read_chunk(2, 0).unwrap().unwrap(), chunk);
+    }
+
+    // A lock taken on another world doesn't cover this one, nor does one that was taken over.
+    let other = crate::region::TestWorld::new();
+    let other_lock = other.open().lock_session().unwrap();
+    assert!(world.strip_light(&other_lock, [0, 0], [31, 15]).is_err());
+    fs::write(dir.path().join("session.lock"), 1i64.to_be_bytes()).unwrap();
+    let stripped = world.strip_light(&lock, [0, 0], [31, 15]);
+    assert!(matches!(stripped, Err(Error::SessionLocked)));
+}
+
+/// A chunk read while walking a world: the dimension it is in, its chunk coordinates and the
+/// chunk itself.
+pub type WorldChunk = (String, (i32, i32), Chunk);
+
+/// A match found by [`World::find_matching`]: the dimension and chunk coordinates of the chunk it
+/// is in, and the path to it within the chunk.
+pub type WorldMatch = (String, (i32, i32), NBTPath);
+
+// Opens the source of the chunks a world keeps in a folder, given the folder's path.
+type Sources<S> = Arc<dyn Fn(&Path) -> S + Send + Sync>;
+
+/// A world folder. Its chunks are read and written through a [`ChunkSource`] for each folder of
+/// chunks, by default the [`RegionDirectory`] of the folder's region files.
+pub struct World<S = RegionDirectory> {
+    dir: PathBuf,
+    sources: Sources<S>,
+}
+
+assert_send_sync!(World, Dimension, SessionLock);
+
+impl World {
+    /// Opens the world saved in `dir`. Nothing is read until it is needed.
+    pub fn open<P: Into<PathBuf>>(dir: P) -> error::Result<Self> {
+        Self::with_sources(dir, |folder: &Path| RegionDirectory::new(folder))
+    }
+}
+
+impl<S> World<S> {
+    /// Opens the world saved in `dir`, keeping its chunks in the sources `open` returns instead of
+    /// region files, such as a database or network storage. `open` is called with the folder the
+    /// game would keep the chunks in, such as `<dir>/DIM-1/region` or `<dir>/entities`, which
+    /// doesn't have to exist. `level.dat` and `session.lock` are still read from `dir`, and the
+    /// dimensions are still found from its folders, see [`dimensions`](Self::dimensions).
+    pub fn with_sources<P, F>(dir: P, open: F) -> error::Result<Self>
+    where
+        P: Into<PathBuf>,
+        F: Fn(&Path) -> S + Send + Sync + 'static,
+    {
+        let dir = dir.into();
+        if !dir.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} is not a world folder", dir.display()),
+            )
+            .into());
+        }
+        Ok(Self {
+            dir,
+            sources: Arc::new(open),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Claims the world for writing through its `session.lock` file, like the game does when it
+    /// loads a world. The file is locked for as long as the returned handle lives, and the current
+    /// time is written to it, which versions before 1.16 check instead. Fails with
+    /// [`Error::SessionLocked`] if a running game, server or other tool holds the lock.
+    pub fn lock_session(&self) -> error::Result<SessionLock> {
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.dir.join("session.lock"))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(Error::SessionLocked),
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as i64);
+        file.set_len(0)?;
+        file.write_all(&timestamp.to_be_bytes())?;
+        file.sync_all()?;
+        Ok(SessionLock {
+            dir: self.dir.clone(),
+            file,
+            timestamp,
+        })
+    }
+
+    /// The dimensions that have been generated, found by looking for folders with region files:
+    /// the world folder itself for the overworld, `DIM-1` for the nether, `DIM1` for the end and
+    /// `dimensions/<namespace>/<path>` for dimensions added by data packs. The vanilla dimensions
+    /// come first, followed by the others sorted by id.
+    pub fn dimensions(&self) -> error::Result<Vec<Dimension<S>>> {
+        let mut dimensions: Vec<_> = [
+            ("minecraft:overworld", self.dir.clone()),
+            ("minecraft:the_nether", self.dir.join("DIM-1")),
+            ("minecraft:the_end", self.dir.join("DIM1")),
+        ]
+        .into_iter()
+        .map(|(id, dir)| self.dimension_in(id.to_owned(), dir))
+        .filter(Dimension::is_generated)
+        .collect();
+
+        let vanilla = dimensions.len();
+        let custom = self.dir.join("dimensions");
+        if custom.is_dir() {
+            for entry in fs::read_dir(&custom)? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    let namespace = entry.file_name().to_string_lossy().into_owned();
+                    self.find_dimensions(&namespace, "", &entry.path(), &mut dimensions)?;
+                }
+            }
+        }
+        dimensions[vanilla..].sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(dimensions)
+    }
+
+    /// The dimension with the namespaced `id`, if it has been generated.
+    pub fn dimension(&self, id: &str) -> error::Result<Option<Dimension<S>>> {
+        Ok(self
+            .dimensions()?
+            .into_iter()
+            .find(|dimension| dimension.id == id))
+    }
+
+    fn dimension_in(&self, id: String, dir: PathBuf) -> Dimension<S> {
+        Dimension {
+            id,
+            dir,
+            sources: self.sources.clone(),
+        }
+    }
+
+    // Adds the dimensions in `dir`, the folder for `path` in `namespace`, and below it.
+    fn find_dimensions(
+        &self,
+        namespace: &str,
+        path: &str,
+        dir: &Path,
+        dimensions: &mut Vec<Dimension<S>>,
+    ) -> error::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !entry.file_type()?.is_dir() || name == "region" {
+                continue;
+            }
+            let path = if path.is_empty() {
+                name
+            } else {
+                format!("{}/{}", path, name)
+            };
+            let dimension = self.dimension_in(format!("{}:{}", namespace, path), entry.path());
+            if dimension.is_generated() {
+                if !dimensions.iter().any(|known| known.id == dimension.id) {
+                    dimensions.push(dimension);
+                }
+            } else {
+                self.find_dimensions(namespace, &path, &entry.path(), dimensions)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: ChunkSource> World<S> {
+    /// Reads every chunk of every dimension, one dimension after another.
+    pub fn iter_chunks(&self) -> error::Result<impl Iterator<Item = error::Result<WorldChunk>>> {
+        Ok(self.dimensions()?.into_iter().flat_map(|dimension| {
+            let (positions, error) = match dimension.chunk_source().positions() {
+                Ok(positions) => (positions, None),
+                Err(err) => (Vec::new(), Some(Err(err))),
+            };
+            error.into_iter().chain(dimension.read_chunks(positions))
+        }))
+    }
+
+    /// Like [`iter_chunks`](Self::iter_chunks), but reads the chunks of different regions in
+    /// parallel. Each thread works through one region at a time, with a source of its own, and
+    /// hands chunks on as it reads them, so only a few chunks per thread are held in memory at
+    /// once. The order of the chunks is unspecified.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter_chunks(
+        &self,
+    ) -> error::Result<impl ParallelIterator<Item = error::Result<WorldChunk>>> {
+        let mut batches = Vec::new();
+        for dimension in self.dimensions()? {
+            for positions in by_region(dimension.chunk_source().positions()?) {
+                batches.push((dimension.clone(), positions));
+            }
+        }
+        Ok(batches
+            .into_par_iter()
+            .flat_map_iter(|(dimension, positions)| dimension.read_chunks(positions)))
+    }
+
+    /// Gathers statistics about each dimension, listed in the order of
+    /// [`dimensions`](Self::dimensions). Chunks are read from the `region` folders and entities
+    /// from the `entities` folders, or from the chunks themselves in worlds saved before 1.17. With
+    /// the `rayon` feature the regions are read in parallel.
+    pub fn analyze(&self) -> error::Result<Vec<DimensionStats>> {
+        self.analyze_with_progress(|_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`analyze`](Self::analyze), but calls `progress` after each chunk that was read.
+    pub fn analyze_with_progress<G>(&self, progress: G) -> error::Result<Vec<DimensionStats>>
+    where
+        G: FnMut(Progress) -> ControlFlow<()> + Send,
+    {
+        let dimensions = self.dimensions()?;
+        let mut stats: Vec<_> = dimensions
+            .iter()
+            .map(|dimension| DimensionStats {
+                id: dimension.id.clone(),
+                ..DimensionStats::default()
+            })
+            .collect();
+        let mut batches = Vec::new();
+        for (i, dimension) in dimensions.iter().enumerate() {
+            for (position, path) in RegionDirectory::new(dimension.region_dir()).regions()? {
+                stats[i]
+                    .region_sizes
+                    .insert(position, fs::metadata(path)?.len());
+            }
+            for folder in ["region", "entities"] {
+                for positions in by_region(dimension.source(folder).positions()?) {
+                    batches.push((i, folder, positions));
+                }
+            }
+        }
+
+        let state = Mutex::new((
+            Progress {
+                chunks_total: batches
+                    .iter()
+                    .map(|(_, _, positions)| positions.len())
+                    .sum(),
+                ..Progress::default()
+            },
+            progress,
+        ));
+        let cancelled = AtomicBool::new(false);
+        let analyze = |(i, folder, positions): (usize, &str, Vec<(i32, i32)>)| {
+            let mut source = dimensions[i].source(folder);
+            let mut stats = DimensionStats::default();
+            for (x, z) in positions {
+                if cancelled.load(Ordering::Relaxed) {
+                    return Err(Error::Cancelled);
+                }
+                let Some(stored) = source.get_chunk(x, z)? else {
+                    continue;
+                };
+                let bytes = stored.decompress()?;
+                if folder == "entities" {
+                    stats.add_entity_chunk(&bytes)?;
+                } else {
+                    stats.add_chunk(&bytes)?;
+                }
+
+                let mut state = state.lock().unwrap();
+                let (progress, callback) = &mut *state;
+                if let Err(err) = progress.advance(stored.data.len() as u64 + 5, callback) {
+                    cancelled.store(true, Ordering::Relaxed);
+                    return Err(err);
+                }
+            }
+            Ok((i, stats))
+        };
+        #[cfg(feature = "rayon")]
+        let partial: error::Result<Vec<_>> = batches.into_par_iter().map(analyze).collect();
+        #[cfg(not(feature = "rayon"))]
+        let partial: error::Result<Vec<_>> = batches.into_iter().map(analyze).collect();
+
+        for (i, partial) in partial? {
+            stats[i].merge(partial);
+        }
+        Ok(stats)
+    }
+
+    /// Searches the chunks and entity chunks of every dimension for the compound entries whose key
+    /// matches the glob `key` and, if given, whose value matches the glob `value`, see
+    /// [`NBTValue::find_matching`]. Matches are ordered by dimension, with those in the chunks
+    /// before those in the entity chunks, and then by region and chunk. With the `rayon` feature
+    /// the regions are searched in parallel.
+    pub fn find_matching(&self, key: &str, value: Option<&str>) -> error::Result<Vec<WorldMatch>> {
+        let dimensions = self.dimensions()?;
+        let mut batches = Vec::new();
+        for dimension in &dimensions {
+            for folder in ["region", "entities"] {
+                for positions in by_region(dimension.source(folder).positions()?) {
+                    batches.push((dimension, folder, positions));
+                }
+            }
+        }
+
+        let search = |(dimension, folder, mut positions): (&Dimension<S>, &str, Vec<_>)| {
+            let mut source = dimension.source(folder);
+            positions.sort();
+            let mut found = Vec::new();
+            for (x, z) in positions {
+                let Some(bytes) = source.read_chunk(x, z)? else {
+                    continue;
+                };
+                let chunk: NBTValue = crate::from_slice(&bytes)?;
+                for (path, _) in chunk.find_matching(key, value) {
+                    found.push((dimension.id.clone(), (x, z), path));
+                }
+            }
+            Ok(found)
+        };
+        #[cfg(feature = "rayon")]
+        let found: error::Result<Vec<_>> = batches.into_par_iter().map(search).collect();
+        #[cfg(not(feature = "rayon"))]
+        let found: error::Result<Vec<_>> = batches.into_iter().map(search).collect();
+        Ok(found?.into_iter().flatten().collect())
+    }
+
+    /// Copies the chunks that overlap the area between the block coordinates `min` and `max`, given
+    /// as x and z, into a new world at `target`, along with `level.dat`. Every dimension is
+    /// copied, and with each chunk its entities and points of interest. The new world keeps its
+    /// chunks in the same kind of sources as this one. Chunks are copied as stored, keeping their
+    /// compression and timestamps. The new world's session is locked while it is written, so that
+    /// no game opens it half done. Fails if `target` exists and isn't an empty folder.
+    pub fn export_area<P: Into<PathBuf>>(
+        &self,
+        min: [i32; 2],
+        max: [i32; 2],
+        target: P,
+    ) -> error::Result<World<S>> {
+        self.export_area_with_progress(min, max, target, |_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`export_area`](Self::export_area), but calls `progress` after each chunk that was
+    /// copied. A cancelled export leaves the chunks copied so far in `target`.
+    pub fn export_area_with_progress<P, G>(
+        &self,
+        min: [i32; 2],
+        max: [i32; 2],
+        target: P,
+        mut progress: G,
+    ) -> error::Result<World<S>>
+    where
+        P: Into<PathBuf>,
+        G: FnMut(Progress) -> ControlFlow<()> + Send,
+    {
+        let target = target.into();
+        if target.exists() && fs::read_dir(&target)?.next().is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} is not empty", target.display()),
+            )
+            .into());
+        }
+        fs::create_dir_all(&target)?;
+        let exported = World {
+            dir: target,
+            sources: self.sources.clone(),
+        };
+        let lock = exported.lock_session()?;
+        let target = &exported.dir;
+        let level_dat = self.dir.join("level.dat");
+        if level_dat.is_file() {
+            fs::copy(level_dat, target.join("level.dat"))?;
+        }
+
+        let inside = area(min, max);
+        // The source of each folder to copy, the folder it goes to and the chunks to copy.
+        let mut folders = Vec::new();
+        for dimension in self.dimensions()? {
+            let relative = dimension
+                .dir
+                .strip_prefix(&self.dir)
+                .unwrap_or(Path::new(""));
+            for folder in ["region", "entities", "poi"] {
+                let mut source = dimension.source(folder);
+                let positions: Vec<_> = source.positions()?.into_iter().filter(&inside).collect();
+                if !positions.is_empty() {
+                    folders.push((source, target.join(relative).join(folder), positions));
+                }
+            }
+        }
+
+        let mut done = Progress {
+            chunks_total: folders
+                .iter()
+                .map(|(_, _, positions)| positions.len())
+                .sum(),
+            ..Progress::default()
+        };
+        for (mut source, folder, positions) in folders {
+            let mut copy = (self.sources)(&folder);
+            for (x, z) in positions {
+                let Some(stored) = source.get_chunk(x, z)? else {
+                    continue;
+                };
+                let bytes = stored.data.len() as u64 + 5;
+                let timestamp = source.timestamp(x, z)?.unwrap_or(0);
+                lock.check()?;
+                copy.put_chunk_with_timestamp(x, z, stored, timestamp)?;
+                done.advance(bytes, &mut progress)?;
+            }
+        }
+        drop(lock);
+        Ok(exported)
+    }
+
+    /// Strips the stored light from the chunks that overlap the area between the block coordinates
+    /// `min` and `max`, given as x and z, in every dimension, so that the game computes it again.
+    /// Returns how many chunks were changed. See [`Dimension::strip_light`].
+    pub fn strip_light(
+        &self,
+        lock: &SessionLock,
+        min: [i32; 2],
+        max: [i32; 2],
+    ) -> error::Result<usize> {
+        let mut stripped = 0;
+        for dimension in self.dimensions()? {
+            stripped += dimension.strip_light(lock, min, max)?;
+        }
+        Ok(stripped)
+    }
+
+    /// Copies the blocks, block entities and entities of the overworld in the box between the block
+    /// coordinates `min` and `max`, both included, into a structure whose origin is the box's
+    /// lowest corner. See [`Dimension::extract_structure`].
+    pub fn extract_structure(&self, min: [i32; 3], max: [i32; 3]) -> error::Result<StructureFile> {
+        let overworld = self.dimension_in("minecraft:overworld".into(), self.dir.clone());
+        overworld.extract_structure(min, max)
+    }
+}
+
+impl<S> Clone for World<S> {
+    fn clone(&self) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            sources: self.sources.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for World<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("World")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+// Whether a chunk overlaps the area between the block coordinates `min` and `max`.
+fn area(min: [i32; 2], max: [i32; 2]) -> impl Fn(&(i32, i32)) -> bool {
+    let (min_x, min_z) = chunk_at_block(min[0].min(max[0]), min[1].min(max[1]));
+    let (max_x, max_z) = chunk_at_block(min[0].max(max[0]), min[1].max(max[1]));
+    move |&(x, z)| (min_x..=max_x).contains(&x) && (min_z..=max_z).contains(&z)
+}
+
+// Splits chunk positions up by the region they are in, so that each part can be worked through
+// with a source of its own.
+fn by_region(positions: Vec<(i32, i32)>) -> impl Iterator<Item = Vec<(i32, i32)>> {
+    let mut regions: BTreeMap<(i32, i32), Vec<_>> = BTreeMap::new();
+    for (x, z) in positions {
+        regions
+            .entry(region_at_chunk(x, z))
+            .or_default()
+            .push((x, z));
+    }
+    regions.into_values()
+}
+
+/// Statistics about one dimension of a world, see [`World::analyze`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DimensionStats {
+    /// Namespaced id of the dimension.
+    pub id: String,
+    /// Number of chunks saved.
+    pub chunks: usize,
+    /// Number of chunks by the time players have spent in them: never, under a minute, under an
+    /// hour, under a day, and a day or more. See [`INHABITED_TIME_BUCKETS`].
+    pub inhabited_time: [usize; 5],
+    /// Sum of the inhabited time of every chunk, in ticks.
+    pub total_inhabited_time: i64,
+    /// Number of entities by id.
+    pub entities: BTreeMap<String, usize>,
+    /// Number of block entities by id.
+    pub block_entities: BTreeMap<String, usize>,
+    /// Size in bytes of each region file in the dimension's `region` folder, by region
+    /// coordinates. Empty if the world keeps its chunks elsewhere.
+    pub region_sizes: BTreeMap<(i32, i32), u64>,
+}
+
+/// The inhabited times, in ticks, at which the buckets of [`DimensionStats::inhabited_time`] after
+/// the first start.
+pub const INHABITED_TIME_BUCKETS: [i64; 4] = [1, 20 * 60, 20 * 60 * 60, 20 * 60 * 60 * 24];
+
+impl DimensionStats {
+    /// Size in bytes of all region files together.
+    pub fn total_region_size(&self) -> u64 {
+        self.region_sizes.values().sum()
+    }
+
+    fn add_chunk(&mut self, bytes: &[u8]) -> error::Result<()> {
+        #[derive(Deserialize)]
+        struct Summary {
+            #[serde(rename = "InhabitedTime", default)]
+            inhabited_time: i64,
+            #[serde(default)]
+            block_entities: Vec<Id>,
+            // Chunks before 1.18 keep their data in a `Level` compound.
+            #[serde(rename = "Level")]
+            level: Option<LegacySummary>,
+        }
+
+        #[derive(Deserialize)]
+        struct LegacySummary {
+            #[serde(rename = "InhabitedTime", default)]
+            inhabited_time: i64,
+            #[serde(rename = "TileEntities", default)]
+            block_entities: Vec<Id>,
+            #[serde(rename = "Entities", default)]
+            entities: Vec<Id>,
+        }
+
+        let summary: Summary = crate::from_slice(bytes)?;
+        let (inhabited_time, block_entities, entities) = match summary.level {
+            Some(level) => (level.inhabited_time, level.block_entities, level.entities),
+            None => (summary.inhabited_time, summary.block_entities, Vec::new()),
+        };
+        self.chunks += 1;
+        let bucket = INHABITED_TIME_BUCKETS
+            .iter()
+            .take_while(|&&start| inhabited_time >= start)
+            .count();
+        self.inhabited_time[bucket] += 1;
+        self.total_inhabited_time += inhabited_time;
+        count_ids(&mut self.block_entities, block_entities);
+        count_ids(&mut self.entities, entities);
+        Ok(())
+    }
+
+    fn add_entity_chunk(&mut self, bytes: &[u8]) -> error::Result<()> {
+        #[derive(Deserialize)]
+        struct EntityChunk {
+            #[serde(rename = "Entities", default)]
+            entities: Vec<Id>,
+        }
+
+        let chunk: EntityChunk = crate::from_slice(bytes)?;
+        count_ids(&mut self.entities, chunk.entities);
+        Ok(())
+    }
+
+    fn merge(&mut self, other: DimensionStats) {
+        self.chunks += other.chunks;
+        for (count, other) in self.inhabited_time.iter_mut().zip(other.inhabited_time) {
+            *count += other;
+        }
+        self.total_inhabited_time += other.total_inhabited_time;
+        for (id, count) in other.entities {
+            *self.entities.entry(id).or_default() += count;
+        }
+        for (id, count) in other.block_entities {
+            *self.block_entities.entry(id).or_default() += count;
+        }
+        self.region_sizes.extend(other.region_sizes);
+    }
+}
+
+// The id of an entity or block entity, skipping everything else.
+#[derive(Deserialize)]
+struct Id {
+    #[serde(default)]
+    id: String,
+}
+
+fn count_ids(counts: &mut BTreeMap<String, usize>, ids: Vec<Id>) {
+    for Id { id } in ids {
+        *counts.entry(id).or_default() += 1;
+    }
+}
+
+/// Exclusive access to a world, see [`World::lock_session`]. The lock is released when the
+/// handle is dropped.
+#[derive(Debug)]
+pub struct SessionLock {
+    dir: PathBuf,
+    file: File,
+    timestamp: i64,
+}
+
+impl SessionLock {
+    /// Checks that no one has taken over the world since it was locked, by making sure
+    /// `session.lock` still holds the time written when locking. Call this before saving, as
+    /// games before 1.16 do.
+    pub fn check(&self) -> error::Result<()> {
+        let mut file = &self.file;
+        let mut timestamp = [0; 8];
+        file.seek(SeekFrom::Start(0))?;
+        match file.read_exact(&mut timestamp) {
+            Ok(()) if i64::from_be_bytes(timestamp) == self.timestamp => Ok(()),
+            _ => Err(Error::SessionLocked),
+        }
+    }
+
+    /// Checks the lock like [`check`](Self::check), after making sure it was taken on the world
+    /// that `dir` belongs to.
+    pub(crate) fn check_for(&self, dir: &Path) -> error::Result<()> {
+        if !dir.starts_with(&self.dir) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} is not in the locked world {}",
+                    dir.display(),
+                    self.dir.display()
+                ),
+            )
+            .into());
+        }
+        self.check()
+    }
+}
+
+/// One dimension of a world, see [`World::dimensions`].
+pub struct Dimension<S = RegionDirectory> {
+    id: String,
+    dir: PathBuf,
+    sources: Sources<S>,
+}
+
+impl<S> Dimension<S> {
+    /// Namespaced id, such as `minecraft:the_nether`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The folder holding the dimension's `region`, `entities` and `poi` folders.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn region_dir(&self) -> PathBuf {
+        self.dir.join("region")
+    }
+
+    /// The source of the dimension's chunks, addressed by chunk coordinates.
+    pub fn chunk_source(&self) -> S {
+        self.source("region")
+    }
+
+    /// The source of the chunks of entities that the game has kept apart since 1.17.
+    pub fn entity_source(&self) -> S {
+        self.source("entities")
+    }
+
+    fn source(&self, folder: &str) -> S {
+        (self.sources)(&self.dir.join(folder))
+    }
+
+    fn is_generated(&self) -> bool {
+        self.region_dir().is_dir()
+    }
+}
+
+impl<S: ChunkSource> Dimension<S> {
+    /// Reads the chunk at chunk coordinates `x`, `z`, or `None` if it hasn't been generated.
+    pub fn read_chunk(&self, x: i32, z: i32) -> error::Result<Option<Chunk>> {
+        let bytes = self.chunk_source().read_chunk(x, z)?;
+        bytes.map(|bytes| Chunk::from_slice(&bytes)).transpose()
+    }
+
+    /// Strips the stored light from the chunks that overlap the area between the block coordinates
+    /// `min` and `max`, given as x and z, with [`RawChunk::strip_light`], and returns how many
+    /// chunks were changed. The chunks are written back compressed with zlib. `lock` must have
+    /// been taken on this dimension's world with [`World::lock_session`], and is checked before
+    /// each chunk is written, failing with [`Error::SessionLocked`] if someone took the world over.
+    pub fn strip_light(
+        &self,
+        lock: &SessionLock,
+        min: [i32; 2],
+        max: [i32; 2],
+    ) -> error::Result<usize> {
+        let mut source = self.chunk_source();
+        let positions: Vec<_> = source
+            .positions()?
+            .into_iter()
+            .filter(area(min, max))
+            .collect();
+        let mut stripped = 0;
+        for (x, z) in positions {
+            let Some(bytes) = source.read_chunk(x, z)? else {
+                continue;
+            };
+            let mut chunk = RawChunk::from_slice(&bytes)?;
+            chunk.strip_light();
+            lock.check_for(&self.dir)?;
+            source.put_chunk(x, z, StoredChunk::compress(&chunk.to_bytes()?)?)?;
+            stripped += 1;
+        }
+        Ok(stripped)
+    }
+
+    /// Copies the blocks, block entities and entities in the box between the block coordinates
+    /// `min` and `max`, both included, into a structure whose origin is the box's lowest corner,
+    /// the way a structure block saves. Air is kept, so pasting the structure clears the blocks it
+    /// covers. Positions in chunks that haven't been generated are left out, and sections missing
+    /// from a chunk are read as air. Block entities lose their `x`, `y` and `z`, while entities
+    /// keep their data as saved. They are read from the `entities` folder, or from the chunks in
+    /// worlds saved before 1.17. `DataVersion` is the highest of the chunks read.
+    pub fn extract_structure(&self, min: [i32; 3], max: [i32; 3]) -> error::Result<StructureFile> {
+        #[derive(Deserialize)]
+        struct Entities {
+            #[serde(rename = "Entities", default)]
+            entities: Vec<NBTValue>,
+            // Chunks before 1.17 keep their entities in the `Level` compound.
+            #[serde(rename = "Level")]
+            level: Option<Box<Entities>>,
+        }
+
+        let (min, max) = (
+            [0, 1, 2].map(|i| min[i].min(max[i])),
+            [0, 1, 2].map(|i| min[i].max(max[i])),
+        );
+        let mut structure = StructureFile {
+            data_version: 0,
+            size: [0, 1, 2].map(|i| max[i] - min[i] + 1),
+            palette: Vec::new(),
+            palettes: Vec::new(),
+            blocks: Vec::new(),
+            entities: Vec::new(),
+        };
+        let mut indices: BTreeMap<BlockState, i32> = BTreeMap::new();
+        let (mut chunks, mut entity_chunks) = (self.chunk_source(), self.entity_source());
+        // The part of `min..=max` along one axis inside the 16 blocks starting at `start`.
+        let clamp = |i: usize, start: i32| start.max(min[i])..=(start + 15).min(max[i]);
+
+        for chunk_x in (min[0] >> 4)..=(max[0] >> 4) {
+            for chunk_z in (min[2] >> 4)..=(max[2] >> 4) {
+                let Some(bytes) = chunks.read_chunk(chunk_x, chunk_z)? else {
+                    continue;
+                };
+                let chunk = Chunk::from_slice(&bytes)?;
+                structure.data_version = structure.data_version.max(chunk.data_version);
+                for section_y in (min[1] >> 4)..=(max[1] >> 4) {
+                    let section = chunk
+                        .sections
+                        .iter()
+                        .find(|section| i32::from(section.y) == section_y);
+                    let states = match section {
+                        Some(section) => section.block_states.unpack(4096)?,
+                        None => vec![BlockState::new("minecraft:air"); 4096],
+                    };
+                    for y in clamp(1, section_y * 16) {
+                        for z in clamp(2, chunk_z * 16) {
+                            for x in clamp(0, chunk_x * 16) {
+                                let state =
+                                    &states[(((y & 15) * 16 + (z & 15)) * 16 + (x & 15)) as usize];
+                                let next = indices.len() as i32;
+                                let index = *indices.entry(state.clone()).or_insert_with(|| {
+                                    structure.palette.push(state.clone());
+                                    next
+                                });
+                                let nbt = chunk
+                                    .block_entities
+                                    .iter()
+                                    .find(|entity| block_entity_pos(entity) == Some([x, y, z]))
+                                    .map(|entity| {
+                                        let mut entity = entity.clone();
+                                        if let NBTValue::Compound(entity) = &mut entity {
+                                            for key in ["x", "y", "z"] {
+                                                entity.remove(key);
+                                            }
+                                        }
+                                        entity
+                                    });
+                                structure.blocks.push(StructureBlock {
+                                    pos: [x - min[0], y - min[1], z - min[2]],
+                                    state: index,
+                                    nbt,
+                                });
+                            }
+                        }
+                    }
+                }
+
+                let legacy: Entities = crate::from_slice(&bytes)?;
+                let mut entities = legacy.level.map_or_else(Vec::new, |level| level.entities);
+                if let Some(bytes) = entity_chunks.read_chunk(chunk_x, chunk_z)? {
+                    entities.extend(crate::from_slice::<Entities>(&bytes)?.entities);
+                }
+                for nbt in entities {
+                    let Some(pos) = entity_pos(&nbt) else {
+                        continue;
+                    };
+                    let inside =
+                        (0..3).all(|i| (min[i] as f64..(max[i] + 1) as f64).contains(&pos[i]));
+                    if !inside {
+                        continue;
+                    }
+                    let pos = [0, 1, 2].map(|i| pos[i] - min[i] as f64);
+                    structure.entities.push(StructureEntity {
+                        pos,
+                        block_pos: pos.map(|coordinate| coordinate.floor() as i32),
+                        nbt: Some(nbt),
+                    });
+                }
+            }
+        }
+        Ok(structure)
+    }
+
+    // Reads the chunks at `positions`, leaving out those that haven't been generated.
+    fn read_chunks(
+        self,
+        positions: Vec<(i32, i32)>,
+    ) -> impl Iterator<Item = error::Result<WorldChunk>> {
+        let mut source = self.chunk_source();
+        positions
+            .into_iter()
+            .filter_map(move |(x, z)| match source.read_chunk(x, z) {
+                Ok(Some(bytes)) => {
+                    Some(Chunk::from_slice(&bytes).map(|chunk| (self.id.clone(), (x, z), chunk)))
+                }
+                Ok(None) => None,
+                Err(err) => Some(Err(err)),
+            })
+    }
+}
+
+impl<S> Clone for Dimension<S> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            dir: self.dir.clone(),
+            sources: self.sources.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for Dimension<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dimension")
+            .field("id", &self.id)
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> PartialEq for Dimension<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.dir == other.dir
+    }
+}
+
+impl<S> Eq for Dimension<S> {}
+
+// The `Pos` of an entity.
+fn entity_pos(entity: &NBTValue) -> Option<[f64; 3]> {
+    let NBTValue::Compound(entity) = entity else {
+        return None;
+    };
+    match entity.get("Pos") {
+        Some(NBTValue::List(pos)) => match pos.as_slice() {
+            [NBTValue::Double(x), NBTValue::Double(y), NBTValue::Double(z)] => Some([*x, *y, *z]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
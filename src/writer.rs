@@ -1,77 +1,242 @@
-use std::io;
+use alloc::{borrow::ToOwned, vec::Vec};
+use core::marker::PhantomData;
 
-use byteorder::WriteBytesExt;
+use byteorder::{BigEndian, ByteOrder};
 
-use crate::{error, kind::NBTKind};
+use crate::{
+    error, io,
+    kind::NBTKind,
+    ser::SerializerOptions,
+    tag::{CompoundMap, NBTValue},
+};
 
 #[derive(Copy, Clone)]
-pub(crate) enum DelayedHeader {
-    MapKey(&'static str),
+pub(crate) enum DelayedHeader<'a> {
+    MapKey(&'a str),
     List(usize),
 }
 
-pub(crate) struct Writer<W: io::Write> {
+/// Writes NBT primitives to `W`. Multi-byte numbers are encoded with the byte order `O`, which is
+/// big-endian for Java Edition and little-endian for Bedrock Edition.
+pub(crate) struct Writer<W: io::Write, O: ByteOrder = BigEndian> {
     writer: W,
+    options: SerializerOptions,
+    byte_order: PhantomData<O>,
 }
 
-impl<W: io::Write> Writer<W> {
-    pub(crate) fn new(writer: W) -> Self {
-        Self { writer }
+/// Most bytes a String tag can hold, since its length is prefixed as a `u16`.
+pub(crate) const MAX_STRING_LEN: usize = u16::MAX as usize;
+
+/// Maps every NaN to the same quiet NaN and negative zero to zero.
+pub(crate) fn canonical_f32(n: f32) -> f32 {
+    if n.is_nan() {
+        f32::NAN
+    } else if n == 0.0 {
+        0.0
+    } else {
+        n
+    }
+}
+
+/// Maps every NaN to the same quiet NaN and negative zero to zero.
+pub(crate) fn canonical_f64(n: f64) -> f64 {
+    if n.is_nan() {
+        f64::NAN
+    } else if n == 0.0 {
+        0.0
+    } else {
+        n
+    }
+}
+
+impl<W: io::Write, O: ByteOrder> Writer<W, O> {
+    pub(crate) fn new(writer: W, options: SerializerOptions) -> Self {
+        Self {
+            writer,
+            options,
+            byte_order: PhantomData,
+        }
+    }
+
+    pub(crate) fn options(&self) -> &SerializerOptions {
+        &self.options
     }
 
     pub(crate) fn write_tag_header(
         &mut self,
         kind: NBTKind,
-        delayed_header: Option<DelayedHeader>,
+        delayed_header: Option<DelayedHeader<'_>>,
     ) -> error::Result<()> {
-        self.writer.write_u8(kind.header_byte())?;
+        self.writer.write_all(&[kind.header_byte()])?;
         if let Some(header) = delayed_header {
             match header {
                 DelayedHeader::MapKey(key) => self.write_string(key)?,
-                DelayedHeader::List(length) => self.write_i32(length as i32)?,
+                DelayedHeader::List(length) => self.write_length(length)?,
             }
         }
         Ok(())
     }
 
     pub(crate) fn write_i8(&mut self, n: i8) -> error::Result<()> {
-        self.writer.write_i8(n)?;
+        self.writer.write_all(&[n as u8])?;
         Ok(())
     }
 
     pub(crate) fn write_u16(&mut self, n: u16) -> error::Result<()> {
-        self.writer.write_u16::<byteorder::BigEndian>(n)?;
+        let mut buf = [0; 2];
+        O::write_u16(&mut buf, n);
+        self.writer.write_all(&buf)?;
         Ok(())
     }
 
     pub(crate) fn write_i16(&mut self, n: i16) -> error::Result<()> {
-        self.writer.write_i16::<byteorder::BigEndian>(n)?;
+        let mut buf = [0; 2];
+        O::write_i16(&mut buf, n);
+        self.writer.write_all(&buf)?;
         Ok(())
     }
 
     pub(crate) fn write_i32(&mut self, n: i32) -> error::Result<()> {
-        self.writer.write_i32::<byteorder::BigEndian>(n)?;
+        let mut buf = [0; 4];
+        O::write_i32(&mut buf, n);
+        self.writer.write_all(&buf)?;
         Ok(())
     }
 
+    /// Writes the `i32` length of a list or array, rejecting lengths that don't fit.
+    pub(crate) fn write_length(&mut self, length: usize) -> error::Result<()> {
+        let length = i32::try_from(length)
+            .map_err(|_| error::Error::InvalidLength(length.try_into().unwrap_or(i64::MAX)))?;
+        self.write_i32(length)
+    }
+
     pub(crate) fn write_i64(&mut self, n: i64) -> error::Result<()> {
-        self.writer.write_i64::<byteorder::BigEndian>(n)?;
+        let mut buf = [0; 8];
+        O::write_i64(&mut buf, n);
+        self.writer.write_all(&buf)?;
         Ok(())
     }
 
     pub(crate) fn write_f32(&mut self, n: f32) -> error::Result<()> {
-        self.writer.write_f32::<byteorder::BigEndian>(n)?;
+        let n = if self.options.canonicalize_floats {
+            canonical_f32(n)
+        } else {
+            n
+        };
+        let mut buf = [0; 4];
+        O::write_f32(&mut buf, n);
+        self.writer.write_all(&buf)?;
         Ok(())
     }
 
     pub(crate) fn write_f64(&mut self, n: f64) -> error::Result<()> {
-        self.writer.write_f64::<byteorder::BigEndian>(n)?;
+        let n = if self.options.canonicalize_floats {
+            canonical_f64(n)
+        } else {
+            n
+        };
+        let mut buf = [0; 8];
+        O::write_f64(&mut buf, n);
+        self.writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) -> error::Result<()> {
+        self.write_length(bytes.len())?;
+        self.writer.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Writes `bytes` as they are, without a length.
+    #[cfg(feature = "std")]
+    pub(crate) fn write_raw(&mut self, bytes: &[u8]) -> error::Result<()> {
+        self.writer.write_all(bytes)?;
         Ok(())
     }
 
     pub(crate) fn write_string(&mut self, string: &str) -> error::Result<()> {
-        self.write_u16(string.len() as u16)?;
-        self.writer.write(string.as_bytes())?;
+        let length =
+            u16::try_from(string.len()).map_err(|_| error::Error::StringTooLong(string.len()))?;
+        self.write_u16(length)?;
+        self.writer.write_all(string.as_bytes())?;
         Ok(())
     }
+
+    /// Writes the payload of a List of Strings that holds `string` in pieces short enough for a
+    /// String tag, split at char boundaries.
+    pub(crate) fn write_long_string(&mut self, mut string: &str) -> error::Result<()> {
+        let mut pieces = Vec::new();
+        while !string.is_empty() {
+            let mut end = string.len().min(MAX_STRING_LEN);
+            while !string.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (piece, rest) = string.split_at(end);
+            pieces.push(piece);
+            string = rest;
+        }
+        self.write_tag_header(NBTKind::String, Some(DelayedHeader::List(pieces.len())))?;
+        pieces.iter().try_for_each(|piece| self.write_string(piece))
+    }
+
+    /// Writes `value` as the root compound of a document named `name`.
+    #[cfg(feature = "std")]
+    pub(crate) fn write_document(&mut self, name: &str, value: &NBTValue) -> error::Result<()> {
+        match value {
+            NBTValue::Compound(entries) => self.write_root(name, entries),
+            _ => Err(error::Error::ExpectedRootCompound),
+        }
+    }
+
+    /// Writes a document whose root compound is named `name` and holds `entries`.
+    pub(crate) fn write_root(&mut self, name: &str, entries: &CompoundMap) -> error::Result<()> {
+        self.write_tag_header(NBTKind::Compound, Some(DelayedHeader::MapKey(name)))?;
+        self.write_entries(entries)
+    }
+
+    fn write_entries(&mut self, entries: &CompoundMap) -> error::Result<()> {
+        for (name, entry) in entries {
+            self.write_tag_header(entry.kind(), Some(DelayedHeader::MapKey(name)))?;
+            self.write_value(entry)?;
+        }
+        self.write_tag_header(NBTKind::End, None)
+    }
+
+    /// Writes the payload of `value`.
+    pub(crate) fn write_value(&mut self, value: &NBTValue) -> error::Result<()> {
+        match value {
+            NBTValue::Byte(n) => self.write_i8(*n),
+            NBTValue::Short(n) => self.write_i16(*n),
+            NBTValue::Int(n) => self.write_i32(*n),
+            NBTValue::Long(n) => self.write_i64(*n),
+            NBTValue::Float(n) => self.write_f32(*n),
+            NBTValue::Double(n) => self.write_f64(*n),
+            NBTValue::String(string) => self.write_string(string),
+            NBTValue::ByteArray(bytes) => {
+                self.write_length(bytes.len())?;
+                bytes.iter().try_for_each(|&n| self.write_i8(n))
+            }
+            NBTValue::IntArray(ints) => {
+                self.write_length(ints.len())?;
+                ints.iter().try_for_each(|&n| self.write_i32(n))
+            }
+            NBTValue::LongArray(longs) => {
+                self.write_length(longs.len())?;
+                longs.iter().try_for_each(|&n| self.write_i64(n))
+            }
+            NBTValue::List(elements) => {
+                let kind = elements.first().map_or(NBTKind::End, NBTValue::kind);
+                if elements.iter().any(|element| element.kind() != kind) {
+                    return Err(error::Error::Message(
+                        "list elements must all be of the same kind".to_owned(),
+                    ));
+                }
+                self.write_tag_header(kind, Some(DelayedHeader::List(elements.len())))?;
+                elements
+                    .iter()
+                    .try_for_each(|element| self.write_value(element))
+            }
+            NBTValue::Compound(entries) => self.write_entries(entries),
+        }
+    }
 }